@@ -0,0 +1,26 @@
+//! Call functions defined in Uiua from Rust
+
+use uiua::{Uiua, UiuaResult};
+
+const CODE: &str = "
+Mean ← ÷⧻∶/+.
+MinMax ← ⊃/↧/↥
+";
+
+fn main() -> UiuaResult {
+    let mut env = Uiua::with_native_sys();
+    env.load_str(CODE)?;
+    for samples in [
+        vec![1.0, 2.0, 3.0],
+        vec![2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0],
+    ] {
+        let mean = env.call_binding("Mean", [samples.clone().into()])?[0].try_num()?;
+        // The values are in stack order, so the last one was on top
+        let [max, min] = &env.call_binding("minmax", [samples.clone().into()])?[..] else {
+            unreachable!("MinMax returns 2 values")
+        };
+        let (min, max) = (min.try_num()?, max.try_num()?);
+        println!("{samples:?}: mean {mean}, min {min}, max {max}");
+    }
+    Ok(())
+}
@@ -0,0 +1,53 @@
+//! Generates the glyph finder's index from the primitives and their docs
+
+use std::{env, fmt::Write, fs, path::Path};
+
+use uiua::primitive::{PrimDocFragment, PrimDocLine, Primitive};
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    let mut code = String::from("&[\n");
+    for prim in Primitive::all().filter(|prim| !prim.is_deprecated()) {
+        let Some(names) = prim.names() else {
+            continue;
+        };
+        let doc = prim.doc();
+        let summary = (doc.map(|doc| doc.short_text().to_lowercase())).unwrap_or_default();
+        let mut text = summary.clone();
+        for line in doc.into_iter().flat_map(|doc| &doc.lines) {
+            if let PrimDocLine::Text(frags) = line {
+                text.push('\n');
+                text.push_str(&fragments_text(frags).to_lowercase());
+            }
+        }
+        let path = match prim {
+            Primitive::Sys(op) => format!("Primitive::Sys(SysOp::{op:?})"),
+            prim => format!("Primitive::{prim:?}"),
+        };
+        writeln!(
+            code,
+            "    FinderEntry {{ prim: {path}, name: {:?}, ascii: {:?}, glyph: {:?}, \
+            summary: {summary:?}, doc: {text:?} }},",
+            names.text.to_lowercase(),
+            names.ascii.map(|ascii| ascii.to_string()),
+            names.glyph,
+        )
+        .unwrap();
+    }
+    code.push(']');
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::write(Path::new(&out_dir).join("finder_index.rs"), code).unwrap();
+}
+
+fn fragments_text(frags: &[PrimDocFragment]) -> String {
+    (frags.iter())
+        .map(|frag| match frag {
+            PrimDocFragment::Text(s)
+            | PrimDocFragment::Code(s)
+            | PrimDocFragment::Emphasis(s)
+            | PrimDocFragment::Strong(s)
+            | PrimDocFragment::Link { text: s, .. } => s.clone(),
+            PrimDocFragment::Primitive { prim, .. } => prim.name().unwrap_or_default().into(),
+        })
+        .collect()
+}
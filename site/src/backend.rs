@@ -2,31 +2,84 @@ use std::{
     any::Any,
     collections::HashMap,
     io::Cursor,
+    mem::take,
+    ops::Range,
     sync::{
         atomic::{AtomicU64, Ordering},
         Mutex,
     },
 };
 
-use leptos::*;
-use uiua::{value::Value, DiagnosticKind, Handle, SysBackend, Uiua, UiuaError, UiuaResult};
+use uiua::{
+    example_ua, value::Value, DiagnosticKind, Handle, HttpRequest, HttpResponse, SysBackend, Uiua,
+    UiuaError, UiuaResult,
+};
+use web_sys::XmlHttpRequest;
 
 pub struct WebBackend {
     pub stdout: Mutex<Vec<OutputItem>>,
     pub stderr: Mutex<String>,
     pub trace: Mutex<String>,
+    /// The remaining standard input, which comes from the editor's input box
+    ///
+    /// Once it runs out, reading from stdin gives the end-of-input value rather than waiting for more.
+    stdin: Mutex<String>,
     pub files: Mutex<HashMap<String, Vec<u8>>>,
+    /// The modification times of the files, in seconds since the Unix epoch
+    modified: Mutex<HashMap<String, f64>>,
+    /// Environment variables, which examples can set with [`WebBackend::with_vars`]
+    pub vars: HashMap<String, String>,
+    cwd: Mutex<String>,
     next_thread_id: AtomicU64,
     thread_results: Mutex<HashMap<Handle, UiuaResult<Vec<Value>>>>,
 }
 
+/// The fake home directory, which is also the initial current directory
+const HOME_DIR: &str = "/home/uiua";
+
+impl WebBackend {
+    /// Get the key of a path in the virtual file map
+    ///
+    /// Relative paths are resolved against the current directory.
+    /// Keys of paths in the home directory are relative to it, without a leading `/`.
+    fn file_key(&self, path: &str) -> String {
+        let path = resolve_path(&self.cwd.lock().unwrap(), path);
+        match path.strip_prefix(HOME_DIR) {
+            Some("") => String::new(),
+            Some(rest) if rest.starts_with('/') => rest[1..].into(),
+            _ => path,
+        }
+    }
+    pub fn with_stdin(self, stdin: impl Into<String>) -> Self {
+        *self.stdin.lock().unwrap() = stdin.into();
+        self
+    }
+    pub fn with_vars<'a>(mut self, vars: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        (self.vars).extend(vars.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+}
+
 impl Default for WebBackend {
     fn default() -> Self {
         Self {
             stdout: Vec::new().into(),
             stderr: String::new().into(),
             trace: String::new().into(),
-            files: HashMap::new().into(),
+            stdin: String::new().into(),
+            files: [(
+                "example.ua".to_string(),
+                example_ua(|ex| ex.as_bytes().to_vec()),
+            )]
+            .into_iter()
+            .collect::<HashMap<_, _>>()
+            .into(),
+            modified: HashMap::new().into(),
+            vars: [("HOME", HOME_DIR), ("USER", "uiua")]
+                .into_iter()
+                .map(|(name, value)| (name.into(), value.into()))
+                .collect(),
+            cwd: HOME_DIR.to_string().into(),
             next_thread_id: 0.into(),
             thread_results: HashMap::new().into(),
         }
@@ -38,7 +91,8 @@ pub enum OutputItem {
     Image(Vec<u8>),
     Gif(Vec<u8>),
     Audio(Vec<u8>),
-    Error(String),
+    /// An error message, and the character ranges in the code where the error happened
+    Error(String, Vec<Range<usize>>),
     Diagnostic(String, DiagnosticKind),
     Separator,
 }
@@ -73,10 +127,18 @@ impl SysBackend for WebBackend {
     fn print_str_trace(&self, s: &str) {
         self.trace.lock().unwrap().push_str(s);
     }
+    fn scan_all_stdin(&self) -> Result<String, String> {
+        Ok(take(&mut *self.stdin.lock().unwrap()))
+    }
     fn scan_line_stdin(&self) -> Result<Option<String>, String> {
-        Ok(window()
-            .prompt_with_message("Enter a line of text for stdin")
-            .unwrap_or(None))
+        let mut stdin = self.stdin.lock().unwrap();
+        if stdin.is_empty() {
+            return Ok(None);
+        }
+        let (line, rest) = stdin.split_once('\n').unwrap_or((stdin.as_str(), ""));
+        let line = line.strip_suffix('\r').unwrap_or(line).to_string();
+        *stdin = rest.to_string();
+        Ok(Some(line))
     }
     fn show_image(&self, image: image::DynamicImage) -> Result<(), String> {
         let mut bytes = Cursor::new(Vec::new());
@@ -94,20 +156,95 @@ impl SysBackend for WebBackend {
         Ok(())
     }
     fn file_write_all(&self, path: &str, contents: &[u8]) -> Result<(), String> {
-        self.files
+        let path = self.file_key(path);
+        self.modified
             .lock()
             .unwrap()
-            .insert(path.to_string(), contents.to_vec());
+            .insert(path.clone(), self.now());
+        self.files.lock().unwrap().insert(path, contents.to_vec());
         Ok(())
     }
     fn file_read_all(&self, path: &str) -> Result<Vec<u8>, String> {
         self.files
             .lock()
             .unwrap()
-            .get(path)
+            .get(&self.file_key(path))
             .cloned()
             .ok_or_else(|| format!("File not found: {path}"))
     }
+    fn file_exists(&self, path: &str) -> bool {
+        let path = self.file_key(path);
+        let files = self.files.lock().unwrap();
+        files.contains_key(&path) || is_dir(&files, &path)
+    }
+    fn is_file(&self, path: &str) -> Result<bool, String> {
+        let path = self.file_key(path);
+        let files = self.files.lock().unwrap();
+        if files.contains_key(&path) {
+            Ok(true)
+        } else if is_dir(&files, &path) {
+            Ok(false)
+        } else {
+            Err(format!("File not found: {path}"))
+        }
+    }
+    fn list_dir(&self, path: &str) -> Result<Vec<(String, bool)>, String> {
+        let dir = self.file_key(path);
+        let files = self.files.lock().unwrap();
+        if !is_dir(&files, &dir) {
+            return Err(format!("Directory not found: {path}"));
+        }
+        let mut entries: Vec<(String, bool)> = (files.keys())
+            .filter_map(|key| {
+                let rest = if dir.is_empty() {
+                    key.as_str()
+                } else {
+                    key.strip_prefix(&dir)?.strip_prefix('/')?
+                };
+                Some(match rest.split_once('/') {
+                    Some((name, _)) => (name.to_string(), true),
+                    None => (rest.to_string(), false),
+                })
+            })
+            .collect();
+        entries.sort();
+        entries.dedup();
+        Ok(entries)
+    }
+    fn delete_file(&self, path: &str) -> Result<(), String> {
+        let key = self.file_key(path);
+        self.modified.lock().unwrap().remove(&key);
+        (self.files.lock().unwrap().remove(&key))
+            .map(drop)
+            .ok_or_else(|| format!("File not found: {path}"))
+    }
+    fn remove_dir(&self, path: &str) -> Result<(), String> {
+        // Directories only exist as the parents of files, so an empty one cannot exist
+        if is_dir(&self.files.lock().unwrap(), &self.file_key(path)) {
+            Err(format!("Directory not empty: {path}"))
+        } else {
+            Err(format!("Directory not found: {path}"))
+        }
+    }
+    fn file_metadata(&self, path: &str) -> Result<(u64, f64), String> {
+        let key = self.file_key(path);
+        let size = (self.files.lock().unwrap().get(&key))
+            .map(|contents| contents.len() as u64)
+            .ok_or_else(|| format!("File not found: {path}"))?;
+        let modified = (self.modified.lock().unwrap().get(&key).copied()).unwrap_or(0.0);
+        Ok((size, modified))
+    }
+    fn var(&self, name: &str) -> Option<String> {
+        self.vars.get(name).cloned()
+    }
+    fn current_directory(&self) -> Result<String, String> {
+        Ok(self.cwd.lock().unwrap().clone())
+    }
+    fn change_directory(&self, path: &str) -> Result<(), String> {
+        let mut cwd = self.cwd.lock().unwrap();
+        *cwd = resolve_path(&cwd, path);
+        Ok(())
+    }
     fn play_audio(&self, wav_bytes: Vec<u8>) -> Result<(), String> {
         self.stdout
             .lock()
@@ -115,6 +252,9 @@ impl SysBackend for WebBackend {
             .push(OutputItem::Audio(wav_bytes));
         Ok(())
     }
+    fn now(&self) -> f64 {
+        js_sys::Date::now() / 1000.0
+    }
     fn sleep(&self, seconds: f64) -> Result<(), String> {
         let start = instant::now();
         while (instant::now() - start) / 1000.0 < seconds {}
@@ -138,4 +278,78 @@ impl SysBackend for WebBackend {
             None => Err(Err("Invalid thread handle".into())),
         }
     }
+    fn http_request(&self, request: HttpRequest) -> Result<HttpResponse, String> {
+        // The request is synchronous because the interpreter cannot wait for a `fetch` promise.
+        // Browsers do not allow synchronous requests to time out.
+        let failed = |_| {
+            format!(
+                "HTTP request to {} failed. The server may not allow requests from this site.",
+                request.url
+            )
+        };
+        let xhr = XmlHttpRequest::new().map_err(failed)?;
+        xhr.open_with_async(&request.method, &request.url, false)
+            .map_err(failed)?;
+        // Get the body's bytes as the low bytes of the characters of the response text
+        xhr.override_mime_type("text/plain; charset=x-user-defined")
+            .map_err(failed)?;
+        for (name, value) in &request.headers {
+            xhr.set_request_header(name, value).map_err(failed)?;
+        }
+        if request.body.is_empty() {
+            xhr.send()
+        } else {
+            xhr.send_with_opt_u8_array(Some(&request.body))
+        }
+        .map_err(failed)?;
+        let headers = (xhr.get_all_response_headers().map_err(failed)?)
+            .split("\r\n")
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().into(), value.trim().into()))
+            .collect();
+        let body = (xhr.response_text().map_err(failed)?)
+            .unwrap_or_default()
+            .chars()
+            .map(|c| c as u32 as u8)
+            .collect();
+        Ok(HttpResponse {
+            status: xhr.status().map_err(failed)?,
+            headers,
+            body,
+        })
+    }
+    fn regex_find(&self, pattern: &str, subject: &str) -> Result<Vec<(usize, String)>, String> {
+        uiua::regex_find(pattern, subject)
+    }
+    fn regex_captures(&self, pattern: &str, subject: &str) -> Result<Vec<String>, String> {
+        uiua::regex_captures(pattern, subject)
+    }
+}
+
+/// Resolve a path against a directory, giving an absolute path without `.` or `..` parts
+fn resolve_path(dir: &str, path: &str) -> String {
+    let mut parts: Vec<&str> = if path.starts_with('/') {
+        Vec::new()
+    } else {
+        dir.split('/').filter(|part| !part.is_empty()).collect()
+    };
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    format!("/{}", parts.join("/"))
+}
+
+/// Check if a key is a directory, which it is if it is the parent of any file
+fn is_dir(files: &HashMap<String, Vec<u8>>, key: &str) -> bool {
+    key.is_empty()
+        || (files.keys()).any(|file| {
+            file.strip_prefix(key)
+                .is_some_and(|rest| rest.starts_with('/'))
+        })
 }
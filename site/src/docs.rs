@@ -4,11 +4,17 @@ use enum_iterator::all;
 use instant::Duration;
 use leptos::*;
 use leptos_router::*;
-use uiua::primitive::{PrimClass, Primitive};
+use uiua::{
+    primitive::{PrimClass, Primitive},
+    SysOp,
+};
 use wasm_bindgen::JsCast;
-use web_sys::{Event, EventInit, HtmlInputElement, ScrollBehavior, ScrollIntoViewOptions};
+use web_sys::{
+    Event, EventInit, HtmlInputElement, KeyboardEvent, ScrollBehavior, ScrollIntoViewOptions,
+};
 
 use crate::{
+    editor::insert_into_last_editor,
     element,
     other::*,
     primitive::*,
@@ -22,6 +28,8 @@ use crate::{
 pub enum DocsPage {
     Tour,
     Tutorial(TutorialPage),
+    /// The page of a single primitive, whose path is its name
+    Primitive(Primitive),
     Search(String),
     Design,
     Technical,
@@ -52,7 +60,10 @@ impl IntoParam for DocsPage {
                 "changelog" => Some(Self::Changelog),
                 "rtl" => Some(Self::RightToLeft),
                 "constants" => Some(Self::Constants),
-                value => Some(Self::Search(value.into())),
+                value => Some(match Primitive::from_name(value) {
+                    Some(prim) => Self::Primitive(prim),
+                    None => Self::Search(value.into()),
+                }),
             })
             .ok_or_else(|| ParamsError::MissingParam(name.to_string()))
     }
@@ -67,13 +78,23 @@ pub struct DocsParams {
 pub fn Docs() -> impl IntoView {
     move || {
         let Ok(params) = use_params::<DocsParams>().get() else {
-            return view!( <DocsHome/>).into_view();
+            // Searches made on the docs home are kept in the query
+            let search = use_query_map()
+                .with(|query| query.get("search").cloned())
+                .unwrap_or_default();
+            return view!( <DocsHome search=search/>).into_view();
         };
         let page = params.page;
         let page_view = match page {
             DocsPage::Tour => Tour().into_view(),
             DocsPage::Tutorial(tut) => view!( <Tutorial page=tut/>).into_view(),
-            DocsPage::Search(search) => return view!( <DocsHome search=search/>).into_view(),
+            DocsPage::Primitive(prim) => view!( <PrimPage prim=prim/>).into_view(),
+            DocsPage::Search(search) => {
+                let search = urlencoding::decode(&search)
+                    .map(|s| s.into_owned())
+                    .unwrap_or_default();
+                return view!( <DocsHome search=search/>).into_view();
+            }
             DocsPage::Design => Design().into_view(),
             DocsPage::Technical => Technical().into_view(),
             DocsPage::Install => Install().into_view(),
@@ -105,9 +126,6 @@ fn scroll_to_docs_functions(options: &ScrollIntoViewOptions) {
 
 #[component]
 fn DocsHome(#[prop(optional)] search: String) -> impl IntoView {
-    let search = urlencoding::decode(&search)
-        .map(|s| s.into_owned())
-        .unwrap_or_default();
     let (results, set_result) = create_signal(None);
     let (clear_button, set_clear_button) = create_signal(None);
     let (old_allowed, set_old_allowed) = create_signal(Allowed::all());
@@ -138,8 +156,15 @@ fn DocsHome(#[prop(optional)] search: String) -> impl IntoView {
                 move || {
                     let search_input = element::<HtmlInputElement>("function-search").value();
                     if search_input == text {
+                        // The search goes in the query so that searching
+                        // for a primitive's exact name stays on this page
+                        let value = if text.is_empty() {
+                            "/docs".into()
+                        } else {
+                            format!("/docs?search={}", urlencoding::encode(&text))
+                        };
                         BrowserIntegration {}.navigate(&LocationChange {
-                            value: format!("/docs/{}", urlencoding::encode(&text)),
+                            value,
                             scroll: false,
                             replace: false,
                             ..Default::default()
@@ -213,6 +238,14 @@ fn DocsHome(#[prop(optional)] search: String) -> impl IntoView {
         <h2 id="uiuisms">"Uiuisms"</h2>
         <p><A href="/docs/isms">"Uiuisms"</A>" is a curated list of Uiua functions for solving common problems."</p>
 
+        <h2 id="glyph-finder">"Glyph Finder"</h2>
+        <p>"Describe what you are looking for, or type a glyph to find its name. Press Enter to insert the selected glyph into the last editor you used, or to copy it if there is none."</p>
+        <GlyphFinder/>
+
+        <h2 id="index">"Index"</h2>
+        <p>"Every built-in function, by category. Click one to go to its page."</p>
+        <PrimIndex/>
+
         <h2 id="functions" class="doc-functions">"Functions"</h2>
         <div id="function-search-wrapper">
             <div class="input-div">
@@ -233,6 +266,47 @@ fn DocsHome(#[prop(optional)] search: String) -> impl IntoView {
     }
 }
 
+/// The categories of the docs index and the classes of primitives in each
+const INDEX_CATEGORIES: [(&str, &[PrimClass]); 6] = [
+    ("Stack", &[PrimClass::Stack]),
+    (
+        "Math",
+        &[PrimClass::MonadicPervasive, PrimClass::DyadicPervasive],
+    ),
+    ("Array", &[PrimClass::MonadicArray, PrimClass::DyadicArray]),
+    (
+        "Modifiers",
+        &[
+            PrimClass::IteratingModifier,
+            PrimClass::AggregatingModifier,
+            PrimClass::OtherModifier,
+        ],
+    ),
+    ("IO", &[PrimClass::Sys]),
+    (
+        "Other",
+        &[PrimClass::Control, PrimClass::Misc, PrimClass::Constant],
+    ),
+];
+
+#[component]
+fn PrimIndex() -> impl IntoView {
+    INDEX_CATEGORIES
+        .into_iter()
+        .map(|(category, classes)| {
+            let prims = Primitive::all()
+                .filter(|p| classes.contains(&p.class()) && p.name().is_some())
+                .filter(|p| !p.is_deprecated())
+                .map(|p| view!(<Prim prim=p/>" "))
+                .collect::<Vec<_>>();
+            view! {
+                <h3>{ category }</h3>
+                <p>{ prims }</p>
+            }
+        })
+        .collect::<Vec<_>>()
+}
+
 #[derive(Default, Clone, PartialEq, Eq)]
 struct Allowed {
     classes: HashSet<PrimClass>,
@@ -399,30 +473,22 @@ impl Allowed {
             if of_class.is_empty() {
                 continue;
             }
-            let (header, description) = match class {
-                PrimClass::Stack => ("Stack", "Work with the stack"),
-                PrimClass::MonadicPervasive => {
-                    ("Monadic Pervasive", "Operate on every element in an array")
+            let header = class_header(class);
+            let description = match class {
+                PrimClass::Stack => "Work with the stack",
+                PrimClass::MonadicPervasive => "Operate on every element in an array",
+                PrimClass::DyadicPervasive => "Operate on every pair of elements in two arrays",
+                PrimClass::MonadicArray => "Operate on a single array",
+                PrimClass::DyadicArray => "Operate on two arrays",
+                PrimClass::IteratingModifier => {
+                    "Iterate and apply a function to an array or arrays"
                 }
-                PrimClass::DyadicPervasive => (
-                    "Dyadic Pervasive",
-                    "Operate on every pair of elements in two arrays",
-                ),
-                PrimClass::MonadicArray => ("Monadic Array", "Operate on a single array"),
-                PrimClass::DyadicArray => ("Dyadic Array", "Operate on two arrays"),
-                PrimClass::IteratingModifier => (
-                    "Iterating Modifiers",
-                    "Iterate and apply a function to an array or arrays",
-                ),
-                PrimClass::AggregatingModifier => (
-                    "Aggregating Modifiers",
-                    "Apply a function to aggregate an array",
-                ),
-                PrimClass::OtherModifier => ("Other Modifiers", ""),
-                PrimClass::Control => ("Control", "Control the flow of execution"),
-                PrimClass::Misc => ("Miscellaneous", ""),
-                PrimClass::Constant => ("Constants", "Push a constant value onto the stack"),
-                PrimClass::Sys => ("System", "Interact with the system"),
+                PrimClass::AggregatingModifier => "Apply a function to aggregate an array",
+                PrimClass::OtherModifier => "",
+                PrimClass::Control => "Control the flow of execution",
+                PrimClass::Misc => "",
+                PrimClass::Constant => "Push a constant value onto the stack",
+                PrimClass::Sys => "Interact with the system",
             };
             table_cells.push(view! {
                 <td id=id style="vertical-align: top;"><div>
@@ -441,3 +507,158 @@ impl Allowed {
         view!( <table>{ rows }</table>)
     }
 }
+
+/// What the glyph finder searches for a primitive
+struct FinderEntry {
+    prim: Primitive,
+    name: &'static str,
+    ascii: Option<&'static str>,
+    glyph: Option<char>,
+    /// The one-line summary, lowercased
+    summary: &'static str,
+    /// The whole documentation text, lowercased
+    doc: &'static str,
+}
+
+/// The glyph finder's index, generated from the primitives and their docs by the build script
+static FINDER_INDEX: &[FinderEntry] = include!(concat!(env!("OUT_DIR"), "/finder_index.rs"));
+
+/// Check if the characters of a query appear in order in some text
+fn fuzzy_matches(query: &str, text: &str) -> bool {
+    let mut text = text.chars();
+    query.chars().all(|q| text.any(|c| c == q))
+}
+
+/// How well a primitive matches a glyph finder query, or `None` if it does not match
+fn finder_score(entry: &FinderEntry, query: &str) -> Option<u8> {
+    let mut chars = query.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if entry.glyph == Some(c) {
+            return Some(100);
+        }
+    }
+    if entry.ascii == Some(query) {
+        return Some(90);
+    }
+    let words: Vec<&str> = query.split_whitespace().collect();
+    if entry.name == query {
+        return Some(80);
+    } else if entry.name.starts_with(query) {
+        return Some(60);
+    } else if entry.name.contains(query) {
+        return Some(50);
+    } else if words.len() == 1 && fuzzy_matches(query, entry.name) {
+        return Some(30);
+    }
+    // Descriptions are matched by their words, ignoring short ones like "a" and "by"
+    let long_words: Vec<&str> = (words.iter().copied())
+        .filter(|word| word.chars().count() >= 3)
+        .collect();
+    let words = if long_words.is_empty() {
+        words
+    } else {
+        long_words
+    };
+    let in_summary = (words.iter())
+        .filter(|word| entry.summary.contains(**word))
+        .count();
+    let in_doc = (words.iter())
+        .filter(|word| entry.doc.contains(**word))
+        .count();
+    // At least half of the words must be somewhere in the docs
+    if in_doc == 0 || in_doc * 2 < words.len() {
+        return None;
+    }
+    Some(((in_summary * 10 + in_doc * 5) / words.len()).clamp(1, 25) as u8)
+}
+
+/// The best matches for a glyph finder query
+fn find_glyphs(query: &str) -> Vec<Primitive> {
+    const MAX_RESULTS: usize = 10;
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let mut matches: Vec<_> = (FINDER_INDEX.iter())
+        .filter_map(|entry| Some((finder_score(entry, &query)?, entry.prim)))
+        .collect();
+    // The sort is stable, so equally good matches stay in the usual order
+    matches.sort_by_key(|(score, _)| u8::MAX - score);
+    matches.truncate(MAX_RESULTS);
+    matches.into_iter().map(|(_, prim)| prim).collect()
+}
+
+/// A search box for finding glyphs by their names or what they do
+#[component]
+fn GlyphFinder() -> impl IntoView {
+    let (query, set_query) = create_signal(String::new());
+    let (selected, set_selected) = create_signal(0);
+    let (message, set_message) = create_signal(None);
+    let results = create_memo(move |_| find_glyphs(&query.get()));
+    // Insert the glyph into an editor, or copy it if there is none
+    let choose = move |prim: Primitive| {
+        let text = prim.to_string();
+        if insert_into_last_editor(&text) {
+            set_message.set(Some(format!("Inserted {text}")));
+        } else {
+            _ = window().navigator().clipboard().unwrap().write_text(&text);
+            set_message.set(Some(format!("Copied {text}")));
+        }
+    };
+    let on_input = move |event: Event| {
+        set_query.set(event_target_value(&event));
+        set_selected.set(0);
+        set_message.set(None);
+    };
+    let on_keydown = move |event: KeyboardEvent| {
+        let count = results.with_untracked(Vec::len);
+        match event.key().as_str() {
+            "ArrowDown" if count > 0 => set_selected.update(|i| *i = (*i + 1) % count),
+            "ArrowUp" if count > 0 => set_selected.update(|i| *i = (*i + count - 1) % count),
+            "Enter" => {
+                if let Some(prim) =
+                    results.with_untracked(|r| r.get(selected.get_untracked()).copied())
+                {
+                    choose(prim);
+                }
+            }
+            _ => return,
+        }
+        event.prevent_default();
+    };
+    let result_rows = move || {
+        (results.get().into_iter().enumerate())
+            .map(|(i, prim)| {
+                let class = move || {
+                    if selected.get() == i {
+                        "glyph-finder-result selected-glyph-finder-result"
+                    } else {
+                        "glyph-finder-result"
+                    }
+                };
+                let summary = prim.doc().map(|doc| doc.short_text().into_owned());
+                view! {
+                    <div
+                        class=class
+                        on:click=move |_| choose(prim)
+                        on:mouseover=move |_| set_selected.set(i)>
+                        <Prim prim=prim/>
+                        <span class="glyph-finder-summary">{ summary }</span>
+                    </div>
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+    view! {
+        <div class="input-div">
+            "⌕ "
+            <input
+                type="text"
+                placeholder="Find a glyph, like \"group by a mask\"..."
+                on:input=on_input
+                on:keydown=on_keydown/>
+        </div>
+        <div class="glyph-finder-results">{ result_rows }</div>
+        <p class="glyph-finder-message">{ move || message.get() }</p>
+    }
+}
@@ -2,35 +2,41 @@ use std::{
     cell::{Cell, RefCell},
     iter,
     mem::{replace, take},
+    ops::Range,
+    path::Path,
     rc::Rc,
     str::FromStr,
     time::Duration,
 };
 
-use base64::engine::{
-    general_purpose::{STANDARD, URL_SAFE},
-    Engine,
-};
+use base64::engine::{general_purpose::STANDARD, Engine};
 use image::ImageOutputFormat;
-use leptos::{ev::keydown, *};
+use leptos::{ev::keydown, leptos_dom::helpers::TimeoutHandle, *};
 use leptos_router::{use_navigate, NavigateOptions};
 use uiua::{
     format::{format_str, FormatConfig},
     image_to_bytes,
-    lex::is_ident_char,
-    primitive::Primitive,
+    lex::{is_ident_char, Span},
+    primitive::{PrimClass, Primitive},
     run::RunMode,
+    value::Value,
     value_to_gif_bytes, value_to_image, value_to_wav_bytes, DiagnosticKind, SysBackend, Uiua,
+    UiuaResult,
 };
 use wasm_bindgen::{JsCast, JsValue};
 use web_sys::{
-    Event, HtmlBrElement, HtmlDivElement, HtmlInputElement, HtmlSelectElement, HtmlStyleElement,
-    KeyboardEvent, MouseEvent, Node,
+    Blob, BlobPropertyBag, Event, HtmlBrElement, HtmlDivElement, HtmlInputElement,
+    HtmlSelectElement, HtmlStyleElement, KeyboardEvent, MouseEvent, Node, ScrollBehavior,
+    ScrollIntoViewOptions, ScrollLogicalPosition, Url,
 };
 
 use crate::{
     backend::{OutputItem, WebBackend},
-    element, prim_class, Prim,
+    element,
+    pad::{pad_fragment, PadFiles, PAD_URL},
+    prim_class,
+    primitive::{class_header, prim_sig},
+    Prim,
 };
 
 #[derive(Debug, Clone, Copy, Default)]
@@ -41,6 +47,32 @@ pub enum EditorSize {
     Pad,
 }
 
+/// What the link button last copied
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CopiedLink {
+    Nothing,
+    Link,
+    /// The code itself, because a link would be too long
+    Code,
+}
+
+/// How long the code must stay the same before the pad's URL is updated
+const URL_SYNC_DELAY: Duration = Duration::from_millis(500);
+
+/// A challenge whose answer is checked when the editor's code is run
+///
+/// The code is run on the values pushed by each input, and its stack is compared
+/// to the stack of the answer run on the same input.
+#[derive(Debug, Clone)]
+pub struct ChallengeDef {
+    /// The input shown to the user
+    pub example: String,
+    /// Hidden inputs
+    pub tests: Vec<String>,
+    /// The code that gives the expected outputs
+    pub answer: String,
+}
+
 #[derive(Default)]
 pub enum EditorMode {
     #[default]
@@ -50,6 +82,15 @@ pub enum EditorMode {
 
 thread_local! {
     static ID: Cell<u64> = Cell::new(0);
+    /// Inserts text into the editor that was focused last
+    static LAST_EDITOR: RefCell<Option<Box<dyn Fn(&str) -> bool>>> = RefCell::new(None);
+}
+
+/// Insert text at the cursor of the editor that was focused last
+///
+/// Returns `false` if no editor on the page has been focused.
+pub fn insert_into_last_editor(text: &str) -> bool {
+    LAST_EDITOR.with(|last| last.borrow().as_ref().is_some_and(|insert| insert(text)))
 }
 
 /// An editor for Uiua code
@@ -62,8 +103,13 @@ pub fn Editor<'a>(
     #[prop(optional)] mode: EditorMode,
     #[prop(optional)] progress_lines: bool,
     #[prop(optional)] no_run: bool,
+    #[prop(optional)] vars: &'a [(&'a str, &'a str)],
+    #[prop(optional)] files: PadFiles,
+    #[prop(optional)] input: &'a str,
+    #[prop(optional)] challenge: Option<ChallengeDef>,
 ) -> impl IntoView {
-    let no_run = no_run || example.contains("&sl");
+    // Challenges are only checked when the user runs them
+    let no_run = no_run || example.contains("&sl") || challenge.is_some();
     let id = ID.with(|id| {
         let i = id.get();
         id.set(i + 1);
@@ -106,7 +152,7 @@ pub fn Editor<'a>(
     } else {
         examples.iter().map(|e| e.lines().count()).max().unwrap()
     };
-    let code_height_em = code_max_lines as f32 * 1.2;
+    let code_height_em = code_max_lines.max(1) as f32 * 1.2;
 
     let code_id = move || format!("code{id}");
     let glyph_doc_id = move || format!("glyphdoc{id}");
@@ -126,14 +172,30 @@ pub fn Editor<'a>(
 
     let code_text = move || code_text(&code_id());
     let get_code_cursor = move || get_code_cursor_impl(&code_id());
-    let (copied_link, set_copied_link) = create_signal(false);
+    let (copied_link, set_copied_link) = create_signal(CopiedLink::Nothing);
     let (settings_open, set_settings_open) = create_signal(false);
+    // The pad's files, the active one of which is in the editor
+    let (files, set_files) = create_signal(files);
+    // Show or hide the glyph buttons
+    let (glyphs_pinned, set_glyphs_pinned) = create_signal(get_glyphs_pinned());
+    let (show_glyphs, set_show_glyphs) = create_signal(match size {
+        EditorSize::Small => glyphs_pinned.get_untracked(),
+        EditorSize::Medium | EditorSize::Pad => true,
+    });
+    // The glyph filter, and where the cursor was when it was opened
+    let glyph_filter_id = move || format!("glyphfilter{id}");
+    let (glyph_filter, set_glyph_filter) = create_signal(String::new());
+    let (glyph_filter_cursor, set_glyph_filter_cursor) = create_signal(None);
 
     /// Handles setting the code in the editor, setting the cursor, and managing the history
     struct State {
         code_id: String,
         set_line_count: WriteSignal<usize>,
-        set_copied_link: WriteSignal<bool>,
+        set_copied_link: WriteSignal<CopiedLink>,
+        /// Whether to put the code in the URL, which is only done in the pad
+        sync_url: bool,
+        url_timeout: Cell<Option<TimeoutHandle>>,
+        files: ReadSignal<PadFiles>,
         past: RefCell<Vec<Record>>,
         future: RefCell<Vec<Record>>,
         curr: RefCell<Record>,
@@ -184,7 +246,7 @@ pub fn Editor<'a>(
                 self.past.borrow_mut().push(prev);
                 self.future.borrow_mut().clear();
             }
-            set_code_html(&self.code_id, code);
+            set_code_html(&self.code_id, code, &[]);
             if matches!(cursor, Cursor::Ignore) {
                 if let Some(before) = maybe_before {
                     self.set_cursor(before);
@@ -202,11 +264,59 @@ pub fn Editor<'a>(
             set_code_cursor(&self.code_id, to.0, to.1);
         }
         fn set_code_html(&self, code: &str) {
-            set_code_html(&self.code_id, code);
+            set_code_html(&self.code_id, code, &[]);
+        }
+        /// Underline the ranges of errors in the code
+        ///
+        /// The underlines go away the next time the code changes.
+        fn show_errors(&self, errors: &[Range<usize>]) {
+            let cursor = get_code_cursor_impl(&self.code_id);
+            set_code_html(&self.code_id, &self.curr.borrow().code, errors);
+            if let Some(cursor) = cursor {
+                self.set_cursor(cursor);
+            }
+        }
+        /// Select the range of an error and scroll to it
+        fn jump_to(&self, range: &Range<usize>) {
+            self.set_cursor((range.start as u32, range.end as u32));
+            element::<HtmlDivElement>(&self.code_id)
+                .scroll_into_view_with_scroll_into_view_options(
+                    ScrollIntoViewOptions::new()
+                        .behavior(ScrollBehavior::Smooth)
+                        .block(ScrollLogicalPosition::Nearest),
+                );
         }
         fn set_changed(&self) {
-            self.set_copied_link.set(false);
+            self.set_copied_link.set(CopiedLink::Nothing);
             self.set_line_count();
+            self.sync_url();
+        }
+        /// Put the code in the URL once it has stopped changing for a moment
+        fn sync_url(&self) {
+            if !self.sync_url {
+                return;
+            }
+            if let Some(handle) = self.url_timeout.take() {
+                handle.clear();
+            }
+            let mut files = self.files.get_untracked();
+            files.set_active_code(self.curr.borrow().code.clone());
+            let handle = set_timeout_with_handle(
+                move || {
+                    // Code that is too long for a link is left out of the URL
+                    let url = match pad_fragment(&files) {
+                        Some(fragment) => format!("/pad#{fragment}"),
+                        None => "/pad".into(),
+                    };
+                    _ = (window().history().unwrap()).replace_state_with_url(
+                        &JsValue::NULL,
+                        "",
+                        Some(&url),
+                    );
+                },
+                URL_SYNC_DELAY,
+            );
+            self.url_timeout.set(handle.ok());
         }
         fn set_line_count(&self) {
             self.set_line_count
@@ -242,6 +352,9 @@ pub fn Editor<'a>(
         code_id: code_id(),
         set_line_count,
         set_copied_link,
+        sync_url: matches!(size, EditorSize::Pad),
+        url_timeout: Default::default(),
+        files,
         past: Default::default(),
         future: Default::default(),
         curr: {
@@ -257,6 +370,19 @@ pub fn Editor<'a>(
     });
     let (state, _) = create_signal(state);
     let state = move || state.get();
+    let vars: Vec<(String, String)> = (vars.iter())
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    let (vars, _) = create_signal(vars);
+    // The input box for stdin, which starts open in the pad or if there is initial input
+    let (stdin, set_stdin) = create_signal(input.to_string());
+    let (input_open, set_input_open) =
+        create_signal(matches!(size, EditorSize::Pad) || !input.is_empty());
+
+    // The challenge, whose solution can be shown once it has been attempted
+    let (challenge, _) = create_signal(challenge);
+    let (attempted, set_attempted) = create_signal(false);
+    let (solution_shown, set_solution_shown) = create_signal(false);
 
     // Run the code
     let run = move |format: bool, set_cursor: bool| {
@@ -300,27 +426,34 @@ pub fn Editor<'a>(
             code_text
         };
 
-        // Update URL
-        {
-            let encoded = URL_SAFE.encode(&input);
-            if let EditorSize::Pad = size {
-                window()
-                    .history()
-                    .unwrap()
-                    .replace_state_with_url(
-                        &JsValue::NULL,
-                        "",
-                        Some(&format!("/pad?src={encoded}")),
-                    )
-                    .unwrap();
-            }
-        }
-
         // Run code
+        // In the pad, the file being edited is run, and it can import the others
+        let files = matches!(size, EditorSize::Pad).then(|| {
+            let mut files = files.get_untracked();
+            files.set_active_code(input.clone());
+            files
+        });
         set_output.set(view!(<div class="running-text">"Running"</div>).into_view());
         set_timeout(
             move || {
-                let output = run_code(&input);
+                if let Some(challenge) = challenge.get_untracked() {
+                    let cases = check_challenge(&input, &challenge);
+                    set_output.set(render_challenge_cases(cases));
+                    set_attempted.set(true);
+                    return;
+                }
+                let output = run_code(&input, files.as_ref(), &vars.get(), stdin.get());
+                // Underline errors, unless the code was changed while it ran
+                // The code in the editor is already formatted, so the error spans line up with it
+                let error_ranges: Vec<_> = (output.iter())
+                    .flat_map(|item| match item {
+                        OutputItem::Error(_, ranges) => ranges.clone(),
+                        _ => Vec::new(),
+                    })
+                    .collect();
+                if !error_ranges.is_empty() && state().curr.borrow().code == input {
+                    state().show_errors(&error_ranges);
+                }
                 let mut allow_autoplay = !matches!(size, EditorSize::Small);
                 let render_output_item = |item| match item {
                     OutputItem::String(s) => {
@@ -332,15 +465,16 @@ pub fn Editor<'a>(
                     }
                     OutputItem::Image(bytes) => {
                         let encoded = STANDARD.encode(bytes);
-                        view!(<div><img class="output-image" src={format!("data:image/png;base64,{encoded}")} /></div>).into_view()
+                        let src = format!("data:image/png;base64,{encoded}");
+                        view!(<OutputImage src=src/>).into_view()
                     }
                     OutputItem::Gif(bytes) => {
                         let encoded = STANDARD.encode(bytes);
-                        view!(<div><img class="output-image" src={format!("data:image/gif;base64,{encoded}")} /></div>).into_view()
+                        let src = format!("data:image/gif;base64,{encoded}");
+                        view!(<OutputImage src=src/>).into_view()
                     }
                     OutputItem::Audio(bytes) => {
-                        let encoded = STANDARD.encode(bytes);
-                        let src = format!("data:audio/wav;base64,{}", encoded);
+                        let src = wav_url(&bytes);
                         if allow_autoplay {
                             allow_autoplay = false;
                             view!(<div><audio class="output-audio" controls autoplay src=src/></div>).into_view()
@@ -349,12 +483,23 @@ pub fn Editor<'a>(
                                 .into_view()
                         }
                     }
-                    OutputItem::Error(error) => {
-                        view!(<div class="output-item output-error">{error}</div>).into_view()
-                    }
+                    OutputItem::Error(error, ranges) => match ranges.into_iter().next() {
+                        Some(range) => view! {
+                            <div
+                                class="output-item output-error output-error-jump"
+                                title="Click to jump to the error"
+                                on:click=move |_| state().jump_to(&range)>
+                                {error}
+                            </div>
+                        }
+                        .into_view(),
+                        None => {
+                            view!(<div class="output-item output-error">{error}</div>).into_view()
+                        }
+                    },
                     OutputItem::Diagnostic(message, kind) => {
                         let class = match kind {
-                            DiagnosticKind::Warning => "output-warning",
+                            DiagnosticKind::Warning | DiagnosticKind::Unused => "output-warning",
                             DiagnosticKind::Advice => "output-advice",
                             DiagnosticKind::Style => "output-style",
                         };
@@ -573,6 +718,12 @@ pub fn Editor<'a>(
                 _ = window().navigator().clipboard().unwrap().write_text(&text);
                 remove_code(start, end);
             }
+            // Filter the glyphs
+            "g" if os_ctrl(event) => {
+                set_glyph_filter_cursor.set(get_code_cursor());
+                set_show_glyphs.set(true);
+                _ = element::<HtmlInputElement>(&glyph_filter_id()).focus();
+            }
             // Undo
             "z" if os_ctrl(event) => state().undo(),
             // Redo
@@ -692,6 +843,35 @@ pub fn Editor<'a>(
         }
     });
 
+    // Insert text at a cursor that was saved earlier, or at the end of the code
+    let insert_code_at = move |cursor: Option<(u32, u32)>, text: &str| {
+        let code = state().curr.borrow().code.clone();
+        let len = code.chars().count() as u32;
+        let (start, end) = cursor.unwrap_or((len, len));
+        let (start, end) = (start.min(end).min(len), start.max(end).min(len));
+        let new: String = (code.chars().take(start as usize))
+            .chain(text.chars())
+            .chain(code.chars().skip(end as usize))
+            .collect();
+        let after = start + text.chars().count() as u32;
+        state().set_code(&new, Cursor::Set(after, after));
+    };
+
+    // Remember where the cursor was when the editor loses focus, so that text can be inserted there
+    let code_focusout = move |_| {
+        let cursor = get_code_cursor();
+        set_glyph_filter_cursor.set(cursor);
+        let insert = move |text: &str| {
+            // The editor may be gone if the page has changed
+            if document().get_element_by_id(&code_id()).is_none() {
+                return false;
+            }
+            insert_code_at(cursor, text);
+            true
+        };
+        LAST_EDITOR.with(|last| *last.borrow_mut() = Some(Box::new(insert)));
+    };
+
     // Handle paste evens
     let code_paste = move |event: Event| {
         let event = event.dyn_into::<web_sys::ClipboardEvent>().unwrap();
@@ -732,62 +912,122 @@ pub fn Editor<'a>(
         _ = glyph_doc_element().style().set_property("display", "none");
     };
 
+    // Recently used glyphs, which are shared by all editors
+    let (recent_glyphs, set_recent_glyphs) = create_signal(get_recent_glyphs());
+    let use_glyph = move |p: Primitive| {
+        push_recent_glyph(p);
+        set_recent_glyphs.set(get_recent_glyphs());
+    };
+
     // Glyph buttons
     // These are the buttons that appear above the editor and allow the user to insert glyphs
-    let mut glyph_buttons: Vec<_> = Primitive::non_deprecated()
-        .filter_map(|p| {
-            let text = p
-                .glyph()
-                .map(Into::into)
-                .or_else(|| p.ascii().map(|s| s.to_string()))?;
-            let mut title = p.name().unwrap_or_default().to_string();
-            if let Some(ascii) = p.ascii() {
-                title = format!("({}) {}", ascii, title);
-            }
-            // Navigate to the docs page on ctrl/shift+click
-            let onclick = move |event: MouseEvent| {
-                if !on_mac && event.ctrl_key() || on_mac && event.meta_key() {
-                    // Open the docs page
-                    window()
-                        .open_with_url_and_target(
-                            &format!("/docs/{}", p.name().unwrap_or_default()),
-                            "_blank",
-                        )
-                        .unwrap();
-                } else if event.shift_key() {
-                    // Redirect to the docs page
-                    use_navigate()(
+    let glyph_button = move |p: Primitive| {
+        let text = p
+            .glyph()
+            .map(Into::into)
+            .or_else(|| p.ascii().map(|s| s.to_string()))?;
+        let mut title = p.name().unwrap_or_default().to_string();
+        if let Some(ascii) = p.ascii() {
+            title = format!("({}) {}", ascii, title);
+        }
+        // Navigate to the docs page on ctrl/shift+click
+        let onclick = move |event: MouseEvent| {
+            if !on_mac && event.ctrl_key() || on_mac && event.meta_key() {
+                // Open the docs page
+                window()
+                    .open_with_url_and_target(
                         &format!("/docs/{}", p.name().unwrap_or_default()),
-                        NavigateOptions::default(),
-                    );
+                        "_blank",
+                    )
+                    .unwrap();
+            } else if event.shift_key() {
+                // Redirect to the docs page
+                use_navigate()(
+                    &format!("/docs/{}", p.name().unwrap_or_default()),
+                    NavigateOptions::default(),
+                );
+            } else {
+                replace_code(&p.to_string());
+                use_glyph(p);
+            }
+        };
+        // Show the glyph doc on mouseover
+        let onmouseover = move |_| {
+            if let Some(doc) = p.doc() {
+                set_glyph_doc.set(
+                    view! {
+                        <Prim prim=p/>
+                        " "
+                        <span class="glyph-doc-sig">{ prim_sig(p) }</span>
+                        <br/>
+                        { doc.short_text().into_owned() }
+                    }
+                    .into_view(),
+                );
+                _ = glyph_doc_element().style().remove_property("display");
+            }
+        };
+        // Hide the button if it does not match the filter
+        let style = move || {
+            if glyph_matches(p, &glyph_filter.get()) {
+                ""
+            } else {
+                "display:none"
+            }
+        };
+        Some(
+            view! {
+                <button
+                    class="glyph-button glyph-title"
+                    style=style
+                    data-title=title
+                    on:click=onclick
+                    on:mouseover=onmouseover
+                    on:mouseleave=onmouseleave>
+                    <div class={prim_class(p)}>{ text }</div>
+                </button>
+            }
+            .into_view(),
+        )
+    };
+
+    // The glyph buttons grouped by class, with the most recently used ones first
+    let recent_glyph_group = move || {
+        let buttons: Vec<_> = (recent_glyphs.get().into_iter())
+            .filter_map(glyph_button)
+            .collect();
+        (!buttons.is_empty()).then(|| {
+            view! {
+                <div class="glyph-group">
+                    <div class="glyph-group-name">"Recent"</div>
+                    <div class="glyph-group-buttons">{ buttons }</div>
+                </div>
+            }
+        })
+    };
+    let mut glyph_groups: Vec<_> = PrimClass::all()
+        .filter_map(|class| {
+            let prims: Vec<_> = Primitive::non_deprecated()
+                .filter(|p| p.class() == class && has_glyph_button(*p))
+                .collect();
+            let buttons: Vec<_> = prims.iter().filter_map(|&p| glyph_button(p)).collect();
+            if buttons.is_empty() {
+                return None;
+            }
+            let style = move || {
+                let filter = glyph_filter.get();
+                if prims.iter().any(|&p| glyph_matches(p, &filter)) {
+                    ""
                 } else {
-                    replace_code(&p.to_string());
-                }
-            };
-            // Show the glyph doc on mouseover
-            let onmouseover = move |_| {
-                if let Some(doc) = p.doc() {
-                    set_glyph_doc.set(
-                        view! {
-                            <Prim prim=p/>
-                            <br/>
-                            { doc.short_text().into_owned() }
-                        }
-                        .into_view(),
-                    );
-                    _ = glyph_doc_element().style().remove_property("display");
+                    "display:none"
                 }
             };
             Some(
                 view! {
-                    <button
-                        class="glyph-button glyph-title"
-                        data-title=title
-                        on:click=onclick
-                        on:mouseover=onmouseover
-                        on:mouseleave=onmouseleave>
-                        <div class={prim_class(p)}>{ text }</div>
-                    </button>
+                    <div class="glyph-group" style=style>
+                        <div class="glyph-group-name">{ class_header(class) }</div>
+                        <div class="glyph-group-buttons">{ buttons }</div>
+                    </div>
                 }
                 .into_view(),
             )
@@ -795,6 +1035,7 @@ pub fn Editor<'a>(
         .collect();
 
     // Additional code buttons
+    let mut syntax_buttons = Vec::new();
     for (glyph, title, class, surround, doc) in [
         ("_", "strand", "strand-span", None, "arrays#creating-arrays"),
         (
@@ -874,7 +1115,7 @@ pub fn Editor<'a>(
                 _ = glyph_doc_element().style().remove_property("display");
             }
         };
-        glyph_buttons.push(
+        syntax_buttons.push(
             view! {
                 <button
                     class=class
@@ -902,12 +1143,6 @@ pub fn Editor<'a>(
         ""
     };
 
-    // Show or hide the glyph buttons
-    let (show_glyphs, set_show_glyphs) = create_signal(match size {
-        EditorSize::Small => false,
-        EditorSize::Medium | EditorSize::Pad => true,
-    });
-
     // Glyphs toggle button
     let show_glyphs_text = move || if show_glyphs.get() { "↥" } else { "↧" };
     let show_glyphs_title = move || {
@@ -928,6 +1163,87 @@ pub fn Editor<'a>(
         }
     };
 
+    // Filter the glyph buttons by name
+    let glyph_filter_input = move |event: Event| {
+        let input: HtmlInputElement = event.target().unwrap().dyn_into().unwrap();
+        set_glyph_filter.set(input.value());
+    };
+    let glyph_filter_keydown = move |event: KeyboardEvent| {
+        let input: HtmlInputElement = event.target().unwrap().dyn_into().unwrap();
+        match event.key().as_str() {
+            // Insert the best match where the cursor was
+            "Enter" => {
+                event.prevent_default();
+                let filter = glyph_filter.get_untracked();
+                let lower = filter.to_lowercase();
+                let matches: Vec<_> = Primitive::non_deprecated()
+                    .filter(|&p| has_glyph_button(p) && glyph_matches(p, &filter))
+                    .collect();
+                let best = (matches.iter())
+                    .find(|p| p.name().is_some_and(|name| name == lower))
+                    .or_else(|| {
+                        (matches.iter())
+                            .find(|p| p.name().is_some_and(|name| name.starts_with(&lower)))
+                    })
+                    .or(matches.first());
+                if let Some(&p) = best {
+                    insert_code_at(glyph_filter_cursor.get_untracked(), &p.to_string());
+                    use_glyph(p);
+                    input.set_value("");
+                    set_glyph_filter.set(String::new());
+                }
+            }
+            // Go back to the code
+            "Escape" => {
+                input.set_value("");
+                set_glyph_filter.set(String::new());
+                _ = element::<HtmlDivElement>(&code_id()).focus();
+            }
+            _ => {}
+        }
+    };
+    // Pin the glyph buttons so that they are shown in every editor
+    let toggle_glyphs_pinned = move |_| {
+        let pinned = !glyphs_pinned.get();
+        set_glyphs_pinned.set(pinned);
+        set_local_var("glyphs-pinned", pinned);
+        if pinned {
+            set_show_glyphs.set(true);
+        }
+    };
+    let pin_button_class = move || {
+        if glyphs_pinned.get() {
+            "glyph-pin-button pinned"
+        } else {
+            "glyph-pin-button"
+        }
+    };
+    let pin_button_title = move || {
+        if glyphs_pinned.get() {
+            "Unpin the glyphs"
+        } else {
+            "Show the glyphs in every editor"
+        }
+    };
+    // Hide the additional syntax buttons while filtering
+    let syntax_group_style = move || {
+        if glyph_filter.get().is_empty() {
+            ""
+        } else {
+            "display:none"
+        }
+    };
+    glyph_groups.insert(
+        0,
+        view! {
+            <div class="glyph-group" style=syntax_group_style>
+                <div class="glyph-group-name">"Syntax"</div>
+                <div class="glyph-group-buttons">{ syntax_buttons }</div>
+            </div>
+        }
+        .into_view(),
+    );
+
     // Show the example number if there are multiple examples
     let examples_len = examples.len();
     let example_text =
@@ -969,23 +1285,156 @@ pub fn Editor<'a>(
 
     // Copy a link to the code
     let copy_link = move |_| {
-        let encoded = URL_SAFE.encode(code_text());
-        let url = format!("https://uiua.org/pad?src={encoded}");
-        _ = window().navigator().clipboard().unwrap().write_text(&url);
+        let code = code_text();
+        let clipboard = window().navigator().clipboard().unwrap();
+        // Only the pad has other files
+        let link_files = if let EditorSize::Pad = size {
+            let mut files = files.get_untracked();
+            files.set_active_code(code.clone());
+            files
+        } else {
+            PadFiles::single(code.clone())
+        };
+        // Links that are too long may be cut off, so the code is copied instead
+        let Some(fragment) = pad_fragment(&link_files) else {
+            _ = clipboard.write_text(&code);
+            set_copied_link.set(CopiedLink::Code);
+            return;
+        };
+        _ = clipboard.write_text(&format!("{PAD_URL}#{fragment}"));
         if let EditorSize::Pad = size {
             window()
                 .history()
                 .unwrap()
-                .push_state_with_url(&JsValue::NULL, "", Some(&format!("/pad?src={encoded}")))
+                .push_state_with_url(&JsValue::NULL, "", Some(&format!("/pad#{fragment}")))
                 .unwrap();
         }
-        set_copied_link.set(true);
+        set_copied_link.set(CopiedLink::Link);
+    };
+    let copy_link_title = move || match copied_link.get() {
+        CopiedLink::Nothing => "Copy link",
+        CopiedLink::Link => "Copied!",
+        CopiedLink::Code => "The code is too long for a link, so the code itself was copied",
+    };
+
+    // Switch to another of the pad's files
+    let open_file = move |index: usize| {
+        if files.with_untracked(|files| files.active == index) {
+            return;
+        }
+        let code = state().curr.borrow().code.clone();
+        set_files.update(|files| {
+            files.set_active_code(code);
+            files.active = index;
+        });
+        let code = files.with_untracked(|files| files.active_code().to_string());
+        state().set_code(&code, Cursor::Ignore);
+        state().clear_history();
+        state().sync_url();
+    };
+    // Ask for a file name, or return `None` if it is cancelled or invalid
+    let prompt_file_name = move |message: &str, default: &str, index: Option<usize>| {
+        let name = (window().prompt_with_message_and_default(message, default))
+            .ok()
+            .flatten()?;
+        match files.with_untracked(|files| files.check_name(&name, index)) {
+            Ok(name) => Some(name),
+            Err(e) => {
+                _ = window().alert_with_message(&e);
+                None
+            }
+        }
+    };
+    let new_file = move |_| {
+        let default = files.with_untracked(PadFiles::unused_name);
+        if let Some(name) = prompt_file_name("New file name", &default, None) {
+            set_files.update(|files| files.files.push((name, String::new())));
+            open_file(files.with_untracked(|files| files.files.len() - 1));
+        }
+    };
+    let rename_file = move |index: usize| {
+        let old = files.with_untracked(|files| files.files[index].0.clone());
+        if let Some(name) = prompt_file_name(&format!("Rename {old} to"), &old, Some(index)) {
+            set_files.update(|files| files.files[index].0 = name);
+            state().sync_url();
+        }
+    };
+    let delete_file = move |index: usize| {
+        let name = files.with_untracked(|files| files.files[index].0.clone());
+        let confirmed =
+            (window().confirm_with_message(&format!("Delete {name}?"))).unwrap_or(false);
+        if !confirmed {
+            return;
+        }
+        let code = state().curr.borrow().code.clone();
+        let was_active = files.with_untracked(|files| files.active == index);
+        set_files.update(|files| {
+            files.set_active_code(code);
+            files.remove(index);
+        });
+        if was_active {
+            let code = files.with_untracked(|files| files.active_code().to_string());
+            state().set_code(&code, Cursor::Ignore);
+            state().clear_history();
+        }
+        state().sync_url();
+    };
+
+    // File tabs, which are only shown in the pad
+    let file_tabs = move || {
+        let files = files.get();
+        let deletable = files.files.len() > 1;
+        let tabs = (files.files.iter().enumerate())
+            .map(|(i, (name, _))| {
+                let class = if i == files.active {
+                    "file-tab active-file-tab"
+                } else {
+                    "file-tab"
+                };
+                let delete = move |event: MouseEvent| {
+                    event.stop_propagation();
+                    delete_file(i);
+                };
+                view! {
+                    <div
+                        class=class
+                        title="Double-click to rename"
+                        on:click=move |_| open_file(i)
+                        on:dblclick=move |_| rename_file(i)>
+                        {name.clone()}
+                        {deletable.then(|| view! {
+                            <button class="file-tab-delete" title="Delete file" on:click=delete>"×"</button>
+                        })}
+                    </div>
+                }
+            })
+            .collect::<Vec<_>>();
+        view! {
+            {tabs}
+            <button class="file-tab-new" title="New file" on:click=new_file>"+"</button>
+        }
+    };
+
+    // Input box toggle
+    let input_toggle_text = move || {
+        if input_open.get() {
+            "▾ Input"
+        } else {
+            "▸ Input"
+        }
     };
-    let copy_link_title = move || {
-        if copied_link.get() {
-            "Copied!"
+    let input_toggle_title = move || {
+        if input_open.get() {
+            "Hide input"
         } else {
-            "Copy a link to this code"
+            "Show input"
+        }
+    };
+    let input_style = move || {
+        if input_open.get() {
+            ""
+        } else {
+            "display:none"
         }
     };
 
@@ -1028,12 +1477,58 @@ pub fn Editor<'a>(
     set_font_name(&get_font_name());
     set_font_size(&get_font_size());
 
+    // Reveal the challenge's solution
+    let challenge_solution = move || {
+        let answer = challenge.get()?.answer;
+        attempted.get().then(|| {
+            if solution_shown.get() {
+                view! {
+                    <div class="challenge-solution">
+                        "Solution: "<code class="code-font">{ answer }</code>
+                    </div>
+                }
+                .into_view()
+            } else {
+                view! {
+                    <div class="challenge-solution">
+                        <button
+                            class="code-button"
+                            on:click=move |_| set_solution_shown.set(true)>
+                            "Show solution"
+                        </button>
+                    </div>
+                }
+                .into_view()
+            }
+        })
+    };
+
     // Render
     view! {
         <div id="editor-wrapper">
             <div id="editor">
                 <div style=glyph_buttons_style>
-                    <div class="glyph-buttons">{glyph_buttons}</div>
+                    <div class="glyph-palette-header">
+                        <input
+                            type="text"
+                            id=glyph_filter_id
+                            class="glyph-filter"
+                            placeholder="Filter glyphs by name (Ctrl+G / ⌘G)"
+                            on:input=glyph_filter_input
+                            on:keydown=glyph_filter_keydown/>
+                        <button
+                            class=pin_button_class
+                            data-title=pin_button_title
+                            on:click=toggle_glyphs_pinned>
+                            "📌"
+                        </button>
+                    </div>
+                    <div
+                        class="glyph-buttons"
+                        on:mouseenter=move |_| set_recent_glyphs.set(get_recent_glyphs())>
+                        {recent_glyph_group}
+                        {glyph_groups}
+                    </div>
                 </div>
                 <div id="settings" style=settings_style>
                     <div>
@@ -1069,6 +1564,11 @@ pub fn Editor<'a>(
                     </div>
                 </div>
                 <div class=editor_class>
+                    {
+                        matches!(size, EditorSize::Pad).then(|| view! {
+                            <div class="file-tabs">{file_tabs}</div>
+                        })
+                    }
                     <div id="code-area">
                         <div id={glyph_doc_id} class="glyph-doc" style="display: none">
                             { move || glyph_doc.get() }
@@ -1107,7 +1607,8 @@ pub fn Editor<'a>(
                                 class="code-entry"
                                 style={format!("height: {code_height_em}em;")}
                                 on:input=code_input
-                                on:paste=code_paste>
+                                on:paste=code_paste
+                                on:focusout=code_focusout>
                                 "Loading..."
                             </div>
                         </div>
@@ -1130,6 +1631,23 @@ pub fn Editor<'a>(
                                 on:click=next_example>{ ">" } </button>
                         </div>
                     </div>
+                    <div class="stdin">
+                        <button
+                            class="stdin-toggle"
+                            title=input_toggle_title
+                            on:click=move |_| set_input_open.update(|open| *open = !*open)>
+                            {input_toggle_text}
+                        </button>
+                        <textarea
+                            class="stdin-input"
+                            style=input_style
+                            placeholder="Input for &sc and &sca"
+                            spellcheck="false"
+                            on:input=move |event| set_stdin.set(event_target_value(&event))>
+                            {stdin.get_untracked()}
+                        </textarea>
+                    </div>
+                    { challenge_solution }
                 </div>
             </div>
             <div id="editor-help">
@@ -1188,6 +1706,48 @@ fn set_execution_limit(limit: f64) {
     set_local_var("execution-limit", limit);
 }
 
+/// Whether a primitive matches the glyph filter
+fn glyph_matches(prim: Primitive, filter: &str) -> bool {
+    let filter = filter.trim().to_lowercase();
+    filter.is_empty()
+        || prim.name().is_some_and(|name| name.contains(&filter))
+        || prim
+            .ascii()
+            .is_some_and(|ascii| ascii.to_string() == filter)
+        || prim.glyph().is_some_and(|glyph| filter.contains(glyph))
+}
+
+/// Whether a primitive has a button in the glyph palette
+fn has_glyph_button(prim: Primitive) -> bool {
+    prim.glyph().is_some() || prim.ascii().is_some()
+}
+
+/// The number of recently used glyphs to show
+const RECENT_GLYPH_COUNT: usize = 12;
+
+/// Get the recently used glyphs, most recent first
+///
+/// They are stored as comma-separated names, since some names contain spaces.
+fn get_recent_glyphs() -> Vec<Primitive> {
+    get_local_var("recent-glyphs", String::new)
+        .split(',')
+        .filter_map(Primitive::from_name)
+        .collect()
+}
+
+fn push_recent_glyph(prim: Primitive) {
+    let mut recent = get_recent_glyphs();
+    recent.retain(|&p| p != prim);
+    recent.insert(0, prim);
+    recent.truncate(RECENT_GLYPH_COUNT);
+    let names: Vec<_> = recent.iter().filter_map(|p| p.name()).collect();
+    set_local_var("recent-glyphs", names.join(","));
+}
+
+fn get_glyphs_pinned() -> bool {
+    get_local_var("glyphs-pinned", || false)
+}
+
 fn get_font_name() -> String {
     get_local_var("font-name", || "DejaVuSansMono".into())
 }
@@ -1373,7 +1933,8 @@ fn set_code_cursor(id: &str, start: u32, end: u32) {
     }
 }
 
-fn set_code_html(id: &str, code: &str) {
+/// Set the highlighted code in the editor, underlining the character ranges of any errors
+fn set_code_html(id: &str, code: &str, errors: &[Range<usize>]) {
     use uiua::lsp::*;
 
     // log!("set_code_html({:?})", code);
@@ -1440,6 +2001,15 @@ fn set_code_html(id: &str, code: &str) {
             SpanKind::Strand => "strand-span",
             _ => "",
         };
+        // Empty error ranges, like at the end of the input, still underline a character
+        let in_error = errors.iter().any(|error| {
+            error.start < span.end.char_pos && span.start.char_pos < error.end.max(error.start + 1)
+        });
+        let color_class = if in_error {
+            format!("{color_class} error-span")
+        } else {
+            color_class.into()
+        };
 
         if !text.is_empty() && text.chars().all(|c| c == '\n') {
             html.push_str("</div>");
@@ -1518,15 +2088,215 @@ fn set_code_html(id: &str, code: &str) {
     elem.set_inner_html(&html);
 }
 
+/// Whether a value on the stack should be shown as an image
+///
+/// Images are rank 2 or 3 arrays of numbers from 0 to 1.
+/// The last axis of a rank 3 image is its grayscale, RGB, or RGBA color channels.
+fn looks_like_image(value: &Value) -> bool {
+    let size_fits = match *value.shape() {
+        [h, w] => h > 25 && w > 25,
+        [h, w, c] => h > 25 && w > 25 && matches!(c, 1 | 3 | 4),
+        _ => false,
+    };
+    size_fits
+        && match value {
+            Value::Num(nums) => (nums.row_slices().flatten()).all(|n| (0.0..=1.0).contains(n)),
+            Value::Byte(bytes) => (bytes.row_slices().flatten()).all(|&b| b <= 1),
+            _ => false,
+        }
+}
+
+/// Make an object URL for WAV audio, falling back to a data URL
+fn wav_url(bytes: &[u8]) -> String {
+    let blob_url = || {
+        let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+        let mut options = BlobPropertyBag::new();
+        options.type_("audio/wav");
+        let blob = Blob::new_with_u8_array_sequence_and_options(&parts, &options).ok()?;
+        Url::create_object_url_with_blob(&blob).ok()
+    };
+    blob_url().unwrap_or_else(|| format!("data:audio/wav;base64,{}", STANDARD.encode(bytes)))
+}
+
+/// An image output, which is kept small until it is clicked
+#[component]
+fn OutputImage(src: String) -> impl IntoView {
+    let (zoomed, set_zoomed) = create_signal(false);
+    let title = move || {
+        if zoomed.get() {
+            "Click to shrink"
+        } else {
+            "Click to zoom"
+        }
+    };
+    view! {
+        <div>
+            <img
+                class="output-image"
+                class:zoomed=zoomed
+                title=title
+                src=src
+                on:click=move |_| set_zoomed.update(|zoomed| *zoomed = !*zoomed)/>
+        </div>
+    }
+}
+
+/// The result of running a challenge's code on one input
+struct ChallengeCase {
+    input: String,
+    passed: bool,
+    expected: Vec<String>,
+    actual: Vec<String>,
+}
+
+/// Run code on the example and hidden inputs of a challenge
+fn check_challenge(code: &str, challenge: &ChallengeDef) -> Vec<ChallengeCase> {
+    iter::once(&challenge.example)
+        .chain(&challenge.tests)
+        .map(|input| {
+            let expected = run_on_input(input, &challenge.answer);
+            let actual = run_on_input(input, code);
+            let passed = match (&expected, &actual) {
+                (Ok(expected), Ok(actual)) => {
+                    expected.len() == actual.len()
+                        && (expected.iter().zip(actual)).all(|(e, a)| values_match(e, a))
+                }
+                _ => false,
+            };
+            let show = |res: UiuaResult<Vec<Value>>| match res {
+                Ok(stack) => stack.iter().flat_map(|v| lines_of(v.show())).collect(),
+                Err(e) => lines_of(e.show(false)),
+            };
+            ChallengeCase {
+                input: input.clone(),
+                passed,
+                expected: show(expected),
+                actual: show(actual),
+            }
+        })
+        .collect()
+}
+
+fn lines_of(s: String) -> Vec<String> {
+    s.lines().map(Into::into).collect()
+}
+
+/// Run code on the values pushed by some input code and return the resulting stack
+fn run_on_input(input: &str, code: &str) -> UiuaResult<Vec<Value>> {
+    let mut env = Uiua::with_backend(WebBackend::default())
+        .with_mode(RunMode::All)
+        .with_execution_limit(Duration::from_secs_f64(get_execution_limit()));
+    env.load_str(input)?;
+    env.load_str(code)?;
+    Ok(env.take_stack())
+}
+
+/// Whether two values have the same shape and data, allowing for floating point error
+fn values_match(expected: &Value, actual: &Value) -> bool {
+    match (expected, actual) {
+        (Value::Num(_) | Value::Byte(_), Value::Num(_) | Value::Byte(_)) => {
+            expected.shape() == actual.shape()
+                && (expected.clone().into_flat_values())
+                    .zip(actual.clone().into_flat_values())
+                    .all(|(e, a)| match (e.try_num(), a.try_num()) {
+                        (Ok(e), Ok(a)) => {
+                            e == a
+                                || e.is_nan() && a.is_nan()
+                                || (e - a).abs() <= 1e-9 * e.abs().max(a.abs()).max(1.0)
+                        }
+                        _ => false,
+                    })
+        }
+        _ => expected == actual,
+    }
+}
+
+/// Show whether each challenge case passed
+///
+/// The example's output is always shown. The input and outputs of failed hidden cases are shown.
+fn render_challenge_cases(cases: Vec<ChallengeCase>) -> View {
+    let passed = cases.iter().filter(|case| case.passed).count();
+    let total = cases.len();
+    let lines = |lines: Vec<String>| {
+        (lines.into_iter())
+            .map(|line| view!(<div class="output-item">{line}</div>))
+            .collect::<Vec<_>>()
+    };
+    let items: Vec<_> = (cases.into_iter().enumerate())
+        .map(|(i, case)| {
+            let (class, mark) = if case.passed {
+                ("output-item challenge-pass", "✔")
+            } else {
+                ("output-item challenge-fail", "✘")
+            };
+            let title = if i == 0 {
+                format!("{mark} Example")
+            } else {
+                format!("{mark} Test {i}")
+            };
+            let details = (i == 0 || !case.passed).then(|| {
+                view! {
+                    <div class="challenge-case">
+                        <div class="output-item challenge-label">"Input: "{ case.input }</div>
+                        <div class="output-item challenge-label">"Output:"</div>
+                        { lines(case.actual) }
+                        {
+                            (!case.passed).then(|| view! {
+                                <div class="output-item challenge-label">"Expected:"</div>
+                                { lines(case.expected) }
+                            })
+                        }
+                    </div>
+                }
+            });
+            view! {
+                <div class=class>{ title }</div>
+                { details }
+            }
+            .into_view()
+        })
+        .collect();
+    let summary = if passed == total {
+        view!(<div class="output-item challenge-pass">"All tests passed!"</div>)
+    } else {
+        view!(<div class="output-item challenge-fail">{ format!("{passed} of {total} tests passed") }</div>)
+    };
+    view! {
+        { items }
+        <div class="output-item"><hr/></div>
+        { summary }
+    }
+    .into_view()
+}
+
 /// Run code and return the output
-fn run_code(code: &str) -> Vec<OutputItem> {
-    let io = WebBackend::default();
+///
+/// If `files` is given, they are put in the virtual file system, and the code is run as the active file.
+/// Reading from stdin consumes `stdin`, and gives the end-of-input value once it is exhausted.
+fn run_code(
+    code: &str,
+    files: Option<&PadFiles>,
+    vars: &[(String, String)],
+    stdin: String,
+) -> Vec<OutputItem> {
+    let io = WebBackend::default()
+        .with_vars(vars.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+        .with_stdin(stdin);
+    if let Some(files) = files {
+        (io.files.lock().unwrap())
+            .extend((files.files.iter()).map(|(name, code)| (name.clone(), code.clone().into())));
+    }
     // Run
     let mut env = Uiua::with_backend(io)
         .with_mode(RunMode::All)
         .with_execution_limit(Duration::from_secs_f64(get_execution_limit()));
     let mut error = None;
-    let values = match env.load_str(code) {
+    // Running the active file with its path means importing it is a cycle, like it is natively
+    let res = match files {
+        Some(files) => env.load_str_path(code, files.active_name()),
+        None => env.load_str(code),
+    };
+    let values = match res {
         Ok(()) => env.take_stack(),
         Err(e) => {
             error = Some(e);
@@ -1547,12 +2317,12 @@ fn run_code(code: &str) -> Vec<OutputItem> {
             }
         }
         // Try to convert the value to an image
-        if let Ok(image) = value_to_image(&value) {
-            if image.width() > 25 && image.height() > 25 {
-                if let Ok(bytes) = image_to_bytes(&image, ImageOutputFormat::Png) {
-                    stack.push(OutputItem::Image(bytes));
-                    continue;
-                }
+        if looks_like_image(&value) {
+            if let Ok(bytes) = value_to_image(&value)
+                .and_then(|image| image_to_bytes(&image, ImageOutputFormat::Png))
+            {
+                stack.push(OutputItem::Image(bytes));
+                continue;
             }
         }
         // Try to convert the value to a gif
@@ -1617,7 +2387,17 @@ fn run_code(code: &str) -> Vec<OutputItem> {
         }
         let formatted = error.show(false);
         let execution_limit_reached = formatted.contains("Maximum execution time exceeded");
-        output.push(OutputItem::Error(formatted));
+        // Only spans in the code being run can be shown, not ones in imported files
+        let entry = files.map(|files| Path::new(files.active_name()));
+        let ranges = (error.parts().into_iter())
+            .filter_map(|(_, span)| match span {
+                Span::Code(span) if span.path.as_deref() == entry => {
+                    Some(span.start.char_pos..span.end.char_pos)
+                }
+                _ => None,
+            })
+            .collect();
+        output.push(OutputItem::Error(formatted, ranges));
         if execution_limit_reached {
             output.push(OutputItem::String(
                 "You can increase the execution time limit in the editor settings".into(),
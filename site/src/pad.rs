@@ -1,18 +1,172 @@
-use base64::engine::{general_purpose::URL_SAFE, Engine};
+use base64::engine::{
+    general_purpose::{URL_SAFE, URL_SAFE_NO_PAD},
+    Engine,
+};
 use leptos::*;
 use leptos_router::*;
+use miniz_oxide::{deflate::compress_to_vec, inflate::decompress_to_vec};
 
 use crate::editor::*;
 
+pub const PAD_URL: &str = "https://uiua.org/pad";
+
+/// Links longer than this are not made, since they may be cut off when shared
+pub const MAX_LINK_LEN: usize = 4000;
+
 #[component]
 pub fn Pad() -> impl IntoView {
-    let mut src = use_query_map()
-        .with_untracked(|params| params.get("src").cloned())
+    // Links used to put the code in the query rather than the fragment
+    let legacy_src = || {
+        let src = use_query_map().with_untracked(|params| params.get("src").cloned())?;
+        let decoded = URL_SAFE.decode(src.as_bytes()).ok()?;
+        Some(PadFiles::single(
+            String::from_utf8_lossy(&decoded).into_owned(),
+        ))
+    };
+    let files = window()
+        .location()
+        .hash()
+        .ok()
+        .and_then(|hash| decode_fragment(hash.trim_start_matches('#')))
+        .or_else(legacy_src)
         .unwrap_or_default();
-    if let Ok(decoded) = URL_SAFE.decode(src.as_bytes()) {
-        src = String::from_utf8_lossy(&decoded).to_string();
-    }
+    let src = files.active_code().to_string();
     view! {
-        <Editor size=EditorSize::Pad example={ &src }/>
+        <Editor size=EditorSize::Pad example={ &src } files=files/>
+    }
+}
+
+/// The name of the pad's file when there is only one
+pub const MAIN_FILE: &str = "main.ua";
+
+/// The files in the pad
+///
+/// Files can import each other, and the one being edited is the one that is run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PadFiles {
+    /// The names and code of the files
+    pub files: Vec<(String, String)>,
+    /// The index of the file being edited
+    pub active: usize,
+}
+
+impl Default for PadFiles {
+    fn default() -> Self {
+        Self::single(String::new())
+    }
+}
+
+impl PadFiles {
+    /// Make a pad with only a main file
+    pub fn single(code: String) -> Self {
+        PadFiles {
+            files: vec![(MAIN_FILE.into(), code)],
+            active: 0,
+        }
+    }
+    pub fn active_name(&self) -> &str {
+        &self.files[self.active].0
+    }
+    pub fn active_code(&self) -> &str {
+        &self.files[self.active].1
+    }
+    pub fn set_active_code(&mut self, code: String) {
+        self.files[self.active].1 = code;
+    }
+    /// Remove a file, keeping a nearby file active
+    pub fn remove(&mut self, index: usize) {
+        self.files.remove(index);
+        if self.active > index || self.active == self.files.len() {
+            self.active -= 1;
+        }
+    }
+    /// A name for a new file that is not taken
+    pub fn unused_name(&self) -> String {
+        (2..)
+            .map(|i| format!("file{i}.ua"))
+            .find(|name| self.files.iter().all(|(n, _)| n != name))
+            .unwrap()
+    }
+    /// Check that a file name can be used, adding a `.ua` extension if it is missing
+    ///
+    /// `index` is the file being renamed, if any.
+    pub fn check_name(&self, name: &str, index: Option<usize>) -> Result<String, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("File names cannot be empty".into());
+        }
+        if name.contains(['/', '\\']) {
+            return Err("File names cannot contain slashes".into());
+        }
+        let name = if name.ends_with(".ua") {
+            name.to_string()
+        } else {
+            format!("{name}.ua")
+        };
+        let taken =
+            (self.files.iter().enumerate()).any(|(i, (n, _))| *n == name && Some(i) != index);
+        if taken {
+            return Err(format!("There is already a file named {name}"));
+        }
+        Ok(name)
+    }
+    /// Serialize the files as the index of the active file followed by each file's name,
+    /// code length in bytes, and code, all separated by newlines
+    fn serialize(&self) -> String {
+        let mut text = self.active.to_string();
+        for (name, code) in &self.files {
+            text.push_str(&format!("\n{name}\n{}\n{code}", code.len()));
+        }
+        text
+    }
+    fn deserialize(text: &str) -> Option<Self> {
+        let (active, mut rest) = text.split_once('\n')?;
+        let active = active.parse().ok()?;
+        let mut files = Vec::new();
+        while !rest.is_empty() {
+            let (name, after_name) = rest.split_once('\n')?;
+            let (len, after_len) = after_name.split_once('\n')?;
+            let len: usize = len.parse().ok()?;
+            let code = after_len.get(..len)?;
+            files.push((name.to_string(), code.to_string()));
+            rest = after_len[len..]
+                .strip_prefix('\n')
+                .unwrap_or(&after_len[len..]);
+        }
+        (active < files.len()).then_some(PadFiles { files, active })
+    }
+}
+
+/// Encode code so that it can be put in a URL fragment
+///
+/// The code is compressed with deflate, then encoded with URL-safe base64.
+pub fn encode_code(code: &str) -> String {
+    URL_SAFE_NO_PAD.encode(compress_to_vec(code.as_bytes(), 9))
+}
+
+/// Decode code that was encoded with [`encode_code`]
+pub fn decode_code(encoded: &str) -> Option<String> {
+    let compressed = URL_SAFE_NO_PAD.decode(encoded.as_bytes()).ok()?;
+    let bytes = decompress_to_vec(&compressed).ok()?;
+    String::from_utf8(bytes).ok()
+}
+
+/// Get the URL fragment for a link to the pad with some files, or `None` if the link would be too long
+///
+/// A lone main file is encoded by itself, so that its links look the same as before the pad had files.
+/// Otherwise, the fragment is `files=` followed by the encoded files.
+pub fn pad_fragment(files: &PadFiles) -> Option<String> {
+    let fragment = match files.files.as_slice() {
+        [(name, code)] if name == MAIN_FILE => encode_code(code),
+        _ => format!("files={}", encode_code(&files.serialize())),
+    };
+    (PAD_URL.len() + 1 + fragment.len() <= MAX_LINK_LEN).then_some(fragment)
+}
+
+/// Decode a fragment made by [`pad_fragment`]
+pub fn decode_fragment(fragment: &str) -> Option<PadFiles> {
+    match fragment.strip_prefix("files=") {
+        Some(encoded) => PadFiles::deserialize(&decode_code(encoded)?),
+        None => decode_code(fragment).map(PadFiles::single),
     }
 }
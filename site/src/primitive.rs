@@ -41,8 +41,8 @@ fn doc_lines_to_view(lines: &[PrimDocLine]) -> impl IntoView {
         .collect::<Vec<_>>()
 }
 
-#[component]
-pub fn PrimDocs(prim: Primitive) -> impl IntoView {
+/// Describe a primitive's arguments and outputs, like "Dyadic pervasive function"
+pub fn prim_sig(prim: Primitive) -> String {
     let mut sig = String::new();
     if prim.class() == PrimClass::Constant {
         sig.push_str("Constant");
@@ -80,25 +80,97 @@ pub fn PrimDocs(prim: Primitive) -> impl IntoView {
         }
         sig.push_str(" function");
     }
+    sig
+}
+
+/// The name of a class of primitives
+pub fn class_header(class: PrimClass) -> &'static str {
+    match class {
+        PrimClass::Stack => "Stack",
+        PrimClass::MonadicPervasive => "Monadic Pervasive",
+        PrimClass::DyadicPervasive => "Dyadic Pervasive",
+        PrimClass::MonadicArray => "Monadic Array",
+        PrimClass::DyadicArray => "Dyadic Array",
+        PrimClass::IteratingModifier => "Iterating Modifiers",
+        PrimClass::AggregatingModifier => "Aggregating Modifiers",
+        PrimClass::OtherModifier => "Other Modifiers",
+        PrimClass::Control => "Control",
+        PrimClass::Misc => "Miscellaneous",
+        PrimClass::Constant => "Constants",
+        PrimClass::Sys => "System",
+    }
+}
+
+/// All the ways to write a primitive
+fn prim_names(prim: Primitive) -> Option<impl IntoView> {
+    prim.names().map(|names| {
+        let glyph = names
+            .glyph
+            .map(|glyph| view!("Glyph: "<code>{ glyph.to_string() }</code>" "));
+        let ascii = names
+            .ascii
+            .map(|ascii| view!("ASCII: "<code>{ ascii.to_string() }</code>" "));
+        let name = view!("Name: "<code>{ names.text }</code>);
+        view!(<p class="prim-names">{ glyph }{ ascii }{ name }</p>)
+    })
+}
+
+/// How many arguments a primitive takes and how many values it returns
+fn prim_arg_counts(prim: Primitive) -> Option<impl IntoView> {
+    if prim.class() == PrimClass::Constant {
+        return None;
+    }
+    let args = prim
+        .args()
+        .map(|args| args.to_string())
+        .unwrap_or_else(|| "variable".into());
+    let outputs = prim
+        .outputs()
+        .map(|outputs| outputs.to_string())
+        .unwrap_or_else(|| "variable".into());
+    let functions = prim
+        .modifier_args()
+        .map(|margs| view!("Functions: "<code>{ margs.to_string() }</code>" "));
+    Some(view! {
+        <p>{ functions }"Arguments: "<code>{ args }</code>" Outputs: "<code>{ outputs }</code></p>
+    })
+}
+
+/// The heading of a primitive's documentation, with its signature and the ways to write it
+#[component]
+fn PrimHeader(prim: Primitive) -> impl IntoView {
+    let sig = prim_sig(prim);
     let long_name = if let Primitive::Sys(op) = prim {
         Some(format!(" - {}", op.long_name()))
     } else {
         None
     };
-    let body = prim.doc().map(|doc| {
+    let names = prim_names(prim);
+    let id = prim.name();
+
+    view! {
+        <h1 id=id><Prim prim=prim hide_docs=true/>{ long_name }</h1>
+        <p><h3>{ sig }</h3></p>
+        { names }
+    }
+}
+
+/// The documentation text and examples of a primitive, in the order they are written
+fn prim_doc_body(prim: Primitive) -> Option<impl IntoView> {
+    prim.doc().map(|doc| {
         view! {
             <p style="white-space: pre-wrap">{doc_line_fragments_to_view( &doc.short)}</p>
             { doc_lines_to_view( &doc.lines) }
         }
-    });
-
-    let id = prim.name();
+    })
+}
 
+#[component]
+pub fn PrimDocs(prim: Primitive) -> impl IntoView {
     view! {
         <div>
-            <h1 id=id><Prim prim=prim hide_docs=true/>{ long_name }</h1>
-            <p><h3>{ sig }</h3></p>
-            { body }
+            <PrimHeader prim=prim/>
+            { prim_doc_body(prim) }
         </div>
     }
 }
@@ -120,3 +192,15 @@ pub fn AllFunctions() -> impl IntoView {
         }
     }
 }
+
+/// The page of a single primitive
+#[component]
+pub fn PrimPage(prim: Primitive) -> impl IntoView {
+    view! {
+        <div>
+            <PrimHeader prim=prim/>
+            { prim_arg_counts(prim) }
+            { prim_doc_body(prim) }
+        </div>
+    }
+}
@@ -16,8 +16,10 @@ pub enum TutorialPage {
     Bindings,
     Functions,
     AdvancedStack,
+    Inverses,
     Modules,
     Testing,
+    Challenges,
 }
 
 impl TutorialPage {
@@ -33,8 +35,10 @@ impl TutorialPage {
             Self::Bindings => "Bindings",
             Self::Functions => "Modifiers and Functions",
             Self::AdvancedStack => "Advanced Stack Manipulation",
+            Self::Inverses => "Inverses",
             Self::Modules => "Modules",
             Self::Testing => "Testing",
+            Self::Challenges => "Challenges",
         }
     }
 }
@@ -49,8 +53,10 @@ pub fn Tutorial(page: TutorialPage) -> impl IntoView {
         TutorialPage::Bindings => TutorialBindings().into_view(),
         TutorialPage::Functions => TutorialFunctions().into_view(),
         TutorialPage::AdvancedStack => TutorialAdvancedStack().into_view(),
+        TutorialPage::Inverses => TutorialInverses().into_view(),
         TutorialPage::Modules => TutorialModules().into_view(),
         TutorialPage::Testing => TutorialTesting().into_view(),
+        TutorialPage::Challenges => TutorialChallenges().into_view(),
     };
     view! {
         <TutorialNav page=page/>
@@ -234,11 +240,9 @@ fn primitive_rows(prims: impl IntoIterator<Item = Primitive>) -> Vec<impl IntoVi
 fn TutorialMath() -> impl IntoView {
     use Primitive::*;
     let math_table = primitive_rows([
-        Add, Sub, Mul, Div, Mod, Pow, Log, Neg, Abs, Ceil, Floor, Round, Sqrt, Sign,
-    ]);
-    let comp_table = primitive_rows([
-        Eq, Ne, Lt, Gt, Le, Ge, Min, Max, Floor, Ceil, Round, Sin, Atan,
+        Add, Sub, Mul, Div, Mod, Pow, Log, Neg, Abs, Ceil, Floor, Round, Sqrt, Sign, Sin, Atan,
     ]);
+    let comp_table = primitive_rows([Eq, Ne, Lt, Gt, Le, Ge, Min, Max, Floor, Ceil, Round]);
 
     view! {
         <h1>"Math and Comparison"</h1>
@@ -438,6 +442,8 @@ fn TutorialTypes() -> impl IntoView {
         <Editor example="&p $ Hello, \n   $ World!"/>
         <p>"This style of string is also useful when your string contains a lot of quotes that you don't want to escape."</p>
         <Editor example="$ An then she was like, \"No way!\"\n$ And I was like, \"Way...\""/>
+        <p><Prim prim=Sys(SysOp::ScanLine)/>" reads a line of input as a string. In the editor, the input comes from the "<code>"Input"</code>" box below the code. Once the input runs out, "<Prim prim=Sys(SysOp::ScanLine)/>" gives "<code>"0"</code>"."</p>
+        <Editor example="&sc\n&sc\n&sc" input="Hello\nWorld"/>
         <br/>
 
         <h2 id="character-arithmetic">"Character Arithmetic"</h2>
@@ -662,6 +668,44 @@ fn TutorialAdvancedStack() -> impl IntoView {
     }
 }
 
+#[component]
+fn TutorialInverses() -> impl IntoView {
+    use Primitive::*;
+    view! {
+        <h1>"Inverses"</h1>
+        <p>"A lot of code follows the same pattern: transform a value, do something to the transformed value, then undo the transformation. Uiua has built-in support for this pattern."</p>
+
+        <h2 id="invert"><Prim prim=Invert/></h2>
+        <p><Prim prim=Invert/>" calls the inverse of its function."</p>
+        <Editor example="⍘(+1) 5"/>
+        <Editor example="⍘⊟ [1_2_3 4_5_6]"/>
+        <Editor example="⍘⋯ [1 0 1 1]"/>
+        <p>"Your own functions can be inverted as long as every function in them can be."</p>
+        <Editor example="F ← ×2+1\n⍘F F 5"/>
+        <p>"Not every function has an inverse."</p>
+        <Editor example="⍘⧻ [1 2 3]"/> // Should fail
+
+        <h2 id="under"><Prim prim=Under/></h2>
+        <p><Prim prim=Under/>" takes 2 functions. It calls the first function, calls the second function, then calls the inverse of the first function."</p>
+        <p>"For example, you can operate on the end of an array by "<Prim prim=Reverse/>"sing it, operating on the beginning, and reversing it back."</p>
+        <Editor example="⍜⇌(⊂0) [1 2 3]"/>
+        <p>"Arithmetic with a constant is undone with the opposite operation. Here, we add 1, double the result, then subtract 1."</p>
+        <Editor example="⍜(+1)(×2) 5"/>
+        <p>"Some functions can't be inverted on their own but still work with "<Prim prim=Under/>". "<Prim prim=Take/>", "<Prim prim=Drop/>", "<Prim prim=Select/>", and "<Prim prim=Pick/>" put the modified values back where they came from."</p>
+        <Editor example="⍜(↙3)(×10) [1 2 3 4 5]"/>
+        <Editor example="⍜(↘3)(×10) [1 2 3 4 5]"/>
+        <Editor example="⍜(⊏1_3)(×10) [1 2 3 4 5]"/>
+        <Editor example="⍜⊡(×10) 1_1 [1_2 3_4]"/>
+        <p>"With "<Prim prim=Transpose/>", you can work on the columns of an array as if they were rows."</p>
+        <Editor example="⍜⍉(⊂[0 0]) [1_2 3_4]"/>
+        <p>"Invertible functions can be chained."</p>
+        <Editor example="⍜(↙2↘1)(×10) [1 2 3 4 5]"/>
+        <p>"Using a function with no inverse is an error."</p>
+        <Editor example="⍜⧻(+1) [1 2 3]"/> // Should fail
+        <p>"See "<Prim prim=Under/>"'s documentation for the full list of functions it works with."</p>
+    }
+}
+
 #[component]
 fn TutorialModules() -> impl IntoView {
     use Primitive::*;
@@ -749,3 +793,100 @@ fn TutorialTesting() -> impl IntoView {
         <p><code>"uiua test"</code>" will only run test code, but also any non-test bindings and any non-test code which makes imports."</p>
     }
 }
+
+#[component]
+fn TutorialChallenges() -> impl IntoView {
+    use Primitive::*;
+    view! {
+        <h1>"Challenges"</h1>
+        <p>"These challenges are a chance to practice what you have learned in the previous sections."</p>
+        <p>"Each challenge's input is already on the stack when your code runs. Write code that leaves the correct output on the stack, then press "<code>"Run"</code>"."</p>
+        <p>"Your code is checked against the example input and some hidden inputs. Numbers only need to be very close to the expected ones, so you do not need to worry about floating point error."</p>
+        <p>"Once you have tried a challenge, you can reveal a solution. There are many ways to solve each one!"</p>
+        <Challenge
+            number=1
+            prompt="adds 3 to a number, then doubles it"
+            example="5"
+            answer="×2+3"
+            tests={&["0", "¯2", "10", "1.5"]}/>
+        <Challenge
+            number=2
+            prompt="finds the average of two numbers"
+            example="3 5"
+            answer="÷2+"
+            tests={&["1 2", "¯4 4", "0.1 0.2"]}/>
+        <Challenge
+            number=3
+            prompt="checks whether a number is even, giving 1 or 0"
+            example="4"
+            answer="=0◿2"
+            tests={&["7", "0", "¯3"]}/>
+        <Challenge
+            number=4
+            prompt="sums an array"
+            example="[1 2 3]"
+            answer="/+"
+            tests={&["[5]", "[0.1 0.2 0.3]", "[]"]}/>
+        <Challenge
+            number=5
+            prompt="counts how many times 5 appears in an array"
+            example="[1 5 3 5]"
+            answer="/+=5"
+            tests={&["[5 5 5]", "[]", "[1 2 3]"]}/>
+        <Challenge
+            number=6
+            prompt="joins two strings with a space between them"
+            example=r#""Hello" "World""#
+            answer="⊂⊂∶@ "
+            tests={&[r#""a" "b""#, r#""" "x""#]}/>
+        <Challenge
+            number=7
+            prompt="finds the largest number in each row of a matrix"
+            example="[1_2_3 4_1_0]"
+            answer="≡/↥"
+            tests={&["[[5]]", "[¯1_¯2 3_3 0_7]"]}/>
+        <Challenge
+            number=8
+            prompt="finds the mean of an array"
+            example="[1 2 3 4]"
+            answer="÷⧻∶/+."
+            tests={&["[5]", "[0.5 1.5]", "[¯3 3 9]"]}/>
+        <Challenge
+            number=9
+            prompt="leaves both the sum and the product of two numbers on the stack"
+            example="3 4"
+            answer="⊃+×"
+            tests={&["0 5", "¯2 ¯3", "1.5 2"]}/>
+        <Challenge
+            number=10
+            prompt="adds 1 to the first item of an array"
+            example="[1 2 3]"
+            answer="⍜⊢(+1)"
+            tests={&["[0]", "[¯1 5 ¯1]"]}/>
+        <p>"Stuck on one? "<Prim prim=Under/>", "<Prim prim=Fork/>", and "<Prim prim=Rows/>" are covered in "<A href="/docs/inverses">"Inverses"</A>", "<A href="/docs/advancedstack">"Advanced Stack Manipulation"</A>", and "<A href="/docs/functions">"Modifiers and Functions"</A>"."</p>
+    }
+}
+
+/// An exercise whose answer is checked on several inputs
+#[component]
+fn Challenge<'a>(
+    number: u8,
+    prompt: &'a str,
+    example: &'a str,
+    answer: &'a str,
+    tests: &'a [&'a str],
+) -> impl IntoView {
+    let def = ChallengeDef {
+        example: example.into(),
+        tests: tests.iter().map(|&test| test.into()).collect(),
+        answer: answer.into(),
+    };
+    view! {
+        <div class="challenge">
+            <h3>"Challenge "{ number }</h3>
+            <p>"Write a program that "<strong>{ prompt.to_string() }</strong>"."</p>
+            <p>"Example input: "<code>{ example.to_string() }</code></p>
+            <Editor challenge=def/>
+        </div>
+    }
+}
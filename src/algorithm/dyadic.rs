@@ -1,8 +1,8 @@
 //! Algorithms for dyadic array operations
 
-use std::{borrow::Cow, cmp::Ordering, iter::repeat, mem::take, sync::Arc};
+use std::{borrow::Cow, cmp::Ordering, collections::HashMap, iter::repeat, mem::take, sync::Arc};
 
-use ecow::EcoVec;
+use ecow::{eco_vec, EcoVec};
 use tinyvec::tiny_vec;
 
 use crate::{
@@ -14,7 +14,10 @@ use crate::{
     Uiua, UiuaResult,
 };
 
-use super::{op2_bytes_retry_fill, op_bytes_ref_retry_fill, op_bytes_retry_fill, FillContext};
+use super::{
+    op2_bytes_retry_fill, op_bytes_ref_retry_fill, op_bytes_retry_fill, shape_error, FillContext,
+    RowKey,
+};
 
 impl Value {
     fn coerce_to_functions<T, C: FillContext, E: ToString>(
@@ -175,6 +178,9 @@ impl Value {
     }
 }
 
+const JOIN_SHAPE_RULE: &str =
+    "The shape of one array must match the shape of the other's rows, or their rows must match";
+
 impl<T: ArrayValue> Array<T> {
     pub fn join(self, other: Self, env: &Uiua) -> UiuaResult<Self> {
         self.join_impl(other, env)
@@ -201,10 +207,14 @@ impl<T: ArrayValue> Array<T> {
                         ))));
                     }
                     if self.shape() != &other.shape()[1..] {
-                        return Err(C::fill_error(ctx.error(format!(
-                            "Cannot join arrays of shapes {} and {}",
-                            self.format_shape(),
-                            other.format_shape()
+                        return Err(C::fill_error(ctx.error(shape_error(
+                            format!(
+                                "Cannot join arrays of shapes {} and {}",
+                                self.format_shape(),
+                                other.format_shape()
+                            ),
+                            JOIN_SHAPE_RULE,
+                            true,
                         ))));
                     }
                     other.shape
@@ -233,10 +243,14 @@ impl<T: ArrayValue> Array<T> {
                             array.fill_to_shape(&new_shape, fill);
                         }
                     } else if self.shape[1..] != other.shape[1..] {
-                        return Err(C::fill_error(ctx.error(format!(
-                            "Cannot join arrays of shapes {} and {}",
-                            self.format_shape(),
-                            other.format_shape()
+                        return Err(C::fill_error(ctx.error(shape_error(
+                            format!(
+                                "Cannot join arrays of shapes {} and {}",
+                                self.format_shape(),
+                                other.format_shape()
+                            ),
+                            JOIN_SHAPE_RULE,
+                            true,
                         ))));
                     }
                     self.data.extend(other.data);
@@ -267,10 +281,14 @@ impl<T: ArrayValue> Array<T> {
                 ))));
             }
             if &self.shape()[1..] != other.shape() {
-                return Err(C::fill_error(ctx.error(format!(
-                    "Cannot append arrays of shapes {} and {}",
-                    self.format_shape(),
-                    other.format_shape()
+                return Err(C::fill_error(ctx.error(shape_error(
+                    format!(
+                        "Cannot append arrays of shapes {} and {}",
+                        self.format_shape(),
+                        other.format_shape()
+                    ),
+                    JOIN_SHAPE_RULE,
+                    true,
                 ))));
             }
             take(&mut self.shape)
@@ -282,6 +300,115 @@ impl<T: ArrayValue> Array<T> {
     }
 }
 
+impl Value {
+    pub fn join_sep(self, rows: Self, env: &Uiua) -> UiuaResult<Self> {
+        if rows.rank() == 0 {
+            return Err(env.error("Cannot join the rows of a scalar"));
+        }
+        // Lists of boxes are joined by their contents
+        let pieces: Vec<Value> = match rows {
+            Value::Func(arr)
+                if arr.rank() == 1 && arr.data.iter().all(|f| f.as_boxed().is_some()) =>
+            {
+                arr.data
+                    .iter()
+                    .map(|f| f.as_boxed().unwrap().clone())
+                    .collect()
+            }
+            rows => rows.into_rows().collect(),
+        };
+        let all = |f: fn(&Value) -> bool| f(&self) && pieces.iter().all(f);
+        Ok(if all(|v| matches!(v, Value::Byte(_))) {
+            join_sep_as(self, pieces, env, |v| match v {
+                Value::Byte(a) => a,
+                _ => unreachable!(),
+            })?
+            .into()
+        } else if all(|v| matches!(v, Value::Num(_) | Value::Byte(_))) {
+            join_sep_as(self, pieces, env, |v| match v {
+                Value::Num(a) => a,
+                Value::Byte(a) => a.convert(),
+                _ => unreachable!(),
+            })?
+            .into()
+        } else if all(|v| matches!(v, Value::Char(_))) {
+            join_sep_as(self, pieces, env, |v| match v {
+                Value::Char(a) => a,
+                _ => unreachable!(),
+            })?
+            .into()
+        } else if all(|v| matches!(v, Value::Func(_))) {
+            join_sep_as(self, pieces, env, |v| match v {
+                Value::Func(a) => a,
+                _ => unreachable!(),
+            })?
+            .into()
+        } else {
+            let row_type = (pieces.iter())
+                .find(|piece| piece.type_name() != self.type_name())
+                .map_or(self.type_name(), Value::type_name);
+            return Err(env.error(format!(
+                "Cannot join {row_type} rows with a {} separator",
+                self.type_name()
+            )));
+        })
+    }
+}
+
+fn join_sep_as<T: ArrayValue>(
+    sep: Value,
+    pieces: Vec<Value>,
+    env: &Uiua,
+    extract: impl Fn(Value) -> Array<T>,
+) -> UiuaResult<Array<T>> {
+    let pieces = pieces.into_iter().map(&extract).collect();
+    Array::join_sep(extract(sep), pieces, env)
+}
+
+impl<T: ArrayValue> Array<T> {
+    pub fn join_sep(sep: Self, pieces: Vec<Self>, env: &Uiua) -> UiuaResult<Self> {
+        let row_shape: Shape = match pieces.first() {
+            Some(first) => first.shape.iter().skip(1).copied().collect(),
+            None => sep.shape.iter().skip(1).copied().collect(),
+        };
+        for piece in &pieces {
+            // A scalar piece is a single row
+            let piece_row_shape = piece.shape.get(1..).unwrap_or(&[]);
+            if piece_row_shape != &row_shape[..] {
+                return Err(env.error(format!(
+                    "Cannot join rows of shapes {} and {}",
+                    pieces[0].format_shape(),
+                    piece.format_shape()
+                )));
+            }
+        }
+        // The separator is either a single row or a list of rows
+        let sep_rows = if sep.shape == row_shape {
+            1
+        } else if sep.rank() > 0 && sep.shape[1..] == row_shape[..] {
+            sep.row_count()
+        } else {
+            return Err(env.error(format!(
+                "Cannot join rows of shape {} with a separator of shape {}",
+                FormatShape(&row_shape),
+                sep.format_shape()
+            )));
+        };
+        let row_count = pieces.iter().map(Array::row_count).sum::<usize>()
+            + sep_rows * pieces.len().saturating_sub(1);
+        let mut shape = row_shape;
+        shape.insert(0, row_count);
+        let mut data = EcoVec::with_capacity(shape.iter().product());
+        for (i, piece) in pieces.into_iter().enumerate() {
+            if i > 0 {
+                data.extend_from_slice(&sep.data);
+            }
+            data.extend_from_slice(&piece.data);
+        }
+        Ok(Array::new(shape, data))
+    }
+}
+
 impl Value {
     pub fn couple(mut self, other: Self, env: &Uiua) -> UiuaResult<Self> {
         self.couple_impl(other, env)?;
@@ -361,10 +488,14 @@ impl<T: ArrayValue> Array<T> {
                 self.fill_to_shape(&new_shape, fill.clone());
                 other.fill_to_shape(&new_shape, fill);
             } else {
-                return Err(C::fill_error(ctx.error(format!(
-                    "Cannot couple arrays with shapes {} and {}",
-                    self.format_shape(),
-                    other.format_shape()
+                return Err(C::fill_error(ctx.error(shape_error(
+                    format!(
+                        "Cannot couple arrays with shapes {} and {}",
+                        self.format_shape(),
+                        other.format_shape()
+                    ),
+                    "Coupled arrays must have the same shape",
+                    true,
                 ))));
             }
         }
@@ -411,9 +542,21 @@ impl Value {
         let Some(mut value) = row_values.next() else {
             return Ok(Value::default());
         };
+        let row_shape = Shape::from(value.shape());
         let mut count = 1;
         for row in row_values {
             count += 1;
+            if row.shape() != &*row_shape && !has_fill(&value, ctx) && !has_fill(&row, ctx) {
+                return Err(ctx.error(shape_error(
+                    format!(
+                        "Cannot make an array from rows with shapes {} and {}",
+                        FormatShape(&row_shape),
+                        row.format_shape()
+                    ),
+                    "All rows of an array must have the same shape",
+                    true,
+                )));
+            }
             if count == 2 {
                 value.couple_impl(row, ctx)?;
             } else {
@@ -427,6 +570,15 @@ impl Value {
     }
 }
 
+/// Check if there is a fill value that could pad a value to a different shape
+fn has_fill<C: FillContext>(value: &Value, ctx: C) -> bool {
+    match value {
+        Value::Num(_) | Value::Byte(_) => ctx.fill::<f64>().is_some(),
+        Value::Char(_) => ctx.fill::<char>().is_some(),
+        Value::Func(_) => ctx.fill::<Arc<Function>>().is_some(),
+    }
+}
+
 impl<T: ArrayValue> Array<T> {
     #[track_caller]
     pub fn from_row_arrays(values: impl IntoIterator<Item = Self>, env: &Uiua) -> UiuaResult<Self> {
@@ -505,8 +657,15 @@ impl<T: ArrayValue> Array<T> {
     }
     pub fn reshape(&mut self, dims: &[isize], env: &Uiua) -> UiuaResult {
         let mut neg_count = 0;
-        for dim in dims {
-            if *dim < 0 {
+        for &dim in dims {
+            if dim < -1 {
+                return Err(env.error(format!(
+                    "Cannot reshape array with dimension ¯{}. \
+                    Only ¯1 may be used to derive a dimension.",
+                    -dim
+                )));
+            }
+            if dim < 0 {
                 neg_count += 1;
             }
         }
@@ -755,7 +914,9 @@ impl<T: ArrayValue> Array<T> {
                 }
             }
             let row_len = self.row_len();
-            if all_bools {
+            if row_len == 0 {
+                self.shape[0] = amount.iter().sum();
+            } else if all_bools {
                 let new_flat_len = true_count * row_len;
                 let mut new_data = CowSlice::with_capacity(new_flat_len);
                 for (b, r) in amount.iter().zip(self.data.chunks_exact(row_len)) {
@@ -766,10 +927,9 @@ impl<T: ArrayValue> Array<T> {
                 self.data = new_data;
                 self.shape[0] = true_count;
             } else {
-                let mut new_data = CowSlice::new();
-                let mut new_len = 0;
+                let new_len: usize = amount.iter().sum();
+                let mut new_data = CowSlice::with_capacity(new_len * row_len);
                 for (n, r) in amount.iter().zip(self.data.chunks_exact(row_len)) {
-                    new_len += *n;
                     for _ in 0..*n {
                         new_data.extend_from_slice(r);
                     }
@@ -1347,10 +1507,9 @@ fn rotate<T>(by: &[isize], shape: &[usize], data: &mut [T]) {
     let row_len = shape[1..].iter().product();
     let offset = by[0];
     let mid = (row_count as isize + offset).rem_euclid(row_count as isize) as usize;
-    let (left, right) = data.split_at_mut(mid * row_len);
-    left.reverse();
-    right.reverse();
-    data.reverse();
+    if mid != 0 {
+        data.rotate_left(mid * row_len);
+    }
     let index = &by[1..];
     let shape = &shape[1..];
     if index.is_empty() || shape.is_empty() {
@@ -1438,21 +1597,12 @@ impl<T: ArrayValue> Array<T> {
         indices: &[isize],
         env: &Uiua,
     ) -> UiuaResult<Self> {
-        if indices_shape.len() > 1 {
-            let row_count = indices_shape[0];
-            let row_len = indices_shape[1..].iter().product();
-            let mut rows = Vec::with_capacity(row_count);
-            for indices_row in indices.chunks_exact(row_len) {
-                rows.push(self.select_impl(&indices_shape[1..], indices_row, env)?);
-            }
-            Array::from_row_arrays(rows, env)
-        } else {
-            let mut res = self.select(indices, env)?;
-            if indices_shape.is_empty() {
-                res.shape.remove(0);
-            }
-            Ok(res)
-        }
+        let mut res = self.select(indices, env)?;
+        // The selected rows are gathered into a list, so the first axis
+        // is replaced with the shape of the indices
+        res.shape = (indices_shape.iter().chain(&res.shape[1..]).copied()).collect();
+        res.validate_shape();
+        Ok(res)
     }
     fn unselect_impl(
         &self,
@@ -1563,18 +1713,43 @@ impl<T: ArrayValue> Array<T> {
 
 impl Value {
     pub fn windows(&self, from: &Self, env: &Uiua) -> UiuaResult<Self> {
-        let size_spec = self.as_naturals(env, "Window size must be a list of natural numbers")?;
+        let (size_spec, stride_spec) = if self.rank() == 2 {
+            if self.row_count() != 2 {
+                return Err(env.error(format!(
+                    "A rank 2 window specification must have 2 rows, \
+                    the sizes and the strides, but it has {}",
+                    self.row_count()
+                )));
+            }
+            let requirement = "Window sizes and strides must be lists of natural numbers";
+            let mut rows = self.clone().into_rows();
+            let sizes = rows.next().unwrap().as_naturals(env, requirement)?;
+            let strides = rows.next().unwrap().as_naturals(env, requirement)?;
+            if strides.contains(&0) {
+                return Err(env.error("Window strides cannot be 0"));
+            }
+            (sizes, strides)
+        } else {
+            let sizes = self.as_naturals(env, "Window size must be a list of natural numbers")?;
+            let strides = vec![1; sizes.len()];
+            (sizes, strides)
+        };
         Ok(match from {
-            Value::Num(a) => a.windows(&size_spec, env)?.into(),
-            Value::Byte(a) => a.windows(&size_spec, env)?.into(),
-            Value::Char(a) => a.windows(&size_spec, env)?.into(),
-            Value::Func(a) => a.windows(&size_spec, env)?.into(),
+            Value::Num(a) => a.windows(&size_spec, &stride_spec, env)?.into(),
+            Value::Byte(a) => a.windows(&size_spec, &stride_spec, env)?.into(),
+            Value::Char(a) => a.windows(&size_spec, &stride_spec, env)?.into(),
+            Value::Func(a) => a.windows(&size_spec, &stride_spec, env)?.into(),
         })
     }
 }
 
 impl<T: ArrayValue> Array<T> {
-    pub fn windows(&self, size_spec: &[usize], env: &Uiua) -> UiuaResult<Self> {
+    pub fn windows(
+        &self,
+        size_spec: &[usize],
+        stride_spec: &[usize],
+        env: &Uiua,
+    ) -> UiuaResult<Self> {
         if size_spec.len() > self.shape.len() {
             return Err(env.error(format!(
                 "Window size {size_spec:?} has too many axes for shape {}",
@@ -1583,19 +1758,20 @@ impl<T: ArrayValue> Array<T> {
         }
         // Determine the shape of the windows array
         let mut new_shape = Shape::with_capacity(self.shape.len() + size_spec.len());
-        new_shape.extend(
-            self.shape
-                .iter()
-                .zip(size_spec)
-                .map(|(a, b)| (a + 1).saturating_sub(*b)),
-        );
+        new_shape.extend((self.shape.iter().zip(size_spec).zip(stride_spec)).map(
+            |((sh, size), stride)| {
+                if size > sh {
+                    0
+                } else {
+                    (sh - size) / stride + 1
+                }
+            },
+        ));
         new_shape.extend_from_slice(size_spec);
         new_shape.extend_from_slice(&self.shape[size_spec.len()..]);
-        // Check if the window size is too large
-        for (size, sh) in size_spec.iter().zip(&self.shape) {
-            if *size > *sh {
-                return Ok(Self::new(new_shape, CowSlice::new()));
-            }
+        // Check if there are no windows or if the windows are empty
+        if new_shape.contains(&0) {
+            return Ok(Self::new(new_shape, CowSlice::new()));
         }
         // Make a new window shape with the same rank as the windowed array
         let mut true_size: Vec<usize> = Vec::with_capacity(self.shape.len());
@@ -1603,8 +1779,11 @@ impl<T: ArrayValue> Array<T> {
         if true_size.len() < self.shape.len() {
             true_size.extend(&self.shape[true_size.len()..]);
         }
+        let mut true_stride: Vec<usize> = Vec::with_capacity(self.shape.len());
+        true_stride.extend(stride_spec);
+        true_stride.resize(self.shape.len(), 1);
 
-        let mut dst = EcoVec::new();
+        let mut dst = EcoVec::with_capacity(new_shape.iter().product());
         let mut corner = vec![0; self.shape.len()];
         let mut curr = vec![0; self.shape.len()];
         'windows: loop {
@@ -1635,10 +1814,10 @@ impl<T: ArrayValue> Array<T> {
             }
             // Go to the next corner
             for i in (0..corner.len()).rev() {
-                if corner[i] == self.shape[i] - true_size[i] {
+                if corner[i] + true_stride[i] + true_size[i] > self.shape[i] {
                     corner[i] = 0;
                 } else {
-                    corner[i] += 1;
+                    corner[i] += true_stride[i];
                     continue 'windows;
                 }
             }
@@ -1669,7 +1848,17 @@ impl Value {
 
 impl<T: ArrayValue> Array<T> {
     pub fn find(&self, searched: &Self, env: &Uiua) -> UiuaResult<Array<u8>> {
-        if self.rank() > searched.rank() || self.row_count() > searched.row_count() {
+        // Pad the shape of the searched-for array
+        let mut searched_for_shape = self.shape.clone();
+        while searched_for_shape.len() < searched.shape.len() {
+            searched_for_shape.insert(0, 1);
+        }
+
+        if self.rank() > searched.rank()
+            || (searched_for_shape.iter())
+                .zip(&searched.shape)
+                .any(|(a, b)| a > b)
+        {
             return Err(env.error(format!(
                 "Cannot search for array of shape {} in array of shape {}",
                 self.format_shape(),
@@ -1677,12 +1866,6 @@ impl<T: ArrayValue> Array<T> {
             )));
         }
 
-        // Pad the shape of the searched-for array
-        let mut searched_for_shape = self.shape.clone();
-        while searched_for_shape.len() < searched.shape.len() {
-            searched_for_shape.insert(0, 1);
-        }
-
         // Determine the ouput shape
         let output_shape: Shape = searched
             .shape
@@ -1691,6 +1874,12 @@ impl<T: ArrayValue> Array<T> {
             .map(|(a, b)| a + 1 - b)
             .collect();
 
+        // An empty array is found everywhere
+        if self.data.is_empty() {
+            let data = eco_vec![1u8; output_shape.iter().product()];
+            return Ok(Array::new(output_shape, data));
+        }
+
         let mut data = EcoVec::new();
         let mut corner = vec![0; searched.shape.len()];
         let mut curr = vec![0; searched.shape.len()];
@@ -1761,8 +1950,8 @@ impl Value {
             (Value::Byte(a), Value::Byte(b)) => a.member(b, env)?.into(),
             (Value::Char(a), Value::Char(b)) => a.member(b, env)?.into(),
             (Value::Func(a), Value::Func(b)) => a.member(b, env)?.into(),
-            (Value::Num(a), Value::Byte(b)) => a.member(b, env)?.into(),
-            (Value::Byte(a), Value::Num(b)) => a.member(b, env)?.into(),
+            (Value::Num(a), Value::Byte(b)) => a.member(&b.clone().convert(), env)?.into(),
+            (Value::Byte(a), Value::Num(b)) => a.clone().convert().member(b, env)?.into(),
             (a, b) => {
                 return Err(env.error(format!(
                     "Cannot look for members of {} array in {} array",
@@ -1775,36 +1964,15 @@ impl Value {
 }
 
 impl<T: ArrayValue> Array<T> {
-    pub fn member<U>(&self, of: &Array<U>, env: &Uiua) -> UiuaResult<Array<u8>>
-    where
-        T: ArrayCmp<U>,
-        U: ArrayValue,
-    {
+    pub fn member(&self, of: &Self, env: &Uiua) -> UiuaResult<Array<u8>> {
         let elems = self;
         Ok(match elems.rank().cmp(&of.rank()) {
-            Ordering::Equal => {
-                let mut result_data = EcoVec::with_capacity(elems.row_count());
-                if elems.rank() == 1 {
-                    for elem in &elems.data {
-                        result_data.push(of.data.iter().any(|of| elem.array_eq(of)) as u8);
-                    }
-                    return Ok(Array::from(result_data));
-                }
-                'elem: for elem in elems.row_slices() {
-                    for of in of.row_slices() {
-                        if elem.len() == of.len() && elem.iter().zip(of).all(|(a, b)| a.array_eq(b))
-                        {
-                            result_data.push(1);
-                            continue 'elem;
-                        }
-                    }
-                    result_data.push(0);
-                }
-                let shape: Shape = self.shape.iter().cloned().take(1).collect();
-                let res = Array::new(shape, result_data);
-                res.validate_shape();
-                res
+            Ordering::Equal | Ordering::Greater if of.rank() > 0 => {
+                let (shape, indices) = row_lookup(elems, of);
+                let data: EcoVec<u8> = indices.into_iter().map(|i| i.is_some() as u8).collect();
+                Array::new(shape, data)
             }
+            Ordering::Equal => Array::from(elems.data[0].array_eq(&of.data[0]) as u8),
             Ordering::Greater => {
                 let mut rows = Vec::with_capacity(elems.row_count());
                 for elem in elems.rows() {
@@ -1832,6 +2000,37 @@ impl<T: ArrayValue> Array<T> {
     }
 }
 
+/// Find the index of the first row of `searched_in` that matches each
+/// row-sized cell of `searched_for`
+///
+/// Returns the shape of the cells' frame along with the indices
+fn row_lookup<T: ArrayValue>(
+    searched_for: &Array<T>,
+    searched_in: &Array<T>,
+) -> (Shape, Vec<Option<usize>>) {
+    let frame_rank = searched_for.rank() + 1 - searched_in.rank();
+    let shape: Shape = searched_for.shape[..frame_rank].iter().copied().collect();
+    let cell_count: usize = shape.iter().product();
+    if searched_for.shape[frame_rank..] != searched_in.shape[1..] {
+        return (shape, vec![None; cell_count]);
+    }
+    let cell_len = searched_in.row_len();
+    if cell_len == 0 {
+        let index = (searched_in.row_count() > 0).then_some(0);
+        return (shape, vec![index; cell_count]);
+    }
+    let mut rows = HashMap::with_capacity(searched_in.row_count());
+    for (i, row) in searched_in.row_slices().enumerate() {
+        rows.entry(RowKey(row)).or_insert(i);
+    }
+    let indices = searched_for
+        .data
+        .chunks_exact(cell_len)
+        .map(|cell| rows.get(&RowKey(cell)).copied())
+        .collect();
+    (shape, indices)
+}
+
 impl Value {
     pub fn index_of(&self, searched_in: &Value, env: &Uiua) -> UiuaResult<Value> {
         Ok(match (self, searched_in) {
@@ -1856,35 +2055,17 @@ impl<T: ArrayValue> Array<T> {
     fn index_of(&self, searched_in: &Array<T>, env: &Uiua) -> UiuaResult<Array<f64>> {
         let searched_for = self;
         Ok(match searched_for.rank().cmp(&searched_in.rank()) {
+            Ordering::Equal | Ordering::Greater if searched_in.rank() > 0 => {
+                let (shape, indices) = row_lookup(searched_for, searched_in);
+                let not_found = searched_in.row_count();
+                let data: EcoVec<f64> = indices
+                    .into_iter()
+                    .map(|i| i.unwrap_or(not_found) as f64)
+                    .collect();
+                Array::new(shape, data)
+            }
             Ordering::Equal => {
-                let mut result_data = EcoVec::with_capacity(searched_for.row_count());
-                if searched_for.rank() == 1 {
-                    for elem in &searched_for.data {
-                        result_data.push(
-                            searched_in
-                                .data
-                                .iter()
-                                .position(|of| elem.array_eq(of))
-                                .unwrap_or(searched_in.row_count())
-                                as f64,
-                        );
-                    }
-                    return Ok(Array::from(result_data));
-                }
-                'elem: for elem in searched_for.row_slices() {
-                    for (i, of) in searched_in.row_slices().enumerate() {
-                        if elem.len() == of.len() && elem.iter().zip(of).all(|(a, b)| a.array_eq(b))
-                        {
-                            result_data.push(i as f64);
-                            continue 'elem;
-                        }
-                    }
-                    result_data.push(searched_in.row_count() as f64);
-                }
-                let shape: Shape = self.shape.iter().cloned().take(1).collect();
-                let res = Array::new(shape, result_data);
-                res.validate_shape();
-                res
+                Array::from(!searched_for.data[0].array_eq(&searched_in.data[0]) as u8 as f64)
             }
             Ordering::Greater => {
                 let mut rows = Vec::with_capacity(searched_for.row_count());
@@ -1926,3 +2107,90 @@ impl<T: ArrayValue> Array<T> {
         })
     }
 }
+
+impl Value {
+    /// Get the matrix product of two arrays
+    pub fn matrix_product(&self, other: &Self, env: &Uiua) -> UiuaResult<Self> {
+        Ok(match (self, other) {
+            (Value::Num(a), Value::Num(b)) => a.matrix_product(b, env)?.into(),
+            (Value::Num(a), Value::Byte(b)) => a.matrix_product(&b.convert_ref(), env)?.into(),
+            (Value::Byte(a), Value::Num(b)) => a.convert_ref().matrix_product(b, env)?.into(),
+            (Value::Byte(a), Value::Byte(b)) => a
+                .convert_ref()
+                .matrix_product(&b.convert_ref(), env)?
+                .into(),
+            (a, b) => {
+                return Err(env.error(format!(
+                    "Cannot get the matrix product of {} and {} arrays",
+                    a.type_name(),
+                    b.type_name()
+                )))
+            }
+        })
+    }
+}
+
+impl Array<f64> {
+    /// Get the matrix product of two arrays
+    ///
+    /// A rank `1` array is treated as a row vector on the left and a column vector on the right.
+    pub fn matrix_product(&self, other: &Self, env: &Uiua) -> UiuaResult<Self> {
+        let mismatch = || {
+            env.error(format!(
+                "Cannot get the matrix product of arrays with shapes {} and {}",
+                self.format_shape(),
+                other.format_shape()
+            ))
+        };
+        let (n, k) = match self.shape() {
+            [k] => (1, *k),
+            [n, k] => (*n, *k),
+            _ => return Err(mismatch()),
+        };
+        let m = match other.shape() {
+            [k2] if *k2 == k => 1,
+            [k2, m] if *k2 == k => *m,
+            _ => return Err(mismatch()),
+        };
+        let mut data = eco_vec![0.0; n * m];
+        let slice = data.make_mut();
+        // Iterating over the inner dimension in the middle loop means the innermost
+        // loop runs over contiguous rows of both the right matrix and the output
+        for (out_row, a_row) in
+            (slice.chunks_exact_mut(m.max(1))).zip(self.data.chunks_exact(k.max(1)))
+        {
+            for (&a_elem, b_row) in a_row.iter().zip(other.data.chunks_exact(m.max(1))) {
+                for (out, &b_elem) in out_row.iter_mut().zip(b_row) {
+                    *out += a_elem * b_elem;
+                }
+            }
+        }
+        let shape: Shape = (self.rank() == 2)
+            .then_some(n)
+            .into_iter()
+            .chain((other.rank() == 2).then_some(m))
+            .collect();
+        Ok(Array::new(shape, data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::tests::error_message;
+
+    #[test]
+    fn matmul_errors() {
+        assert_eq!(
+            error_message("matmul [1_2 3_4] [1_2 3_4 5_6]"),
+            "1:1: Cannot get the matrix product of arrays with shapes [2 × 2] and [3 × 2]"
+        );
+        assert_eq!(
+            error_message("matmul ↯2_2_2 0 [1 2]"),
+            "1:1: Cannot get the matrix product of arrays with shapes [2 × 2 × 2] and [2]"
+        );
+        assert_eq!(
+            error_message("matmul \"ab\" [1 2]"),
+            "1:1: Cannot get the matrix product of character and number arrays"
+        );
+    }
+}
@@ -149,14 +149,12 @@ pub fn iff(env: &mut Uiua) -> UiuaResult {
         let if_false_sig = if_false.signature();
         if if_true_sig.outputs != 1 {
             return Err(env.error(format!(
-                "If's true branch must return 1 value, but it returns {}",
-                if_true_sig.outputs
+                "If's true branch must return 1 value, but its signature is {if_true_sig}"
             )));
         }
         if if_false_sig.outputs != 1 {
             return Err(env.error(format!(
-                "If's false branch must return 1 value, but it returns {}",
-                if_false_sig.outputs
+                "If's false branch must return 1 value, but its signature is {if_false_sig}"
             )));
         }
         let arg_count = if_true_sig.args.max(if_false_sig.args);
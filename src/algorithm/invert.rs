@@ -4,7 +4,7 @@ use std::{cell::RefCell, collections::HashMap, fmt};
 
 use crate::{
     check::instrs_signature,
-    function::{Function, Instr},
+    function::{Function, FunctionId, Instr},
     primitive::Primitive,
     value::Value,
 };
@@ -81,7 +81,7 @@ fn invert_instr_fragment(mut instrs: &[Instr]) -> Option<Vec<Instr>> {
         }
         [gi @ Push(g), fi @ Push(f), Prim(Bind, _)] => {
             let mut instrs = if let Some(g) = g.as_function() {
-                g.instrs.clone()
+                g.instrs.to_vec()
             } else {
                 vec![gi.clone()]
             };
@@ -107,6 +107,7 @@ fn invert_instr_fragment(mut instrs: &[Instr]) -> Option<Vec<Instr>> {
         &invert_pow_pattern,
         &invert_log_pattern,
         &invert_repeat_pattern,
+        &invert_join_sep_pattern,
     ];
 
     let mut inverted = Vec::new();
@@ -155,7 +156,7 @@ fn under_instrs_impl(instrs: &[Instr]) -> Option<(Vec<Instr>, Vec<Instr>)> {
     match instrs {
         [gi @ Push(g), fi @ Push(f), Prim(Bind, _)] => {
             let mut instrs = if let Some(g) = g.as_function() {
-                g.instrs.clone()
+                g.instrs.to_vec()
             } else {
                 vec![gi.clone()]
             };
@@ -605,6 +606,21 @@ fn invert_repeat_pattern(input: &[Instr]) -> Option<(&[Instr], Vec<Instr>)> {
     }
 }
 
+fn invert_join_sep_pattern(input: &[Instr]) -> Option<(&[Instr], Vec<Instr>)> {
+    let (input, mut instrs) = Val.invert_extract(input)?;
+    let (Instr::Prim(Primitive::JoinSep, span), input) = input.split_first()? else {
+        return None;
+    };
+    let boxed = Function::new_inferred(
+        FunctionId::Primitive(Primitive::Box),
+        [Instr::Prim(Primitive::Box, *span)],
+    )
+    .ok()?;
+    instrs.push(Instr::push(boxed));
+    instrs.push(Instr::Prim(Primitive::Split, *span));
+    Some((input, instrs))
+}
+
 #[derive(Debug)]
 struct Val;
 impl InvertPattern for Val {
@@ -658,3 +674,21 @@ impl UnderPattern for Val {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::error::tests::error_message;
+
+    #[test]
+    fn inverse_errors() {
+        assert_eq!(error_message("⍘⧻ 3"), "1:1: No inverse found for ⧻");
+        assert_eq!(
+            error_message("F ← ⧻⇌\n⍘F [1]"),
+            "2:1: No inverse found for F"
+        );
+        assert_eq!(
+            error_message("⍜⧻(+1) [1]"),
+            "1:1: ⧻ has no inverse, so it cannot be used with under"
+        );
+    }
+}
@@ -1,12 +1,22 @@
 //! Algorithms for looping modifiers
 
-use std::ops::{Add, Div, Mul, Sub};
+#[cfg(feature = "parallel")]
+use std::sync::{
+    atomic::{self, AtomicUsize},
+    Arc,
+};
+use std::{
+    cell::Cell,
+    ops::{Add, Div, Mul, Sub},
+};
 
 use ecow::EcoVec;
+#[cfg(feature = "parallel")]
+use rayon::{prelude::*, ThreadPool};
 use tinyvec::tiny_vec;
 
 use crate::{
-    algorithm::pervade::bin_pervade_generic,
+    algorithm::pervade::{self, bin_pervade_generic},
     array::{Array, ArrayValue, FormatShape, Shape},
     cowslice::cowslice,
     primitive::Primitive,
@@ -15,6 +25,82 @@ use crate::{
     Uiua, UiuaResult,
 };
 
+/// The minimum number of values for [`Primitive::Rows`] or [`Primitive::Each`] to use multiple threads
+#[cfg(feature = "parallel")]
+const PARALLEL_THRESHOLD: usize = 256;
+
+/// Get the thread pool to apply a function with, if it is worth doing so
+#[cfg(feature = "parallel")]
+fn parallel_pool(f: &Value, count: usize, env: &Uiua) -> Option<Arc<ThreadPool>> {
+    let pool = env.thread_pool.as_ref()?;
+    (count >= PARALLEL_THRESHOLD && f.is_pure()).then(|| pool.clone())
+}
+
+/// Apply a function to each value on a thread pool, keeping the results in order
+///
+/// If any call fails, the error from the first failing value is returned,
+/// and that value's index is set as the row being processed.
+#[cfg(feature = "parallel")]
+fn par_map(
+    pool: &ThreadPool,
+    f: &Value,
+    values: Vec<Value>,
+    result_name: &'static str,
+    env: &mut Uiua,
+) -> UiuaResult<Vec<Value>> {
+    // Several chunks per thread keep the threads busy if some values take longer than others
+    let chunk_size = values.len().div_ceil(pool.current_num_threads() * 4);
+    let count = values.len();
+    let mut values = values.into_iter();
+    let mut jobs = Vec::new();
+    while values.len() > 0 {
+        let start = count - values.len();
+        let chunk: Vec<Value> = values.by_ref().take(chunk_size).collect();
+        jobs.push((env.thread_env(Vec::new()), start, chunk));
+    }
+    // Each chunk stops at its first failing call, whose row is shown in the error's trace.
+    // Values after the first failing one so far are skipped, because their results are not needed.
+    let first_failure = AtomicUsize::new(usize::MAX);
+    let results: Vec<(usize, Vec<Value>, UiuaResult)> = pool.install(|| {
+        jobs.into_par_iter()
+            .map(|(mut env, start, chunk)| {
+                let mut mapped = Vec::with_capacity(chunk.len());
+                for (i, value) in (start..).zip(chunk) {
+                    if i > first_failure.load(atomic::Ordering::Relaxed) {
+                        break;
+                    }
+                    env.push(value);
+                    let res = (env.call(f.clone())).and_then(|()| env.pop(result_name));
+                    match res {
+                        Ok(value) => mapped.push(value),
+                        Err(e) => {
+                            first_failure.fetch_min(i, atomic::Ordering::Relaxed);
+                            return (start, mapped, Err(e));
+                        }
+                    }
+                }
+                (start, mapped, Ok(()))
+            })
+            .collect()
+    });
+    let mut mapped = Vec::with_capacity(count);
+    for (start, values, res) in results {
+        if let Err(e) = res {
+            env.set_row(start + values.len());
+            return Err(e);
+        }
+        mapped.extend(values);
+    }
+    Ok(mapped)
+}
+
+/// Get the primitive a function is, if the fast paths may be used for it
+///
+/// In strict math mode, division goes through the generic paths so it can be checked.
+fn fast_primitive(f: &Value, env: &Uiua) -> Option<(Primitive, bool)> {
+    (f.as_flipped_primitive()).filter(|(prim, _)| !(env.strict_math && *prim == Primitive::Div))
+}
+
 fn flip<A, B, C>(f: impl Fn(A, B) -> C) -> impl Fn(B, A) -> C {
     move |b, a| f(a, b)
 }
@@ -24,9 +110,9 @@ pub fn reduce(env: &mut Uiua) -> UiuaResult {
     let f = env.pop(FunctionArg(1))?;
     let xs = env.pop(ArrayArg(1))?;
 
-    match (f.as_flipped_primitive(), xs) {
+    match (fast_primitive(&f, env), xs) {
         (Some((prim, flipped)), Value::Num(nums)) => env.push(match prim {
-            Primitive::Add => fast_reduce(nums, 0.0, Add::add),
+            Primitive::Add => fast_sum(nums),
             Primitive::Sub if flipped => fast_reduce(nums, 0.0, Sub::sub),
             Primitive::Sub => fast_reduce(nums, 0.0, flip(Sub::sub)),
             Primitive::Mul => fast_reduce(nums, 1.0, Mul::mul),
@@ -37,14 +123,17 @@ pub fn reduce(env: &mut Uiua) -> UiuaResult {
             _ => return generic_fold1(f, Value::Num(nums), None, env),
         }),
         (Some((prim, flipped)), Value::Byte(bytes)) => env.push(match prim {
-            Primitive::Add => fast_reduce(bytes.convert(), 0.0, |a, b| a + b),
-            Primitive::Sub if flipped => fast_reduce(bytes.convert(), 0.0, |a, b| a - b),
-            Primitive::Sub => fast_reduce(bytes.convert(), 0.0, |a, b| b - a),
-            Primitive::Mul => fast_reduce(bytes.convert(), 1.0, |a, b| a * b),
-            Primitive::Div if flipped => fast_reduce(bytes.convert(), 1.0, |a, b| a / b),
-            Primitive::Div => fast_reduce(bytes.convert(), 1.0, |a, b| b / a),
-            Primitive::Max => fast_reduce(bytes.convert(), f64::NEG_INFINITY, |a, b| a.max(b)),
-            Primitive::Min => fast_reduce(bytes.convert(), f64::INFINITY, |a, b| a.min(b)),
+            Primitive::Add => Value::from(fast_sum(bytes.convert())),
+            Primitive::Sub if flipped => fast_reduce(bytes.convert(), 0.0, |a, b| a - b).into(),
+            Primitive::Sub => fast_reduce(bytes.convert(), 0.0, |a, b| b - a).into(),
+            Primitive::Mul => fast_reduce(bytes.convert(), 1.0, |a, b| a * b).into(),
+            Primitive::Div if flipped => fast_reduce(bytes.convert(), 1.0, |a, b| a / b).into(),
+            Primitive::Div => fast_reduce(bytes.convert(), 1.0, |a, b| b / a).into(),
+            // The maximum and minimum of bytes are bytes, but the identities are not
+            Primitive::Max if bytes.row_count() > 0 => fast_reduce(bytes, 0, u8::max).into(),
+            Primitive::Min if bytes.row_count() > 0 => fast_reduce(bytes, u8::MAX, u8::min).into(),
+            Primitive::Max => fast_reduce(bytes.convert(), f64::NEG_INFINITY, f64::max).into(),
+            Primitive::Min => fast_reduce(bytes.convert(), f64::INFINITY, f64::min).into(),
             _ => return generic_fold1(f, Value::Byte(bytes), None, env),
         }),
         (_, xs) => generic_fold1(f, xs, None, env)?,
@@ -52,6 +141,9 @@ pub fn reduce(env: &mut Uiua) -> UiuaResult {
     Ok(())
 }
 
+/// Reduce an array with a primitive function without calling it through the interpreter
+///
+/// The identity is only used when the array has no rows.
 pub fn fast_reduce<T>(mut arr: Array<T>, identity: T, f: impl Fn(T, T) -> T) -> Array<T>
 where
     T: ArrayValue + Copy,
@@ -59,10 +151,12 @@ where
     match arr.shape.len() {
         0 => arr,
         1 => {
-            let data = arr.data.as_mut_slice();
-            let folded = data.iter().copied().fold(identity, f);
-            data[0] = folded;
+            let Some((&first, rest)) = arr.data.split_first() else {
+                return Array::new(Shape::default(), cowslice![identity]);
+            };
+            let folded = rest.iter().fold(first, |acc, &x| f(acc, x));
             arr.data.truncate(1);
+            arr.data.as_mut_slice()[0] = folded;
             arr.shape = Shape::default();
             arr
         }
@@ -89,6 +183,42 @@ where
     }
 }
 
+/// The number of independent partial sums used by [`fast_sum`]
+const SUM_LANES: usize = 8;
+
+/// Sum the rows of an array
+///
+/// Lists are summed in several interleaved partial sums that are combined at the end.
+/// This is faster and accumulates less rounding error than summing in order,
+/// so the result may differ from reducing with a non-primitive function in the last few bits.
+pub fn fast_sum(mut arr: Array<f64>) -> Array<f64> {
+    if arr.rank() != 1 || arr.data.len() < SUM_LANES * 2 {
+        return fast_reduce(arr, 0.0, Add::add);
+    }
+    let mut lanes = [0.0; SUM_LANES];
+    let mut chunks = arr.data.chunks_exact(SUM_LANES);
+    for chunk in chunks.by_ref() {
+        for (lane, &x) in lanes.iter_mut().zip(chunk) {
+            *lane += x;
+        }
+    }
+    for (lane, &x) in lanes.iter_mut().zip(chunks.remainder()) {
+        *lane += x;
+    }
+    // Combine the lanes pairwise
+    let mut width = SUM_LANES;
+    while width > 1 {
+        width /= 2;
+        for i in 0..width {
+            lanes[i] += lanes[i + width];
+        }
+    }
+    arr.data.truncate(1);
+    arr.data.as_mut_slice()[0] = lanes[0];
+    arr.shape = Shape::default();
+    arr
+}
+
 pub fn fold(env: &mut Uiua) -> UiuaResult {
     crate::profile_function!();
     let f = env.pop(FunctionArg(1))?;
@@ -105,9 +235,9 @@ pub fn fold(env: &mut Uiua) -> UiuaResult {
 fn generic_fold1(f: Value, xs: Value, init: Option<Value>, env: &mut Uiua) -> UiuaResult {
     let sig = f.signature();
     if sig.outputs > 1 {
+        let name = if init.is_some() { "Fold" } else { "Reduce" };
         return Err(env.error(format!(
-            "Reduce's function must return 0 or 1 values, but {} returns {}",
-            f, sig.outputs
+            "{name}'s function must return 0 or 1 values, but its signature is {sig}"
         )));
     }
     let args = sig.args;
@@ -144,10 +274,11 @@ fn generic_fold1(f: Value, xs: Value, init: Option<Value>, env: &mut Uiua) -> Ui
             }
             env.push(acc);
         }
-        args => {
+        _ => {
+            let name = if init.is_some() { "Fold" } else { "Reduce" };
             return Err(env.error(format!(
-                "Cannot reduce a function that takes {args} arguments"
-            )))
+                "{name}'s function must take at most 2 arguments, but its signature is {sig}"
+            )));
         }
     }
     Ok(())
@@ -158,8 +289,7 @@ fn generic_fold_n(f: Value, env: &mut Uiua) -> UiuaResult {
     if sig.args.saturating_sub(sig.outputs) != 1 {
         return Err(env.error(format!(
             "Fold's function must take 1 more argument than it returns, \
-            but it takes {} and returns {}",
-            sig.args, sig.outputs
+            but its signature is {sig}"
         )));
     }
     let mut accs = Vec::with_capacity(sig.outputs);
@@ -188,94 +318,114 @@ fn generic_fold_n(f: Value, env: &mut Uiua) -> UiuaResult {
 
 pub fn scan(env: &mut Uiua) -> UiuaResult {
     crate::profile_function!();
+    scan_impl(false, env)
+}
+
+/// Scan from the end of an array to the beginning
+///
+/// This is what `under``reverse``scan` compiles to.
+pub fn reverse_scan(env: &mut Uiua) -> UiuaResult {
+    crate::profile_function!();
+    scan_impl(true, env)
+}
+
+fn scan_impl(reverse: bool, env: &mut Uiua) -> UiuaResult {
     let f = env.pop(FunctionArg(1))?;
     let xs = env.pop(ArrayArg(1))?;
     if xs.rank() == 0 {
         return Err(env.error("Cannot scan rank 0 array"));
     }
-    match (f.as_flipped_primitive(), xs) {
+    match (fast_primitive(&f, env), xs) {
         (Some((prim, flipped)), Value::Num(nums)) => {
             let arr = match prim {
-                Primitive::Add => fast_scan(nums, Add::add),
-                Primitive::Sub if flipped => fast_scan(nums, Sub::sub),
-                Primitive::Sub => fast_scan(nums, flip(Sub::sub)),
-                Primitive::Mul => fast_scan(nums, Mul::mul),
-                Primitive::Div if flipped => fast_scan(nums, Div::div),
-                Primitive::Div => fast_scan(nums, flip(Div::div)),
-                Primitive::Max => fast_scan(nums, f64::max),
-                Primitive::Min => fast_scan(nums, f64::min),
-                _ => return generic_scan(f, Value::Num(nums), env),
+                Primitive::Add => fast_scan(nums, reverse, Add::add),
+                Primitive::Sub if flipped => fast_scan(nums, reverse, Sub::sub),
+                Primitive::Sub => fast_scan(nums, reverse, flip(Sub::sub)),
+                Primitive::Mul => fast_scan(nums, reverse, Mul::mul),
+                Primitive::Div if flipped => fast_scan(nums, reverse, Div::div),
+                Primitive::Div => fast_scan(nums, reverse, flip(Div::div)),
+                Primitive::Max => fast_scan(nums, reverse, f64::max),
+                Primitive::Min => fast_scan(nums, reverse, f64::min),
+                Primitive::Eq => {
+                    fast_scan(nums, reverse, |a, b| pervade::is_eq::num_num(a, b).into())
+                }
+                Primitive::Ne => {
+                    fast_scan(nums, reverse, |a, b| pervade::is_ne::num_num(a, b).into())
+                }
+                _ => return generic_scan(f, Value::Num(nums), reverse, env),
             };
             env.push(arr);
             Ok(())
         }
         (Some((prim, flipped)), Value::Byte(bytes)) => {
             match prim {
-                Primitive::Add => env.push(fast_scan::<f64>(bytes.convert(), Add::add)),
-                Primitive::Sub if flipped => env.push(fast_scan::<f64>(bytes.convert(), Sub::sub)),
-                Primitive::Sub => env.push(fast_scan::<f64>(bytes.convert(), flip(Sub::sub))),
-                Primitive::Mul => env.push(fast_scan::<f64>(bytes.convert(), Mul::mul)),
-                Primitive::Div if flipped => env.push(fast_scan::<f64>(bytes.convert(), Div::div)),
-                Primitive::Div => env.push(fast_scan::<f64>(bytes.convert(), flip(Div::div))),
-                Primitive::Max => env.push(fast_scan(bytes, u8::max)),
-                Primitive::Min => env.push(fast_scan(bytes, u8::min)),
-                _ => return generic_scan(f, Value::Byte(bytes), env),
+                Primitive::Add => env.push(fast_scan::<f64>(bytes.convert(), reverse, Add::add)),
+                Primitive::Sub if flipped => {
+                    env.push(fast_scan::<f64>(bytes.convert(), reverse, Sub::sub))
+                }
+                Primitive::Sub => {
+                    env.push(fast_scan::<f64>(bytes.convert(), reverse, flip(Sub::sub)))
+                }
+                Primitive::Mul => env.push(fast_scan::<f64>(bytes.convert(), reverse, Mul::mul)),
+                Primitive::Div if flipped => {
+                    env.push(fast_scan::<f64>(bytes.convert(), reverse, Div::div))
+                }
+                Primitive::Div => {
+                    env.push(fast_scan::<f64>(bytes.convert(), reverse, flip(Div::div)))
+                }
+                Primitive::Max => env.push(fast_scan(bytes, reverse, u8::max)),
+                Primitive::Min => env.push(fast_scan(bytes, reverse, u8::min)),
+                Primitive::Eq => env.push(fast_scan(bytes, reverse, pervade::is_eq::generic)),
+                Primitive::Ne => env.push(fast_scan(bytes, reverse, pervade::is_ne::generic)),
+                _ => return generic_scan(f, Value::Byte(bytes), reverse, env),
             }
             Ok(())
         }
-        (_, xs) => generic_scan(f, xs, env),
+        (_, xs) => generic_scan(f, xs, reverse, env),
     }
 }
 
-fn fast_scan<T>(mut arr: Array<T>, f: impl Fn(T, T) -> T) -> Array<T>
+fn fast_scan<T>(mut arr: Array<T>, reverse: bool, f: impl Fn(T, T) -> T) -> Array<T>
 where
     T: ArrayValue + Copy,
 {
-    match arr.shape.len() {
-        0 => unreachable!("fast_scan called on unit array, should have been guarded against"),
-        1 => {
-            if arr.row_count() == 0 {
-                return arr;
-            }
-            let mut acc = arr.data[0];
-            for val in arr.data.as_mut_slice().iter_mut().skip(1) {
-                acc = f(acc, *val);
-                *val = acc;
+    if arr.row_count() == 0 {
+        return arr;
+    }
+    let row_len = arr.row_len();
+    let row_count = arr.row_count();
+    let data = arr.data.as_mut_slice();
+    // Each row is combined with the previously scanned row in place
+    if reverse {
+        for i in (0..row_count - 1).rev() {
+            for j in 0..row_len {
+                data[i * row_len + j] = f(data[(i + 1) * row_len + j], data[i * row_len + j]);
             }
-            arr
         }
-        _ => {
-            let row_len: usize = arr.row_len();
-            if arr.row_count() == 0 {
-                return arr;
-            }
-            let shape = arr.shape.clone();
-            let mut new_data = EcoVec::with_capacity(arr.data.len());
-            let mut rows = arr.into_rows();
-            new_data.extend(rows.next().unwrap().data);
-            for row in rows {
-                let start = new_data.len() - row_len;
-                for (i, r) in row.data.into_iter().enumerate() {
-                    new_data.push(f(new_data[start + i], r));
-                }
+    } else {
+        for i in 1..row_count {
+            for j in 0..row_len {
+                data[i * row_len + j] = f(data[(i - 1) * row_len + j], data[i * row_len + j]);
             }
-            Array::new(shape, new_data)
         }
     }
+    arr
 }
 
-fn generic_scan(f: Value, xs: Value, env: &mut Uiua) -> UiuaResult {
+fn generic_scan(f: Value, mut xs: Value, reverse: bool, env: &mut Uiua) -> UiuaResult {
     let sig = f.signature();
     if sig.outputs != 1 {
         return Err(env.error(format!(
-            "Scan's function must return 1 value, but {} returns {}",
-            f, sig.outputs
+            "Scan's function must return 1 value, but its signature is {sig}"
         )));
     }
     if xs.row_count() == 0 {
         env.push(xs.first_dim_zero());
         return Ok(());
     }
+    if reverse {
+        xs.reverse();
+    }
     let row_count = xs.row_count();
     let mut rows = xs.into_rows();
     let mut acc = rows.next().unwrap();
@@ -293,10 +443,11 @@ fn generic_scan(f: Value, xs: Value, env: &mut Uiua) -> UiuaResult {
             break;
         }
     }
-    env.push(Value::from_row_values(
-        scanned.into_iter().chain(rows),
-        env,
-    )?);
+    let mut scanned = Value::from_row_values(scanned.into_iter().chain(rows), env)?;
+    if reverse {
+        scanned.reverse();
+    }
+    env.push(scanned);
     Ok(())
 }
 
@@ -307,10 +458,9 @@ pub fn each(env: &mut Uiua) -> UiuaResult {
     let output = match sig.outputs {
         0 => false,
         1 => true,
-        n => {
+        _ => {
             return Err(env.error(format!(
-                "Each's function must return 0 or 1 values, but it returns {}",
-                n
+                "Each's function must return 0 or 1 values, but its signature is {sig}"
             )))
         }
     };
@@ -325,8 +475,9 @@ pub fn each(env: &mut Uiua) -> UiuaResult {
             }
         }
         2 => {
-            let xs = env.pop(ArrayArg(1))?;
-            let ys = env.pop(ArrayArg(2))?;
+            let mut xs = env.pop(ArrayArg(1))?;
+            let mut ys = env.pop(ArrayArg(2))?;
+            pervade::fill_value_shapes(&mut xs, &mut ys, &*env)?;
             if output {
                 each2_1(f, xs, ys, env)
             } else {
@@ -348,11 +499,22 @@ pub fn each(env: &mut Uiua) -> UiuaResult {
 }
 
 fn each1_1(f: Value, xs: Value, env: &mut Uiua) -> UiuaResult {
-    let mut new_values = Vec::with_capacity(xs.flat_len());
     let mut new_shape = Shape::from(xs.shape());
+    #[cfg(feature = "parallel")]
+    if let Some(pool) = parallel_pool(&f, xs.flat_len(), env) {
+        let values = xs.into_flat_values().collect();
+        let new_values = par_map(&pool, &f, values, "each's function result", env)?;
+        let mut eached = Value::from_row_values(new_values, env)?;
+        new_shape.extend_from_slice(&eached.shape()[1..]);
+        *eached.shape_mut() = new_shape;
+        env.push(eached);
+        return Ok(());
+    }
+    let mut new_values = Vec::with_capacity(xs.flat_len());
     let mut old_values = xs.into_flat_values();
-    for val in old_values.by_ref() {
+    for (i, val) in old_values.by_ref().enumerate() {
         env.push(val);
+        env.set_row(i);
         let broke = env.call_catch_break(f.clone())?;
         new_values.push(env.pop("each's function result")?);
         if broke {
@@ -371,8 +533,9 @@ fn each1_1(f: Value, xs: Value, env: &mut Uiua) -> UiuaResult {
 
 fn each1_0(f: Value, xs: Value, env: &mut Uiua) -> UiuaResult {
     let values = xs.into_flat_values();
-    for val in values {
+    for (i, val) in values.enumerate() {
         env.push(val);
+        env.set_row(i);
         if env.call_catch_break(f.clone())? {
             break;
         }
@@ -385,6 +548,7 @@ fn each2_1(f: Value, xs: Value, ys: Value, env: &mut Uiua) -> UiuaResult {
     let ys_shape = ys.shape().to_vec();
     let xs_values: Vec<_> = xs.into_flat_values().collect();
     let ys_values: Vec<_> = ys.into_flat_values().collect();
+    let index = Cell::new(0);
     let (mut shape, values) = bin_pervade_generic(
         &xs_shape,
         xs_values,
@@ -394,6 +558,7 @@ fn each2_1(f: Value, xs: Value, ys: Value, env: &mut Uiua) -> UiuaResult {
         |x, y, env| {
             env.push(y);
             env.push(x);
+            env.set_row(index.replace(index.get() + 1));
             env.call_error_on_break(f.clone(), "break is not allowed in multi-argument each")?;
             env.pop("each's function result")
         },
@@ -410,6 +575,7 @@ fn each2_0(f: Value, xs: Value, ys: Value, env: &mut Uiua) -> UiuaResult {
     let ys_shape = ys.shape().to_vec();
     let xs_values: Vec<_> = xs.into_flat_values().collect();
     let ys_values: Vec<_> = ys.into_flat_values().collect();
+    let index = Cell::new(0);
     bin_pervade_generic(
         &xs_shape,
         xs_values,
@@ -419,6 +585,7 @@ fn each2_0(f: Value, xs: Value, ys: Value, env: &mut Uiua) -> UiuaResult {
         |x, y, env| {
             env.push(y);
             env.push(x);
+            env.set_row(index.replace(index.get() + 1));
             env.call_error_on_break(f.clone(), "break is not allowed multi-argument in each")?;
             Ok(())
         },
@@ -440,10 +607,11 @@ fn eachn_1(f: Value, args: Vec<Value>, env: &mut Uiua) -> UiuaResult {
     let elem_count = args[0].flat_len();
     let mut arg_elems: Vec<_> = args.into_iter().map(|v| v.into_flat_values()).collect();
     let mut new_values = Vec::new();
-    for _ in 0..elem_count {
+    for i in 0..elem_count {
         for arg in arg_elems.iter_mut().rev() {
             env.push(arg.next().unwrap());
         }
+        env.set_row(i);
         env.call_error_on_break(f.clone(), "break is not allowed in multi-argument each")?;
         new_values.push(env.pop("each's function result")?);
     }
@@ -465,10 +633,11 @@ fn eachn_0(f: Value, args: Vec<Value>, env: &mut Uiua) -> UiuaResult {
     }
     let elem_count = args[0].flat_len();
     let mut arg_elems: Vec<_> = args.into_iter().map(|v| v.into_flat_values()).collect();
-    for _ in 0..elem_count {
+    for i in 0..elem_count {
         for arg in arg_elems.iter_mut().rev() {
             env.push(arg.next().unwrap());
         }
+        env.set_row(i);
         env.call_error_on_break(f.clone(), "break is not allowed in multi-argument each")?;
     }
     Ok(())
@@ -481,17 +650,24 @@ pub fn rows(env: &mut Uiua) -> UiuaResult {
     let output = match sig.outputs {
         0 => false,
         1 => true,
-        n => {
+        _ => {
             return Err(env.error(format!(
-                "Rows's function must return 0 or 1 values, but it returns {}",
-                n
+                "Rows's function must return 0 or 1 values, but its signature is {sig}"
             )))
         }
     };
     match sig.args {
         0 => Ok(()),
         1 => {
-            let xs = env.pop(ArrayArg(1))?;
+            let mut xs = env.pop(ArrayArg(1))?;
+            // Boxed rows are reversed inside their boxes, so they cannot use this
+            if matches!(f.as_primitive(), Some((Primitive::Reverse, _)))
+                && !matches!(xs, Value::Func(_))
+            {
+                xs.reverse_depth(1);
+                env.push(xs);
+                return Ok(());
+            }
             if output {
                 rows1_1(f, xs, env)
             } else {
@@ -523,9 +699,19 @@ pub fn rows(env: &mut Uiua) -> UiuaResult {
 
 fn rows1_1(f: Value, xs: Value, env: &mut Uiua) -> UiuaResult {
     let mut new_rows = Value::builder(xs.row_count());
+    #[cfg(feature = "parallel")]
+    if let Some(pool) = parallel_pool(&f, xs.row_count(), env) {
+        let rows = xs.into_rows().collect();
+        for row in par_map(&pool, &f, rows, "rows' function result", env)? {
+            new_rows.add_row(row, &env)?;
+        }
+        env.push(new_rows.finish());
+        return Ok(());
+    }
     let mut old_rows = xs.into_rows();
-    for row in old_rows.by_ref() {
+    for (i, row) in old_rows.by_ref().enumerate() {
         env.push(row);
+        env.set_row(i);
         let broke = env.call_catch_break(f.clone())?;
         new_rows.add_row(env.pop("rows' function result")?, &env)?;
         if broke {
@@ -540,8 +726,9 @@ fn rows1_1(f: Value, xs: Value, env: &mut Uiua) -> UiuaResult {
 }
 
 fn rows1_0(f: Value, xs: Value, env: &mut Uiua) -> UiuaResult {
-    for row in xs.into_rows() {
+    for (i, row) in xs.into_rows().enumerate() {
         env.push(row);
+        env.set_row(i);
         let broke = env.call_catch_break(f.clone())?;
         if broke {
             break;
@@ -561,9 +748,10 @@ fn rows2_1(f: Value, xs: Value, ys: Value, env: &mut Uiua) -> UiuaResult {
     let mut new_rows = Vec::with_capacity(xs.row_count());
     let x_rows = xs.into_rows();
     let y_rows = ys.into_rows();
-    for (x, y) in x_rows.into_iter().zip(y_rows) {
+    for (i, (x, y)) in x_rows.into_iter().zip(y_rows).enumerate() {
         env.push(y);
         env.push(x);
+        env.set_row(i);
         env.call_error_on_break(f.clone(), "break is not allowed in multi-argument rows")?;
         new_rows.push(env.pop("rows's function result")?);
     }
@@ -581,9 +769,10 @@ fn rows2_0(f: Value, xs: Value, ys: Value, env: &mut Uiua) -> UiuaResult {
     }
     let x_rows = xs.into_rows();
     let y_rows = ys.into_rows();
-    for (x, y) in x_rows.into_iter().zip(y_rows) {
+    for (i, (x, y)) in x_rows.into_iter().zip(y_rows).enumerate() {
         env.push(y);
         env.push(x);
+        env.set_row(i);
         env.call_error_on_break(f.clone(), "break is not allowed in multi-argument rows")?;
     }
     Ok(())
@@ -603,10 +792,11 @@ fn rowsn_1(f: Value, args: Vec<Value>, env: &mut Uiua) -> UiuaResult {
     let row_count = args[0].row_count();
     let mut arg_elems: Vec<_> = args.into_iter().map(|v| v.into_rows()).collect();
     let mut new_values = Vec::new();
-    for _ in 0..row_count {
+    for i in 0..row_count {
         for arg in arg_elems.iter_mut().rev() {
             env.push(arg.next().unwrap());
         }
+        env.set_row(i);
         env.call_error_on_break(f.clone(), "break is not allowed in multi-argument each")?;
         new_values.push(env.pop("each's function result")?);
     }
@@ -618,10 +808,11 @@ fn rowsn_1(f: Value, args: Vec<Value>, env: &mut Uiua) -> UiuaResult {
 fn rowsn_0(f: Value, args: Vec<Value>, env: &mut Uiua) -> UiuaResult {
     let row_count = args[0].row_count();
     let mut arg_elems: Vec<_> = args.into_iter().map(|v| v.into_rows()).collect();
-    for _ in 0..row_count {
+    for i in 0..row_count {
         for arg in arg_elems.iter_mut().rev() {
             env.push(arg.next().unwrap());
         }
+        env.set_row(i);
         env.call_error_on_break(f.clone(), "break is not allowed in multi-argument each")?;
     }
     Ok(())
@@ -633,15 +824,14 @@ pub fn distribute(env: &mut Uiua) -> UiuaResult {
     let sig = f.signature();
     if sig.outputs != 1 {
         return Err(env.error(format!(
-            "Distribute's function must return 1 value, but it returns {}",
-            sig.outputs
+            "Distribute's function must return 1 value, but its signature is {sig}"
         )));
     }
     match sig.args {
-        n @ (0 | 1) => {
+        0 | 1 => {
             return Err(env.error(format!(
                 "Distribute's function must take at least 2 arguments, \
-                but it takes {n}"
+                but its signature is {sig}"
             )))
         }
         2 => {
@@ -712,7 +902,7 @@ pub fn table(env: &mut Uiua) -> UiuaResult {
     let f = env.pop(FunctionArg(1))?;
     let xs = env.pop(ArrayArg(1))?;
     let ys = env.pop(ArrayArg(2))?;
-    match (f.as_flipped_primitive(), xs, ys) {
+    match (fast_primitive(&f, env), xs, ys) {
         (Some((prim, flipped)), Value::Num(xs), Value::Num(ys)) => {
             if let Err((xs, ys)) = table_nums(prim, flipped, xs, ys, env) {
                 return generic_table(f, Value::Num(xs), Value::Num(ys), env);
@@ -828,8 +1018,7 @@ fn generic_table(f: Value, xs: Value, ys: Value, env: &mut Uiua) -> UiuaResult {
     let sig = f.signature();
     if sig.outputs != 1 {
         return Err(env.error(format!(
-            "Table's function must return 1 value, but {} returns {}",
-            f, sig.outputs
+            "Table's function must return 1 value, but its signature is {sig}"
         )));
     }
     let mut new_shape = Shape::from(xs.shape());
@@ -862,8 +1051,7 @@ pub fn cross(env: &mut Uiua) -> UiuaResult {
     let sig = f.signature();
     if sig.outputs != 1 {
         return Err(env.error(format!(
-            "Cross's function must return 1 value, but {} returns {}",
-            f, sig.outputs
+            "Cross's function must return 1 value, but its signature is {sig}"
         )));
     }
     let mut new_shape = tiny_vec![xs.row_count(), ys.row_count()];
@@ -935,8 +1123,7 @@ pub fn level(env: &mut Uiua) -> UiuaResult {
     let f_sig = f.signature();
     if f_sig.outputs != 1 {
         return Err(env.error(format!(
-            "Level's function must return 1 value, but it returns {}",
-            f_sig.outputs
+            "Level's function must return 1 value, but its signature is {f_sig}"
         )));
     }
     if f_sig.args != ns.len() {
@@ -949,11 +1136,24 @@ pub fn level(env: &mut Uiua) -> UiuaResult {
     match ns.as_slice() {
         [] => return Ok(()),
         &[n] => {
-            let xs = env.pop(ArrayArg(1))?;
+            let mut xs = env.pop(ArrayArg(1))?;
             if xs.rank() == 0 {
                 env.push(xs);
                 return Ok(());
             }
+            // Boxed rows are reversed inside their boxes, so they cannot use this
+            if matches!(f.as_primitive(), Some((Primitive::Reverse, _)))
+                && !matches!(xs, Value::Func(_))
+            {
+                let depth = match n {
+                    None => 0,
+                    Some(n) if n < 0 => (n.unsigned_abs()).min(xs.rank()),
+                    Some(n) => xs.rank().saturating_sub(n as usize),
+                };
+                xs.reverse_depth(depth);
+                env.push(xs);
+                return Ok(());
+            }
             let rank = match n {
                 Some(0) => return each1_1(f, xs, env),
                 Some(-1) => return rows1_1(f, xs, env),
@@ -1235,6 +1435,106 @@ impl<T: ArrayValue> Array<T> {
     }
 }
 
+pub fn split(env: &mut Uiua) -> UiuaResult {
+    crate::profile_function!();
+    let f = env.pop(FunctionArg(1))?;
+    let sig = f.signature();
+    if !(1..=2).contains(&sig.args) || sig.outputs != 1 {
+        return Err(env.error(format!(
+            "Split's function must take 1 or 2 arguments and return 1 value, \
+            but its signature is {sig}"
+        )));
+    }
+    let delim = env.pop(ArrayArg(1))?;
+    let values = env.pop(ArrayArg(2))?;
+    let groups = values.split_groups(&delim, env)?;
+    let mut rows = Vec::with_capacity(groups.len());
+    for group in groups {
+        // A function that takes 2 arguments also gets the delimiter
+        if sig.args == 2 {
+            env.push(delim.clone());
+        }
+        env.push(group);
+        env.call_error_on_break(f.clone(), "break is not allowed in split")?;
+        rows.push(env.pop("split's function result")?);
+    }
+    let res = Value::from_row_values(rows, env)?;
+    env.push(res);
+    Ok(())
+}
+
+impl Value {
+    pub fn split_groups(&self, delim: &Self, env: &Uiua) -> UiuaResult<Vec<Self>> {
+        Ok(match (self, delim) {
+            (Value::Num(a), Value::Num(b)) => a.split_groups(b, env)?.map(Into::into).collect(),
+            (Value::Byte(a), Value::Byte(b)) => a.split_groups(b, env)?.map(Into::into).collect(),
+            (Value::Char(a), Value::Char(b)) => a.split_groups(b, env)?.map(Into::into).collect(),
+            (Value::Func(a), Value::Func(b)) => a.split_groups(b, env)?.map(Into::into).collect(),
+            (Value::Num(a), Value::Byte(b)) => (a.split_groups(&b.clone().convert(), env)?)
+                .map(Into::into)
+                .collect(),
+            (Value::Byte(a), Value::Num(b)) => (a.clone().convert().split_groups(b, env)?)
+                .map(Into::into)
+                .collect(),
+            (a, b) => {
+                return Err(env.error(format!(
+                    "Cannot split {} array by {} delimiter",
+                    a.type_name(),
+                    b.type_name()
+                )))
+            }
+        })
+    }
+}
+
+impl<T: ArrayValue> Array<T> {
+    pub fn split_groups(&self, delim: &Self, env: &Uiua) -> UiuaResult<impl Iterator<Item = Self>> {
+        if self.rank() == 0 {
+            return Err(env.error("Cannot split a scalar"));
+        }
+        // The delimiter is either a single row or a list of rows
+        let delim_rows = if delim.shape[..] == self.shape[1..] {
+            1
+        } else if delim.rank() == self.rank() && delim.shape[1..] == self.shape[1..] {
+            delim.row_count()
+        } else {
+            return Err(env.error(format!(
+                "Cannot split array of shape {} by a delimiter of shape {}",
+                self.format_shape(),
+                delim.format_shape()
+            )));
+        };
+        if delim_rows == 0 {
+            return Err(env.error("Cannot split by an empty delimiter"));
+        }
+        let row_len = self.row_len();
+        let delim_len = delim_rows * row_len;
+        let mut row_shape = self.shape.clone();
+        let mut bounds = Vec::new();
+        let mut start = 0;
+        let mut i = 0;
+        while i + delim_rows <= self.row_count() {
+            let window = &self.data[i * row_len..i * row_len + delim_len];
+            if window.iter().zip(&delim.data).all(|(a, b)| a.array_eq(b)) {
+                bounds.push((start, i));
+                i += delim_rows;
+                start = i;
+            } else {
+                i += 1;
+            }
+        }
+        bounds.push((start, self.row_count()));
+        let data = self.data.clone();
+        Ok(bounds.into_iter().map(move |(start, end)| {
+            row_shape[0] = end - start;
+            Array::new(
+                row_shape.clone(),
+                data.slice(start * row_len..end * row_len),
+            )
+        }))
+    }
+}
+
 pub fn group(env: &mut Uiua) -> UiuaResult {
     crate::profile_function!();
     collapse_groups(
@@ -1325,11 +1625,56 @@ fn collapse_groups(
             }
             env.push(acc);
         }
-        args => {
+        _ => {
             return Err(env.error(format!(
-                "Cannot {name} with a function that takes {args} arguments"
+                "The function given to {name} must take at most 2 arguments, \
+                but its signature is {sig}"
             )))
         }
     }
     Ok(())
 }
+
+#[cfg(all(test, feature = "parallel"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parallel_loops() {
+        let run = |threads, code| {
+            let mut env = (Uiua::with_native_sys())
+                .with_thread_count(threads)
+                .with_rng_seed(1);
+            match env.load_str(code) {
+                Ok(()) => Ok(env.take_stack()),
+                Err(e) => Err(e.message()),
+            }
+        };
+        for code in [
+            "≡(/+⇡) ⇡1000",
+            "∵(⊂.) ⇡1000",
+            "∵(×2) ↯50_20 ⇡1000",
+            "≡(⬚0↙5⇡) ◿7⇡1000",
+            "≡≡(+1) ↯40_30_2 0",
+            "≡(⍥(+1)3) ⇡500",
+            "≡(⍤\"too big\" <900.) ⇡1000",
+            "∵(⍤\"odd\" =0◿2.) ⇡1000",
+            // Impure functions are run in order
+            "≡(∘&sl 0) ⇡300",
+            "≡(⎋>5.) ⇡1000",
+            "/+ ∵(|1 !!) ↯400 [(⚂)]",
+            "≅ ⇡2000 ⍏ ≡(tag;) ⇡2000",
+        ] {
+            assert_eq!(run(1, code), run(4, code), "{code}");
+        }
+        // Instructions run on other threads count toward the limit
+        for threads in [1, 4] {
+            let error = (Uiua::with_native_sys())
+                .with_thread_count(threads)
+                .with_instruction_limit(20000)
+                .load_str("≡(⍥(+1)50) ⇡1000")
+                .unwrap_err();
+            assert!(error.is_timeout(), "{threads} threads: {error}");
+        }
+    }
+}
@@ -1,6 +1,11 @@
 //! Algorithms for performing operations on arrays
 
-use std::convert::Infallible;
+use std::{
+    convert::Infallible,
+    hash::{Hash, Hasher},
+};
+
+use ecow::EcoVec;
 
 use crate::{
     array::{Array, ArrayValue, Shape},
@@ -14,6 +19,35 @@ pub mod loops;
 mod monadic;
 pub mod pervade;
 
+/// A row that is hashed and compared with [`ArrayValue::array_hash`] and [`array_eq`](crate::array::ArrayCmp::array_eq)
+struct RowKey<'a, T>(&'a [T]);
+
+impl<'a, T: ArrayValue> Hash for RowKey<'a, T> {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        self.0.iter().for_each(|x| x.array_hash(hasher));
+    }
+}
+
+impl<'a, T: ArrayValue> PartialEq for RowKey<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len() && self.0.iter().zip(other.0).all(|(a, b)| a.array_eq(b))
+    }
+}
+
+impl<'a, T: ArrayValue> Eq for RowKey<'a, T> {}
+
+/// Finish an error message about arrays whose shapes do not agree
+///
+/// The message is followed by the `rule` that the shapes break and, if `suggest_fill` is set,
+/// a suggestion to use [`Primitive::Fill`](crate::primitive::Primitive::Fill).
+fn shape_error(message: String, rule: &str, suggest_fill: bool) -> String {
+    let mut message = format!("{message}. {rule}.");
+    if suggest_fill {
+        message.push_str(" Use fill ⬚ to pad the arrays to a common shape.");
+    }
+    message
+}
+
 fn max_shape(a: &[usize], b: &[usize]) -> Shape {
     let shape_len = a.len().max(b.len());
     let mut new_shape = Shape::with_capacity(shape_len);
@@ -145,3 +179,19 @@ fn op2_bytes_retry_fill<T, C: FillContext>(
         }
     }
 }
+
+/// Make a character array from strings, padding them with the fill character or spaces
+///
+/// The strings are the rows of the given shape, and their length becomes the last axis.
+pub(crate) fn padded_strings(mut shape: Shape, strings: Vec<String>, env: &Uiua) -> Array<char> {
+    let fill = env.char_fill().unwrap_or(' ');
+    let max_len = strings.iter().map(|s| s.chars().count()).max().unwrap_or(0);
+    let mut data = EcoVec::with_capacity(strings.len() * max_len);
+    for s in strings {
+        let len = s.chars().count();
+        data.extend(s.chars());
+        data.extend(std::iter::repeat_n(fill, max_len - len));
+    }
+    shape.push(max_len);
+    Array::new(shape, data)
+}
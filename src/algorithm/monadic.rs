@@ -2,21 +2,22 @@
 
 use std::{
     cmp::Ordering,
-    collections::{BTreeMap, BTreeSet, HashMap},
+    collections::{HashMap, HashSet},
+    f64::consts::{PI, TAU},
+    iter::Peekable,
     ptr,
+    str::Chars,
     sync::Arc,
 };
 
 use ecow::EcoVec;
+#[cfg(feature = "parallel")]
 use rayon::prelude::*;
 use tinyvec::tiny_vec;
 
-use crate::{
-    array::*,
-    cowslice::{cowslice, CowSlice},
-    value::Value,
-    Uiua, UiuaResult,
-};
+use crate::{array::*, cowslice::CowSlice, grid_fmt::GridFmt, value::Value, Uiua, UiuaResult};
+
+use super::{padded_strings, RowKey};
 
 impl Value {
     pub fn deshape(&mut self) {
@@ -28,12 +29,117 @@ impl Value {
         )
     }
     pub fn parse_num(&self, env: &Uiua) -> UiuaResult<Self> {
-        Ok(self
-            .as_string(env, "Parsed array must be a string")?
-            .parse::<f64>()
-            .map_err(|e| env.error(format!("Cannot parse into number: {}", e)))?
-            .into())
+        let Value::Char(chars) = self else {
+            return Err(env.error(format!(
+                "Parsed array must be a string, but its type is {}",
+                self.type_name()
+            )));
+        };
+        if chars.rank() <= 1 {
+            let s: String = chars.data.iter().collect();
+            return Ok(parse_num_str(&s, env)?.into());
+        }
+        // Parse each row
+        let row_len = chars.shape.last().copied().unwrap_or(1);
+        let shape = Shape::from(&chars.shape[..chars.rank() - 1]);
+        let mut data = EcoVec::with_capacity(shape.iter().product());
+        if row_len > 0 {
+            for row in chars.data.chunks_exact(row_len) {
+                let s: String = row.iter().collect();
+                data.push(parse_num_str(&s, env)?);
+            }
+        } else {
+            for _ in 0..shape.iter().product::<usize>() {
+                data.push(parse_num_str("", env)?);
+            }
+        }
+        Ok(Array::new(shape, data).into())
     }
+    pub fn unparse_num(&self, env: &Uiua) -> UiuaResult<Self> {
+        let strings: Vec<String> = match self {
+            Value::Num(nums) => nums.data.iter().map(format_num).collect(),
+            Value::Byte(bytes) => bytes.data.iter().map(|b| b.to_string()).collect(),
+            value => {
+                return Err(env.error(format!(
+                    "Cannot format {} array as numbers",
+                    value.type_name()
+                )))
+            }
+        };
+        if self.rank() == 0 {
+            return Ok(strings.into_iter().next().unwrap().into());
+        }
+        // Pad each formatted number to the length of the longest one
+        Ok(padded_strings(Shape::from(self.shape()), strings, env).into())
+    }
+}
+
+/// Sort row indices, on multiple threads if the `parallel` feature is enabled
+fn sort_indices(indices: &mut [usize], compare: impl Fn(&usize, &usize) -> Ordering + Sync) {
+    #[cfg(feature = "parallel")]
+    indices.par_sort_by(compare);
+    #[cfg(not(feature = "parallel"))]
+    indices.sort_by(compare);
+}
+
+/// Parse a number the way it would be written in Uiua code
+///
+/// Surrounding whitespace and fill characters are ignored.
+fn parse_num_str(s: &str, env: &Uiua) -> UiuaResult<f64> {
+    let fill = env.char_fill();
+    let s = s.trim_matches(|c: char| c.is_whitespace() || Some(c) == fill);
+    let (negative, positive) = match s.strip_prefix(['¯', '-']) {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    };
+    let n = match positive {
+        "∞" => f64::INFINITY,
+        "π" => PI,
+        "τ" => TAU,
+        "η" => PI / 2.0,
+        _ => Some(positive)
+            .filter(|positive| is_num_literal(positive))
+            .and_then(|positive| positive.replace('¯', "-").parse::<f64>().ok())
+            .ok_or_else(|| env.error(format!("Cannot parse {s:?} as a number")))?,
+    };
+    Ok(if negative { -n } else { n })
+}
+
+/// Check that a string is an unsigned number literal that the lexer would accept
+///
+/// This is digits, optionally followed by a fractional part and an exponent.
+fn is_num_literal(s: &str) -> bool {
+    let mut chars = s.chars().peekable();
+    let digits = |chars: &mut Peekable<Chars>| {
+        let mut any = false;
+        while chars.next_if(char::is_ascii_digit).is_some() {
+            any = true;
+        }
+        any
+    };
+    if !digits(&mut chars) {
+        return false;
+    }
+    if chars.next_if_eq(&'.').is_some() && !digits(&mut chars) {
+        return false;
+    }
+    if chars.next_if(|&c| c == 'e' || c == 'E').is_some() {
+        chars.next_if(|&c| c == '-' || c == '¯');
+        if !digits(&mut chars) {
+            return false;
+        }
+    }
+    chars.next().is_none()
+}
+
+/// Format a number the way `show` would print it as a scalar
+pub(crate) fn format_num(n: &f64) -> String {
+    n.fmt_grid(false)
+        .into_iter()
+        .next()
+        .unwrap()
+        .into_iter()
+        .collect()
 }
 
 impl<T: ArrayValue> Array<T> {
@@ -51,7 +157,7 @@ impl Value {
         )?;
         let mut shape = Shape::from(shape.as_slice());
         let data = range(&shape, env)?;
-        if shape.len() > 1 {
+        if shape.len() != 1 {
             shape.push(shape.len());
         }
         Ok(Array::new(shape, data).into())
@@ -59,10 +165,7 @@ impl Value {
 }
 
 fn range(shape: &[usize], env: &Uiua) -> UiuaResult<CowSlice<f64>> {
-    if shape.is_empty() {
-        return Ok(cowslice![0.0]);
-    }
-    if shape.contains(&0) {
+    if shape.is_empty() || shape.contains(&0) {
         return Ok(CowSlice::new());
     }
     let mut len = shape.len();
@@ -148,30 +251,40 @@ impl<T: ArrayValue> Array<T> {
 
 impl Value {
     pub fn reverse(&mut self) {
+        self.reverse_depth(0)
+    }
+    pub fn reverse_depth(&mut self, depth: usize) {
         self.generic_mut_deep(
-            Array::reverse,
-            Array::reverse,
-            Array::reverse,
-            Array::reverse,
+            |a| a.reverse_depth(depth),
+            |a| a.reverse_depth(depth),
+            |a| a.reverse_depth(depth),
+            |a| a.reverse_depth(depth),
         )
     }
 }
 
 impl<T: ArrayValue> Array<T> {
     pub fn reverse(&mut self) {
-        if self.shape.is_empty() || self.flat_len() == 0 {
+        self.reverse_depth(0)
+    }
+    /// Reverse the array along the axis at the given depth
+    pub fn reverse_depth(&mut self, depth: usize) {
+        if depth >= self.rank() || self.flat_len() == 0 {
             return;
         }
-        let row_count = self.row_count();
-        let row_len = self.row_len();
+        let row_count = self.shape[depth];
+        let row_len: usize = self.shape[depth + 1..].iter().product();
+        let chunk_len = row_count * row_len;
         let data = self.data.as_mut_slice();
-        for i in 0..row_count / 2 {
-            let left = i * row_len;
-            let right = (row_count - i - 1) * row_len;
-            let left = &mut data[left] as *mut T;
-            let right = &mut data[right] as *mut T;
-            unsafe {
-                ptr::swap_nonoverlapping(left, right, row_len);
+        for chunk in data.chunks_exact_mut(chunk_len) {
+            for i in 0..row_count / 2 {
+                let left = i * row_len;
+                let right = (row_count - i - 1) * row_len;
+                let left = &mut chunk[left] as *mut T;
+                let right = &mut chunk[right] as *mut T;
+                unsafe {
+                    ptr::swap_nonoverlapping(left, right, row_len);
+                }
             }
         }
     }
@@ -248,6 +361,26 @@ impl Value {
         self.generic_ref_env_deep(Array::fall, Array::fall, Array::fall, Array::fall, env)
             .map(Self::from_iter)
     }
+    pub fn first_min_index(&self, env: &Uiua) -> UiuaResult<Self> {
+        self.generic_ref_env_deep(
+            Array::first_min_index,
+            Array::first_min_index,
+            Array::first_min_index,
+            Array::first_min_index,
+            env,
+        )
+        .map(|i| (i as f64).into())
+    }
+    pub fn first_max_index(&self, env: &Uiua) -> UiuaResult<Self> {
+        self.generic_ref_env_deep(
+            Array::first_max_index,
+            Array::first_max_index,
+            Array::first_max_index,
+            Array::first_max_index,
+            env,
+        )
+        .map(|i| (i as f64).into())
+    }
     pub fn classify(&self, env: &Uiua) -> UiuaResult<Self> {
         self.generic_ref_env_deep(
             Array::classify,
@@ -258,6 +391,16 @@ impl Value {
         )
         .map(Self::from_iter)
     }
+    pub fn unique(&self, env: &Uiua) -> UiuaResult<Self> {
+        self.generic_ref_env_deep(
+            Array::unique,
+            Array::unique,
+            Array::unique,
+            Array::unique,
+            env,
+        )
+        .map(Self::from)
+    }
     pub fn deduplicate(&mut self) {
         self.generic_mut_deep(
             Array::deduplicate,
@@ -277,7 +420,7 @@ impl<T: ArrayValue> Array<T> {
             return Ok(Vec::new());
         }
         let mut indices = (0..self.row_count()).collect::<Vec<_>>();
-        indices.par_sort_by(|&a, &b| {
+        sort_indices(&mut indices, |&a, &b| {
             self.row_slice(a)
                 .iter()
                 .zip(self.row_slice(b))
@@ -295,7 +438,7 @@ impl<T: ArrayValue> Array<T> {
             return Ok(Vec::new());
         }
         let mut indices = (0..self.row_count()).collect::<Vec<_>>();
-        indices.par_sort_by(|&a, &b| {
+        sort_indices(&mut indices, |&a, &b| {
             self.row_slice(a)
                 .iter()
                 .zip(self.row_slice(b))
@@ -305,15 +448,46 @@ impl<T: ArrayValue> Array<T> {
         });
         Ok(indices)
     }
+    /// Get the index of the first row that compares as `ordering` to all others
+    fn first_extreme_index(&self, ordering: Ordering, env: &Uiua) -> UiuaResult<usize> {
+        if self.rank() == 0 {
+            return Err(env.error(if ordering == Ordering::Less {
+                "Cannot rise a scalar"
+            } else {
+                "Cannot fall a scalar"
+            }));
+        }
+        if self.row_count() == 0 {
+            return Err(env.error("Cannot take first of an empty array"));
+        }
+        let mut best = 0;
+        for i in 1..self.row_count() {
+            let cmp = (self.row_slice(i).iter())
+                .zip(self.row_slice(best))
+                .map(|(a, b)| a.array_cmp(b))
+                .find(|x| x != &Ordering::Equal)
+                .unwrap_or(Ordering::Equal);
+            if cmp == ordering {
+                best = i;
+            }
+        }
+        Ok(best)
+    }
+    pub fn first_min_index(&self, env: &Uiua) -> UiuaResult<usize> {
+        self.first_extreme_index(Ordering::Less, env)
+    }
+    pub fn first_max_index(&self, env: &Uiua) -> UiuaResult<usize> {
+        self.first_extreme_index(Ordering::Greater, env)
+    }
     pub fn classify(&self, env: &Uiua) -> UiuaResult<Vec<usize>> {
         if self.rank() == 0 {
             return Err(env.error("Cannot classify a rank-0 array"));
         }
-        let mut classes = BTreeMap::new();
+        let mut classes = HashMap::with_capacity(self.row_count());
         let mut classified = Vec::with_capacity(self.row_count());
-        for row in self.rows() {
+        for row in self.row_slices() {
             let new_class = classes.len();
-            let class = *classes.entry(row).or_insert(new_class);
+            let class = *classes.entry(RowKey(row)).or_insert(new_class);
             classified.push(class);
         }
         Ok(classified)
@@ -323,17 +497,27 @@ impl<T: ArrayValue> Array<T> {
             return;
         }
         let mut deduped = CowSlice::new();
-        let mut seen = BTreeSet::new();
+        let mut seen = HashSet::with_capacity(self.row_count());
         let mut new_len = 0;
-        for row in self.rows() {
-            if seen.insert(row.clone()) {
-                deduped.extend_from_slice(&row.data);
+        for row in self.row_slices() {
+            if seen.insert(RowKey(row)) {
+                deduped.extend_from_slice(row);
                 new_len += 1;
             }
         }
         self.data = deduped;
         self.shape[0] = new_len;
     }
+    pub fn unique(&self, env: &Uiua) -> UiuaResult<Array<u8>> {
+        if self.rank() == 0 {
+            return Err(env.error("Cannot get the unique rows of a rank-0 array"));
+        }
+        let mut seen = HashSet::with_capacity(self.row_count());
+        let mask: EcoVec<u8> = (self.row_slices())
+            .map(|row| seen.insert(RowKey(row)) as u8)
+            .collect();
+        Ok(Array::from(mask))
+    }
 }
 
 impl Value {
@@ -344,7 +528,7 @@ impl Value {
                 invs.try_extend(fs.data.iter().map(|f| {
                     f.inverse()
                         .map(Into::into)
-                        .ok_or_else(|| env.error("No inverse found"))
+                        .ok_or_else(|| env.error(format!("No inverse found for {f}")))
                 }))?;
                 Self::Func(Array::new(fs.shape.clone(), invs))
             }
@@ -358,7 +542,8 @@ impl Value {
                 let mut afters = EcoVec::with_capacity(fs.row_count());
                 for f in fs.data {
                     let f = Arc::try_unwrap(f).unwrap_or_else(|f| (*f).clone());
-                    let (before, after) = f.under().ok_or_else(|| env.error("No inverse found"))?;
+                    let message = format!("{f} has no inverse, so it cannot be used with under");
+                    let (before, after) = f.under().ok_or_else(|| env.error(message))?;
                     befores.push(before.into());
                     afters.push(after.into());
                 }
@@ -391,28 +576,31 @@ impl Value {
 
 impl Array<f64> {
     pub fn bits(&self, env: &Uiua) -> UiuaResult<Array<u8>> {
-        let mut nats = Vec::new();
+        let mut max: u128 = 0;
         for &n in &self.data {
-            if n.fract() != 0.0 {
-                return Err(env.error("Array must be a list of naturals"));
+            if n.fract() != 0.0 || n < 0.0 {
+                return Err(env.error(format!(
+                    "Argument to bits must be an array of natural numbers, but it contains {}",
+                    n.grid_string()
+                )));
             }
-            nats.push(n as u128);
+            max = max.max(n as u128);
         }
-        let mut max = if let Some(max) = nats.iter().max() {
-            *max
-        } else {
+        if self.data.is_empty() {
             let mut shape = self.shape.clone();
             shape.push(0);
             return Ok(Array::new(shape, CowSlice::new()));
-        };
-        let mut max_bits = 0;
-        while max != 0 {
+        }
+        // Zero still has one digit
+        let mut max_bits = 1;
+        while max > 1 {
             max_bits += 1;
             max >>= 1;
         }
         let mut new_data = EcoVec::with_capacity(self.data.len() * max_bits);
         // Little endian
-        for n in nats {
+        for &n in &self.data {
+            let n = n as u128;
             for i in 0..max_bits {
                 new_data.push(u8::from(n & (1 << i) != 0));
             }
@@ -458,6 +646,9 @@ impl Array<u8> {
 
 impl Value {
     pub fn wher(&self, env: &Uiua) -> UiuaResult<Self> {
+        if self.rank() >= 2 {
+            return self.multi_where(env);
+        }
         let counts = self.as_naturals(env, "Argument to where must be a list of naturals")?;
         let total: usize = counts.iter().fold(0, |acc, &b| acc.saturating_add(b));
         let mut data = EcoVec::with_capacity(total);
@@ -469,6 +660,34 @@ impl Value {
         }
         Ok(Array::from(data).into())
     }
+    /// Where for arrays of rank 2 or more, which yields a row of coordinates for each count
+    fn multi_where(&self, env: &Uiua) -> UiuaResult<Self> {
+        let counts = self.as_number_array(
+            env,
+            "Argument to where must be an array of naturals",
+            |_| true,
+            |n| n.fract() == 0.0 && n >= 0.0,
+            |n| n as usize,
+        )?;
+        let rank = counts.shape.len();
+        let total: usize = counts.data.iter().fold(0, |acc, &b| acc.saturating_add(b));
+        let mut data = EcoVec::with_capacity(total * rank);
+        let mut index = vec![0; rank];
+        for &b in counts.data.iter() {
+            for _ in 0..b {
+                data.extend(index.iter().map(|&i| i as f64));
+            }
+            // Increment the multi-dimensional index
+            for (i, &dim) in index.iter_mut().zip(&counts.shape).rev() {
+                *i += 1;
+                if *i < dim {
+                    break;
+                }
+                *i = 0;
+            }
+        }
+        Ok(Array::new(tiny_vec![total, rank], data).into())
+    }
     pub fn inverse_where(&self, env: &Uiua) -> UiuaResult<Self> {
         let indices =
             self.as_naturals(env, "Argument to inverse where must be a list of naturals")?;
@@ -504,3 +723,20 @@ impl Value {
         Ok(Array::from(data).into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::error::tests::error_message;
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            error_message("parse \"3x\""),
+            "1:1: Cannot parse \"3x\" as a number"
+        );
+        assert_eq!(
+            error_message("1 parse ⬚@ [\"5\" \"dog\"]"),
+            "1:3: Cannot parse \"dog\" as a number"
+        );
+    }
+}
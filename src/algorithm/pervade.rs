@@ -8,9 +8,9 @@ use std::{
     slice::{self, ChunksExact},
 };
 
-use crate::{array::*, cowslice::CowSlice, Uiua, UiuaError, UiuaResult};
+use crate::{array::*, cowslice::CowSlice, value::Value, Uiua, UiuaError, UiuaResult};
 
-use super::{max_shape, FillContext};
+use super::{max_shape, monadic::format_num, shape_error, FillContext};
 
 #[allow(clippy::len_without_is_empty)]
 pub trait Arrayish {
@@ -123,6 +123,9 @@ where
     }
 }
 
+const PERVASIVE_SHAPE_RULE: &str =
+    "The shape of one argument must be a prefix of the shape of the other";
+
 fn fill_shapes<A, B, C>(a: &mut Array<A>, b: &mut Array<B>, ctx: C) -> Result<(), C::Error>
 where
     A: ArrayValue,
@@ -180,10 +183,14 @@ where
                 }
             }
             if !a.shape_prefixes_match(b) {
-                return Err(C::fill_error(ctx.error(format!(
-                    "Shapes {} and {} do not match",
-                    a.format_shape(),
-                    b.format_shape()
+                return Err(C::fill_error(ctx.error(shape_error(
+                    format!(
+                        "Shapes {} and {} do not match",
+                        a.format_shape(),
+                        b.format_shape()
+                    ),
+                    PERVASIVE_SHAPE_RULE,
+                    ctx.fill::<A>().is_none(),
                 ))));
             }
         }
@@ -191,6 +198,29 @@ where
     Ok(())
 }
 
+/// Fill two values to matching shapes the way pervasive functions do
+pub(crate) fn fill_value_shapes<C>(a: &mut Value, b: &mut Value, ctx: C) -> Result<(), C::Error>
+where
+    C: FillContext,
+{
+    macro_rules! fill_with {
+        ($a:expr) => {
+            match b {
+                Value::Num(b) => fill_shapes($a, b, ctx),
+                Value::Byte(b) => fill_shapes($a, b, ctx),
+                Value::Char(b) => fill_shapes($a, b, ctx),
+                Value::Func(b) => fill_shapes($a, b, ctx),
+            }
+        };
+    }
+    match a {
+        Value::Num(a) => fill_with!(a),
+        Value::Byte(a) => fill_with!(a),
+        Value::Char(a) => fill_with!(a),
+        Value::Func(a) => fill_with!(a),
+    }
+}
+
 pub fn bin_pervade<A, B, C, F>(
     mut a: Array<A>,
     mut b: Array<B>,
@@ -256,6 +286,10 @@ where
     Ok(())
 }
 
+/// Apply a pervasive function to two arrays, writing the result into one of them
+///
+/// The result is written into the higher-rank array, so only that array's buffer
+/// is copied, and only if it is shared.
 pub fn bin_pervade_mut<T>(
     a: &mut Array<T>,
     mut b: Array<T>,
@@ -266,61 +300,53 @@ where
     T: ArrayValue + Copy,
 {
     fill_shapes(a, &mut b, env)?;
-    let a_data = a.data.as_mut_slice();
-    let b_data = b.data.as_mut_slice();
-    let ash = a.shape.as_slice();
-    let bsh = b.shape.as_slice();
-    if ash == bsh {
-        for (a, b) in a_data.iter_mut().zip(b_data) {
-            *a = f(*a, *b);
-        }
+    if a.rank() >= b.rank() {
+        bin_pervade_recursive_mut(a.data.as_mut_slice(), &a.shape, &b.data, &b.shape, f);
     } else {
-        let use_a = bin_pervade_recursive_mut(a_data, ash, b_data, bsh, f);
-        if !use_a {
-            *a = b;
-        }
+        bin_pervade_recursive_mut(
+            b.data.as_mut_slice(),
+            &b.shape,
+            &a.data,
+            &a.shape,
+            |b, a| f(a, b),
+        );
+        *a = b;
     }
     Ok(())
 }
 
+/// Apply a pervasive function in place
+///
+/// `acc` must have at least the rank of `other`.
 fn bin_pervade_recursive_mut<T>(
-    a_data: &mut [T],
-    a_shape: &[usize],
-    b_data: &mut [T],
-    b_shape: &[usize],
+    acc: &mut [T],
+    acc_shape: &[usize],
+    other: &[T],
+    other_shape: &[usize],
     f: impl Fn(T, T) -> T + Copy,
-) -> bool
-where
+) where
     T: ArrayValue + Copy,
 {
-    match (a_shape, b_shape) {
-        ([], []) => {
-            panic!("should never call `bin_pervade_recursive_mut` with scalars")
-        }
-        (_, []) => {
-            let b_scalar = b_data[0];
-            for a in a_data {
-                *a = f(*a, b_scalar);
-            }
-            true
+    if acc.is_empty() {
+        return;
+    }
+    if acc_shape == other_shape {
+        for (a, b) in acc.iter_mut().zip(other) {
+            *a = f(*a, *b);
         }
-        ([], _) => {
-            let a_scalar = a_data[0];
-            for b in b_data {
-                *b = f(a_scalar, *b);
-            }
-            false
+    } else if other_shape.is_empty() {
+        let scalar = other[0];
+        for a in acc {
+            *a = f(*a, scalar);
         }
-        (ash, bsh) => {
-            let a_row_len = a_data.len() / ash[0];
-            let b_row_len = b_data.len() / bsh[0];
-            for (a, b) in a_data
-                .chunks_exact_mut(a_row_len)
-                .zip(b_data.chunks_exact_mut(b_row_len))
-            {
-                bin_pervade_recursive_mut(a, &ash[1..], b, &bsh[1..], f);
-            }
-            ash.len() > bsh.len()
+    } else {
+        let acc_row_len = acc.len() / acc_shape[0];
+        let other_row_len = other.len() / other_shape[0];
+        for (a, b) in acc
+            .chunks_exact_mut(acc_row_len)
+            .zip(other.chunks_exact(other_row_len))
+        {
+            bin_pervade_recursive_mut(a, &acc_shape[1..], b, &other_shape[1..], f);
         }
     }
 }
@@ -489,6 +515,82 @@ pub mod round {
     }
 }
 
+pub mod upper {
+    use super::*;
+    pub fn char(a: char) -> char {
+        simple_case(a, a.to_uppercase())
+    }
+    pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot get the uppercase of {a}"))
+    }
+}
+pub mod lower {
+    use super::*;
+    pub fn char(a: char) -> char {
+        simple_case(a, a.to_lowercase())
+    }
+    pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot get the lowercase of {a}"))
+    }
+}
+/// Only use a case mapping if it maps to a single character
+fn simple_case(a: char, mut mapped: impl Iterator<Item = char>) -> char {
+    match (mapped.next(), mapped.next()) {
+        (Some(c), None) => c,
+        _ => a,
+    }
+}
+pub mod char_class {
+    use super::*;
+    use unicode_categories::UnicodeCategories;
+    pub fn char(a: char) -> u8 {
+        if a.is_alphabetic() {
+            1
+        } else if a.is_numeric() {
+            2
+        } else if a.is_whitespace() {
+            3
+        } else if a.is_punctuation() {
+            4
+        } else {
+            0
+        }
+    }
+    pub fn error<T: Display>(a: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot classify {a}"))
+    }
+}
+
+pub mod round_to {
+    use super::*;
+    pub fn num_num(a: f64, b: f64) -> f64 {
+        let step = a.abs();
+        if step == 0.0 {
+            return b;
+        }
+        let rounded = (b / step).round();
+        // Dividing by the reciprocal avoids drift for steps like 0.1
+        let recip = 1.0 / step;
+        if (recip - recip.round()).abs() < 1e-9 {
+            rounded / recip.round()
+        } else {
+            rounded * step
+        }
+    }
+    pub fn byte_byte(a: u8, b: u8) -> f64 {
+        num_num(a.into(), b.into())
+    }
+    pub fn byte_num(a: u8, b: f64) -> f64 {
+        num_num(a.into(), b)
+    }
+    pub fn num_byte(a: f64, b: u8) -> f64 {
+        num_num(a, b.into())
+    }
+    pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
+        env.error(format!("Cannot round {b} to a multiple of {a}"))
+    }
+}
+
 macro_rules! cmp_impl {
     ($name:ident $eq:tt $ordering:expr) => {
         pub mod $name {
@@ -593,6 +695,9 @@ pub mod mul {
     pub fn byte_byte(a: u8, b: u8) -> f64 {
         f64::from(b) * f64::from(a)
     }
+    pub fn bool_bool(a: u8, b: u8) -> u8 {
+        a & b
+    }
     pub fn byte_num(a: u8, b: f64) -> f64 {
         b * f64::from(a)
     }
@@ -648,6 +753,15 @@ pub mod atan2 {
     pub fn num_num(a: f64, b: f64) -> f64 {
         a.atan2(b)
     }
+    pub fn byte_byte(a: u8, b: u8) -> f64 {
+        f64::from(a).atan2(f64::from(b))
+    }
+    pub fn byte_num(a: u8, b: f64) -> f64 {
+        f64::from(a).atan2(b)
+    }
+    pub fn num_byte(a: f64, b: u8) -> f64 {
+        a.atan2(f64::from(b))
+    }
     pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
         env.error(format!("Cannot get the atan2 of {a} and {b}"))
     }
@@ -675,7 +789,11 @@ pub mod pow {
 pub mod log {
     use super::*;
     pub fn num_num(a: f64, b: f64) -> f64 {
-        b.log(a)
+        if a == std::f64::consts::E {
+            b.ln()
+        } else {
+            b.log(a)
+        }
     }
     pub fn byte_byte(a: u8, b: u8) -> f64 {
         f64::from(b).log(f64::from(a))
@@ -684,10 +802,10 @@ pub mod log {
         b.log(f64::from(a))
     }
     pub fn num_byte(a: f64, b: u8) -> f64 {
-        f64::from(b).log(a)
+        num_num(a, b.into())
     }
     pub fn error<T: Display>(a: T, b: T, env: &Uiua) -> UiuaError {
-        env.error(format!("Cannot get the log base {b} of {a}"))
+        env.error(format!("Cannot get the log base {a} of {b}"))
     }
 }
 
@@ -844,10 +962,15 @@ fn bin_pervade_recursive_generic<A: PervasiveInput, B: PervasiveInput, C>(
             let a_cells = a_shape[0];
             let b_cells = b_shape[0];
             if a_cells != b_cells {
-                return Err(env.error(format!(
-                    "Shapes {} and {} do not match",
-                    FormatShape(a_shape),
-                    FormatShape(b_shape)
+                return Err(env.error(shape_error(
+                    format!(
+                        "Shapes {} and {} do not match",
+                        FormatShape(a_shape),
+                        FormatShape(b_shape)
+                    ),
+                    PERVASIVE_SHAPE_RULE,
+                    // Callers fill the arrays first, so a fill could not have helped
+                    env.fill::<f64>().is_none(),
                 )));
             }
             let a_chunk_size = a.len() / a_cells;
@@ -916,3 +1039,147 @@ fn bin_pervade_recursive_generic<A: PervasiveInput, B: PervasiveInput, C>(
     }
     Ok(())
 }
+
+/// Check that a math operation did not produce `NaN` from inputs that were not `NaN`
+///
+/// This is used in strict math mode. `describe` is given the offending input elements.
+fn check_strict<const N: usize>(
+    output: &Value,
+    inputs: [&Value; N],
+    describe: impl Fn([f64; N]) -> String,
+    env: &Uiua,
+) -> UiuaResult {
+    let Value::Num(output) = output else {
+        return Ok(());
+    };
+    let element = |value: &Value, i: usize| -> Option<f64> {
+        let shape = value.shape();
+        if !output.shape.starts_with(shape) {
+            return None;
+        }
+        let j = i / output.shape[shape.len()..].iter().product::<usize>();
+        match value {
+            Value::Num(arr) => Some(arr.data[j]),
+            Value::Byte(arr) => Some(arr.data[j].into()),
+            _ => None,
+        }
+    };
+    for (i, n) in output.data.iter().enumerate() {
+        if !n.is_nan() {
+            continue;
+        }
+        let elems = inputs.map(|input| element(input, i));
+        if elems.iter().any(|e| e.is_some_and(f64::is_nan)) {
+            continue;
+        }
+        return Err(
+            env.error(match elems.iter().copied().collect::<Option<Vec<_>>>() {
+                Some(elems) => describe(elems.try_into().unwrap()),
+                None => "Result is not a real number".into(),
+            }),
+        );
+    }
+    Ok(())
+}
+
+pub fn strict_sqrt(x: Value, env: &Uiua) -> UiuaResult<Value> {
+    let res = x.clone().sqrt(env)?;
+    check_strict(
+        &res,
+        [&x],
+        |[x]| format!("The square root of {} is not a real number", format_num(&x)),
+        env,
+    )?;
+    Ok(res)
+}
+
+pub fn strict_div(a: Value, b: Value, env: &Uiua) -> UiuaResult<Value> {
+    let res = a.clone().div(b.clone(), env)?;
+    check_strict(
+        &res,
+        [&a, &b],
+        |[a, b]| {
+            format!(
+                "{} divided by {} is undefined",
+                format_num(&b),
+                format_num(&a)
+            )
+        },
+        env,
+    )?;
+    Ok(res)
+}
+
+pub fn strict_pow(a: Value, b: Value, env: &Uiua) -> UiuaResult<Value> {
+    let res = a.clone().pow(b.clone(), env)?;
+    check_strict(
+        &res,
+        [&a, &b],
+        |[a, b]| {
+            format!(
+                "{} to the power of {} is not a real number",
+                format_num(&b),
+                format_num(&a)
+            )
+        },
+        env,
+    )?;
+    Ok(res)
+}
+
+pub fn strict_log(a: Value, b: Value, env: &Uiua) -> UiuaResult<Value> {
+    let res = a.clone().log(b.clone(), env)?;
+    check_strict(
+        &res,
+        [&a, &b],
+        |[a, b]| {
+            format!(
+                "The log base {} of {} is not a real number",
+                format_num(&a),
+                format_num(&b)
+            )
+        },
+        env,
+    )?;
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strict_math() {
+        let message = |code| {
+            Uiua::with_native_sys()
+                .with_strict_math(true)
+                .load_str(code)
+                .unwrap_err()
+                .located_message()
+        };
+        assert_eq!(
+            message("√¯1"),
+            "1:1: The square root of ¯1 is not a real number"
+        );
+        assert_eq!(message("÷0 0"), "1:1: 0 divided by 0 is undefined");
+        assert_eq!(
+            message("ⁿ0.5 ¯2"),
+            "1:1: ¯2 to the power of 0.5 is not a real number"
+        );
+        assert_eq!(
+            message("ₙ2 [4 ¯8]"),
+            "1:1: The log base 2 of ¯8 is not a real number"
+        );
+        assert_eq!(
+            message("√[1 ¯4 9]"),
+            "1:1: The square root of ¯4 is not a real number"
+        );
+        // Loops do not bypass the checks
+        for code in ["/÷ [0 0]", "\\÷ [0 0]", "⊞÷ [0] [0]", "/÷ [1 0 0]"] {
+            assert_eq!(message(code), "1:2: 0 divided by 0 is undefined", "{code}");
+        }
+        // Valid inputs and NaN inputs are unaffected
+        let mut env = Uiua::with_native_sys().with_strict_math(true);
+        env.load_str("[√4 ÷0 1 ⁿ0.5 4 ₙ2 8 √NaN ÷0 NaN]").unwrap();
+    }
+}
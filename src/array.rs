@@ -222,10 +222,12 @@ impl<T: ArrayValue> Array<T> {
         } else {
             row_shape.remove(0)
         };
-        let mut data = self.data.into_iter().rev();
-        (0..row_count).map(move |_| {
-            let row: CowSlice<_> = data.by_ref().take(row_len).rev().collect();
-            Array::new(row_shape.clone(), row)
+        let data = self.data;
+        (0..row_count).rev().map(move |i| {
+            Array::new(
+                row_shape.clone(),
+                data.slice(i * row_len..(i + 1) * row_len),
+            )
         })
     }
     pub(crate) fn first_dim_zero(&self) -> Self {
@@ -343,6 +345,13 @@ impl From<String> for Array<char> {
     }
 }
 
+impl Array<u8> {
+    /// Check if every element is `0` or `1`
+    pub fn is_boolean(&self) -> bool {
+        self.data.iter().all(|&b| b <= 1)
+    }
+}
+
 impl From<Vec<bool>> for Array<u8> {
     fn from(data: Vec<bool>) -> Self {
         Self::new(
@@ -409,7 +418,8 @@ impl ArrayValue for u8 {
         env.byte_fill()
     }
     fn array_hash<H: Hasher>(&self, hasher: &mut H) {
-        self.hash(hasher)
+        // Bytes hash like the numbers they represent, so that equal byte and number arrays hash the same
+        f64::from(*self).array_hash(hasher)
     }
 }
 
@@ -9,7 +9,12 @@ use crate::{
 
 #[derive(Debug, Clone)]
 pub enum Item {
-    Scoped { items: Vec<Item>, test: bool },
+    Scoped {
+        items: Vec<Item>,
+        test: bool,
+        /// The spans of the `---` or `~~~` delimiters, the second of which may be missing
+        delimiters: (CodeSpan, Option<CodeSpan>),
+    },
     Words(Vec<Sp<Word>>),
     Binding(Binding),
     ExtraNewlines(CodeSpan),
@@ -18,6 +23,8 @@ pub enum Item {
 #[derive(Debug, Clone)]
 pub struct Binding {
     pub name: Sp<Ident>,
+    /// The span of the `←` or `=`
+    pub arrow_span: CodeSpan,
     pub signature: Option<Sp<Signature>>,
     pub words: Vec<Sp<Word>>,
 }
@@ -31,6 +31,27 @@ pub(crate) fn instrs_signature(instrs: &[Instr]) -> Result<Signature, String> {
     Ok(Signature { args, outputs })
 }
 
+/// Check whether some instructions provably have no effect
+///
+/// This is the case if they take no arguments, leave nothing on the stack,
+/// and only push plain values and move them around.
+pub(crate) fn instrs_are_noop(instrs: &[Instr]) -> bool {
+    use Primitive::*;
+    !instrs.is_empty()
+        && instrs.iter().all(|instr| match instr {
+            Instr::Push(val) => !matches!(**val, Value::Func(_)),
+            Instr::Prim(prim, _) => {
+                prim.as_constant().is_some()
+                    || matches!(prim, Dup | Over | Flip | Pop | Identity | Roll | Unroll)
+            }
+            Instr::PushTempInline { .. }
+            | Instr::PopTempInline { .. }
+            | Instr::CopyTempInline { .. } => true,
+            _ => false,
+        })
+        && instrs_signature(instrs).is_ok_and(|sig| sig == Signature::new(0, 0))
+}
+
 /// An environment that emulates the runtime but only keeps track of the stack.
 struct VirtualEnv<'a> {
     stack: Vec<BasicValue<'a>>,
@@ -45,7 +66,7 @@ enum BasicValue<'a> {
     Arr(Vec<Self>),
     Other,
     Unknown,
-    DifferentSignatures,
+    DifferentSignatures(Signature, Signature),
 }
 
 impl<'a> BasicValue<'a> {
@@ -60,7 +81,7 @@ impl<'a> BasicValue<'a> {
                 args: 0,
                 outputs: 1,
             },
-            BasicValue::Other | BasicValue::Unknown | BasicValue::DifferentSignatures => {
+            BasicValue::Other | BasicValue::Unknown | BasicValue::DifferentSignatures(..) => {
                 Signature {
                     args: 0,
                     outputs: 1,
@@ -135,7 +156,7 @@ impl<'a> VirtualEnv<'a> {
             Instr::Dynamic(f) => self.handle_sig(f.signature)?,
             Instr::DropTempInline { .. } => {}
             Instr::Prim(prim, _) => match prim {
-                Reduce | Scan => {
+                Reduce | Scan | ReverseScan => {
                     let sig = self.pop()?.expect_function(|| prim)?;
                     let outputs = match (sig.args, sig.outputs) {
                         (0, _) => return Err(format!("{prim}'s function has no args")),
@@ -168,6 +189,7 @@ impl<'a> VirtualEnv<'a> {
                     self.handle_args_outputs(args, outputs)?;
                 }
                 Spawn => self.handle_mod(prim, None, None, 1, Some(1))?,
+                Split => self.handle_mod(prim, None, Some(1), 2, Some(1))?,
                 Repeat => {
                     let f = self.pop()?;
                     let n = self.pop()?;
@@ -455,6 +477,24 @@ impl<'a> VirtualEnv<'a> {
                         }
                     }
                 }
+                Unpack => match self.pop()? {
+                    BasicValue::Arr(items) => {
+                        self.set_min_height();
+                        self.stack.extend(items.into_iter().rev());
+                    }
+                    _ => return Err("unpack with an unknown length".into()),
+                },
+                Destructure => {
+                    let count = match self.pop()? {
+                        BasicValue::Num(n) if n.fract() == 0.0 && n >= 0.0 => n as usize,
+                        _ => return Err("destructure with an unknown count".into()),
+                    };
+                    self.pop()?;
+                    self.set_min_height();
+                    for _ in 0..count {
+                        self.stack.push(BasicValue::Other);
+                    }
+                }
                 Join => {
                     let a = self.pop()?;
                     let b = self.pop()?;
@@ -495,12 +535,15 @@ impl<'a> VirtualEnv<'a> {
                                         }
                                     }
                                 } else {
-                                    self.stack.push(BasicValue::DifferentSignatures);
+                                    self.stack.push(BasicValue::DifferentSignatures(
+                                        sig,
+                                        item.signature(),
+                                    ));
                                     return Ok(());
                                 }
                             }
                             let (id, instrs) = if let Some(f) = function {
-                                (f.id.clone(), f.instrs.as_slice())
+                                (f.id.clone(), &*f.instrs)
                             } else {
                                 (FunctionId::Constant, Default::default())
                             };
@@ -563,8 +606,10 @@ impl<'a> VirtualEnv<'a> {
         match self.pop()? {
             BasicValue::Func(f) => self.handle_sig(f.signature())?,
             BasicValue::Unknown => return Err("call with unknown function".into()),
-            BasicValue::DifferentSignatures => {
-                return Err("call could potentially have different signatures".into())
+            BasicValue::DifferentSignatures(a, b) => {
+                return Err(format!(
+                    "call could potentially have different signatures {a} and {b}"
+                ))
             }
             val => self.stack.push(val),
         }
@@ -583,16 +628,16 @@ impl<'a> VirtualEnv<'a> {
             if let Some(f_args) = f_args {
                 if sig.args != f_args {
                     return Err(format!(
-                        "{prim}'s function {f:?} has {} args, expected {}",
-                        sig.args, f_args
+                        "{prim}'s function must take {f_args} arguments, \
+                        but its signature is {sig}"
                     ));
                 }
             }
             if let Some(f_outputs) = f_outputs {
                 if sig.outputs != f_outputs {
                     return Err(format!(
-                        "{prim}'s function {f:?} has {} outputs, expected {}",
-                        sig.outputs, f_outputs
+                        "{prim}'s function must return {f_outputs} values, \
+                        but its signature is {sig}"
                     ));
                 }
             }
@@ -613,7 +658,9 @@ impl<'a> VirtualEnv<'a> {
         if let BasicValue::Func(f) = self.pop()? {
             let sig = f.signature();
             if sig.outputs != 1 {
-                return Err(format!("{prim}'s function {f:?} did not return 1 value",));
+                return Err(format!(
+                    "{prim}'s function must return 1 value, but its signature is {sig}"
+                ));
             }
             for _ in 0..sig.args {
                 self.pop()?;
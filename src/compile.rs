@@ -1,17 +1,18 @@
 use std::{
-    collections::hash_map::DefaultHasher,
+    collections::{hash_map::DefaultHasher, BTreeSet},
     hash::{Hash, Hasher},
-    sync::Arc,
+    mem::take,
+    sync::{atomic, Arc},
 };
 
 use crate::{
     algorithm::invert::under_instrs,
     array::Array,
     ast::*,
-    check::instrs_signature,
+    check::{instrs_are_noop, instrs_signature},
     function::*,
     lex::{CodeSpan, Sp, Span},
-    primitive::Primitive,
+    primitive::{PrimClass, Primitive},
     run::RunMode,
     value::Value,
     Diagnostic, DiagnosticKind, Ident, SysOp, UiuaError, UiuaResult,
@@ -26,12 +27,30 @@ impl Uiua {
         }
         Ok(())
     }
-    fn item(&mut self, item: Item, in_test: bool) -> UiuaResult {
-        fn words_have_import(words: &[Sp<Word>]) -> bool {
-            words
-                .iter()
-                .any(|w| matches!(w.value, Word::Primitive(Primitive::Sys(SysOp::Import))))
+    /// Run the items of a test scope, recording failures rather than stopping at them
+    fn test_items(&mut self, items: Vec<Item>) -> UiuaResult {
+        for item in items {
+            let is_test = matches!(item, Item::Words(_));
+            let array_height = self.scope.array.len();
+            let assertions = self.assertions.load(atomic::Ordering::Relaxed);
+            match self.item(item, true) {
+                // Only lines that check an assertion count as passed tests
+                Ok(())
+                    if is_test && self.assertions.load(atomic::Ordering::Relaxed) > assertions =>
+                {
+                    self.test_results.passed += 1
+                }
+                Ok(()) => {}
+                Err(e) if e.is_interrupted() => return Err(e),
+                Err(e) => {
+                    self.scope.array.truncate(array_height);
+                    self.test_results.failures.push(e);
+                }
+            }
         }
+        Ok(())
+    }
+    fn item(&mut self, item: Item, in_test: bool) -> UiuaResult {
         fn words_are_export(words: &[Sp<Word>]) -> bool {
             let [word] = words else {
                 return false;
@@ -56,7 +75,13 @@ impl Uiua {
             }
         }
         match item {
-            Item::Scoped { items, test } => {
+            Item::Scoped {
+                items, test: true, ..
+            } if self.mode != RunMode::Normal => {
+                let scope_stack = self.in_scope(true, |env| env.test_items(items))?;
+                self.test_results.values.extend(scope_stack);
+            }
+            Item::Scoped { items, test, .. } => {
                 let scope_stack = self.in_scope(true, |env| env.items(items, test))?;
                 self.stack.extend(scope_stack);
             }
@@ -66,9 +91,15 @@ impl Uiua {
                     RunMode::Test => in_test,
                     RunMode::All => true,
                 };
-                if can_run || words_have_import(&words) || words_are_export(&words) {
+                let imports = words_have_import(&words);
+                if can_run || imports || words_are_export(&words) {
+                    let span = words_span(&words);
                     let instrs = self.compile_words(words, true)?;
-                    self.exec_global_instrs(instrs)?;
+                    self.warn_if_noop(&instrs, span);
+                    self.deny_warnings()?;
+                    self.exec_line_instrs(instrs, imports)?;
+                } else {
+                    self.mark_words_used(&words);
                 }
             }
             Item::Binding(binding) => {
@@ -78,6 +109,8 @@ impl Uiua {
                 };
                 if can_run || words_have_import(&binding.words) {
                     self.binding(binding)?;
+                } else {
+                    self.mark_words_used(&binding.words);
                 }
             }
             Item::ExtraNewlines(_) => {}
@@ -90,7 +123,21 @@ impl Uiua {
         spans.push(span.into());
         idx
     }
+    /// Run the instructions of a top-level line or binding
+    ///
+    /// In compile-only mode, only lines that import files are run,
+    /// though a lone constant is still pushed so that a binding to it gets its value.
+    fn exec_line_instrs(&mut self, instrs: Vec<Instr>, imports: bool) -> UiuaResult {
+        if !self.compile_only || self.import_depth > 0 || imports {
+            return self.exec_global_instrs(instrs);
+        }
+        if let [Instr::Push(val)] = instrs.as_slice() {
+            self.push(Value::clone(val));
+        }
+        Ok(())
+    }
     fn binding(&mut self, binding: Binding) -> UiuaResult {
+        let imports = words_have_import(&binding.words);
         let instrs = self.compile_words(binding.words, true)?;
         let make_fn = |instrs: Vec<Instr>, sig: Signature| {
             let func = Function::new(FunctionId::Named(binding.name.value.clone()), instrs, sig);
@@ -104,7 +151,7 @@ impl Uiua {
                     } else {
                         return Err(UiuaError::Run(Span::Code(declared_sig.span.clone()).sp(
                             format!(
-                                "Function signature mismatch:  declared {} but inferred {}",
+                                "Function signature mismatch: declared {} but inferred {}",
                                 declared_sig.value, sig
                             ),
                         )));
@@ -112,7 +159,25 @@ impl Uiua {
                 }
 
                 if sig.args == 0 && (sig.outputs > 0 || instrs.is_empty()) {
-                    self.exec_global_instrs(instrs)?;
+                    // Duplicating the value keeps a copy on the stack on purpose
+                    let copies = matches!(
+                        instrs.last(),
+                        Some(Instr::Prim(Primitive::Dup | Primitive::Over, _))
+                    );
+                    if sig.outputs > 1 && !copies {
+                        self.warn(
+                            format!(
+                                "{}'s body produces {} values, but only the one on top is bound. \
+                                The other {} will be left on the stack.",
+                                binding.name.value,
+                                sig.outputs,
+                                sig.outputs - 1
+                            ),
+                            binding.name.span.clone(),
+                        );
+                        self.deny_warnings()?;
+                    }
+                    self.exec_line_instrs(instrs, imports)?;
                     if let Some(value) = self.stack.pop() {
                         match value {
                             Value::Func(fs) => match fs.into_scalar() {
@@ -125,6 +190,9 @@ impl Uiua {
                             },
                             val => val,
                         }
+                    } else if sig.outputs > 0 {
+                        // The binding was only compiled, so its value is unknown
+                        Value::default()
                     } else {
                         Function::new(
                             FunctionId::Named(binding.name.value.clone()),
@@ -152,20 +220,207 @@ impl Uiua {
         let mut globals = self.globals.lock();
         let idx = globals.len();
         globals.push(val);
-        self.scope.names.insert(binding.name.value, idx);
+        drop(globals);
+        let name = binding.name.value;
+        let span = binding.name.span;
+        if let Some(prev) = (self.scope.binding_spans).insert(name.clone(), span.clone()) {
+            self.scope.unused.remove(&name);
+            self.warn(
+                format!("`{name}` was already bound at {prev}. This binding replaces it."),
+                span,
+            );
+            self.deny_warnings()?;
+        }
+        // Single-character names are exempt so that they can be bound without being used
+        if name.chars().count() > 1 {
+            self.scope.unused.insert(name.clone());
+        }
+        self.scope.names.insert(name, idx);
         Ok(())
     }
-    fn compile_words(&mut self, words: Vec<Sp<Word>>, call: bool) -> UiuaResult<Vec<Instr>> {
-        self.new_functions.push(Vec::new());
-        self.words(words, call)?;
+    /// Evaluate a primitive at compile time if all its arguments are constants
+    ///
+    /// Returns whether the primitive and its arguments were replaced by a push of the result.
+    /// If the evaluation fails, nothing is folded, so the error is still raised at the primitive's
+    /// span when the code runs, where it may be caught by [`Primitive::Try`].
+    fn fold_constants(&mut self, prim: Primitive, span: &CodeSpan, span_i: usize) -> bool {
+        if !self.constant_folding || !is_foldable(prim) || prim.outputs() != Some(1) {
+            return false;
+        }
+        let args = match prim.args() {
+            Some(args @ 1..) => args as usize,
+            _ => return false,
+        };
+        let instrs = self.new_functions.last().unwrap();
+        if instrs.len() < args {
+            return false;
+        }
+        let mut values = Vec::with_capacity(args);
+        for instr in &instrs[instrs.len() - args..] {
+            match instr {
+                Instr::Push(val) if !matches!(**val, Value::Func(_)) => {
+                    values.push((**val).clone())
+                }
+                Instr::Prim(p, _) => match p.as_constant() {
+                    Some(n) => values.push(n.into()),
+                    None => return false,
+                },
+                _ => return false,
+            }
+        }
+        // The last value is the first argument
+        let foldable = match (prim.class(), values.as_slice()) {
+            // Arrays of incompatible shapes depend on the fill value
+            (PrimClass::DyadicPervasive, [b, a]) => {
+                a.shape().starts_with(b.shape()) || b.shape().starts_with(a.shape())
+            }
+            (_, [n]) if prim == Primitive::Range => {
+                n.as_nat(self, "").is_ok_and(|n| n <= MAX_FOLDED_RANGE)
+            }
+            _ => true,
+        };
+        if !foldable {
+            return false;
+        }
+        let stack = take(&mut self.stack);
+        self.stack.extend(values);
+        self.push_span(span_i, Some(prim));
+        let res = prim.run(self);
+        self.pop_span();
+        let result = self.stack.pop();
+        self.stack = stack;
+        let result = match (res, result) {
+            (Ok(()), Some(result)) => result,
+            // The error is still raised at runtime, where it may be caught
+            (Err(error), _) => {
+                if !error.is_fill() && self.try_depth == 0 {
+                    self.warn(
+                        format!("This will always fail: {}", error.message()),
+                        span.clone(),
+                    );
+                }
+                return false;
+            }
+            (Ok(()), None) => return false,
+        };
+        let instrs = self.new_functions.last_mut().unwrap();
+        instrs.truncate(instrs.len() - args);
+        self.push_instr(Instr::push(result));
+        true
+    }
+    /// Record a warning unless warnings are disabled
+    fn warn(&mut self, message: impl Into<String>, span: impl Into<Span>) {
+        if self.warnings {
+            self.diagnostics
+                .insert(Diagnostic::new(message, span, DiagnosticKind::Warning));
+        }
+    }
+    /// Warn about bindings in the current scope that were never referenced
+    ///
+    /// Only bindings in the file being run are reported, not ones in imported files,
+    /// since an importer may only use some of what a file defines.
+    pub(crate) fn warn_unused_bindings(&mut self) -> UiuaResult {
+        for name in take(&mut self.scope.unused) {
+            if let Some(span) = self.scope.binding_spans.get(&name) {
+                if self.warnings && self.import_depth == 0 {
+                    self.diagnostics.insert(Diagnostic::new(
+                        format!("`{name}` is never used"),
+                        span.clone(),
+                        DiagnosticKind::Unused,
+                    ));
+                }
+            }
+        }
+        self.deny_warnings()?;
+        self.flush_diagnostics();
+        Ok(())
+    }
+    /// Stop with an error if there are warnings and they are denied
+    fn deny_warnings(&mut self) -> UiuaResult {
+        if !self.deny_warnings {
+            return Ok(());
+        }
+        let is_warning = |diag: &Diagnostic| {
+            matches!(diag.kind, DiagnosticKind::Warning | DiagnosticKind::Unused)
+        };
+        if !self.diagnostics.iter().any(is_warning) {
+            return Ok(());
+        }
+        let (warnings, others): (BTreeSet<_>, _) = take(&mut self.diagnostics)
+            .into_iter()
+            .partition(is_warning);
+        self.diagnostics = others;
+        Err(UiuaError::Warnings(warnings.into_iter().collect()))
+    }
+    /// Print the accumulated diagnostics if they should be printed as they are encountered
+    fn flush_diagnostics(&mut self) {
         if self.print_diagnostics {
             for diagnostic in self.take_diagnostics() {
-                eprintln!("{}", diagnostic.show(true));
+                eprintln!("{}", diagnostic.show(self.color));
+            }
+        }
+    }
+    /// Mark a name as referenced
+    fn mark_used(&mut self, ident: &Ident) {
+        if self.scope.names.contains_key(ident) {
+            self.scope.unused.remove(ident);
+        } else if self.scope.local {
+            if let Some(scope) = self.higher_scopes.last_mut() {
+                scope.unused.remove(ident);
+            }
+        }
+    }
+    /// Mark the names referenced by words that are not compiled as referenced
+    fn mark_words_used(&mut self, words: &[Sp<Word>]) {
+        for word in words {
+            match &word.value {
+                Word::Ident(ident) => self.mark_used(ident),
+                Word::Strand(items) => self.mark_words_used(items),
+                Word::Array(arr) => {
+                    for line in &arr.lines {
+                        self.mark_words_used(line);
+                    }
+                }
+                Word::Func(func) => {
+                    for line in &func.lines {
+                        self.mark_words_used(line);
+                    }
+                }
+                Word::Modified(m) => self.mark_words_used(&m.operands),
+                _ => {}
+            }
+        }
+    }
+    /// Warn about a line that provably does nothing
+    fn warn_if_noop(&mut self, instrs: &[Instr], span: Option<CodeSpan>) {
+        if let Some(span) = span {
+            if instrs_are_noop(instrs) {
+                self.warn("This line has no effect", span);
             }
         }
+    }
+    /// Get the value of a string literal, sharing the buffer of an identical earlier one
+    fn intern_string(&mut self, s: String) -> Value {
+        self.string_literals
+            .lock()
+            .entry(s)
+            .or_insert_with_key(|s| s.clone().into())
+            .clone()
+    }
+    fn compile_words(&mut self, words: Vec<Sp<Word>>, call: bool) -> UiuaResult<Vec<Instr>> {
+        self.new_functions.push(Vec::new());
+        self.words(words, call)?;
+        self.deny_warnings()?;
+        self.flush_diagnostics();
         let instrs = self.new_functions.pop().unwrap();
         Ok(instrs)
     }
+    /// Infer the signature of some words without running them
+    #[cfg(feature = "lsp")]
+    pub(crate) fn words_signature(&mut self, words: Vec<Sp<Word>>) -> UiuaResult<Signature> {
+        let instrs = self.compile_words(words, true)?;
+        instrs_signature(&instrs).map_err(|e| self.error(e))
+    }
     fn compile_operand_words(
         &mut self,
         words: Vec<Sp<Word>>,
@@ -176,7 +431,7 @@ impl Uiua {
         if let [Instr::Push(val)] = instrs.as_slice() {
             if let Some(f) = val.as_function() {
                 sig = Some(f.signature());
-                instrs = f.instrs.clone();
+                instrs = f.instrs.to_vec();
             }
         }
         let sig = if let Some(sig) = sig {
@@ -209,6 +464,23 @@ impl Uiua {
             }
             // First reverse = last
             ([.., Instr::Prim(top @ Reverse, _)], Instr::Prim(First, _)) => *top = Last,
+            // First rise = index of minimum
+            ([.., Instr::Prim(top @ Rise, _)], Instr::Prim(First, _)) => *top = FirstMinIndex,
+            // First fall = index of maximum
+            ([.., Instr::Prim(top @ Fall, _)], Instr::Prim(First, _)) => *top = FirstMaxIndex,
+            // Under reverse scan = reverse scan
+            (
+                [.., Instr::Prim(Reverse, _), Instr::Push(_), Instr::Prim(Scan, _)],
+                Instr::Prim(Reverse, _),
+            ) => {
+                let Some(Instr::Prim(_, span)) = instrs.pop() else {
+                    unreachable!()
+                };
+                let f = instrs.pop().unwrap();
+                instrs.pop();
+                instrs.push(f);
+                instrs.push(Instr::Prim(ReverseScan, span));
+            }
             // // Coalesce inline stack ops
             // ([.., Instr::])
             (_, instr) => instrs.push(instr),
@@ -217,10 +489,19 @@ impl Uiua {
     fn word(&mut self, word: Sp<Word>, call: bool) -> UiuaResult {
         match word.value {
             Word::Number(_, n) => {
-                self.push_instr(Instr::push(n));
+                // Boolean literals are stored as bytes so that masks built from them stay small
+                // Negative zero stays a number so that it keeps its sign
+                if n == 1.0 || (n == 0.0 && n.is_sign_positive()) {
+                    self.push_instr(Instr::push(n as u8));
+                } else {
+                    self.push_instr(Instr::push(n));
+                }
             }
             Word::Char(c) => self.push_instr(Instr::push(c)),
-            Word::String(s) => self.push_instr(Instr::push(s)),
+            Word::String(s) => {
+                let value = self.intern_string(s);
+                self.push_instr(Instr::push(value));
+            }
             Word::FormatString(frags) => {
                 let signature = Signature::new(frags.len() - 1, 1);
                 let f = Function::new(
@@ -377,7 +658,9 @@ impl Uiua {
                 .get(&ident)
         }) {
             // Name exists in scope
-            let value = self.globals.lock()[*idx].clone();
+            let idx = *idx;
+            self.mark_used(&ident);
+            let value = self.globals.lock()[idx].clone();
             let should_call = matches!(&value, Value::Func(f) if f.shape.is_empty());
             self.push_instr(Instr::push(value));
             if should_call && call {
@@ -391,8 +674,13 @@ impl Uiua {
     }
     fn func(&mut self, func: Func, span: CodeSpan) -> UiuaResult {
         let mut instrs = Vec::new();
+        // A single line that does nothing may be an intentionally empty operand
+        let multiline = func.lines.len() > 1;
         for line in func.lines {
-            instrs.extend(self.compile_words(line, true)?);
+            let span = words_span(&line).filter(|_| multiline);
+            let line_instrs = self.compile_words(line, true)?;
+            self.warn_if_noop(&line_instrs, span);
+            instrs.extend(line_instrs);
         }
 
         // Validate signature
@@ -621,12 +909,12 @@ impl Uiua {
         }
 
         if call {
-            self.words(modified.operands, false)?;
+            self.modifier_operands(modified.modifier.value, modified.operands)?;
             let span = self.add_span(modified.modifier.span);
             self.push_instr(Instr::Prim(modified.modifier.value, span));
         } else {
             self.new_functions.push(Vec::new());
-            self.words(modified.operands, false)?;
+            self.modifier_operands(modified.modifier.value, modified.operands)?;
             self.primitive(
                 modified.modifier.value,
                 modified.modifier.span.clone(),
@@ -649,6 +937,14 @@ impl Uiua {
         }
         Ok(())
     }
+    /// Compile the operands of a modifier that is not inlined
+    fn modifier_operands(&mut self, modifier: Primitive, operands: Vec<Sp<Word>>) -> UiuaResult {
+        let is_try = modifier == Primitive::Try;
+        self.try_depth += is_try as usize;
+        let res = self.words(operands, false);
+        self.try_depth -= is_try as usize;
+        res
+    }
     fn handle_primitive_deprecation(&mut self, prim: Primitive, span: &CodeSpan) {
         if let Some(suggestion) = prim.deprecation_suggestion() {
             let suggestion = if suggestion.is_empty() {
@@ -656,7 +952,7 @@ impl Uiua {
             } else {
                 format!(", {suggestion}")
             };
-            self.diagnostics.insert(Diagnostic::new(
+            self.warn(
                 format!(
                     "Warning: {}{} is deprecated and will be removed in a future version{}",
                     prim.name().unwrap_or_default(),
@@ -664,15 +960,16 @@ impl Uiua {
                     suggestion
                 ),
                 span.clone(),
-                DiagnosticKind::Warning,
-            ));
+            );
         }
     }
     fn primitive(&mut self, prim: Primitive, span: CodeSpan, call: bool) -> UiuaResult {
         self.handle_primitive_deprecation(prim, &span);
         let span_i = self.add_span(span.clone());
         if call || prim.as_constant().is_some() {
-            self.push_instr(Instr::Prim(prim, span_i));
+            if !self.fold_constants(prim, &span, span_i) {
+                self.push_instr(Instr::Prim(prim, span_i));
+            }
         } else {
             let instrs = [Instr::Prim(prim, span_i)];
             let func = Function::new_inferred(FunctionId::Primitive(prim), instrs);
@@ -689,6 +986,42 @@ impl Uiua {
     }
 }
 
+/// The largest [`Primitive::Range`] that will be evaluated at compile time
+const MAX_FOLDED_RANGE: usize = 10_000;
+
+/// Check if a primitive always gives the same result for the same arguments
+///
+/// Primitives that do IO, use randomness, or depend on a fill value are excluded.
+fn is_foldable(prim: Primitive) -> bool {
+    use Primitive::*;
+    matches!(
+        prim.class(),
+        PrimClass::MonadicPervasive | PrimClass::DyadicPervasive
+    ) || matches!(
+        prim,
+        Len | Shape | Range | First | Last | Reverse | Deshape | Transpose | Rise | Fall
+    )
+}
+
+/// Check if a line imports a file
+fn words_have_import(words: &[Sp<Word>]) -> bool {
+    words
+        .iter()
+        .any(|w| matches!(w.value, Word::Primitive(Primitive::Sys(SysOp::Import))))
+}
+
+/// Get the span of a line, ignoring spaces and comments
+fn words_span(words: &[Sp<Word>]) -> Option<CodeSpan> {
+    let mut code = words
+        .iter()
+        .filter(|word| !matches!(word.value, Word::Spaces | Word::Comment(_)));
+    let first = code.next()?.span.clone();
+    Some(match code.next_back() {
+        Some(last) => first.merge(last.span.clone()),
+        None => first,
+    })
+}
+
 fn words_look_pervasive(words: &[Sp<Word>]) -> bool {
     use Primitive::*;
     words.iter().all(|word| match &word.value {
@@ -700,3 +1033,190 @@ fn words_look_pervasive(words: &[Sp<Word>]) -> bool {
         _ => false,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constant_folding() {
+        let instr_count = |code: &str, folding: bool| {
+            let mut env = Uiua::with_native_sys().with_constant_folding(folding);
+            env.load_str(code).unwrap();
+            let globals = env.globals.lock();
+            globals.last().unwrap().as_function().unwrap().instrs.len()
+        };
+        for (code, folded, unfolded) in [
+            ("F ← +÷2 τ", 2, 4),
+            ("F ← ⊏∶ ×2 ⇡10", 3, 6),
+            ("F ← +⊢⇌[1 2 3]", 2, 3),
+            // Not pure
+            ("F ← ⊂+⚂ 1", 4, 4),
+            // Depends on the fill value
+            ("F ← ⊂⬚0(×[1 2] [1 2 3])", 4, 4),
+            ("F ← ⊂+[1 2] [1 2 3]", 4, 4),
+            // Errors are left to be raised at runtime
+            ("F ← ⊂⍣(+@a @b)⋅0", 4, 4),
+        ] {
+            assert_eq!(instr_count(code, true), folded, "{code}");
+            assert_eq!(instr_count(code, false), unfolded, "{code}");
+        }
+        // But they are warned about at compile time, unless they are tried
+        let mut env = Uiua::with_native_sys();
+        env.load_str("F ← ⊂⍣(+@a @b)⋅0").unwrap();
+        assert!(env.take_diagnostics().is_empty());
+        env.load_str("G ← ⊂+@a @b").unwrap();
+        let diagnostics: Vec<_> = env.take_diagnostics().into_iter().collect();
+        let [diagnostic] = &diagnostics[..] else {
+            panic!("expected one diagnostic, got {diagnostics:?}")
+        };
+        assert_eq!(diagnostic.kind, DiagnosticKind::Warning);
+        assert_eq!(
+            diagnostic.message,
+            "This will always fail: Cannot add character and character"
+        );
+        assert_eq!(diagnostic.span.to_string(), "1:6");
+    }
+
+    #[test]
+    fn interning() {
+        // Identical string literals share one buffer
+        let mut env = Uiua::with_native_sys();
+        env.load_str("F ← ⊂\"hi\"\nG ← |1 ⊂\"hi\"\n\"hi\" \"hi\"")
+            .unwrap();
+        let stack = env.take_stack();
+        let [a, b] = &stack[..] else {
+            panic!("expected two values")
+        };
+        let (a, b) = (a.as_char_array().unwrap(), b.as_char_array().unwrap());
+        assert_eq!(a.data.as_ptr(), b.data.as_ptr());
+        // Renaming a function does not copy its instructions
+        let f = Function::new(FunctionId::Main, [Instr::push(1.0)], Signature::new(0, 1));
+        let mut g = f.clone();
+        g.id = FunctionId::Constant;
+        assert!(Arc::ptr_eq(&f.instrs, &g.instrs));
+        assert_ne!(f, g);
+    }
+
+    #[test]
+    fn signature_errors() {
+        for (code, message) in [
+            (
+                "F ← (|2.1 +1)",
+                "Function signature mismatch: declared |2.1 but inferred |1.1",
+            ),
+            (
+                "F ← |3.1 +",
+                "Function signature mismatch: declared |3.1 but inferred |2.1",
+            ),
+            (
+                "F ← (|1 !(|2 +1))",
+                "Function signature mismatch: declared |2.1 but inferred |1.1",
+            ),
+            (
+                "/(++) [1 2]",
+                "Reduce's function must take at most 2 arguments, but its signature is |3.1",
+            ),
+            (
+                "≡(.) [1 2]",
+                "Rows's function must return 0 or 1 values, but its signature is |1.2",
+            ),
+            (
+                "F ← !⊡∶[(+)(.)]",
+                "Cannot infer function signature: \
+                call could potentially have different signatures |2.1 and |1.2",
+            ),
+        ] {
+            let mut env = Uiua::with_native_sys();
+            let error = env.load_str(code).unwrap_err().message();
+            assert!(error.ends_with(message), "{code}: {error}");
+        }
+    }
+
+    #[test]
+    fn warnings() {
+        let warnings = |code: &str, enabled: bool| {
+            let mut env = Uiua::with_native_sys().with_warnings(enabled);
+            env.load_str(code).unwrap();
+            env.take_diagnostics()
+                .into_iter()
+                .filter(|diag| diag.kind == DiagnosticKind::Warning)
+                .count()
+        };
+        for (code, count) in [
+            ("X ← 1 2 3", 1),
+            ("X ← 1", 0),
+            ("X ← .1", 0),
+            (";5", 1),
+            (";+1 2", 1),
+            ("5;6", 0),
+            ("F ← (\n  +1\n  ;;5 6\n)", 1),
+            ("F ← (\n  +1\n  ;\n)", 0),
+            ("⍣(;5)(;)", 0),
+        ] {
+            assert_eq!(warnings(code, true), count, "{code}");
+            assert_eq!(warnings(code, false), 0, "{code}");
+        }
+    }
+
+    #[test]
+    fn binding_warnings() {
+        let messages = |code: &str| {
+            let mut env = Uiua::with_native_sys();
+            env.load_str(code).unwrap();
+            env.take_diagnostics()
+                .into_iter()
+                .filter(|diag| {
+                    matches!(diag.kind, DiagnosticKind::Warning | DiagnosticKind::Unused)
+                })
+                .map(|diag| diag.message)
+                .collect::<Vec<_>>()
+        };
+        for (code, expected) in [
+            ("Foo ← 5", vec!["`Foo` is never used"]),
+            ("Foo ← 5\nFoo", vec![]),
+            ("Foo ← 5\nBar ← +1 Foo\nBar", vec![]),
+            ("X ← 5", vec![]),
+            (
+                "Foo ← 1\nFoo ← 2\nFoo",
+                vec!["`Foo` was already bound at 1:1. This binding replaces it."],
+            ),
+            ("Foo ← 5\n---\nFoo\n---", vec![]),
+            ("---\nFoo ← 5\n---", vec!["`Foo` is never used"]),
+        ] {
+            assert_eq!(messages(code), expected, "{code}");
+        }
+        // Unused bindings in imported files are not reported
+        let path = std::env::temp_dir().join("uiua_binding_warnings_lib.ua");
+        std::fs::write(&path, "Sq ← ×.\nCube ← ×.×.\nSq_Cube").unwrap();
+        let code = format!(
+            "Lib ← &i {:?}\nSqr ← use \"Sq\" Lib\nSqr 3",
+            path.to_string_lossy()
+        );
+        assert!(messages(&code).is_empty(), "{:?}", messages(&code));
+        _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn deny_warnings() {
+        let run = |code: &str| {
+            let mut env = Uiua::with_native_sys().with_deny_warnings(true);
+            let res = env.load_str(code);
+            (res, env.take_stack())
+        };
+        // Nothing after the warning is run
+        let (res, stack) = run("1\n;5\n2");
+        match res {
+            Err(UiuaError::Warnings(warnings)) => {
+                assert_eq!(warnings.len(), 1);
+                assert_eq!(warnings[0].message, "This line has no effect");
+            }
+            res => panic!("expected denied warnings, got {res:?}"),
+        }
+        assert_eq!(stack, [Value::from(1)]);
+        let (res, _) = run("Foo ← 5");
+        assert!(matches!(res, Err(UiuaError::Warnings(_))), "{res:?}");
+        let (res, _) = run("Foo ← 5\nFoo");
+        assert!(res.is_ok());
+    }
+}
@@ -96,7 +96,9 @@ impl<T: Clone> CowSlice<T> {
     where
         F: FnOnce(&mut EcoVec<T>) -> R,
     {
-        if self.data.is_unique() && self.start == 0 && self.end == self.data.len() as u32 {
+        if self.data.is_unique() && self.start == 0 {
+            // A truncated unique buffer can be reused without copying
+            self.data.truncate(self.end as usize);
             let res = f(&mut self.data);
             self.end = self.data.len() as u32;
             res
@@ -112,6 +114,23 @@ impl<T: Clone> CowSlice<T> {
     }
 }
 
+#[test]
+fn cow_slice_shares_until_modified() {
+    let mut slice = CowSlice::from([1, 2, 3, 4]);
+    let copy = slice.clone();
+    assert_eq!(slice.as_ptr(), copy.as_ptr());
+    slice.as_mut_slice()[0] = 5;
+    assert_ne!(slice.as_ptr(), copy.as_ptr());
+    assert_eq!(copy, [1, 2, 3, 4]);
+
+    // Modifying a truncated unique slice reuses its buffer
+    let ptr = slice.as_ptr();
+    slice.truncate(2);
+    slice.modify(|vec| vec.push(6));
+    assert_eq!(slice.as_ptr(), ptr);
+    assert_eq!(slice, [5, 2, 6]);
+}
+
 #[test]
 fn cow_slice_modify() {
     let mut slice = CowSlice::from([1, 2, 3]);
@@ -0,0 +1,237 @@
+//! Reading and writing CSV as described in [RFC 4180](https://www.rfc-editor.org/rfc/rfc4180)
+
+use std::sync::Arc;
+
+use ecow::EcoVec;
+use tinyvec::tiny_vec;
+
+use crate::{array::Array, function::Function, value::Value, Uiua};
+
+/// Split CSV text into records of fields
+///
+/// Records may end with either CRLF or LF.
+pub(crate) fn parse_csv(input: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut line = 1;
+    let mut chars = input.chars().peekable();
+    // Whether the current record has any content, so that a trailing newline does not add an empty record
+    let mut started = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if field.is_empty() => {
+                let start_line = line;
+                loop {
+                    match chars.next() {
+                        Some('"') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            field.push('"');
+                        }
+                        Some('"') => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            field.push(c);
+                        }
+                        None => {
+                            return Err(format!(
+                                "Invalid CSV: the quoted field starting on line {start_line} \
+                                is never closed"
+                            ))
+                        }
+                    }
+                }
+                match chars.peek() {
+                    Some(',' | '\n' | '\r') | None => {}
+                    Some(c) => {
+                        return Err(format!(
+                            "Invalid CSV on line {line}: expected `,` or the end of the line \
+                            after a quoted field, but found `{c}`"
+                        ))
+                    }
+                }
+            }
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+                line += 1;
+                started = false;
+                continue;
+            }
+            c => field.push(c),
+        }
+        started = true;
+    }
+    if started {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Make a rank 3 character array from CSV records
+///
+/// Each row is a record, and each row of a record is a field.
+/// Fields and missing fields are padded with the fill character or spaces.
+pub(crate) fn csv_to_chars(records: Vec<Vec<String>>, env: &Uiua) -> Array<char> {
+    let fill = env.char_fill().unwrap_or(' ');
+    let field_count = records.iter().map(Vec::len).max().unwrap_or(0);
+    let field_len = (records.iter().flatten())
+        .map(|field| field.chars().count())
+        .max()
+        .unwrap_or(0);
+    let mut data = EcoVec::with_capacity(records.len() * field_count * field_len);
+    for record in &records {
+        for i in 0..field_count {
+            let field = record.get(i).map(String::as_str).unwrap_or("");
+            let len = field.chars().count();
+            data.extend(field.chars());
+            data.extend(std::iter::repeat_n(fill, field_len - len));
+        }
+    }
+    Array::new(tiny_vec![records.len(), field_count, field_len], data)
+}
+
+/// Make a number matrix from CSV records
+///
+/// Missing fields are filled with the fill number.
+pub(crate) fn csv_to_nums(records: Vec<Vec<String>>, env: &Uiua) -> Result<Array<f64>, String> {
+    let field_count = records.iter().map(Vec::len).max().unwrap_or(0);
+    let mut data = EcoVec::with_capacity(records.len() * field_count);
+    for (r, record) in records.iter().enumerate() {
+        for (c, field) in record.iter().enumerate() {
+            let trimmed = field.trim();
+            let n = match trimmed.parse::<f64>() {
+                Ok(n) if !trimmed.is_empty() => n,
+                _ => {
+                    return Err(format!(
+                        "Cell at row {}, column {} is not a number: {field:?}",
+                        r + 1,
+                        c + 1
+                    ))
+                }
+            };
+            data.push(n);
+        }
+        if record.len() < field_count {
+            let Some(fill) = env.num_fill() else {
+                return Err(format!(
+                    "Row {} has {} field{}, but another row has {field_count}. \
+                    A fill value can be used to pad the missing fields.",
+                    r + 1,
+                    record.len(),
+                    if record.len() == 1 { "" } else { "s" }
+                ));
+            };
+            data.extend(std::iter::repeat_n(fill, field_count - record.len()));
+        }
+    }
+    Ok(Array::new(tiny_vec![records.len(), field_count], data))
+}
+
+/// Write a table as CSV
+///
+/// Accepted values are a rank 2 array of numbers or [`box`](crate::primitive::Primitive::Box)ed strings and numbers,
+/// or a rank 3 character array where each row is a record of padded fields.
+/// Trailing whitespace is trimmed from the fields of a character array.
+pub(crate) fn write_csv(value: &Value) -> Result<String, String> {
+    let records: Vec<Vec<String>> = match value {
+        Value::Num(_) | Value::Byte(_) | Value::Func(_) if value.rank() == 2 => (value.rows())
+            .map(|record| record.rows().map(|field| csv_cell(&field)).collect())
+            .collect::<Result<_, _>>()?,
+        Value::Char(chars) if chars.rank() == 3 => {
+            let (field_count, field_len) = (chars.shape()[1], chars.shape()[2]);
+            let fields: Vec<String> = if field_len == 0 {
+                vec![String::new(); chars.row_count() * field_count]
+            } else {
+                (chars.data.chunks_exact(field_len))
+                    .map(|field| field.iter().collect::<String>().trim_end().into())
+                    .collect()
+            };
+            (fields.chunks(field_count.max(1)))
+                .map(<[String]>::to_vec)
+                .collect()
+        }
+        value => {
+            return Err(format!(
+                "Cannot write a {} array with shape {} as CSV. \
+                It must be a rank 2 array of numbers or boxes, or a rank 3 character array.",
+                value.type_name(),
+                value.format_shape()
+            ))
+        }
+    };
+    let mut csv = String::new();
+    for record in records {
+        for (i, field) in record.iter().enumerate() {
+            if i > 0 {
+                csv.push(',');
+            }
+            if field.contains([',', '"', '\n', '\r']) {
+                csv.push('"');
+                csv.push_str(&field.replace('"', "\"\""));
+                csv.push('"');
+            } else {
+                csv.push_str(field);
+            }
+        }
+        csv.push_str("\r\n");
+    }
+    Ok(csv)
+}
+
+fn csv_cell(value: &Value) -> Result<String, String> {
+    match value {
+        Value::Num(nums) if nums.rank() == 0 => Ok(nums.data[0].to_string()),
+        Value::Byte(bytes) if bytes.rank() == 0 => Ok(bytes.data[0].to_string()),
+        Value::Char(chars) if chars.rank() <= 1 => Ok(chars.data.iter().collect()),
+        Value::Func(fs) if fs.rank() == 0 => csv_cell(unbox(&fs.data[0])?),
+        value => Err(format!(
+            "Cannot write a {} array with shape {} as a CSV field",
+            value.type_name(),
+            value.format_shape()
+        )),
+    }
+}
+
+fn unbox(f: &Arc<Function>) -> Result<&Value, String> {
+    f.as_boxed()
+        .filter(|_| f.is_constant())
+        .ok_or_else(|| format!("Cannot write function {f} as a CSV field"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::tests::error_message;
+
+    #[test]
+    fn csv_errors() {
+        assert_eq!(
+            error_message("&csvn &fras \"tests/csv/bad_number.csv\""),
+            "1:1: Cell at row 2, column 2 is not a number: \"x\""
+        );
+        assert_eq!(
+            error_message("&csvr &fras \"tests/csv/unclosed.csv\""),
+            "1:1: Invalid CSV: the quoted field starting on line 2 is never closed"
+        );
+        assert_eq!(
+            error_message("&csvr \"a\\n\\\"b\\\"c\""),
+            "1:1: Invalid CSV on line 2: expected `,` or the end of the line \
+            after a quoted field, but found `c`"
+        );
+        assert_eq!(
+            error_message("&csvn \"1,2\\n3\""),
+            "1:1: Row 2 has 1 field, but another row has 2. \
+            A fill value can be used to pad the missing fields."
+        );
+        assert_eq!(
+            error_message("&csvw [1 2]"),
+            "1:1: Cannot write a number array with shape [2] as CSV. \
+            It must be a rank 2 array of numbers or boxes, or a rank 3 character array."
+        );
+    }
+}
@@ -2,17 +2,17 @@ use std::{
     collections::HashMap,
     convert::Infallible,
     error::Error,
-    fmt, fs, io,
+    fmt, io,
     path::{Path, PathBuf},
     sync::Arc,
 };
 
 use ariadne::{Color, Config, Label, Report, ReportKind, Source};
+use serde_json::{json, Value as Json};
 
 use crate::{
-    example_ua,
     function::FunctionId,
-    lex::{CodeSpan, Sp, Span},
+    lex::{CodeSpan, Loc, Sp, Span},
     parse::ParseError,
     value::Value,
 };
@@ -30,7 +30,10 @@ pub enum UiuaError {
     Throw(Box<Value>, Span),
     Break(usize, Span),
     Timeout(Span),
+    Interrupted(Span),
     Fill(Box<Self>),
+    /// Warnings that were treated as errors
+    Warnings(Vec<Diagnostic>),
 }
 
 pub type UiuaResult<T = ()> = Result<T, UiuaError>;
@@ -51,6 +54,17 @@ impl From<Sp<String>> for UiuaError {
 pub struct TraceFrame {
     pub id: FunctionId,
     pub span: Span,
+    /// The row that was being processed if the frame is a loop like [`Primitive::Rows`](crate::primitive::Primitive::Rows)
+    pub row: Option<usize>,
+}
+
+impl TraceFrame {
+    fn label(&self) -> String {
+        match self.row {
+            Some(row) => format!("{} row {row}", self.id),
+            None => self.id.to_string(),
+        }
+    }
 }
 
 impl fmt::Display for UiuaError {
@@ -73,18 +87,95 @@ impl fmt::Display for UiuaError {
                 write!(f, "{error}")?;
                 format_trace(f, trace)
             }
-            UiuaError::Throw(value, span) => write!(f, "{span}: {value}"),
+            UiuaError::Throw(value, span) => write!(f, "{span}: {}", throw_message(value)),
             UiuaError::Break(_, span) => write!(f, "{span}: Break amount exceeded loop depth"),
             UiuaError::Timeout(_) => write!(f, "Maximum execution time exceeded"),
+            UiuaError::Interrupted(_) => write!(f, "Program interrupted"),
             UiuaError::Fill(error) => error.fmt(f),
+            UiuaError::Warnings(warnings) => {
+                for (i, warning) in warnings.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "{}: {warning}", warning.span)?;
+                }
+                Ok(())
+            }
         }
     }
 }
 
+/// Get the message for a thrown value
+///
+/// Strings are shown as-is. Other values are shown as they would be on the stack.
+fn throw_message(value: &Value) -> String {
+    match value {
+        Value::Char(chars) if chars.rank() <= 1 => chars.data.iter().collect(),
+        value => {
+            let shown = value.show();
+            if shown.contains('\n') {
+                format!("\n{shown}")
+            } else {
+                shown
+            }
+        }
+    }
+}
+
+/// The kind of a [`UiuaError`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UiuaErrorKind {
+    /// The code could not be parsed
+    Parse,
+    /// A file could not be loaded
+    Io,
+    /// A formatted file could not be written
+    Format,
+    /// An error occurred while compiling or running the code
+    Run,
+    /// The code produced warnings, and warnings are denied
+    Warnings,
+    /// An execution limit was exceeded
+    Timeout,
+    /// Execution was interrupted
+    Interrupted,
+}
+
 impl UiuaError {
+    /// Get the kind of the error
+    pub fn kind(&self) -> UiuaErrorKind {
+        match self {
+            UiuaError::Load(..) => UiuaErrorKind::Io,
+            UiuaError::Format(..) => UiuaErrorKind::Format,
+            UiuaError::Parse(_) => UiuaErrorKind::Parse,
+            UiuaError::Run(_) | UiuaError::Throw(..) | UiuaError::Break(..) => UiuaErrorKind::Run,
+            UiuaError::Traced { error, .. } | UiuaError::Fill(error) => error.kind(),
+            UiuaError::Timeout(_) => UiuaErrorKind::Timeout,
+            UiuaError::Interrupted(_) => UiuaErrorKind::Interrupted,
+            UiuaError::Warnings(_) => UiuaErrorKind::Warnings,
+        }
+    }
+    /// Get the error's message without its location or trace
+    ///
+    /// If there are several errors, their messages are on separate lines.
     pub fn message(&self) -> String {
+        (self.parts().into_iter())
+            .map(|(message, _)| message)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+    /// Get the span of the error's location
+    ///
+    /// If there are several errors, this is the span of the first one.
+    pub fn span(&self) -> Option<Span> {
+        (self.parts().into_iter())
+            .map(|(_, span)| span)
+            .find(|span| matches!(span, Span::Code(_)))
+    }
+    /// Get the error's message with its location, but without its trace
+    pub(crate) fn located_message(&self) -> String {
         match self {
-            UiuaError::Traced { error, .. } => error.message(),
+            UiuaError::Traced { error, .. } => error.located_message(),
             error => error.to_string(),
         }
     }
@@ -92,7 +183,7 @@ impl UiuaError {
         match self {
             UiuaError::Throw(value, _) => *value,
             UiuaError::Traced { error, .. } => error.value(),
-            error => error.message().into(),
+            error => error.located_message().into(),
         }
     }
     pub fn break_data(self) -> Result<(usize, Span), Self> {
@@ -115,23 +206,47 @@ impl UiuaError {
             _ => false,
         }
     }
+    /// Check if the error was caused by an interrupt
+    pub fn is_interrupted(&self) -> bool {
+        match self {
+            UiuaError::Traced { error, .. } | UiuaError::Fill(error) => error.is_interrupted(),
+            UiuaError::Interrupted(_) => true,
+            _ => false,
+        }
+    }
+    /// Check if the error was caused by exceeding an execution limit
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            UiuaError::Traced { error, .. } | UiuaError::Fill(error) => error.is_timeout(),
+            UiuaError::Timeout(_) => true,
+            _ => false,
+        }
+    }
     /// Mark the error as fill-related
     pub(crate) fn fill(self) -> Self {
         UiuaError::Fill(Box::new(self))
     }
 }
 
+/// The maximum number of distinct frames shown in a trace
+const MAX_TRACE_LINES: usize = 20;
+
 fn format_trace<F: fmt::Write>(f: &mut F, trace: &[TraceFrame]) -> fmt::Result {
     let last = TraceFrame {
         id: FunctionId::Named("".into()),
         span: Span::Builtin,
+        row: None,
     };
     let mut last = &last;
     let mut repetitions = 1;
+    let mut lines = 0;
+    let trace: Vec<&TraceFrame> = (trace.iter())
+        .filter(|frame| frame.id != FunctionId::Main)
+        .collect();
     let max_id_length = trace
         .iter()
         .filter(|frame| frame.span != Span::Builtin)
-        .map(|frame| frame.id.to_string().chars().count())
+        .map(|frame| frame.label().chars().count())
         .max()
         .unwrap_or(0);
     let max_span_length = trace
@@ -142,29 +257,36 @@ fn format_trace<F: fmt::Write>(f: &mut F, trace: &[TraceFrame]) -> fmt::Result {
         })
         .max()
         .unwrap_or(0);
-    for frame in trace {
-        if frame.id == FunctionId::Main {
-            continue;
-        }
-        if frame == last {
+    for (i, frame) in trace.iter().enumerate() {
+        if *frame == last {
             repetitions += 1;
         } else {
             if repetitions > 1 {
                 writeln!(f, " (x {repetitions})")?;
-                repetitions = 0;
+                repetitions = 1;
             } else {
                 writeln!(f)?;
             }
+            if lines == MAX_TRACE_LINES {
+                // Repeated frames would have been folded into one line
+                let remaining = 1
+                    + (trace[i..].windows(2))
+                        .filter(|pair| pair[0] != pair[1])
+                        .count();
+                write!(f, "  ... and {remaining} more")?;
+                return Ok(());
+            }
             match &frame.span {
                 Span::Code(span) => write!(
                     f,
                     "  in {:max_id_length$} at {:max_span_length$}",
-                    frame.id.to_string(),
+                    frame.label(),
                     span
                 )?,
-                Span::Builtin => write!(f, "  in {:max_id_length$}", frame.id.to_string())?,
+                Span::Builtin => write!(f, "  in {:max_id_length$}", frame.label())?,
             }
             last = frame;
+            lines += 1;
         }
     }
     if repetitions > 1 {
@@ -189,36 +311,81 @@ impl From<Infallible> for UiuaError {
 
 impl UiuaError {
     pub fn show(&self, color: bool) -> String {
-        let kind = ReportKind::Error;
         match self {
-            UiuaError::Parse(errors) => report(
-                errors
-                    .iter()
-                    .map(|error| (error.value.to_string(), error.span.clone().into())),
-                kind,
-                color,
-            ),
-            UiuaError::Run(error) => report([(&error.value, error.span.clone())], kind, color),
+            UiuaError::Load(..) | UiuaError::Format(..) => self.to_string(),
             UiuaError::Traced { error, trace } => {
                 let mut s = error.show(color);
                 format_trace(&mut s, trace).unwrap();
                 s
             }
-            UiuaError::Throw(message, span) => report([(&message, span.clone())], kind, color),
-            UiuaError::Break(_, span) => report(
-                [("Break amount exceeded loop depth", span.clone())],
-                kind,
-                color,
-            ),
-            UiuaError::Timeout(span) => report(
-                [("Maximum execution time exceeded", span.clone())],
-                kind,
-                color,
-            ),
             UiuaError::Fill(error) => error.show(color),
-            UiuaError::Load(..) | UiuaError::Format(..) => self.to_string(),
+            _ => report(self.parts(), ReportKind::Error, color),
+        }
+    }
+    /// Get the messages of the error without their locations, along with their spans
+    ///
+    /// Most errors have one part, but there can be several parse errors or denied warnings.
+    /// Errors without a location in the code, like failing to load a file, have a
+    /// [`Span::Builtin`] span.
+    pub fn parts(&self) -> Vec<(String, Span)> {
+        match self {
+            UiuaError::Load(..) | UiuaError::Format(..) => vec![(self.to_string(), Span::Builtin)],
+            UiuaError::Parse(errors) => (errors.iter())
+                .map(|error| (error.value.to_string(), error.span.clone().into()))
+                .collect(),
+            UiuaError::Run(error) => vec![(error.value.clone(), error.span.clone())],
+            UiuaError::Traced { error, .. } | UiuaError::Fill(error) => error.parts(),
+            UiuaError::Throw(value, span) => vec![(throw_message(value), span.clone())],
+            UiuaError::Break(_, span) => {
+                vec![("Break amount exceeded loop depth".into(), span.clone())]
+            }
+            UiuaError::Timeout(span) => {
+                vec![("Maximum execution time exceeded".into(), span.clone())]
+            }
+            UiuaError::Interrupted(span) => vec![("Program interrupted".into(), span.clone())],
+            UiuaError::Warnings(warnings) => (warnings.iter())
+                .map(|warning| (warning.message.clone(), warning.span.clone()))
+                .collect(),
         }
     }
+    /// Get a short name for the kind of error
+    ///
+    /// This is the `code` field of the error's JSON representation.
+    pub fn code(&self) -> &'static str {
+        match self {
+            UiuaError::Load(..) => "load",
+            UiuaError::Format(..) => "format",
+            UiuaError::Parse(_) => "parse",
+            UiuaError::Run(_) => "runtime",
+            UiuaError::Traced { error, .. } | UiuaError::Fill(error) => error.code(),
+            UiuaError::Throw(..) => "throw",
+            UiuaError::Break(..) => "break",
+            UiuaError::Timeout(_) => "timeout",
+            UiuaError::Interrupted(_) => "interrupted",
+            UiuaError::Warnings(_) => "denied-warning",
+        }
+    }
+    /// Format the error as JSON, with one object per line
+    ///
+    /// See [`Diagnostic::to_json`] for the fields of each object.
+    pub fn to_json(&self) -> String {
+        let codes: Vec<&str> = match self {
+            UiuaError::Warnings(warnings) => warnings.iter().map(Diagnostic::code).collect(),
+            _ => vec![self.code()],
+        };
+        let file = match self {
+            UiuaError::Load(path, _) | UiuaError::Format(path, _) => Some(path.as_path()),
+            _ => None,
+        };
+        let parts = self.parts();
+        (parts.iter().enumerate())
+            .map(|(i, (message, span))| {
+                let code = codes.get(i).unwrap_or(&codes[0]);
+                json_object("error", code, message, span, file)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
 /// A message to be displayed to the user that is not an error
@@ -232,6 +399,8 @@ pub struct Diagnostic {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum DiagnosticKind {
     Warning,
+    /// A binding that is never used
+    Unused,
     Advice,
     Style,
 }
@@ -250,11 +419,37 @@ impl Diagnostic {
             kind,
         }
     }
+    /// Get a short name for the kind of diagnostic
+    pub fn code(&self) -> &'static str {
+        match self.kind {
+            DiagnosticKind::Warning => "warning",
+            DiagnosticKind::Unused => "unused-binding",
+            DiagnosticKind::Advice => "advice",
+            DiagnosticKind::Style => "style",
+        }
+    }
+    /// Format the diagnostic as a single-line JSON object
+    ///
+    /// The object has these fields:
+    /// - `severity`: one of `error`, `warning`, `information`, or `hint`
+    /// - `code`: a short name for the kind of diagnostic
+    /// - `message`: the message without its location
+    /// - `file`: the path of the file, or `null` for code that was not loaded from a file
+    /// - `start` and `end`: objects with the 1-based `line` and `column` and the 0-based `byte`
+    ///   offset, or `null` if there is no location
+    pub fn to_json(&self) -> String {
+        let severity = match self.kind {
+            DiagnosticKind::Warning => "warning",
+            DiagnosticKind::Advice => "information",
+            DiagnosticKind::Unused | DiagnosticKind::Style => "hint",
+        };
+        json_object(severity, self.code(), &self.message, &self.span, None)
+    }
     pub fn show(&self, color: bool) -> String {
         report(
             [(&self.message, self.span.clone())],
             match self.kind {
-                DiagnosticKind::Warning => ReportKind::Warning,
+                DiagnosticKind::Warning | DiagnosticKind::Unused => ReportKind::Warning,
                 DiagnosticKind::Advice => ReportKind::Advice,
                 DiagnosticKind::Style => ReportKind::Custom("Style", Color::Green),
             },
@@ -263,6 +458,33 @@ impl Diagnostic {
     }
 }
 
+fn json_object(
+    severity: &str,
+    code: &str,
+    message: &str,
+    span: &Span,
+    file: Option<&Path>,
+) -> String {
+    let loc = |loc: &Loc| json!({ "line": loc.line, "column": loc.col, "byte": loc.byte_pos });
+    let (file, start, end) = match span {
+        Span::Code(span) => (
+            span.path.as_deref().or(file),
+            loc(&span.start),
+            loc(&span.end),
+        ),
+        Span::Builtin => (file, Json::Null, Json::Null),
+    };
+    json!({
+        "severity": severity,
+        "code": code,
+        "message": message,
+        "file": file.map(|path| path.to_string_lossy()),
+        "start": start,
+        "end": end,
+    })
+    .to_string()
+}
+
 fn report<I, T>(errors: I, mut kind: ReportKind, color: bool) -> String
 where
     I: IntoIterator<Item = (T, Span)>,
@@ -283,19 +505,16 @@ where
         Color::Unset
     };
     let mut buffer = Vec::new();
-    let mut chache = None;
+    let mut cache = Cache::default();
     for (message, span) in errors {
         if let Span::Code(span) = span {
-            let cache = chache.get_or_insert_with(|| Cache {
-                input: Source::from(&span.input),
-                files: HashMap::new(),
-            });
+            cache.add(&span);
             let report = Report::<CodeSpan>::build(kind, span.path.clone(), span.start.char_pos)
                 .with_message(message)
                 .with_label(Label::new(span.clone()).with_color(color))
                 .with_config(config)
                 .finish();
-            let _ = report.write(cache, &mut buffer);
+            let _ = report.write(&mut cache, &mut buffer);
         } else {
             if !buffer.ends_with(b"\n") {
                 buffer.push(b'\n');
@@ -333,32 +552,26 @@ impl ariadne::Span for CodeSpan {
     }
 }
 
+/// The source text of the spans in a report
+///
+/// Spans carry the input they were parsed from, so the text shown is always the text that
+/// was run, even if the file has since changed or never existed on disk.
+#[derive(Default)]
 struct Cache {
-    input: Source,
-    files: HashMap<SourceId, Source>,
+    sources: HashMap<SourceId, Source>,
+}
+
+impl Cache {
+    fn add(&mut self, span: &CodeSpan) {
+        (self.sources)
+            .entry(span.path.clone())
+            .or_insert_with(|| Source::from(&span.input));
+    }
 }
 
 impl ariadne::Cache<SourceId> for Cache {
     fn fetch(&mut self, id: &SourceId) -> Result<&Source, Box<dyn fmt::Debug + '_>> {
-        match id {
-            Some(path) => {
-                if !self.files.contains_key(id) {
-                    let text = fs::read_to_string(path)
-                        .or_else(|e| {
-                            if path.to_string_lossy() == "example.ua" {
-                                Ok(example_ua(|ex| ex.clone()))
-                            } else {
-                                Err(e)
-                            }
-                        })
-                        .map_err(|e| Box::new(e) as Box<dyn fmt::Debug>)?;
-                    let source = Source::from(text);
-                    self.files.insert(id.clone(), source);
-                }
-                Ok(&self.files[id])
-            }
-            None => Ok(&self.input),
-        }
+        (self.sources.get(id)).ok_or_else(|| Box::new("Unknown source") as Box<dyn fmt::Debug>)
     }
     fn display<'a>(&self, id: &'a SourceId) -> Option<Box<dyn fmt::Display + 'a>> {
         Some(match id {
@@ -367,3 +580,238 @@ impl ariadne::Cache<SourceId> for Cache {
         })
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{function::FunctionId, lex::Span, Uiua};
+
+    /// Get the located message of the error that running some code causes
+    pub(crate) fn error_message(code: &str) -> String {
+        Uiua::with_native_sys()
+            .load_str(code)
+            .unwrap_err()
+            .located_message()
+    }
+
+    #[test]
+    fn throw_messages() {
+        assert_eq!(
+            error_message("throw \"Matrix is not square\""),
+            "1:1: Matrix is not square"
+        );
+        assert_eq!(error_message("throw [1 2 3]"), "1:1: [1 2 3]");
+        assert_eq!(error_message("∵(1 throw \"e\";) 1_2"), "1:5: e");
+        let shown = Uiua::with_native_sys()
+            .load_str("throw 5")
+            .unwrap_err()
+            .show(false);
+        assert!(shown.starts_with("Error: 5"), "{shown}");
+    }
+
+    #[test]
+    fn shape_error_rendering() {
+        fn strip_color(s: &str) -> String {
+            let mut stripped = String::new();
+            let mut chars = s.chars();
+            while let Some(c) = chars.next() {
+                if c == '\x1b' {
+                    chars.by_ref().find(|&c| c == 'm');
+                } else {
+                    stripped.push(c);
+                }
+            }
+            stripped
+        }
+        let fill_hint = "Use fill ⬚ to pad the arrays to a common shape.";
+        for (code, message, underline) in [
+            (
+                "+[1 2] [3 4 5]",
+                "Shapes [2] and [3] do not match. \
+                The shape of one argument must be a prefix of the shape of the other.",
+                "─",
+            ),
+            (
+                "∵⊂[1 2] [3 4 5]",
+                "Shapes [2] and [3] do not match. \
+                The shape of one argument must be a prefix of the shape of the other.",
+                "─",
+            ),
+            (
+                "⊟[1 2] [3 4 5]",
+                "Cannot couple arrays with shapes [2] and [3]. \
+                Coupled arrays must have the same shape.",
+                "─",
+            ),
+            (
+                "⊂[1_2] [3_4_5]",
+                "Cannot join arrays of shapes [1 × 2] and [1 × 3]. \
+                The shape of one array must match the shape of the other's rows, \
+                or their rows must match.",
+                "─",
+            ),
+            (
+                "[1_2 3_4_5]",
+                "Cannot make an array from rows with shapes [2] and [3]. \
+                All rows of an array must have the same shape.",
+                "───────────",
+            ),
+        ] {
+            let expected = [
+                format!("Error: {message} {fill_hint}"),
+                "   ╭─[:1:1]".into(),
+                format!(" 1 │ {code}"),
+                format!("   │ {underline}  "),
+                "───╯".into(),
+            ]
+            .join("\n");
+            let error = Uiua::with_native_sys().load_str(code).unwrap_err();
+            let plain = error.show(false);
+            let colored = error.show(true);
+            assert_eq!(plain, expected, "{code}");
+            assert_ne!(colored, plain, "{code}");
+            assert_eq!(strip_color(&colored), plain, "{code}");
+        }
+    }
+
+    #[test]
+    fn error_excerpts_use_loaded_source() {
+        // The file does not exist, so the excerpt must come from the loaded input
+        let code = "X ← 5\n+[1 2] [3 4 5]";
+        let error = (Uiua::with_native_sys())
+            .load_str_path(code, "not_a_real_file.ua")
+            .unwrap_err();
+        let shown = error.show(false);
+        assert!(shown.contains("[not_a_real_file.ua:2:1]"), "{shown}");
+        assert!(shown.contains(" 2 │ +[1 2] [3 4 5]\n"), "{shown}");
+    }
+
+    #[test]
+    fn error_traces() {
+        let trace = |mut env: Uiua, code| {
+            let error = env.load_str(code).unwrap_err();
+            let shown = error.show(false);
+            (shown.lines())
+                .skip_while(|line| !line.starts_with("  in"))
+                .map(String::from)
+                .collect::<Vec<_>>()
+        };
+        let code = "Quadratic ← (\n  ÷2 ⊡3\n)\nF ← ≡(Quadratic)\nF [1_2 3_4]";
+        assert_eq!(
+            trace(Uiua::with_native_sys(), code),
+            [
+                "  in `Quadratic` at 4:5",
+                "  in ≡ row 0     at 4:5",
+                "  in `F`         at 5:1",
+            ]
+        );
+
+        // Rows run on several threads report the same row as when run on one
+        let code = "G ← ⍤\"big\" <300.\nF ← ≡(G)\nF ⇡400";
+        let expected = [
+            "  in `G`       at 2:5",
+            "  in ≡ row 300 at 2:5",
+            "  in `F`       at 3:1",
+        ];
+        assert_eq!(trace(Uiua::with_native_sys(), code), expected);
+        #[cfg(feature = "parallel")]
+        assert_eq!(
+            trace(Uiua::with_native_sys().with_thread_count(4), code),
+            expected
+        );
+
+        // Long traces are cut off
+        let error = UiuaError::Traced {
+            error: UiuaError::Run(Span::Builtin.sp("error".into())).into(),
+            trace: (0..30)
+                .map(|i| TraceFrame {
+                    id: FunctionId::Named(format!("F{i}").into()),
+                    span: Span::Builtin,
+                    row: None,
+                })
+                .collect(),
+        };
+        let shown = error.show(false);
+        assert_eq!(
+            shown
+                .lines()
+                .filter(|line| line.starts_with("  in"))
+                .count(),
+            20
+        );
+        assert!(shown.ends_with("  ... and 10 more"), "{shown}");
+        // Repeated frames left out are counted once
+        let frame = |name: &str| TraceFrame {
+            id: FunctionId::Named(name.into()),
+            span: Span::Builtin,
+            row: None,
+        };
+        let error = UiuaError::Traced {
+            error: UiuaError::Run(Span::Builtin.sp("error".into())).into(),
+            trace: ((0..25).map(|i| frame(&format!("F{i}"))))
+                .chain((0..100).map(|_| frame("G")))
+                .chain([frame("H")])
+                .collect(),
+        };
+        assert!(error.show(false).ends_with("  ... and 7 more"));
+    }
+
+    #[test]
+    fn error_accessors() {
+        let error = Uiua::with_native_sys()
+            .load_str_path("F ← +[1 2]\nX ← 2\nF [1 2 3]", "file.ua")
+            .unwrap_err();
+        assert_eq!(error.kind(), UiuaErrorKind::Run);
+        assert!(error
+            .message()
+            .starts_with("Shapes [2] and [3] do not match"));
+        let span = error.span().unwrap();
+        let span = span.code().unwrap();
+        assert_eq!((span.start.line, span.start.col), (1, 5));
+        assert_eq!(span.as_str(), "+");
+        assert_eq!(span.file(), Some(std::path::Path::new("file.ua")));
+
+        let error = Uiua::with_native_sys().load_str("(+\n[1").unwrap_err();
+        assert_eq!(error.kind(), UiuaErrorKind::Parse);
+        assert_eq!(error.parts().len(), 2);
+
+        let error = Uiua::with_native_sys()
+            .load_file("does_not_exist.ua")
+            .unwrap_err();
+        assert_eq!(error.kind(), UiuaErrorKind::Io);
+        assert_eq!(error.span(), None);
+    }
+
+    #[test]
+    fn json_diagnostics() {
+        let error = Uiua::with_native_sys()
+            .load_str_path("X ← 1\n⍤\"a\\\"b\" 0", "file.ua")
+            .unwrap_err();
+        assert_eq!(
+            error.to_json(),
+            r#"{"severity":"error","code":"throw","message":"a\"b","file":"file.ua","start":{"line":2,"column":1,"byte":8},"end":{"line":2,"column":2,"byte":11}}"#
+        );
+        let error = Uiua::with_native_sys().load_str("(+\n[1").unwrap_err();
+        assert_eq!(
+            error.to_json(),
+            [
+                r#"{"severity":"error","code":"parse","message":"Expected term or `]`, found `1`","file":null,"start":{"line":2,"column":2,"byte":4},"end":{"line":2,"column":3,"byte":5}}"#,
+                r#"{"severity":"error","code":"parse","message":"Expected term or `)`, found `1`","file":null,"start":{"line":2,"column":2,"byte":4},"end":{"line":2,"column":3,"byte":5}}"#,
+            ]
+            .join("\n")
+        );
+        let mut env = Uiua::with_native_sys();
+        env.load_str("Foo ← 5").unwrap();
+        let diagnostics: Vec<String> = env
+            .take_diagnostics()
+            .iter()
+            .map(Diagnostic::to_json)
+            .collect();
+        assert_eq!(
+            diagnostics,
+            [
+                r#"{"severity":"hint","code":"unused-binding","message":"`Foo` is never used","file":null,"start":{"line":1,"column":1,"byte":0},"end":{"line":1,"column":4,"byte":3}}"#
+            ]
+        );
+    }
+}
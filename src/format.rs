@@ -424,7 +424,7 @@ impl<'a> Formatter<'a> {
     }
     fn format_item(&mut self, item: &Item) {
         match item {
-            Item::Scoped { items, test } => {
+            Item::Scoped { items, test, .. } => {
                 let delim = if *test { "~~~" } else { "---" };
                 self.output.push_str(delim);
                 self.output.push('\n');
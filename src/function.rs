@@ -196,7 +196,8 @@ impl fmt::Display for Instr {
 #[derive(Clone)]
 pub struct Function {
     pub id: FunctionId,
-    pub instrs: Vec<Instr>,
+    /// Shared so that copying a function, such as to rename it, does not copy its instructions
+    pub instrs: Arc<[Instr]>,
     signature: Signature,
 }
 
@@ -286,7 +287,8 @@ impl Hash for DynamicFunction {
 
 impl PartialEq for Function {
     fn eq(&self, other: &Self) -> bool {
-        self.id == other.id && self.instrs == other.instrs
+        self.id == other.id
+            && (Arc::ptr_eq(&self.instrs, &other.instrs) || self.instrs == other.instrs)
     }
 }
 
@@ -332,7 +334,7 @@ impl fmt::Display for Function {
 }
 
 impl Function {
-    pub fn new(id: FunctionId, instrs: impl Into<Vec<Instr>>, signature: Signature) -> Self {
+    pub fn new(id: FunctionId, instrs: impl Into<Arc<[Instr]>>, signature: Signature) -> Self {
         let instrs = instrs.into();
         Self {
             id,
@@ -340,7 +342,7 @@ impl Function {
             signature,
         }
     }
-    pub fn new_inferred(id: FunctionId, instrs: impl Into<Vec<Instr>>) -> Result<Self, String> {
+    pub fn new_inferred(id: FunctionId, instrs: impl Into<Arc<[Instr]>>) -> Result<Self, String> {
         let instrs = instrs.into();
         let signature = instrs_signature(&instrs)?;
         Ok(Self {
@@ -399,15 +401,15 @@ impl Function {
         )
     }
     pub fn as_primitive(&self) -> Option<(Primitive, usize)> {
-        match self.instrs.as_slice() {
+        match &*self.instrs {
             [Instr::Prim(prim, span)] => Some((*prim, *span)),
             _ => None,
         }
     }
     pub fn into_unboxed(self) -> Result<Value, Self> {
         if self.is_constant() {
-            if let Instr::Push(val) = self.instrs.into_iter().next().unwrap() {
-                Ok(*val)
+            if let [Instr::Push(val)] = &*self.instrs {
+                Ok((**val).clone())
             } else {
                 unreachable!();
             }
@@ -416,21 +418,59 @@ impl Function {
         }
     }
     pub fn as_boxed(&self) -> Option<&Value> {
-        match self.instrs.as_slice() {
+        match &*self.instrs {
             [Instr::Push(val)] => Some(val),
             _ => None,
         }
     }
     pub fn as_boxed_mut(&mut self) -> Option<&mut Value> {
-        match self.instrs.as_mut_slice() {
+        if Arc::get_mut(&mut self.instrs).is_none() {
+            self.instrs = self.instrs.iter().cloned().collect();
+        }
+        match Arc::get_mut(&mut self.instrs).unwrap() {
             [Instr::Push(val)] => Some(val),
             _ => None,
         }
     }
+    /// Check whether calling this function can have effects other than on the stack
+    ///
+    /// Pure functions do no IO, use no randomness or other global state like [`Primitive::Tag`],
+    /// and do not [`Primitive::Break`], so they can be called on different threads
+    /// without changing a program's behavior.
+    /// A function that calls another function is only pure if the called function
+    /// was pushed right before the call, because a function that arrives as data may do anything.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn is_pure(&self) -> bool {
+        self.instrs
+            .iter()
+            .enumerate()
+            .all(|(i, instr)| match instr {
+                Instr::Prim(Primitive::Call, _) | Instr::Call(_) => {
+                    i > 0 && matches!(self.instrs[i - 1], Instr::Push(_))
+                }
+                Instr::Prim(prim, _) => !matches!(
+                    prim,
+                    Primitive::Sys(_)
+                        | Primitive::Rand
+                        | Primitive::Tag
+                        | Primitive::Now
+                        | Primitive::Trace
+                        | Primitive::InvTrace
+                        | Primitive::Dump
+                        | Primitive::Spawn
+                        | Primitive::Wait
+                        | Primitive::Break
+                ),
+                Instr::Push(val) => val.is_pure(),
+                // Dynamic functions may do anything
+                Instr::Dynamic(_) => false,
+                _ => true,
+            })
+    }
     pub(crate) fn as_flipped_primitive(&self) -> Option<(Primitive, bool)> {
         match &self.id {
             FunctionId::Primitive(prim) => Some((*prim, false)),
-            _ => match self.instrs.as_slice() {
+            _ => match &*self.instrs {
                 [Instr::Prim(prim, _)] => Some((*prim, false)),
                 [Instr::Prim(Primitive::Flip, _), Instr::Prim(prim, _)] => Some((*prim, true)),
                 _ => None,
@@ -440,7 +480,7 @@ impl Function {
     pub fn compose(a: Arc<Self>, b: Arc<Self>) -> Self {
         let id = a.id.clone().compose(b.id.clone());
         let sig = a.signature.compose(b.signature);
-        let mut instrs = b.instrs.clone();
+        let mut instrs = b.instrs.to_vec();
         instrs.extend(a.instrs.iter().cloned());
         Self::new(id, instrs, sig)
     }
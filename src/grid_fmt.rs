@@ -2,6 +2,7 @@
 
 use std::{
     any::type_name,
+    cell::Cell,
     f64::{
         consts::{PI, TAU},
         INFINITY,
@@ -12,7 +13,7 @@ use std::{
 };
 
 use crate::{
-    array::{Array, ArrayValue},
+    array::{Array, ArrayValue, FormatShape},
     function::Function,
     primitive::Primitive,
     value::Value,
@@ -21,6 +22,65 @@ use crate::{
 type Grid<T = char> = Vec<Vec<T>>;
 type Metagrid = Grid<Grid>;
 
+/// Limits on how much of an array is shown when it is pretty printed
+///
+/// Axes that are longer than the limit have their middle elided with `…`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShowLimit {
+    /// The maximum number of rows to show, counted across all but the last axis
+    pub rows: usize,
+    /// The maximum number of elements to show along the last axis
+    pub columns: usize,
+}
+
+impl Default for ShowLimit {
+    fn default() -> Self {
+        Self {
+            rows: 1000,
+            columns: 200,
+        }
+    }
+}
+
+thread_local! {
+    static SHOW_LIMIT: Cell<Option<ShowLimit>> = Cell::new(Some(ShowLimit::default()));
+}
+
+/// Set the limit used when pretty printing arrays on the current thread
+///
+/// `None` disables truncation entirely.
+pub fn set_show_limit(limit: Option<ShowLimit>) {
+    let limit = limit.map(|limit| ShowLimit {
+        rows: limit.rows.max(2),
+        columns: limit.columns.max(2),
+    });
+    SHOW_LIMIT.with(|cell| cell.set(limit));
+}
+
+/// Get the limit used when pretty printing arrays on the current thread
+pub fn show_limit() -> Option<ShowLimit> {
+    SHOW_LIMIT.with(Cell::get)
+}
+
+/// Get how many elements to show before and after the elided part of an axis
+fn elision(len: usize, max: usize) -> Option<(usize, usize)> {
+    (len > max).then(|| (max - max / 2, max / 2))
+}
+
+/// Get the maximum number of cells to show along the first axis of an array of rank 2 or more
+fn max_shown_cells(shape: &[usize], rows: usize) -> usize {
+    (rows / shown_rows(&shape[1..], rows).max(1)).max(2)
+}
+
+/// Get the number of rows shown when pretty printing an array of the given shape
+fn shown_rows(shape: &[usize], rows: usize) -> usize {
+    if shape.len() < 2 {
+        return 1;
+    }
+    let cells = shape[0].min(max_shown_cells(shape, rows));
+    cells * shown_rows(&shape[1..], rows)
+}
+
 pub trait GridFmt {
     fn fmt_grid(&self, boxed: bool) -> Grid;
     fn grid_string(&self) -> String {
@@ -174,10 +234,11 @@ impl<T: GridFmt + ArrayValue> GridFmt for Array<T> {
         }
         // Fill the metagrid
         let mut metagrid = Metagrid::new();
+        let limit = show_limit();
 
         // Handle really big arrays
         let mut just_dims = false;
-        if self.shape.len() > 1 {
+        if self.shape.len() > 1 && limit.is_some() {
             let columns = *self.shape.last().unwrap();
             if let Some((w, _)) = term_size::dimensions() {
                 if columns > w / 2 - 1 {
@@ -196,7 +257,16 @@ impl<T: GridFmt + ArrayValue> GridFmt for Array<T> {
         let mut grid: Grid = Grid::new();
 
         if !just_dims {
-            fmt_array(&self.shape, &self.data, stringy, boxed, &mut metagrid);
+            let label = self.rank() == 3 && !boxed;
+            fmt_array(
+                &self.shape,
+                &self.data,
+                stringy,
+                boxed,
+                label,
+                limit,
+                &mut metagrid,
+            );
             // Determine max row heights and column widths
             let metagrid_width = metagrid.iter().map(|row| row.len()).max().unwrap();
             let metagrid_height = metagrid.len();
@@ -219,11 +289,12 @@ impl<T: GridFmt + ArrayValue> GridFmt for Array<T> {
                 column_widths[col] = max_col_width;
             }
             // Pad each metagrid cell to its row's max height and column's max width
+            let numeric = T::NAME == f64::NAME;
             for row in 0..metagrid_height {
                 let row_height = row_heights[row];
                 let mut subrows = vec![vec![]; row_height];
                 for (col_width, cell) in column_widths.iter().zip(&mut metagrid[row]) {
-                    pad_grid_center(*col_width, row_height, true, cell);
+                    pad_grid_center(*col_width, row_height, true, numeric, cell);
                     for (subrow, cell_row) in subrows.iter_mut().zip(take(cell)) {
                         subrow.extend(cell_row);
                     }
@@ -247,6 +318,7 @@ impl<T: GridFmt + ArrayValue> GridFmt for Array<T> {
                     width + 4,
                     (height + 2).max(self.rank() + 1),
                     false,
+                    false,
                     &mut grid,
                 );
                 grid[0][0] = if boxed { '╓' } else { '╭' };
@@ -256,7 +328,7 @@ impl<T: GridFmt + ArrayValue> GridFmt for Array<T> {
                 }
                 *grid.last_mut().unwrap().last_mut().unwrap() = if boxed { '╜' } else { '╯' };
                 // Handle really big grid
-                if let Some((w, _)) = term_size::dimensions() {
+                if let Some((w, _)) = term_size::dimensions().filter(|_| limit.is_some()) {
                     for row in grid.iter_mut() {
                         if row.len() > w {
                             let diff = row.len() - w;
@@ -285,15 +357,97 @@ impl<T: GridFmt + ArrayValue> GridFmt for Array<T> {
             s.push(']');
             return vec![s.chars().collect()];
         }
+        // Note the full shape of truncated arrays
+        if let Some(limit) = limit.filter(|_| !boxed) {
+            let rank = self.rank();
+            let truncated = self.shape[rank - 1] > limit.columns
+                || (rank > 1
+                    && shown_rows(&self.shape, limit.rows)
+                        < self.shape[..rank - 1].iter().product());
+            if truncated {
+                let note = format!("shape {}", FormatShape(&self.shape));
+                grid.push(note.chars().collect());
+            }
+        }
         grid
     }
 }
 
+const RESET: &str = "\x1b[0m";
+const DIM: &str = "\x1b[2m";
+const NEGATIVE: &str = "\x1b[31m";
+const SHAPE: &str = "\x1b[36m";
+
+/// Add ANSI colors to the pretty-printed representation of a value
+///
+/// Negative numbers are only colored if `numbers` is set.
+/// They are never colored in boxed strings or character scalars.
+pub(crate) fn colorize_shown(shown: &str, numbers: bool) -> String {
+    let is_corner = |c: char| matches!(c, '╭' | '─' | '╷' | '╓' | '║' | '╯' | '╜');
+    let is_number = |c: char| c.is_alphanumeric() || matches!(c, '.' | '∞' | 'π' | 'τ' | 'η');
+    let dim = |s: &str, colored: &mut String| {
+        if !s.is_empty() {
+            colored.push_str(DIM);
+            colored.push_str(s);
+            colored.push_str(RESET);
+        }
+    };
+    let mut colored = String::with_capacity(shown.len());
+    for (i, line) in shown.split('\n').enumerate() {
+        if i > 0 {
+            colored.push('\n');
+        }
+        if line.starts_with("shape ") {
+            colored.push_str(SHAPE);
+            colored.push_str(line);
+            colored.push_str(RESET);
+            continue;
+        }
+        // Array outlines are only at the start and end of lines
+        let inner = line.trim_start_matches(is_corner);
+        dim(&line[..line.len() - inner.len()], &mut colored);
+        let body = inner.trim_end_matches(is_corner);
+        let end = &inner[body.len()..];
+        let mut boxed_string = false;
+        let mut chars = body.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                _ if boxed_string => {
+                    boxed_string = c != '⌟';
+                    colored.push(c);
+                }
+                '⌜' => {
+                    boxed_string = true;
+                    colored.push(c);
+                }
+                '@' => {
+                    colored.push(c);
+                    colored.extend(chars.next());
+                }
+                '¯' if numbers => {
+                    colored.push_str(NEGATIVE);
+                    colored.push(c);
+                    while let Some(c) = chars.next_if(|&c| is_number(c)) {
+                        colored.push(c);
+                    }
+                    colored.push_str(RESET);
+                }
+                '…' | '⋮' => dim(c.encode_utf8(&mut [0; 4]), &mut colored),
+                c => colored.push(c),
+            }
+        }
+        dim(end, &mut colored);
+    }
+    colored
+}
+
 fn fmt_array<T: GridFmt + ArrayValue>(
     shape: &[usize],
     data: &[T],
     stringy: bool,
     boxed: bool,
+    label: bool,
+    limit: Option<ShowLimit>,
     metagrid: &mut Metagrid,
 ) {
     if data.is_empty() {
@@ -307,9 +461,16 @@ fn fmt_array<T: GridFmt + ArrayValue>(
     }
     if rank == 1 {
         let mut row = Vec::with_capacity(shape[0]);
+        let elided = limit.and_then(|limit| elision(data.len(), limit.columns));
         if stringy {
             let mut s = String::new();
-            s.extend(data.iter().map(|c| c.to_string()));
+            if let Some((front, back)) = elided {
+                s.extend(data[..front].iter().map(|c| c.to_string()));
+                s.push('…');
+                s.extend(data[data.len() - back..].iter().map(|c| c.to_string()));
+            } else {
+                s.extend(data.iter().map(|c| c.to_string()));
+            }
             let mut s: String = s.chars().map(format_char_inner).collect();
             if boxed {
                 s.insert(0, '⌜');
@@ -320,12 +481,24 @@ fn fmt_array<T: GridFmt + ArrayValue>(
             }
             row.push(vec![s.chars().collect()]);
         } else {
-            for (i, val) in data.iter().enumerate() {
-                let mut grid = val.fmt_grid(false);
+            let mut push_cell = |i: usize, mut grid: Grid| {
                 if i > 0 {
                     pad_grid_min(grid[0].len() + 1, grid.len(), &mut grid)
                 }
                 row.push(grid);
+            };
+            if let Some((front, back)) = elided {
+                for (i, val) in data[..front].iter().enumerate() {
+                    push_cell(i, val.fmt_grid(false));
+                }
+                push_cell(front, vec![vec!['…']]);
+                for val in &data[data.len() - back..] {
+                    push_cell(front + 1, val.fmt_grid(false));
+                }
+            } else {
+                for (i, val) in data.iter().enumerate() {
+                    push_cell(i, val.fmt_grid(false));
+                }
             }
         }
         metagrid.push(row);
@@ -336,19 +509,53 @@ fn fmt_array<T: GridFmt + ArrayValue>(
         metagrid.push(vec![vec![vec![' ']]]);
         return;
     }
+    let elided = limit.and_then(|limit| elision(cell_count, max_shown_cells(shape, limit.rows)));
     let shape = &shape[1..];
     let cell_size = data.len() / cell_count;
+    let start = metagrid.len();
+    let mut labels = Vec::new();
+    let separate = |metagrid: &mut Metagrid| {
+        for _ in 0..rank - 2 {
+            metagrid.push(vec![vec![vec![' ']]; metagrid.last().unwrap().len()]);
+        }
+    };
     for (i, cell) in data.chunks(cell_size).enumerate() {
-        if i > 0 && rank > 2 {
-            for _ in 0..rank - 2 {
-                metagrid.push(vec![vec![vec![' ']]; metagrid.last().unwrap().len()]);
+        if let Some((front, back)) = elided {
+            if i == front {
+                separate(metagrid);
+                metagrid.push(vec![vec![vec!['⋮']]; metagrid.last().unwrap().len()]);
+            }
+            if i >= front && i < cell_count - back {
+                continue;
             }
         }
-        fmt_array(shape, cell, stringy, false, metagrid);
+        if i > 0 {
+            separate(metagrid);
+        }
+        labels.push((metagrid.len(), i));
+        fmt_array(shape, cell, stringy, false, false, limit, metagrid);
+    }
+    // Annotate each cell with its index along the leading axis
+    if label {
+        let mut labels = labels.into_iter().peekable();
+        for (r, row) in metagrid.iter_mut().enumerate().skip(start) {
+            let label = if let Some((_, i)) = labels.next_if(|&(lr, _)| lr == r) {
+                format!("{i}: ")
+            } else {
+                " ".into()
+            };
+            row.insert(0, vec![label.chars().collect()]);
+        }
     }
 }
 
-fn pad_grid_center(width: usize, height: usize, align_numbers: bool, grid: &mut Grid) {
+fn pad_grid_center(
+    width: usize,
+    height: usize,
+    align_numbers: bool,
+    numeric: bool,
+    grid: &mut Grid,
+) {
     grid.truncate(height);
     if grid.len() < height {
         let diff = height - grid.len();
@@ -365,11 +572,12 @@ fn pad_grid_center(width: usize, height: usize, align_numbers: bool, grid: &mut
         row.truncate(width);
         if row.len() < width {
             let diff = width - row.len();
-            let post_pad = if align_numbers && row.last().map_or(false, char::is_ascii_digit) {
-                0
-            } else {
-                diff / 2
-            };
+            let post_pad =
+                if align_numbers && (numeric || row.last().map_or(false, char::is_ascii_digit)) {
+                    0
+                } else {
+                    diff / 2
+                };
             let pre_pad = diff - post_pad;
             for _ in 0..pre_pad {
                 row.insert(0, ' ');
@@ -393,3 +601,123 @@ fn pad_grid_min(width: usize, height: usize, grid: &mut Grid) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn show(code: &str) -> String {
+        let mut env = crate::Uiua::with_native_sys();
+        env.load_str(code).unwrap();
+        env.pop("value").unwrap().show()
+    }
+
+    #[test]
+    fn scalars() {
+        assert_eq!(show("5"), "5");
+        assert_eq!(show("¯2.5"), "¯2.5");
+        assert_eq!(show("∞"), "∞");
+        assert_eq!(show("¯∞"), "¯∞");
+        assert_eq!(show("NaN"), "NaN");
+        assert_eq!(show("@a"), "@a");
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(show("[]"), "[]");
+        assert_eq!(show("\"\""), "\"\"");
+        assert_eq!(show("↯0_3 0"), "╭─   \n╷    \n    ╯");
+    }
+
+    #[test]
+    fn matrices() {
+        assert_eq!(
+            show("[[1 ∞] [100 NaN] [¯∞ 2]]"),
+            "╭─         \n╷   1   ∞  \n  100 NaN  \n   ¯∞   2  \n          ╯"
+        );
+        assert_eq!(
+            show("[¯1_2_3 4_55_¯666]"),
+            "╭─            \n╷ ¯1  2    3  \n   4 55 ¯666  \n             ╯"
+        );
+        assert_eq!(
+            show("[\"ab\" \"cd\"]"),
+            "╭─      \n╷ \"ab\"  \n  \"cd\"  \n       ╯"
+        );
+        assert_eq!(
+            show("↯2_2_3⇡12"),
+            "╭─            \n╷ 0: 0  1  2  \n╷    3  4  5  \n              \n  1: 6  7  8  \n     9 10 11  \n             ╯"
+        );
+    }
+
+    #[test]
+    fn colors() {
+        let show_colored = |code: &str| {
+            let mut env = crate::Uiua::with_native_sys();
+            env.load_str(code).unwrap();
+            let value = env.pop("value").unwrap();
+            let colored = value.show_colored();
+            let stripped: String = colored
+                .split('\x1b')
+                .enumerate()
+                .map(|(i, part)| {
+                    if i == 0 {
+                        part
+                    } else {
+                        &part[part.find('m').unwrap() + 1..]
+                    }
+                })
+                .collect();
+            assert_eq!(stripped, value.show(), "{code}");
+            colored
+        };
+        assert_eq!(show_colored("5"), "5");
+        assert_eq!(show_colored("¯2.5"), "\x1b[31m¯2.5\x1b[0m");
+        assert_eq!(show_colored("[1 ¯∞]"), "[1 \x1b[31m¯∞\x1b[0m]");
+        // Strings and characters are not colored
+        assert_eq!(show_colored("\"a¯1\\\"¯2─\""), "\"a¯1\"¯2─\"");
+        assert_eq!(show_colored("@¯"), "@¯");
+        assert_eq!(show_colored("{\"¯1\" ¯2}"), "[⌜¯1⌟ □\x1b[31m¯2\x1b[0m]");
+        let matrix = show_colored("[1_2 3_¯4]");
+        assert!(matrix.starts_with("\x1b[2m╭─\x1b[0m"), "{matrix}");
+        assert!(matrix.contains("\x1b[31m¯4\x1b[0m"), "{matrix}");
+        set_show_limit(Some(ShowLimit {
+            rows: 4,
+            columns: 4,
+        }));
+        let truncated = show_colored("⇡10");
+        set_show_limit(Some(ShowLimit::default()));
+        assert!(truncated.contains("\x1b[2m…\x1b[0m"), "{truncated}");
+        assert!(
+            truncated.ends_with("\x1b[36mshape [10]\x1b[0m"),
+            "{truncated}"
+        );
+    }
+
+    fn show_limited(code: &str, rows: usize, columns: usize) -> String {
+        set_show_limit(Some(ShowLimit { rows, columns }));
+        let mut env = crate::Uiua::with_native_sys();
+        env.load_str(code).unwrap();
+        let shown = env.pop("value").unwrap().show();
+        set_show_limit(Some(ShowLimit::default()));
+        shown
+    }
+
+    #[test]
+    fn truncation() {
+        assert_eq!(show_limited("⇡10", 4, 4), "[0 1 … 8 9]\nshape [10]");
+        assert_eq!(show_limited("⇡4", 4, 4), "[0 1 2 3]");
+        assert_eq!(show_limited("\"abcdefg\"", 4, 4), "\"ab…fg\"\nshape [7]");
+        let matrix = show_limited("↯6_2⇡12", 4, 4);
+        assert_eq!(matrix.lines().filter(|line| line.contains('⋮')).count(), 1);
+        assert!(matrix.contains(" 0  1") && matrix.contains("10 11"));
+        assert!(!matrix.contains(" 4  5"));
+        assert!(matrix.ends_with("shape [6 × 2]"));
+        set_show_limit(None);
+        assert!(
+            !Value::from(Array::<f64>::from_iter((0..500).map(|i| i as f64)))
+                .show()
+                .contains('…')
+        );
+        set_show_limit(Some(ShowLimit::default()));
+    }
+}
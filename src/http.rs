@@ -0,0 +1,357 @@
+//! A small blocking HTTP/1.1 client used by the native backend
+//!
+//! Every request is sent with `Connection: close`, so the response is read until the server closes the connection.
+//! HTTPS requires the `https` feature.
+
+use std::{
+    io::{self, ErrorKind, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+};
+
+use crate::{HttpRequest, HttpResponse};
+
+struct Url<'a> {
+    https: bool,
+    host: &'a str,
+    port: u16,
+    path: String,
+}
+
+fn parse_url(url: &str) -> Result<Url<'_>, String> {
+    let (https, rest) = if let Some(rest) = url.strip_prefix("http://") {
+        (false, rest)
+    } else if let Some(rest) = url.strip_prefix("https://") {
+        (true, rest)
+    } else {
+        return Err(format!(
+            "Invalid URL {url:?}. It must start with http:// or https://"
+        ));
+    };
+    let (authority, path) = rest.split_at(rest.find(['/', '?', '#']).unwrap_or(rest.len()));
+    let path = path.split('#').next().unwrap_or("");
+    let path = if path.starts_with('/') {
+        path.into()
+    } else {
+        format!("/{path}")
+    };
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) if !port.contains(']') => {
+            let port = port
+                .parse()
+                .map_err(|_| format!("Invalid port in URL {url:?}"))?;
+            (host, port)
+        }
+        _ => (authority, if https { 443 } else { 80 }),
+    };
+    if host.is_empty() {
+        return Err(format!("Invalid URL {url:?}. It has no host."));
+    }
+    Ok(Url {
+        https,
+        host,
+        port,
+        path,
+    })
+}
+
+/// Make a request and wait for the whole response
+pub(crate) fn request(req: &HttpRequest) -> Result<HttpResponse, String> {
+    let url = parse_url(&req.url)?;
+    let message = write_request(req, &url)?;
+    let io_error = |e: io::Error| {
+        if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) {
+            format!(
+                "HTTP request to {} timed out after {} seconds",
+                req.url,
+                req.timeout.as_secs_f64()
+            )
+        } else {
+            format!("HTTP request to {} failed: {e}", req.url)
+        }
+    };
+    let addrs = (url.host.trim_matches(['[', ']']), url.port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Unable to resolve {}: {e}", url.host))?;
+    let mut last_error = None;
+    let mut stream = None;
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, req.timeout) {
+            Ok(s) => {
+                stream = Some(s);
+                break;
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    let mut stream = match (stream, last_error) {
+        (Some(stream), _) => stream,
+        (None, Some(e)) => return Err(io_error(e)),
+        (None, None) => return Err(format!("Unable to resolve {}", url.host)),
+    };
+    (stream.set_read_timeout(Some(req.timeout)))
+        .and_then(|_| stream.set_write_timeout(Some(req.timeout)))
+        .map_err(io_error)?;
+    let mut raw = Vec::new();
+    if url.https {
+        #[cfg(feature = "https")]
+        {
+            let server_name = rustls::ServerName::try_from(url.host).map_err(|e| e.to_string())?;
+            let mut conn = rustls::ClientConnection::new(tls_config(), server_name)
+                .map_err(|e| e.to_string())?;
+            let mut tls = rustls::Stream::new(&mut conn, &mut stream);
+            tls.write_all(&message).map_err(io_error)?;
+            match tls.read_to_end(&mut raw) {
+                Ok(_) => {}
+                // Some servers close the connection without notifying the client
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof && !raw.is_empty() => {}
+                Err(e) => return Err(io_error(e)),
+            }
+        }
+        #[cfg(not(feature = "https"))]
+        return Err("HTTPS requests are not supported without the `https` feature".into());
+    } else {
+        stream.write_all(&message).map_err(io_error)?;
+        stream.read_to_end(&mut raw).map_err(io_error)?;
+    }
+    parse_response(&raw).map_err(|e| format!("Invalid HTTP response from {}: {e}", req.url))
+}
+
+fn write_request(req: &HttpRequest, url: &Url) -> Result<Vec<u8>, String> {
+    // Line breaks would let a method or header inject its own lines into the request
+    if req.method.is_empty() || req.method.contains(char::is_whitespace) {
+        return Err(format!("Invalid HTTP method {:?}", req.method));
+    }
+    for (name, value) in &req.headers {
+        if name.is_empty() || name.contains(|c: char| c == ':' || c.is_whitespace()) {
+            return Err(format!("Invalid HTTP header name {name:?}"));
+        }
+        if value.contains(['\r', '\n']) {
+            return Err(format!("Invalid value for HTTP header {name:?}"));
+        }
+    }
+    let has_header = |name: &str| (req.headers.iter()).any(|(n, _)| n.eq_ignore_ascii_case(name));
+    let mut message = format!("{} {} HTTP/1.1\r\n", req.method, url.path);
+    if !has_header("host") {
+        let default_port = if url.https { 443 } else { 80 };
+        if url.port == default_port {
+            message.push_str(&format!("Host: {}\r\n", url.host));
+        } else {
+            message.push_str(&format!("Host: {}:{}\r\n", url.host, url.port));
+        }
+    }
+    for (name, value) in &req.headers {
+        message.push_str(&format!("{name}: {value}\r\n"));
+    }
+    if !req.body.is_empty() && !has_header("content-length") {
+        message.push_str(&format!("Content-Length: {}\r\n", req.body.len()));
+    }
+    message.push_str("Connection: close\r\n\r\n");
+    let mut message = message.into_bytes();
+    message.extend_from_slice(&req.body);
+    Ok(message)
+}
+
+fn parse_response(raw: &[u8]) -> Result<HttpResponse, String> {
+    let head_len = (raw.windows(4))
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or("the headers never end")?;
+    let head = String::from_utf8_lossy(&raw[..head_len]);
+    let mut lines = head.split("\r\n");
+    let status_line = lines.next().unwrap_or("");
+    let status = match status_line.split(' ').collect::<Vec<_>>()[..] {
+        [version, status, ..] if version.starts_with("HTTP/") => status
+            .parse()
+            .map_err(|_| format!("invalid status line {status_line:?}"))?,
+        _ => return Err(format!("invalid status line {status_line:?}")),
+    };
+    let mut headers = Vec::new();
+    for line in lines {
+        let (name, value) = (line.split_once(':')).ok_or(format!("invalid header {line:?}"))?;
+        headers.push((name.trim().to_string(), value.trim().to_string()));
+    }
+    let header = |name: &str| {
+        (headers.iter())
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    };
+    let rest = &raw[head_len + 4..];
+    let body = if header("transfer-encoding").is_some_and(|te| te.eq_ignore_ascii_case("chunked")) {
+        dechunk(rest)?
+    } else if let Some(len) = header("content-length") {
+        let len: usize = (len.parse()).map_err(|_| format!("invalid content length {len:?}"))?;
+        if rest.len() < len {
+            return Err(format!(
+                "the body should be {len} bytes long, but only {} were received",
+                rest.len()
+            ));
+        }
+        rest[..len].to_vec()
+    } else {
+        rest.to_vec()
+    };
+    Ok(HttpResponse {
+        status,
+        headers,
+        body,
+    })
+}
+
+fn dechunk(mut rest: &[u8]) -> Result<Vec<u8>, String> {
+    let mut body = Vec::new();
+    loop {
+        let line_end = (rest.windows(2))
+            .position(|w| w == b"\r\n")
+            .ok_or("a chunk size never ends")?;
+        let size_str = String::from_utf8_lossy(&rest[..line_end]);
+        let size_str = size_str.split(';').next().unwrap_or("").trim();
+        let size = usize::from_str_radix(size_str, 16)
+            .map_err(|_| format!("invalid chunk size {size_str:?}"))?;
+        rest = &rest[line_end + 2..];
+        if size == 0 {
+            return Ok(body);
+        }
+        if rest.len() < size {
+            return Err("a chunk was cut short".into());
+        }
+        body.extend_from_slice(&rest[..size]);
+        rest = rest[size..].strip_prefix(b"\r\n").unwrap_or(&rest[size..]);
+    }
+}
+
+/// The TLS configuration used for HTTPS, which trusts the Mozilla root certificates
+#[cfg(feature = "https")]
+pub(crate) fn tls_config() -> std::sync::Arc<rustls::ClientConfig> {
+    use once_cell::sync::Lazy;
+    // https://github.com/rustls/rustls/blob/c9cfe3499681361372351a57a00ccd793837ae9c/examples/src/bin/simpleclient.rs
+    static CLIENT_CONFIG: Lazy<std::sync::Arc<rustls::ClientConfig>> = Lazy::new(|| {
+        let mut store = rustls::RootCertStore::empty();
+        store.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+            rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(
+                ta.subject,
+                ta.spki,
+                ta.name_constraints,
+            )
+        }));
+        rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(store)
+            .with_no_client_auth()
+            .into()
+    });
+    CLIENT_CONFIG.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{value::Value, NativeSys, SysBackend, Uiua};
+
+    #[test]
+    fn http_requests() {
+        use std::{
+            io::{Read, Write},
+            net::TcpListener,
+            thread,
+            time::Duration,
+        };
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                let complete = |req: &[u8]| {
+                    req.windows(4).any(|w| w == b"\r\n\r\n")
+                        && (!req.starts_with(b"POST") || req.ends_with(b"hi"))
+                };
+                while !complete(&request) {
+                    let n = stream.read(&mut buf).unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let request = String::from_utf8_lossy(&request);
+                let response: &[u8] = if request.starts_with("GET /binary HTTP/1.1\r\n") {
+                    b"HTTP/1.1 404 Not Found\r\nContent-Type: image/png\r\nContent-Length: 3\r\n\r\n\x00\xff\n"
+                } else if request.starts_with("POST /echo?x=1 HTTP/1.1\r\n")
+                    && request.contains("\r\nX-Name: uiua\r\n")
+                    && request.contains("\r\nContent-Length: 2\r\n")
+                {
+                    b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nTransfer-Encoding: chunked\r\n\r\n\
+                    3\r\nhel\r\n2\r\nlo\r\n0\r\n\r\n"
+                } else if request.starts_with("GET /slow ") {
+                    thread::sleep(Duration::from_secs(1));
+                    continue;
+                } else {
+                    b"HTTP/1.1 400 Bad Request\r\n\r\n"
+                };
+                stream.write_all(response).unwrap();
+            }
+        });
+        let mut env = Uiua::with_native_sys();
+        // Failure statuses are returned, and binary bodies are bytes
+        env.load_str(&format!("&httpg \"http://127.0.0.1:{port}/binary\""))
+            .unwrap();
+        assert_eq!(env.pop("status").unwrap().try_int().unwrap(), 404);
+        assert_eq!(env.pop("headers").unwrap().shape(), [2, 2]);
+        match env.pop("body").unwrap() {
+            Value::Byte(bytes) => assert_eq!(bytes.data.as_slice(), [0, 255, 10]),
+            value => panic!("body should be bytes, but it is {value:?}"),
+        }
+        // Text bodies are strings
+        env.load_str(&format!(
+            "&httpr \"post\" \"http://127.0.0.1:{port}/echo?x=1\" [{{\"X-Name\" \"uiua\"}}] \"hi\""
+        ))
+        .unwrap();
+        assert_eq!(env.pop("status").unwrap().try_int().unwrap(), 200);
+        env.pop("headers").unwrap();
+        assert_eq!(env.pop("body").unwrap().try_string().unwrap(), "hello");
+        let error = NativeSys
+            .http_request(HttpRequest {
+                method: "GET".into(),
+                url: format!("http://127.0.0.1:{port}/slow"),
+                headers: Vec::new(),
+                body: Vec::new(),
+                timeout: Duration::from_millis(200),
+            })
+            .unwrap_err();
+        assert!(
+            error.ends_with("/slow timed out after 0.2 seconds"),
+            "{error}"
+        );
+        let error = env.load_str("&httpg \"example.com\"").unwrap_err();
+        assert!(error
+            .message()
+            .contains("must start with http:// or https://"));
+        // Requests cannot smuggle in extra lines
+        let request = |method: &str, name: &str, value: &str| {
+            NativeSys
+                .http_request(HttpRequest {
+                    method: method.into(),
+                    url: format!("http://127.0.0.1:{port}/"),
+                    headers: vec![(name.into(), value.into())],
+                    body: Vec::new(),
+                    timeout: Duration::from_secs(1),
+                })
+                .unwrap_err()
+        };
+        assert_eq!(
+            request("GET / HTTP/1.1\r\n", "X-Name", "uiua"),
+            "Invalid HTTP method \"GET / HTTP/1.1\\r\\n\""
+        );
+        assert_eq!(
+            request("GET", "X-Name: a\r\nX-Other", "uiua"),
+            "Invalid HTTP header name \"X-Name: a\\r\\nX-Other\""
+        );
+        assert_eq!(
+            request("GET", "X-Name", "a\r\nX-Other: b"),
+            "Invalid value for HTTP header \"X-Name\""
+        );
+        let error = env
+            .load_str(&format!(
+                "&httpr \"POST\" \"http://127.0.0.1:{port}/\" [] [104 256]"
+            ))
+            .unwrap_err();
+        assert!(error
+            .message()
+            .contains("Body bytes must be integers from 0 to 255, but 256 is not"));
+    }
+}
@@ -0,0 +1,187 @@
+//! Conversion between JSON text and Uiua values
+//!
+//! This is the mapping used by [`SysOp::JsonParse`](crate::SysOp::JsonParse)
+//! and [`SysOp::JsonDump`](crate::SysOp::JsonDump):
+//!
+//! | JSON | Uiua |
+//! |------|------|
+//! | number | scalar number |
+//! | `true`/`false` | `1`/`0` |
+//! | `null` | `NaN` |
+//! | string | rank `1` character array |
+//! | array | array whose rows are the items, or a list of boxed items |
+//! | object | list of two boxes: a list of boxed key strings and the values |
+//!
+//! Items of a JSON array are joined into a single array if they all have the same type and shape,
+//! or if a fill value makes them fit. Otherwise, each item is boxed.
+//! The values of an object are joined the same way.
+
+//! Parsing nests at most 128 levels deep.
+
+use std::sync::Arc;
+
+use serde_json::{Map, Number, Value as Json};
+
+use crate::{array::Array, function::Function, value::Value, Uiua};
+
+/// Parse JSON text into a value
+pub(crate) fn parse_json(input: &str, env: &Uiua) -> Result<Value, String> {
+    let json: Json = serde_json::from_str(input).map_err(|e| format!("Invalid JSON: {e}"))?;
+    Ok(json_to_value(json, env))
+}
+
+fn json_to_value(json: Json, env: &Uiua) -> Value {
+    match json {
+        Json::Null => f64::NAN.into(),
+        Json::Bool(b) => b.into(),
+        Json::Number(n) => n.as_f64().unwrap_or(f64::NAN).into(),
+        Json::String(s) => s.into(),
+        Json::Array(items) => rows_to_value(items, env),
+        Json::Object(entries) => {
+            let (keys, values): (Vec<_>, Vec<_>) = entries.into_iter().unzip();
+            let keys = boxed(keys.into_iter().map(Value::from));
+            let values = rows_to_value(values, env);
+            boxed([keys, values])
+        }
+    }
+}
+
+fn rows_to_value(items: Vec<Json>, env: &Uiua) -> Value {
+    let rows: Vec<Value> = items.into_iter().map(|j| json_to_value(j, env)).collect();
+    let joinable = rows.windows(2).all(|w| {
+        matches!(
+            (&w[0], &w[1]),
+            (
+                Value::Num(_) | Value::Byte(_),
+                Value::Num(_) | Value::Byte(_)
+            ) | (Value::Char(_), Value::Char(_))
+        )
+    });
+    if joinable {
+        if let Ok(value) = Value::from_row_values(rows.clone(), env) {
+            return value;
+        }
+    }
+    boxed(rows)
+}
+
+fn boxed(values: impl IntoIterator<Item = Value>) -> Value {
+    (values.into_iter())
+        .map(|value| Arc::new(Function::constant(value)))
+        .collect::<Array<_>>()
+        .into()
+}
+
+/// Serialize a value as JSON text
+pub(crate) fn dump_json(value: &Value) -> Result<String, String> {
+    Ok(value_to_json(value)?.to_string())
+}
+
+fn value_to_json(value: &Value) -> Result<Json, String> {
+    Ok(match value {
+        Value::Num(nums) if nums.rank() == 0 => num_to_json(nums.data[0])?,
+        Value::Byte(bytes) if bytes.rank() == 0 => bytes.data[0].into(),
+        Value::Char(chars) if chars.rank() <= 1 => chars.data.iter().collect::<String>().into(),
+        Value::Func(fs) if fs.rank() == 0 => value_to_json(unbox(&fs.data[0])?)?,
+        Value::Func(fs) if fs.rank() == 1 && fs.row_count() == 2 => {
+            let (keys, values) = (unbox(&fs.data[0])?, unbox(&fs.data[1])?);
+            match object_keys(keys) {
+                Some(keys) if values.row_count() == keys.len() => {
+                    let mut object = Map::new();
+                    for (key, value) in keys.into_iter().zip(values.rows()) {
+                        object.insert(key, value_to_json(&value)?);
+                    }
+                    Json::Object(object)
+                }
+                _ => rows_to_json(value)?,
+            }
+        }
+        value => rows_to_json(value)?,
+    })
+}
+
+fn rows_to_json(value: &Value) -> Result<Json, String> {
+    value.rows().map(|row| value_to_json(&row)).collect()
+}
+
+/// Get the keys of a value that represents an object
+fn object_keys(keys: &Value) -> Option<Vec<String>> {
+    match keys {
+        Value::Func(fs) if fs.rank() == 1 => (fs.data.iter())
+            .map(|f| match f.as_boxed().filter(|_| f.is_constant()) {
+                Some(Value::Char(key)) if key.rank() == 1 => Some(key.data.iter().collect()),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+fn unbox(f: &Function) -> Result<&Value, String> {
+    f.as_boxed()
+        .filter(|_| f.is_constant())
+        .ok_or_else(|| format!("Cannot convert function {f} to JSON"))
+}
+
+fn num_to_json(n: f64) -> Result<Json, String> {
+    if n.is_nan() {
+        Ok(Json::Null)
+    } else if n.is_infinite() {
+        let inf = if n > 0.0 { "∞" } else { "¯∞" };
+        Err(format!("Cannot convert {inf} to JSON"))
+    } else if n.fract() == 0.0 && n.abs() < 2f64.powi(53) {
+        // Whole numbers are written without a decimal point
+        Ok((n as i64).into())
+    } else {
+        Ok(Number::from_f64(n).map_or(Json::Null, Json::Number))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::tests::error_message;
+
+    #[test]
+    fn json_errors() {
+        let message = |json: &str| {
+            let mut env = Uiua::with_native_sys();
+            env.push(json);
+            env.load_str("&jsp").unwrap_err().message()
+        };
+        assert_eq!(
+            message("[1,\n  x]"),
+            "Invalid JSON: expected value at line 2 column 3"
+        );
+        assert_eq!(
+            message("{\"é\" 1}"),
+            "Invalid JSON: expected `:` at line 1 column 7"
+        );
+        assert_eq!(
+            message("[01]"),
+            "Invalid JSON: invalid number at line 1 column 3"
+        );
+        assert_eq!(
+            message("\"\\q\""),
+            "Invalid JSON: invalid escape at line 1 column 3"
+        );
+        assert_eq!(
+            message("\"abc"),
+            "Invalid JSON: EOF while parsing a string at line 1 column 4"
+        );
+        assert_eq!(
+            message("1 2"),
+            "Invalid JSON: trailing characters at line 1 column 3"
+        );
+        assert_eq!(
+            message(&"[".repeat(100_000)),
+            "Invalid JSON: recursion limit exceeded at line 1 column 128"
+        );
+
+        assert_eq!(error_message("&jsd ∞"), "1:1: Cannot convert ∞ to JSON");
+        assert_eq!(
+            error_message("&jsd [(+)(-)]"),
+            "1:1: Cannot convert function + to JSON"
+        );
+    }
+}
@@ -3,6 +3,7 @@ use std::{
     error::Error,
     fmt,
     hash::{Hash, Hasher},
+    ops::Range,
     path::Path,
     sync::Arc,
 };
@@ -102,6 +103,13 @@ impl Span {
             (Span::Builtin, Span::Builtin) => Span::Builtin,
         }
     }
+    /// Get the span of code, if this is not a builtin span
+    pub fn code(&self) -> Option<&CodeSpan> {
+        match self {
+            Span::Code(span) => Some(span),
+            Span::Builtin => None,
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -165,7 +173,15 @@ impl CodeSpan {
         }
     }
     pub fn as_str(&self) -> &str {
-        &self.input[self.start.byte_pos..self.end.byte_pos]
+        &self.input[self.byte_range()]
+    }
+    /// Get the range of byte offsets of the span in its input
+    pub fn byte_range(&self) -> Range<usize> {
+        self.start.byte_pos..self.end.byte_pos
+    }
+    /// Get the path of the file the span is in, if it was loaded from a file
+    pub fn file(&self) -> Option<&Path> {
+        self.path.as_deref()
     }
     pub fn contains_line_col(&self, line: usize, col: usize) -> bool {
         if self.start.line == self.end.line {
@@ -13,10 +13,13 @@ pub mod ast;
 mod check;
 mod compile;
 mod cowslice;
+mod csv;
 mod error;
 pub mod format;
 pub mod function;
 mod grid_fmt;
+mod http;
+mod json;
 pub mod lex;
 pub mod lsp;
 pub mod parse;
@@ -24,12 +27,19 @@ pub mod primitive;
 #[doc(hidden)]
 pub mod profile;
 pub mod run;
+#[cfg(feature = "serde")]
+pub mod serialize;
 mod sys;
 pub mod value;
 
 use std::sync::Arc;
 
-pub use {error::*, run::Uiua, sys::*};
+pub use {
+    error::*,
+    grid_fmt::{set_show_limit, show_limit, ShowLimit},
+    run::Uiua,
+    sys::*,
+};
 
 pub type Ident = Arc<str>;
 
@@ -39,6 +49,15 @@ fn suite() {
         let entry = entry.unwrap();
         let path = entry.path();
         if path.is_file() && path.extension().is_some_and(|s| s == "ua") {
+            // Tests named after a feature only run when it is enabled
+            let enabled = match path.file_stem().and_then(|s| s.to_str()) {
+                Some("regex") => cfg!(feature = "regex"),
+                Some("image") => cfg!(feature = "image"),
+                _ => true,
+            };
+            if !enabled {
+                continue;
+            }
             let mut env = Uiua::with_native_sys();
             if let Err(e) = env.load_file(&path) {
                 panic!("Test failed in {}:\n{}", path.display(), e.show(true));
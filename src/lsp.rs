@@ -17,6 +17,10 @@ pub enum SpanKind {
     Ident,
     Signature,
     Whitespace,
+    /// The `←` or `=` of a binding
+    BindingArrow,
+    /// A `---` or `~~~` scope delimiter
+    ScopeDelimiter,
 }
 
 pub fn spans(input: &str) -> Vec<Sp<SpanKind>> {
@@ -28,9 +32,21 @@ fn items_spans(items: &[Item]) -> Vec<Sp<SpanKind>> {
     let mut spans = Vec::new();
     for item in items {
         match item {
-            Item::Scoped { items, .. } => spans.extend(items_spans(items)),
+            Item::Scoped {
+                items,
+                delimiters: (open, close),
+                ..
+            } => {
+                spans.push(open.clone().sp(SpanKind::ScopeDelimiter));
+                spans.extend(items_spans(items));
+                if let Some(close) = close {
+                    spans.push(close.clone().sp(SpanKind::ScopeDelimiter));
+                }
+            }
             Item::Words(words) => spans.extend(words_spans(words)),
             Item::Binding(binding) => {
+                spans.push(binding.name.span.clone().sp(SpanKind::Ident));
+                spans.push(binding.arrow_span.clone().sp(SpanKind::BindingArrow));
                 if let Some(sig) = &binding.signature {
                     spans.push(sig.span.clone().sp(SpanKind::Signature));
                 }
@@ -102,46 +118,277 @@ pub use server::run_server;
 
 #[cfg(feature = "lsp")]
 mod server {
-    use std::{collections::BTreeMap, sync::Arc};
+    use std::{
+        any::Any,
+        collections::{BTreeMap, BTreeSet, HashMap},
+        mem::take,
+        path::{Path, PathBuf},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
 
     use dashmap::DashMap;
+    use parking_lot::Mutex;
     use tower_lsp::{jsonrpc::Result, lsp_types::*, *};
 
     use super::*;
 
     use crate::{
+        array::Array,
+        ast::{Arr, Func},
         format::{format_str, FormatConfig},
-        lex::Loc,
+        function::Signature,
+        lex::{lex, Loc, Span, Token},
         primitive::PrimDocFragment,
-        Ident, Uiua,
+        run::{RunMode, TestResults},
+        value::Value,
+        DiagnosticKind, Ident, SysBackend, SysOp, Uiua, UiuaError, UiuaErrorKind, UiuaResult,
     };
 
     pub struct LspDoc {
         pub input: String,
+        pub path: Option<PathBuf>,
         pub items: Vec<Item>,
         pub spans: Vec<Sp<SpanKind>>,
         pub bindings: BindingsInfo,
+        pub diagnostics: Vec<crate::Diagnostic>,
+        pub errors: Vec<DocError>,
+        pub signatures: Vec<SignatureHint>,
+        /// The lines that have parse errors
+        pub error_lines: BTreeSet<usize>,
+        /// The errors from the last time the document was run with a command
+        pub run_errors: Vec<DocError>,
+    }
+
+    /// An inferred signature to show as an inlay hint
+    pub struct SignatureHint {
+        /// Where to show the hint
+        pub loc: Loc,
+        pub signature: Signature,
+        /// Whether this is the signature of a line rather than of a binding
+        pub line: bool,
+    }
+
+    /// An error to show in a document
+    pub struct DocError {
+        pub message: String,
+        pub span: CodeSpan,
+        /// Where the error happened if it was in an imported file
+        ///
+        /// In that case, `span` is the import call site.
+        pub imported: Option<CodeSpan>,
     }
 
     type BindingsInfo = BTreeMap<Sp<Ident>, Arc<BindingInfo>>;
 
     impl LspDoc {
-        fn new(input: String) -> Self {
-            let (items, _, _) = parse(&input, None);
+        /// Parse a document
+        ///
+        /// Its diagnostics and errors are empty until it is [checked](LspDoc::check).
+        fn new(input: String, path: Option<PathBuf>) -> Self {
+            let (items, errors, _) = parse(&input, path.as_deref());
+            let error_lines = (errors.iter())
+                .flat_map(|error| error.span.start.line..=error.span.end.line)
+                .collect();
             let spans = items_spans(&items);
             let bindings = bindings_info(&items);
             Self {
                 input,
+                path,
                 items,
                 spans,
                 bindings,
+                diagnostics: Vec::new(),
+                errors: Vec::new(),
+                signatures: Vec::new(),
+                error_lines,
+                run_errors: Vec::new(),
+            }
+        }
+        /// Store the results of [checking](check) the document
+        fn set_checked(&mut self, checked: Checked) {
+            (self.diagnostics, self.errors, self.signatures) = checked;
+            let error_lines = &self.error_lines;
+            (self.signatures).retain(|hint| !error_lines.contains(&hint.loc.line));
+        }
+    }
+
+    // Stdout is used to talk to the client, so the output of running a document is collected
+    // instead of printed. Files can still be read so that imports work.
+    struct DocBackend {
+        /// The directory of the document, which relative paths are resolved against
+        dir: Option<PathBuf>,
+        output: Mutex<String>,
+    }
+
+    impl SysBackend for DocBackend {
+        fn any(&self) -> &dyn Any {
+            self
+        }
+        fn print_str_stdout(&self, s: &str) -> std::result::Result<(), String> {
+            self.output.lock().push_str(s);
+            Ok(())
+        }
+        fn print_str_stderr(&self, s: &str) -> std::result::Result<(), String> {
+            self.output.lock().push_str(s);
+            Ok(())
+        }
+        fn file_exists(&self, path: &str) -> bool {
+            self.resolve(path).exists()
+        }
+        fn file_read_all(&self, path: &str) -> std::result::Result<Vec<u8>, String> {
+            std::fs::read(self.resolve(path)).map_err(|e| e.to_string())
+        }
+    }
+
+    impl DocBackend {
+        fn new(path: Option<&Path>) -> Self {
+            DocBackend {
+                dir: path.and_then(Path::parent).map(Into::into),
+                output: Mutex::new(String::new()),
+            }
+        }
+        fn resolve(&self, path: &str) -> PathBuf {
+            match &self.dir {
+                Some(dir) => dir.join(path),
+                None => path.into(),
+            }
+        }
+    }
+
+    /// The diagnostics, errors, and inferred signatures of a document
+    type Checked = (Vec<crate::Diagnostic>, Vec<DocError>, Vec<SignatureHint>);
+
+    /// Compile a document to collect its diagnostics, errors, and inferred signatures
+    ///
+    /// The document is not run, but the files it imports are,
+    /// so this can take a while and should not be called on the server's async threads.
+    fn check(input: &str, path: Option<&Path>, items: &[Item]) -> Checked {
+        let mut env = Uiua::with_backend(DocBackend::new(path))
+            .with_compile_only(true)
+            .with_execution_limit(Duration::from_millis(500));
+        let res = match path {
+            Some(path) => env.load_str_path(input, path),
+            None => env.load_str(input),
+        };
+        let errors = match res {
+            // Running out of time is not a problem with the code
+            Err(e) if !matches!(e.kind(), UiuaErrorKind::Timeout) => doc_errors(&e, path),
+            _ => Vec::new(),
+        };
+        let in_doc = |span: &CodeSpan| span.path.as_deref() == path;
+        let diagnostics = (env.take_diagnostics().into_iter())
+            .filter(|diag| diag.span.code().is_some_and(in_doc))
+            .collect();
+        let signatures = signature_hints(&mut env, items);
+        (diagnostics, errors, signatures)
+    }
+
+    /// Get the errors to show in a document for an error that happened while checking it
+    ///
+    /// Errors in imported files are moved to the import call site in the document.
+    fn doc_errors(error: &UiuaError, path: Option<&Path>) -> Vec<DocError> {
+        let in_doc = |span: &CodeSpan| span.path.as_deref() == path;
+        let call_site = trace_spans(error).into_iter().find(|span| in_doc(span));
+        (error.parts().into_iter())
+            .filter_map(|(message, span)| {
+                let span = span.code()?.clone();
+                if in_doc(&span) {
+                    return Some(DocError {
+                        message,
+                        span,
+                        imported: None,
+                    });
+                }
+                let file = span.path.as_deref().unwrap_or(Path::new("<unknown>"));
+                Some(DocError {
+                    message: format!(
+                        "Error in imported file {}:{}:{}: {message}",
+                        file.display(),
+                        span.start.line,
+                        span.start.col
+                    ),
+                    span: call_site.clone()?,
+                    imported: Some(span),
+                })
+            })
+            .collect()
+    }
+
+    /// Get the signatures of the top-level function bindings and lines of a document that has been checked
+    fn signature_hints(env: &mut Uiua, items: &[Item]) -> Vec<SignatureHint> {
+        let bindings = env.all_bindings_in_scope();
+        let mut hints = Vec::new();
+        for (i, item) in items.iter().enumerate() {
+            match item {
+                Item::Binding(binding) if binding.signature.is_none() => {
+                    // Only the last binding of a name is still in scope
+                    let rebound = items[i + 1..].iter().any(|item| {
+                        matches!(item, Item::Binding(later) if later.name.value == binding.name.value)
+                    });
+                    if rebound {
+                        continue;
+                    }
+                    let f = (bindings.get(&binding.name.value))
+                        .and_then(Value::as_func_array)
+                        .and_then(Array::as_scalar)
+                        .filter(|f| !f.is_constant());
+                    if let Some(f) = f {
+                        hints.push(SignatureHint {
+                            loc: binding.arrow_span.end,
+                            signature: f.signature(),
+                            line: false,
+                        });
+                    }
+                }
+                Item::Words(words) => {
+                    let code_words: Vec<_> = (words.iter())
+                        .filter(|word| !matches!(word.value, Word::Spaces | Word::Comment(_)))
+                        .collect();
+                    let Some(last) = code_words.last() else {
+                        continue;
+                    };
+                    if let Ok(signature) = env.words_signature(words.clone()) {
+                        hints.push(SignatureHint {
+                            loc: last.span.end,
+                            signature,
+                            line: true,
+                        });
+                    }
+                }
+                _ => {}
             }
         }
+        hints
+    }
+
+    /// Get the spans of the calls an error happened in
+    fn trace_spans(error: &UiuaError) -> Vec<CodeSpan> {
+        match error {
+            UiuaError::Traced { error, trace } => (trace.iter())
+                .filter_map(|frame| frame.span.code().cloned())
+                .chain(trace_spans(error))
+                .collect(),
+            UiuaError::Fill(error) => trace_spans(error),
+            _ => Vec::new(),
+        }
     }
 
     pub struct BindingInfo {
         pub span: CodeSpan,
         pub comment: Option<String>,
+        /// The path of the file, if the binding is bound to [`&i`](SysOp::Import) of a path
+        pub import: Option<String>,
+        /// The imported file and the name string, if the binding is bound to [`use`](Primitive::Use)
+        /// of a module that was imported from a path
+        pub used_from: Option<(String, Sp<String>)>,
+        /// The span of the whole binding
+        pub range: CodeSpan,
+        /// Whether the bound value is a function, judging by the binding's words
+        pub function: bool,
     }
 
     fn bindings_info(items: &[Item]) -> BindingsInfo {
@@ -169,25 +416,56 @@ mod server {
                         full.push_str(comment.trim());
                     } else {
                         last_comment = None;
-                        for word in words {
-                            if let Word::Ident(ident) = &word.value {
-                                if let Some((_, info)) =
-                                    bindings.iter().rev().find(|(name, _)| name.value == *ident)
-                                {
-                                    let info = info.clone();
-                                    bindings.insert(word.span.clone().sp(ident.clone()), info);
-                                }
-                            }
-                        }
+                        insert_uses(&mut bindings, words);
                     }
                 }
                 Item::Binding(binding) => {
                     let comment = last_comment.take();
+                    insert_uses(&mut bindings, &binding.words);
+                    let words: Vec<&Sp<Word>> = (binding.words.iter())
+                        .filter(|w| !matches!(w.value, Word::Spaces))
+                        .collect();
+                    let mut import = None;
+                    let mut used_from = None;
+                    match words.as_slice() {
+                        [Sp {
+                            value: Word::Primitive(Primitive::Sys(SysOp::Import)),
+                            ..
+                        }, Sp {
+                            value: Word::String(path),
+                            ..
+                        }] => import = Some(path.clone()),
+                        [Sp {
+                            value: Word::Primitive(Primitive::Use),
+                            ..
+                        }, Sp {
+                            value: Word::String(name),
+                            span,
+                        }, Sp {
+                            value: Word::Ident(module),
+                            ..
+                        }] => {
+                            used_from = find_binding(&bindings, module)
+                                .and_then(|info| info.import.clone())
+                                .map(|path| (path, span.clone().sp(name.clone())))
+                        }
+                        _ => {}
+                    }
+                    let range = (binding.words.last()).map_or(binding.name.span.clone(), |word| {
+                        binding.name.span.clone().merge(word.span.clone())
+                    });
+                    // Bindings of constant values are evaluated, while anything else is a function
+                    let constant = !words.is_empty() && words.iter().all(|w| is_constant(&w.value));
+                    let function = binding.signature.is_some() || import.is_none() && !constant;
                     bindings.insert(
                         binding.name.clone(),
                         BindingInfo {
                             comment,
                             span: binding.name.span.clone(),
+                            import,
+                            used_from,
+                            range,
+                            function,
                         }
                         .into(),
                     );
@@ -199,8 +477,97 @@ mod server {
         scope_bindings.into_iter().flatten().collect()
     }
 
+    /// Whether a word only pushes a constant value
+    fn is_constant(word: &Word) -> bool {
+        match word {
+            Word::Number(..)
+            | Word::Char(_)
+            | Word::String(_)
+            | Word::MultilineString(_)
+            | Word::Array(_)
+            | Word::Comment(_)
+            | Word::Spaces => true,
+            Word::Strand(words) => words.iter().all(|w| is_constant(&w.value)),
+            Word::Primitive(prim) => prim.as_constant().is_some(),
+            _ => false,
+        }
+    }
+
+    /// Record the uses of bindings in some words
+    fn insert_uses(bindings: &mut BindingsInfo, words: &[Sp<Word>]) {
+        for word in words {
+            match &word.value {
+                Word::Ident(ident) => {
+                    if let Some(info) = find_binding(bindings, ident) {
+                        bindings.insert(word.span.clone().sp(ident.clone()), info);
+                    }
+                }
+                Word::Strand(words) => insert_uses(bindings, words),
+                Word::Array(Arr { lines, .. }) | Word::Func(Func { lines, .. }) => {
+                    for line in lines {
+                        insert_uses(bindings, line);
+                    }
+                }
+                Word::Modified(m) => insert_uses(bindings, &m.operands),
+                _ => {}
+            }
+        }
+    }
+
+    /// Find the latest binding of a name
+    ///
+    /// If no binding has exactly the name, a binding with the name in a different case is found instead.
+    fn find_binding(bindings: &BindingsInfo, name: &str) -> Option<Arc<BindingInfo>> {
+        let exact = (bindings.iter().rev()).find(|(ident, _)| &*ident.value == name);
+        exact
+            .or_else(|| {
+                let lower = name.to_lowercase();
+                (bindings.iter().rev()).find(|(ident, _)| ident.value.to_lowercase() == lower)
+            })
+            .map(|(_, info)| info.clone())
+    }
+
+    /// Resolves bindings to their definitions, following [`use`](Primitive::Use) into imported files
+    #[derive(Default)]
+    struct Resolver {
+        /// The bindings of files that have been parsed
+        files: HashMap<PathBuf, Option<BindingsInfo>>,
+    }
+
+    impl Resolver {
+        fn definition(&mut self, mut info: Arc<BindingInfo>) -> CodeSpan {
+            // Limit the depth in case of import cycles
+            for _ in 0..16 {
+                let Some(found) = self.used(&info) else {
+                    break;
+                };
+                info = found;
+            }
+            info.span.clone()
+        }
+        /// Get the binding in an imported file that a binding is bound to with [`use`](Primitive::Use)
+        fn used(&mut self, info: &BindingInfo) -> Option<Arc<BindingInfo>> {
+            let (path, name) = info.used_from.as_ref()?;
+            let dir = info.span.path.as_deref().and_then(Path::parent);
+            let path = dir.map_or_else(|| PathBuf::from(path), |dir| dir.join(path));
+            find_binding(self.file(&path)?, &name.value)
+        }
+        /// Get the bindings of a file, parsing it if it has not been parsed yet
+        fn file(&mut self, path: &Path) -> Option<&BindingsInfo> {
+            self.files
+                .entry(path.into())
+                .or_insert_with(|| {
+                    let input = std::fs::read_to_string(path).ok()?;
+                    Some(bindings_info(&parse(&input, Some(path)).0))
+                })
+                .as_ref()
+        }
+    }
+
     pub fn run_server() {
         tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .thread_stack_size(crate::run::STACK_SIZE)
             .build()
             .unwrap()
             .block_on(async {
@@ -209,17 +576,60 @@ mod server {
                 let stdin = tokio::io::stdin();
                 let stdout = tokio::io::stdout();
 
-                let (service, socket) = LspService::new(|client| Backend {
-                    client,
-                    docs: DashMap::new(),
-                });
+                let (service, socket) = LspService::new(Backend::new);
                 Server::new(stdin, stdout, socket).serve(service).await;
             });
     }
 
+    /// Settings that the client can change
+    ///
+    /// They are read from the initialization options and from the `uiua` section of the configuration.
+    #[derive(Default)]
+    struct Settings {
+        /// Whether to show the signature of each line, which can be noisy
+        stack_hints: bool,
+    }
+
+    impl Settings {
+        fn update(&mut self, options: &LSPAny) {
+            if let Some(stack_hints) = options.get("stackHints").and_then(LSPAny::as_bool) {
+                self.stack_hints = stack_hints;
+            }
+        }
+    }
+
+    /// How long to wait after a change before checking a document
+    ///
+    /// If the document changes again in that time, only the newest version is checked.
+    const CHECK_DELAY: Duration = Duration::from_millis(250);
+
     struct Backend {
         client: Client,
         docs: DashMap<Url, LspDoc>,
+        /// The workspace folders, which are searched for references
+        workspace: Mutex<Vec<PathBuf>>,
+        /// The position encoding negotiated with the client
+        encoding: Mutex<Encoding>,
+        settings: Mutex<Settings>,
+        /// Whether the client can be asked to request inlay hints again
+        inlay_hint_refresh: AtomicBool,
+        /// The number of changes made to each document, used to debounce checking
+        versions: DashMap<Url, u64>,
+        /// The interrupt handles of the documents that are being run with a command
+        runs: DashMap<Url, Arc<AtomicBool>>,
+    }
+
+    const RUN_FILE_COMMAND: &str = "uiua.runFile";
+    const RUN_TESTS_COMMAND: &str = "uiua.runTests";
+    const STOP_RUN_COMMAND: &str = "uiua.stopRun";
+
+    /// Interrupts a run when dropped, which happens when the client cancels the request
+    struct InterruptOnDrop(Arc<AtomicBool>);
+
+    impl Drop for InterruptOnDrop {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::Relaxed);
+        }
     }
 
     const STACK_FUNCTION_STT: SemanticTokenType = SemanticTokenType::new("stack-function");
@@ -229,10 +639,14 @@ mod server {
     const MONADIC_MODIFIER_STT: SemanticTokenType = SemanticTokenType::new("monadic-modifier");
     const DYADIC_MODIFIER_STT: SemanticTokenType = SemanticTokenType::new("dyadic-modifier");
 
-    const SEMANTIC_TOKEN_TYPES: [SemanticTokenType; 9] = [
+    const SEMANTIC_TOKEN_TYPES: [SemanticTokenType; 13] = [
         SemanticTokenType::STRING,
         SemanticTokenType::NUMBER,
         SemanticTokenType::COMMENT,
+        SemanticTokenType::VARIABLE,
+        SemanticTokenType::FUNCTION,
+        SemanticTokenType::OPERATOR,
+        SemanticTokenType::KEYWORD,
         STACK_FUNCTION_STT,
         NOADIC_FUNCTION_STT,
         MONADIC_FUNCTION_STT,
@@ -241,32 +655,231 @@ mod server {
         DYADIC_MODIFIER_STT,
     ];
 
+    const SEMANTIC_TOKEN_MODIFIERS: [SemanticTokenModifier; 1] =
+        [SemanticTokenModifier::DECLARATION];
+
+    impl Backend {
+        fn new(client: Client) -> Self {
+            Backend {
+                client,
+                docs: DashMap::new(),
+                workspace: Mutex::new(Vec::new()),
+                encoding: Mutex::new(Encoding::default()),
+                settings: Mutex::new(Settings::default()),
+                inlay_hint_refresh: AtomicBool::new(false),
+                versions: DashMap::new(),
+                runs: DashMap::new(),
+            }
+        }
+        fn encoding(&self) -> Encoding {
+            *self.encoding.lock()
+        }
+        /// Store a new version of a document and get its version number
+        fn update(&self, uri: &Url, input: String) -> u64 {
+            let path = uri.to_file_path().ok();
+            self.docs.insert(uri.clone(), LspDoc::new(input, path));
+            let mut version = self.versions.entry(uri.clone()).or_default();
+            *version += 1;
+            *version
+        }
+        /// Check a document and publish its diagnostics
+        ///
+        /// An empty list is published for a valid document so that old diagnostics are cleared.
+        ///
+        /// The document is checked on a blocking thread with a copy of its contents,
+        /// so that the other documents can be used in the meantime.
+        async fn check_and_publish(&self, uri: &Url) {
+            let Some((input, path, items)) = (self.docs.get(uri))
+                .map(|doc| (doc.input.clone(), doc.path.clone(), doc.items.clone()))
+            else {
+                return;
+            };
+            let checked = {
+                let input = input.clone();
+                tokio::task::spawn_blocking(move || check(&input, path.as_deref(), &items)).await
+            };
+            let Ok(checked) = checked else {
+                return;
+            };
+            let diagnostics = {
+                let Some(mut doc) = self.docs.get_mut(uri) else {
+                    return;
+                };
+                // The document changed while it was checked, and the change will be checked too
+                if doc.input != input {
+                    return;
+                }
+                doc.set_checked(checked);
+                lsp_diagnostics(&doc, self.encoding())
+            };
+            self.client
+                .publish_diagnostics(uri.clone(), diagnostics, None)
+                .await;
+            // Hints are requested by the client, so it is told that they may have changed
+            if self.inlay_hint_refresh.load(Ordering::Relaxed) {
+                _ = self.client.inlay_hint_refresh().await;
+            }
+        }
+        /// Call a function with the bindings of every open document and every other Uiua file
+        /// in the workspace
+        ///
+        /// Open documents may have unsaved changes, so they are used instead of the files.
+        /// The `extra` file is included even if it is outside the workspace.
+        fn for_each_file(&self, extra: Option<&Path>, mut f: impl FnMut(&BindingsInfo, &Url)) {
+            for doc in self.docs.iter() {
+                f(&doc.bindings, doc.key());
+            }
+            let mut paths = Vec::new();
+            for folder in self.workspace.lock().iter() {
+                ua_files(folder, &mut paths);
+            }
+            paths.extend(extra.map(PathBuf::from));
+            let mut visited = BTreeSet::new();
+            for path in paths {
+                let Ok(uri) = Url::from_file_path(&path) else {
+                    continue;
+                };
+                if self.docs.contains_key(&uri) || !visited.insert(uri.clone()) {
+                    continue;
+                }
+                if let Ok(input) = std::fs::read_to_string(&path) {
+                    f(&bindings_info(&parse(&input, Some(&path)).0), &uri);
+                }
+            }
+        }
+    }
+
+    fn lsp_diagnostics(doc: &LspDoc, enc: Encoding) -> Vec<Diagnostic> {
+        doc.diagnostics
+            .iter()
+            .filter_map(|diag| {
+                let Span::Code(span) = &diag.span else {
+                    return None;
+                };
+                Some(Diagnostic {
+                    range: uiua_span_to_lsp(span, enc),
+                    severity: Some(match diag.kind {
+                        DiagnosticKind::Warning => DiagnosticSeverity::WARNING,
+                        DiagnosticKind::Advice => DiagnosticSeverity::INFORMATION,
+                        DiagnosticKind::Unused | DiagnosticKind::Style => DiagnosticSeverity::HINT,
+                    }),
+                    tags: (diag.kind == DiagnosticKind::Unused)
+                        .then(|| vec![DiagnosticTag::UNNECESSARY]),
+                    source: Some("uiua".into()),
+                    message: diag.message.clone(),
+                    ..Default::default()
+                })
+            })
+            .chain(
+                doc.errors
+                    .iter()
+                    .chain(&doc.run_errors)
+                    .map(|error| Diagnostic {
+                        range: uiua_span_to_lsp(&error.span, enc),
+                        severity: Some(DiagnosticSeverity::ERROR),
+                        source: Some("uiua".into()),
+                        message: error.message.clone(),
+                        related_information: error.imported.as_ref().and_then(|span| {
+                            let path = span.path.as_deref()?;
+                            let dir = doc.path.as_deref()?.parent()?;
+                            Some(vec![DiagnosticRelatedInformation {
+                                location: Location::new(
+                                    Url::from_file_path(dir.join(path)).ok()?,
+                                    uiua_span_to_lsp(span, enc),
+                                ),
+                                message: "The error happened here".into(),
+                            }])
+                        }),
+                        ..Default::default()
+                    }),
+            )
+            .collect()
+    }
+
     #[tower_lsp::async_trait]
     impl LanguageServer for Backend {
-        async fn initialize(&self, _params: InitializeParams) -> Result<InitializeResult> {
+        async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+            #[allow(deprecated)]
+            let folders = match &params.workspace_folders {
+                Some(folders) => folders.iter().map(|folder| &folder.uri).collect(),
+                None => params.root_uri.iter().collect::<Vec<_>>(),
+            };
+            *self.workspace.lock() = (folders.into_iter())
+                .filter_map(|uri| uri.to_file_path().ok())
+                .collect();
+            // Use the client's most preferred encoding, or UTF-16 which every client supports
+            let encoding = (params.capabilities.general.as_ref())
+                .and_then(|general| general.position_encodings.as_ref())
+                .and_then(|kinds| kinds.iter().find_map(Encoding::from_kind))
+                .unwrap_or_default();
+            *self.encoding.lock() = encoding;
+            if let Some(options) = &params.initialization_options {
+                self.settings.lock().update(options);
+            }
+            let refresh = (params.capabilities.workspace.as_ref())
+                .and_then(|workspace| workspace.inlay_hint.as_ref())
+                .and_then(|inlay_hint| inlay_hint.refresh_support)
+                .unwrap_or(false);
+            self.inlay_hint_refresh.store(refresh, Ordering::Relaxed);
             self.client
                 .log_message(MessageType::INFO, "Initializing Uiua language server")
                 .await;
             self.client
                 .log_message(
                     MessageType::INFO,
-                    format!("Client capabilities: {:#?}", _params.capabilities),
+                    format!("Client capabilities: {:#?}", params.capabilities),
                 )
                 .await;
             Ok(InitializeResult {
                 capabilities: ServerCapabilities {
-                    text_document_sync: Some(TextDocumentSyncCapability::Kind(
-                        TextDocumentSyncKind::FULL,
+                    position_encoding: Some(encoding.kind()),
+                    text_document_sync: Some(TextDocumentSyncCapability::Options(
+                        TextDocumentSyncOptions {
+                            open_close: Some(true),
+                            change: Some(TextDocumentSyncKind::FULL),
+                            save: Some(TextDocumentSyncSaveOptions::Supported(true)),
+                            ..Default::default()
+                        },
                     )),
                     hover_provider: Some(HoverProviderCapability::Simple(true)),
+                    definition_provider: Some(OneOf::Left(true)),
+                    references_provider: Some(OneOf::Left(true)),
+                    document_symbol_provider: Some(OneOf::Left(true)),
+                    code_lens_provider: Some(CodeLensOptions {
+                        resolve_provider: Some(false),
+                    }),
+                    execute_command_provider: Some(ExecuteCommandOptions {
+                        commands: [RUN_FILE_COMMAND, RUN_TESTS_COMMAND, STOP_RUN_COMMAND]
+                            .map(Into::into)
+                            .into(),
+                        ..Default::default()
+                    }),
+                    code_action_provider: Some(CodeActionProviderCapability::Options(
+                        CodeActionOptions {
+                            code_action_kinds: Some(vec![CodeActionKind::QUICKFIX]),
+                            ..Default::default()
+                        },
+                    )),
+                    workspace_symbol_provider: Some(OneOf::Left(true)),
+                    rename_provider: Some(OneOf::Right(RenameOptions {
+                        prepare_provider: Some(true),
+                        work_done_progress_options: Default::default(),
+                    })),
+                    completion_provider: Some(CompletionOptions {
+                        resolve_provider: Some(true),
+                        trigger_characters: Some(vec!["&".into(), "=".into()]),
+                        ..Default::default()
+                    }),
                     document_formatting_provider: Some(OneOf::Left(true)),
+                    document_range_formatting_provider: Some(OneOf::Left(true)),
+                    inlay_hint_provider: Some(OneOf::Left(true)),
                     semantic_tokens_provider: Some(
                         SemanticTokensServerCapabilities::SemanticTokensOptions(
                             SemanticTokensOptions {
                                 work_done_progress_options: WorkDoneProgressOptions::default(),
                                 legend: SemanticTokensLegend {
                                     token_types: SEMANTIC_TOKEN_TYPES.to_vec(),
-                                    token_modifiers: vec![],
+                                    token_modifiers: SEMANTIC_TOKEN_MODIFIERS.to_vec(),
                                 },
                                 range: Some(true),
                                 full: Some(SemanticTokensFullOptions::Bool(true)),
@@ -285,18 +898,39 @@ mod server {
                 .await;
         }
 
-        async fn did_open(&self, param: DidOpenTextDocumentParams) {
-            self.docs.insert(
-                param.text_document.uri,
-                LspDoc::new(param.text_document.text),
-            );
+        async fn did_open(&self, params: DidOpenTextDocumentParams) {
+            let uri = params.text_document.uri;
+            self.update(&uri, params.text_document.text);
+            self.check_and_publish(&uri).await;
         }
 
         async fn did_change(&self, params: DidChangeTextDocumentParams) {
-            self.docs.insert(
-                params.text_document.uri,
-                LspDoc::new(params.content_changes[0].text.clone()),
-            );
+            let uri = params.text_document.uri;
+            let Some(change) = params.content_changes.into_iter().last() else {
+                return;
+            };
+            let version = self.update(&uri, change.text);
+            tokio::time::sleep(CHECK_DELAY).await;
+            // Skip checking if there was a newer change
+            if self.versions.get(&uri).is_some_and(|v| *v == version) {
+                self.check_and_publish(&uri).await;
+            }
+        }
+
+        async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
+            let settings = params.settings.get("uiua").unwrap_or(&params.settings);
+            self.settings.lock().update(settings);
+        }
+
+        async fn did_save(&self, params: DidSaveTextDocumentParams) {
+            self.check_and_publish(&params.text_document.uri).await;
+        }
+
+        async fn did_close(&self, params: DidCloseTextDocumentParams) {
+            let uri = params.text_document.uri;
+            self.docs.remove(&uri);
+            self.versions.remove(&uri);
+            self.client.publish_diagnostics(uri, Vec::new(), None).await;
         }
 
         async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
@@ -308,14 +942,19 @@ mod server {
             } else {
                 return Ok(None);
             };
-            let (line, col) = lsp_pos_to_uiua(params.text_document_position_params.position);
+            let enc = self.encoding();
+            let (line, col) = lsp_pos_to_uiua(
+                params.text_document_position_params.position,
+                &doc.input,
+                enc,
+            );
             let mut prim_range = None;
             for sp in &doc.spans {
                 if sp.span.contains_line_col(line, col) {
                     match sp.value {
                         SpanKind::Primitive(prim) => {
                             if prim.name().is_some() {
-                                prim_range = Some((prim, uiua_span_to_lsp(&sp.span)));
+                                prim_range = Some((prim, uiua_span_to_lsp(&sp.span, enc)));
                             }
                         }
                         _ => {}
@@ -325,38 +964,13 @@ mod server {
             let mut binding_range = None;
             for (ident, binding) in &doc.bindings {
                 if ident.span.contains_line_col(line, col) {
-                    binding_range = Some((ident, binding, uiua_span_to_lsp(&ident.span)));
+                    binding_range = Some((ident, binding, uiua_span_to_lsp(&ident.span, enc)));
                 }
             }
             Ok(Some(if let Some((prim, range)) = prim_range {
                 let mut contents = vec![MarkedString::String(prim.name().unwrap().into())];
-                if let Some(doc) = prim.doc() {
-                    contents.push(MarkedString::String(
-                        doc.short
-                            .iter()
-                            .map(|frag| match frag {
-                                PrimDocFragment::Text(text)
-                                | PrimDocFragment::Code(text)
-                                | PrimDocFragment::Emphasis(text)
-                                | PrimDocFragment::Strong(text)
-                                | PrimDocFragment::Link { text, .. } => text.clone(),
-                                PrimDocFragment::Primitive { prim, named } => {
-                                    let name = prim.name().unwrap();
-                                    if *named {
-                                        if let Some(unicode) = prim.glyph() {
-                                            format!("{} {}", unicode, name)
-                                        } else {
-                                            name.into()
-                                        }
-                                    } else if let Some(unicode) = prim.glyph() {
-                                        unicode.into()
-                                    } else {
-                                        name.into()
-                                    }
-                                }
-                            })
-                            .collect(),
-                    ))
+                if let Some(summary) = prim_summary(prim) {
+                    contents.push(MarkedString::String(summary))
                 }
                 Hover {
                     contents: HoverContents::Array(contents),
@@ -376,6 +990,229 @@ mod server {
             }))
         }
 
+        async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+            let position = params.text_document_position;
+            let Some(doc) = self.docs.get(&position.text_document.uri) else {
+                return Ok(None);
+            };
+            let items = completions(&doc, position.position, self.encoding());
+            Ok(Some(CompletionResponse::Array(items)))
+        }
+
+        async fn completion_resolve(&self, mut item: CompletionItem) -> Result<CompletionItem> {
+            // Primitive items store their name as their data
+            let prim = (item.data.as_ref())
+                .and_then(|data| data.as_str())
+                .and_then(Primitive::from_name);
+            if let Some(prim) = prim {
+                item.detail = prim_summary(prim);
+            }
+            Ok(item)
+        }
+
+        async fn goto_definition(
+            &self,
+            params: GotoDefinitionParams,
+        ) -> Result<Option<GotoDefinitionResponse>> {
+            let position = params.text_document_position_params;
+            let uri = position.text_document.uri;
+            let Some(doc) = self.docs.get(&uri) else {
+                return Ok(None);
+            };
+            let enc = self.encoding();
+            let Some((_, info)) = binding_at(&doc, position.position, enc) else {
+                return Ok(None);
+            };
+            let span = Resolver::default().definition(info.clone());
+            Ok(span_location(&span, &uri, enc).map(GotoDefinitionResponse::Scalar))
+        }
+
+        async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+            let position = params.text_document_position;
+            let enc = self.encoding();
+            let mut resolver = Resolver::default();
+            let target = {
+                let Some(doc) = self.docs.get(&position.text_document.uri) else {
+                    return Ok(None);
+                };
+                let Some((_, info)) = binding_at(&doc, position.position, enc) else {
+                    return Ok(None);
+                };
+                resolver.definition(info.clone())
+            };
+            let mut locations = Vec::new();
+            self.for_each_file(target.path.as_deref(), |bindings, uri| {
+                for (ident, info) in bindings {
+                    if (params.context.include_declaration || ident.span != target)
+                        && resolver.definition(info.clone()) == target
+                    {
+                        let range = uiua_span_to_lsp(&ident.span, enc);
+                        locations.push(Location::new(uri.clone(), range));
+                    }
+                }
+            });
+            locations.sort_by(|a, b| {
+                (a.uri.as_str(), a.range.start).cmp(&(b.uri.as_str(), b.range.start))
+            });
+            Ok(Some(locations))
+        }
+
+        async fn document_symbol(
+            &self,
+            params: DocumentSymbolParams,
+        ) -> Result<Option<DocumentSymbolResponse>> {
+            let Some(doc) = self.docs.get(&params.text_document.uri) else {
+                return Ok(None);
+            };
+            let enc = self.encoding();
+            let last_line = doc.input.rsplit('\n').next().unwrap_or("");
+            let end = Position::new(
+                doc.input.matches('\n').count() as u32,
+                enc.len(last_line) as u32,
+            );
+            let symbols = document_symbols(&doc.items, &doc.bindings, end, enc);
+            Ok(Some(DocumentSymbolResponse::Nested(symbols)))
+        }
+
+        async fn symbol(
+            &self,
+            params: WorkspaceSymbolParams,
+        ) -> Result<Option<Vec<SymbolInformation>>> {
+            let query = params.query.to_lowercase();
+            let enc = self.encoding();
+            let mut symbols = Vec::new();
+            self.for_each_file(None, |bindings, uri| {
+                for (ident, info) in bindings {
+                    if ident.span != info.span || !ident.value.to_lowercase().contains(&query) {
+                        continue;
+                    }
+                    #[allow(deprecated)]
+                    symbols.push(SymbolInformation {
+                        name: ident.value.to_string(),
+                        kind: binding_symbol_kind(info),
+                        tags: None,
+                        deprecated: None,
+                        location: Location::new(uri.clone(), uiua_span_to_lsp(&ident.span, enc)),
+                        container_name: None,
+                    });
+                }
+            });
+            symbols.sort_by(|a, b| {
+                let key =
+                    |s: &SymbolInformation| (s.location.uri.to_string(), s.location.range.start);
+                key(a).cmp(&key(b))
+            });
+            Ok(Some(symbols))
+        }
+
+        async fn prepare_rename(
+            &self,
+            params: TextDocumentPositionParams,
+        ) -> Result<Option<PrepareRenameResponse>> {
+            let Some(doc) = self.docs.get(&params.text_document.uri) else {
+                return Ok(None);
+            };
+            let enc = self.encoding();
+            Ok(binding_at(&doc, params.position, enc).map(|(ident, _)| {
+                PrepareRenameResponse::RangeWithPlaceholder {
+                    range: uiua_span_to_lsp(&ident.span, enc),
+                    placeholder: ident.value.to_string(),
+                }
+            }))
+        }
+
+        async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+            check_binding_name(&params.new_name).map_err(jsonrpc::Error::invalid_params)?;
+            let position = params.text_document_position;
+            let enc = self.encoding();
+            let mut resolver = Resolver::default();
+            let target = {
+                let Some(doc) = self.docs.get(&position.text_document.uri) else {
+                    return Ok(None);
+                };
+                let Some((_, info)) = binding_at(&doc, position.position, enc) else {
+                    return Ok(None);
+                };
+                resolver.definition(info.clone())
+            };
+            let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+            self.for_each_file(target.path.as_deref(), |bindings, uri| {
+                let mut ranges = Vec::new();
+                for (ident, info) in bindings {
+                    if resolver.definition(info.clone()) == target {
+                        ranges.push(uiua_span_to_lsp(&ident.span, enc));
+                    }
+                    // The name in a `use` string is renamed too, but not its quotes
+                    if let Some((_, name)) =
+                        info.used_from.as_ref().filter(|_| ident.span == info.span)
+                    {
+                        if (resolver.used(info))
+                            .is_some_and(|used| resolver.definition(used) == target)
+                        {
+                            let mut range = uiua_span_to_lsp(&name.span, enc);
+                            range.start.character += 1;
+                            range.end.character -= 1;
+                            ranges.push(range);
+                        }
+                    }
+                }
+                if ranges.is_empty() {
+                    return;
+                }
+                ranges.sort_by_key(|range| range.start);
+                ranges.dedup();
+                changes.entry(uri.clone()).or_default().extend(
+                    (ranges.into_iter()).map(|range| TextEdit::new(range, params.new_name.clone())),
+                );
+            });
+            Ok(Some(WorkspaceEdit::new(changes)))
+        }
+
+        async fn inlay_hint(&self, params: InlayHintParams) -> Result<Option<Vec<InlayHint>>> {
+            let Some(doc) = self.docs.get(&params.text_document.uri) else {
+                return Ok(None);
+            };
+            let enc = self.encoding();
+            let stack_hints = self.settings.lock().stack_hints;
+            let hints = (doc.signatures.iter())
+                .filter(|hint| stack_hints || !hint.line)
+                .filter_map(|hint| {
+                    let position = uiua_loc_to_lsp(hint.loc, &doc.input, enc);
+                    if position < params.range.start || params.range.end < position {
+                        return None;
+                    }
+                    let sig = hint.signature;
+                    let values = |n: usize| format!("{n} value{}", if n == 1 { "" } else { "s" });
+                    Some(InlayHint {
+                        position,
+                        label: InlayHintLabel::String(sig.to_string()),
+                        kind: Some(InlayHintKind::TYPE),
+                        text_edits: (!hint.line).then(|| {
+                            let range = Range::new(position, position);
+                            vec![TextEdit::new(range, format!(" {sig}"))]
+                        }),
+                        tooltip: Some(InlayHintTooltip::String(if hint.line {
+                            format!(
+                                "This line takes {} and leaves {} on the stack",
+                                values(sig.args),
+                                values(sig.outputs)
+                            )
+                        } else {
+                            format!(
+                                "This function takes {} and returns {}",
+                                values(sig.args),
+                                values(sig.outputs)
+                            )
+                        })),
+                        padding_left: Some(true),
+                        padding_right: None,
+                        data: None,
+                    })
+                })
+                .collect();
+            Ok(Some(hints))
+        }
+
         async fn formatting(
             &self,
             params: DocumentFormattingParams,
@@ -390,11 +1227,175 @@ mod server {
             else {
                 return Ok(None);
             };
-            let range = Range::new(Position::new(0, 0), Position::new(u32::MAX, u32::MAX));
-            Ok(Some(vec![TextEdit {
+            Ok(Some(diff_edits(
+                &doc.input,
+                &formatted.output,
+                self.encoding(),
+            )))
+        }
+
+        async fn range_formatting(
+            &self,
+            params: DocumentRangeFormattingParams,
+        ) -> Result<Option<Vec<TextEdit>>> {
+            let doc = if let Some(doc) = self.docs.get(&params.text_document.uri) {
+                doc
+            } else {
+                return Ok(None);
+            };
+            // Only names are converted to glyphs so that the rest of the document is untouched
+            let enc = self.encoding();
+            let edits = (doc.spans.iter())
+                .filter_map(|sp| {
+                    let SpanKind::Primitive(prim) = sp.value else {
+                        return None;
+                    };
+                    let glyph = prim.glyph()?.to_string();
+                    let range = uiua_span_to_lsp(&sp.span, enc);
+                    let in_range =
+                        params.range.start <= range.start && range.end <= params.range.end;
+                    (in_range && sp.span.as_str() != glyph).then(|| TextEdit::new(range, glyph))
+                })
+                .collect();
+            Ok(Some(edits))
+        }
+
+        async fn code_action(
+            &self,
+            params: CodeActionParams,
+        ) -> Result<Option<CodeActionResponse>> {
+            let Some(doc) = self.docs.get(&params.text_document.uri) else {
+                return Ok(None);
+            };
+            let actions = code_actions(
+                &doc,
+                &params.text_document.uri,
+                params.range,
+                &params.context.diagnostics,
+                self.encoding(),
+            );
+            Ok(Some(actions))
+        }
+
+        async fn code_lens(&self, params: CodeLensParams) -> Result<Option<Vec<CodeLens>>> {
+            let Some(doc) = self.docs.get(&params.text_document.uri) else {
+                return Ok(None);
+            };
+            let enc = self.encoding();
+            let lens = |range: Range, title: &str, command: &str| CodeLens {
                 range,
-                new_text: formatted.output,
-            }]))
+                command: Some(Command::new(
+                    title.into(),
+                    command.into(),
+                    Some(vec![params.text_document.uri.to_string().into()]),
+                )),
+                data: None,
+            };
+            let mut lenses = vec![lens(Range::default(), "Run file", RUN_FILE_COMMAND)];
+            for delimiter in test_scopes(&doc.items) {
+                let range = uiua_span_to_lsp(delimiter, enc);
+                lenses.push(lens(range, "Run tests", RUN_TESTS_COMMAND));
+            }
+            Ok(Some(lenses))
+        }
+
+        async fn execute_command(&self, params: ExecuteCommandParams) -> Result<Option<LSPAny>> {
+            let uri = (params.arguments.first())
+                .and_then(LSPAny::as_str)
+                .and_then(|uri| Url::parse(uri).ok())
+                .ok_or_else(|| jsonrpc::Error::invalid_params("Expected a document URI"))?;
+            let mode = match params.command.as_str() {
+                RUN_FILE_COMMAND => RunMode::Normal,
+                RUN_TESTS_COMMAND => RunMode::Test,
+                STOP_RUN_COMMAND => {
+                    if let Some(interrupted) = self.runs.get(&uri) {
+                        interrupted.store(true, Ordering::Relaxed);
+                    }
+                    return Ok(None);
+                }
+                command => {
+                    return Err(jsonrpc::Error::invalid_params(format!(
+                        "Unknown command {command}"
+                    )))
+                }
+            };
+            let Some((input, path)) =
+                (self.docs.get(&uri)).map(|doc| (doc.input.clone(), doc.path.clone()))
+            else {
+                return Ok(None);
+            };
+            let run = run_doc(input.clone(), path.clone(), mode);
+            // Only one run of a document happens at a time
+            if let Some(previous) = self.runs.insert(uri.clone(), run.interrupted.clone()) {
+                previous.store(true, Ordering::Relaxed);
+            }
+            let _interrupt = InterruptOnDrop(run.interrupted.clone());
+            let interrupted = run.interrupted.clone();
+            let outcome = run
+                .outcome
+                .await
+                .map_err(|_| jsonrpc::Error::internal_error())?;
+            self.runs
+                .remove_if(&uri, |_, other| Arc::ptr_eq(other, &interrupted));
+
+            if !outcome.output.is_empty() {
+                self.client
+                    .log_message(MessageType::INFO, &outcome.output)
+                    .await;
+            }
+            let name = path.as_deref().and_then(Path::file_name).map_or_else(
+                || uri.to_string(),
+                |name| name.to_string_lossy().into_owned(),
+            );
+            let mut errors = Vec::new();
+            let summary = match &outcome.result {
+                Err(e) if e.is_interrupted() => format!("Stopped running {name}"),
+                Err(e) => {
+                    errors = doc_errors(e, path.as_deref());
+                    format!("{name} failed: {}", e.message())
+                }
+                Ok(()) if mode == RunMode::Test => {
+                    let results = &outcome.test_results;
+                    for failure in &results.failures {
+                        errors.extend(doc_errors(failure, path.as_deref()).into_iter().map(
+                            |error| DocError {
+                                message: format!("Test failed: {}", error.message),
+                                ..error
+                            },
+                        ));
+                    }
+                    format!(
+                        "{name}: {} passed / {} failed",
+                        results.passed,
+                        results.failures.len()
+                    )
+                }
+                Ok(()) => {
+                    let count = outcome.stack.len();
+                    let s = if count == 1 { "" } else { "s" };
+                    format!("Ran {name}: {count} value{s} left on the stack")
+                }
+            };
+            let message_type = if errors.is_empty() {
+                MessageType::INFO
+            } else {
+                MessageType::ERROR
+            };
+            // The errors are only shown if the document has not changed since the run started
+            let diagnostics = match self.docs.get_mut(&uri) {
+                Some(mut doc) if doc.input == input => {
+                    doc.run_errors = errors;
+                    Some(lsp_diagnostics(&doc, self.encoding()))
+                }
+                _ => None,
+            };
+            if let Some(diagnostics) = diagnostics {
+                self.client
+                    .publish_diagnostics(uri.clone(), diagnostics, None)
+                    .await;
+            }
+            self.client.show_message(message_type, &summary).await;
+            Ok(Some(summary.into()))
         }
 
         async fn inline_value(
@@ -444,48 +1445,7 @@ mod server {
             } else {
                 return Ok(None);
             };
-            let mut tokens = Vec::new();
-            let mut prev_line = 0;
-            let mut prev_char = 0;
-            for sp in &doc.spans {
-                let token_type = match sp.value {
-                    SpanKind::String => SemanticTokenType::STRING,
-                    SpanKind::Number => SemanticTokenType::NUMBER,
-                    SpanKind::Comment => SemanticTokenType::COMMENT,
-                    SpanKind::Primitive(p) => match p.class() {
-                        PrimClass::Stack if p.modifier_args().is_none() => STACK_FUNCTION_STT,
-                        PrimClass::MonadicPervasive | PrimClass::MonadicArray => {
-                            MONADIC_FUNCTION_STT
-                        }
-                        PrimClass::DyadicPervasive | PrimClass::DyadicArray => DYADIC_FUNCTION_STT,
-                        _ if p.modifier_args() == Some(1) => MONADIC_MODIFIER_STT,
-                        _ if p.modifier_args() == Some(2) => DYADIC_MODIFIER_STT,
-                        _ if p.args() == Some(0) => NOADIC_FUNCTION_STT,
-                        _ => continue,
-                    },
-                    _ => continue,
-                };
-                let token_type = SEMANTIC_TOKEN_TYPES
-                    .iter()
-                    .position(|t| t == &token_type)
-                    .unwrap() as u32;
-                let span = &sp.span;
-                let start = uiua_loc_to_lsp(span.start);
-                let delta_start = if start.character > prev_char {
-                    start.character - prev_char
-                } else {
-                    start.character
-                };
-                tokens.push(SemanticToken {
-                    delta_line: start.line - prev_line,
-                    delta_start,
-                    length: (span.end.char_pos - span.start.char_pos) as u32,
-                    token_type,
-                    token_modifiers_bitset: 0,
-                });
-                prev_line = start.line;
-                prev_char = start.character;
-            }
+            let tokens = semantic_tokens(&doc, self.encoding());
             Ok(Some(SemanticTokensResult::Tokens(SemanticTokens {
                 result_id: None,
                 data: tokens,
@@ -497,19 +1457,1365 @@ mod server {
         }
     }
 
-    fn lsp_pos_to_uiua(pos: Position) -> (usize, usize) {
-        (pos.line as usize + 1, pos.character as usize + 1)
+    /// Get the semantic tokens of a document in the delta encoding
+    fn semantic_tokens(doc: &LspDoc, enc: Encoding) -> Vec<SemanticToken> {
+        let declarations: BTreeSet<&CodeSpan> = (doc.bindings.iter())
+            .filter(|(ident, info)| ident.span == info.span)
+            .map(|(ident, _)| &ident.span)
+            .collect();
+        let mut tokens = Vec::new();
+        let mut prev = Position::new(0, 0);
+        for sp in &doc.spans {
+            let mut modifiers = 0;
+            let token_type = match sp.value {
+                SpanKind::String => SemanticTokenType::STRING,
+                SpanKind::Number => SemanticTokenType::NUMBER,
+                SpanKind::Comment => SemanticTokenType::COMMENT,
+                SpanKind::Ident => {
+                    if declarations.contains(&sp.span) {
+                        modifiers |= 1;
+                    }
+                    SemanticTokenType::VARIABLE
+                }
+                SpanKind::BindingArrow => SemanticTokenType::OPERATOR,
+                SpanKind::ScopeDelimiter => SemanticTokenType::KEYWORD,
+                SpanKind::Primitive(p) => match (p.modifier_args(), p.class(), p.args()) {
+                    (Some(1), ..) => MONADIC_MODIFIER_STT,
+                    (Some(_), ..) => DYADIC_MODIFIER_STT,
+                    (None, PrimClass::Stack, _) => STACK_FUNCTION_STT,
+                    (None, _, Some(0)) => NOADIC_FUNCTION_STT,
+                    (None, _, Some(1)) => MONADIC_FUNCTION_STT,
+                    (None, _, Some(2)) => DYADIC_FUNCTION_STT,
+                    _ => SemanticTokenType::FUNCTION,
+                },
+                SpanKind::Strand | SpanKind::Signature | SpanKind::Whitespace => continue,
+            };
+            let token_type = SEMANTIC_TOKEN_TYPES
+                .iter()
+                .position(|t| t == &token_type)
+                .unwrap() as u32;
+            // Tokens cannot span multiple lines, so each line of a span is its own token
+            let start = uiua_loc_to_lsp(sp.span.start, &sp.span.input, enc);
+            for (i, text) in sp.span.as_str().split('\n').enumerate() {
+                let start = if i == 0 {
+                    start
+                } else {
+                    Position::new(start.line + i as u32, 0)
+                };
+                let length = enc.len(text.trim_end_matches('\r')) as u32;
+                if length == 0 {
+                    continue;
+                }
+                tokens.push(SemanticToken {
+                    delta_line: start.line - prev.line,
+                    delta_start: if start.line == prev.line {
+                        start.character - prev.character
+                    } else {
+                        start.character
+                    },
+                    length,
+                    token_type,
+                    token_modifiers_bitset: modifiers,
+                });
+                prev = start;
+            }
+        }
+        tokens
+    }
+
+    /// Get the binding identifier at a position
+    fn binding_at(
+        doc: &LspDoc,
+        pos: Position,
+        enc: Encoding,
+    ) -> Option<(&Sp<Ident>, &Arc<BindingInfo>)> {
+        let (line, col) = lsp_pos_to_uiua(pos, &doc.input, enc);
+        (doc.bindings.iter()).find(|(ident, _)| ident.span.contains_line_col(line, col))
+    }
+
+    /// A document being run on another thread
+    struct DocRun {
+        interrupted: Arc<AtomicBool>,
+        outcome: tokio::task::JoinHandle<RunOutcome>,
+    }
+
+    struct RunOutcome {
+        result: UiuaResult,
+        test_results: TestResults,
+        stack: Vec<Value>,
+        output: String,
+    }
+
+    /// Start running a document on another thread
+    ///
+    /// Unlike [checking](check), running has no time limit, so it can be stopped with the interrupt handle.
+    fn run_doc(input: String, path: Option<PathBuf>, mode: RunMode) -> DocRun {
+        let mut env = Uiua::with_backend(DocBackend::new(path.as_deref())).with_mode(mode);
+        let interrupted = env.interrupt_handle();
+        let outcome = tokio::task::spawn_blocking(move || {
+            let result = match &path {
+                Some(path) => env.load_str_path(&input, path),
+                None => env.load_str(&input),
+            };
+            let output = (env.downcast_backend::<DocBackend>())
+                .map(|backend| take(&mut *backend.output.lock()))
+                .unwrap_or_default();
+            RunOutcome {
+                result,
+                test_results: env.take_test_results(),
+                stack: env.take_stack(),
+                output,
+            }
+        });
+        DocRun {
+            interrupted,
+            outcome,
+        }
+    }
+
+    /// Get the opening delimiters of the test scopes in some items
+    fn test_scopes(items: &[Item]) -> Vec<&CodeSpan> {
+        let mut delimiters = Vec::new();
+        for item in items {
+            if let Item::Scoped {
+                items,
+                test,
+                delimiters: (open, _),
+            } = item
+            {
+                if *test {
+                    delimiters.push(open);
+                }
+                delimiters.extend(test_scopes(items));
+            }
+        }
+        delimiters
+    }
+
+    /// Get the quick fixes that apply to a range of a document
+    ///
+    /// A primitive name can be replaced with its glyph, and a binding's `=` can be replaced with `←`.
+    /// Only the touched code is edited, unlike formatting the whole document.
+    fn code_actions(
+        doc: &LspDoc,
+        uri: &Url,
+        range: Range,
+        diagnostics: &[Diagnostic],
+        enc: Encoding,
+    ) -> Vec<CodeActionOrCommand> {
+        let touches = |other: &Range| other.start <= range.end && range.start <= other.end;
+        let action = |title: String, edit: TextEdit| {
+            let diagnostics: Vec<Diagnostic> = (diagnostics.iter())
+                .filter(|diag| touches(&diag.range) && touches(&edit.range))
+                .cloned()
+                .collect();
+            CodeActionOrCommand::CodeAction(CodeAction {
+                title,
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: (!diagnostics.is_empty()).then_some(diagnostics),
+                edit: Some(WorkspaceEdit::new(
+                    [(uri.clone(), vec![edit])].into_iter().collect(),
+                )),
+                is_preferred: Some(true),
+                ..Default::default()
+            })
+        };
+        let mut actions = Vec::new();
+        for sp in &doc.spans {
+            let SpanKind::Primitive(prim) = sp.value else {
+                continue;
+            };
+            let Some(glyph) = prim.glyph().map(String::from) else {
+                continue;
+            };
+            let prim_range = uiua_span_to_lsp(&sp.span, enc);
+            if touches(&prim_range) && sp.span.as_str() != glyph {
+                let title = format!("Replace with glyph {glyph}");
+                actions.push(action(title, TextEdit::new(prim_range, glyph)));
+            }
+        }
+        for arrow in binding_arrows(&doc.items) {
+            let arrow_range = uiua_span_to_lsp(arrow, enc);
+            if touches(&arrow_range) && arrow.as_str() == "=" {
+                let followed_by_space = arrow.input[arrow.end.byte_pos..].starts_with([' ', '\t']);
+                let new_text = if followed_by_space { "←" } else { "← " };
+                let edit = TextEdit::new(arrow_range, new_text.into());
+                actions.push(action("Insert ← ".into(), edit));
+            }
+        }
+        actions
+    }
+
+    /// Get the spans of the arrows of the bindings in some items
+    fn binding_arrows(items: &[Item]) -> Vec<&CodeSpan> {
+        let mut arrows = Vec::new();
+        for item in items {
+            match item {
+                Item::Binding(binding) => arrows.push(&binding.arrow_span),
+                Item::Scoped { items, .. } => arrows.extend(binding_arrows(items)),
+                Item::Words(_) | Item::ExtraNewlines(_) => {}
+            }
+        }
+        arrows
+    }
+
+    /// Get the symbols of the bindings and scopes in some items
+    ///
+    /// `end` is the end of the document, where an unclosed scope ends.
+    fn document_symbols(
+        items: &[Item],
+        bindings: &BindingsInfo,
+        end: Position,
+        enc: Encoding,
+    ) -> Vec<DocumentSymbol> {
+        let mut symbols = Vec::new();
+        for item in items {
+            match item {
+                Item::Binding(binding) => {
+                    let Some(info) = bindings.get(&binding.name) else {
+                        continue;
+                    };
+                    #[allow(deprecated)]
+                    symbols.push(DocumentSymbol {
+                        name: binding.name.value.to_string(),
+                        detail: None,
+                        kind: binding_symbol_kind(info),
+                        tags: None,
+                        deprecated: None,
+                        range: uiua_span_to_lsp(&info.range, enc),
+                        selection_range: uiua_span_to_lsp(&binding.name.span, enc),
+                        children: None,
+                    });
+                }
+                Item::Scoped {
+                    items,
+                    test,
+                    delimiters: (open, close),
+                } => {
+                    let open_range = uiua_span_to_lsp(open, enc);
+                    let range_end = close
+                        .as_ref()
+                        .map_or(end, |close| uiua_span_to_lsp(close, enc).end);
+                    #[allow(deprecated)]
+                    symbols.push(DocumentSymbol {
+                        name: if *test { "test scope" } else { "scope" }.into(),
+                        detail: None,
+                        kind: SymbolKind::NAMESPACE,
+                        tags: None,
+                        deprecated: None,
+                        range: Range::new(open_range.start, range_end),
+                        selection_range: open_range,
+                        children: Some(document_symbols(items, bindings, end, enc)),
+                    });
+                }
+                Item::Words(_) | Item::ExtraNewlines(_) => {}
+            }
+        }
+        symbols
+    }
+
+    fn binding_symbol_kind(info: &BindingInfo) -> SymbolKind {
+        if info.function {
+            SymbolKind::FUNCTION
+        } else {
+            SymbolKind::VARIABLE
+        }
+    }
+
+    /// Check that a name can be given to a binding
+    ///
+    /// The name must be formatted as a single identifier, so names of primitives are not allowed.
+    fn check_binding_name(name: &str) -> std::result::Result<(), String> {
+        let (tokens, errors) = lex(name, None);
+        match tokens.as_slice() {
+            [token]
+                if errors.is_empty()
+                    && token.value == Token::Ident
+                    && token.span.as_str() == name
+                    && !name.starts_with('&') =>
+            {
+                Ok(())
+            }
+            _ => Err(format!(
+                "{name:?} is not a valid binding name. \
+                Binding names must be letters or a single glyph and must not be formatted as primitives."
+            )),
+        }
+    }
+
+    /// Get the location of a span, which is in the document with the given URI if it has no path
+    fn span_location(span: &CodeSpan, uri: &Url, enc: Encoding) -> Option<Location> {
+        let uri = match span.path.as_deref() {
+            Some(path) => Url::from_file_path(path).ok()?,
+            None => uri.clone(),
+        };
+        Some(Location::new(uri, uiua_span_to_lsp(span, enc)))
+    }
+
+    /// Find the Uiua files in a directory and its subdirectories
+    ///
+    /// Hidden directories and `target` directories are skipped.
+    fn ua_files(dir: &Path, files: &mut Vec<PathBuf>) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if path.is_dir() {
+                if !name.starts_with('.') && name != "target" {
+                    ua_files(&path, files);
+                }
+            } else if path.extension().is_some_and(|ext| ext == "ua") {
+                files.push(path);
+            }
+        }
+    }
+
+    /// Get the short documentation of a primitive as plain text
+    fn prim_summary(prim: Primitive) -> Option<String> {
+        let doc = prim.doc()?;
+        Some(
+            (doc.short.iter())
+                .map(|frag| match frag {
+                    PrimDocFragment::Text(text)
+                    | PrimDocFragment::Code(text)
+                    | PrimDocFragment::Emphasis(text)
+                    | PrimDocFragment::Strong(text)
+                    | PrimDocFragment::Link { text, .. } => text.clone(),
+                    PrimDocFragment::Primitive { prim, named } => {
+                        let name = prim.name().unwrap();
+                        if *named {
+                            if let Some(unicode) = prim.glyph() {
+                                format!("{} {}", unicode, name)
+                            } else {
+                                name.into()
+                            }
+                        } else if let Some(unicode) = prim.glyph() {
+                            unicode.into()
+                        } else {
+                            name.into()
+                        }
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Get completions for the identifier or `=` before a position
+    ///
+    /// Primitives are matched by name prefix, with the primitive the formatter would
+    /// turn the prefix into listed first. Choosing a primitive replaces the typed name with its glyph.
+    fn completions(doc: &LspDoc, pos: Position, enc: Encoding) -> Vec<CompletionItem> {
+        let (line, col) = lsp_pos_to_uiua(pos, &doc.input, enc);
+        let line_text = doc.input.split('\n').nth(line - 1).unwrap_or("");
+        let before: String = line_text.chars().take(col - 1).collect();
+        let prefix_start = before
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| c.is_alphabetic())
+            .last()
+            .map_or(before.len(), |(i, _)| i);
+        let prefix_start = if before[..prefix_start].ends_with('&') {
+            prefix_start - 1
+        } else {
+            prefix_start
+        };
+        let prefix = &before[prefix_start..];
+        let range = |len: usize| {
+            let start = pos.character - enc.len(&before[before.len() - len..]) as u32;
+            Range::new(Position::new(pos.line, start), pos)
+        };
+        // Bindings are written with ← after their name
+        if prefix.is_empty() {
+            let mut words = before.trim_end_matches('=').split_whitespace();
+            let is_binding = before.ends_with('=')
+                && words
+                    .next()
+                    .is_some_and(|name| name.chars().all(char::is_alphabetic))
+                && words.next().is_none();
+            return if is_binding {
+                vec![CompletionItem {
+                    label: "←".into(),
+                    kind: Some(CompletionItemKind::OPERATOR),
+                    detail: Some("Bind a name".into()),
+                    filter_text: Some("=".into()),
+                    text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(
+                        range(1),
+                        "← ".into(),
+                    ))),
+                    ..Default::default()
+                }]
+            } else {
+                Vec::new()
+            };
+        }
+        let mut items = Vec::new();
+        // Like the formatter, only lowercase names can become primitives
+        if !prefix.chars().any(char::is_uppercase) {
+            let formatted = Primitive::from_format_name(prefix);
+            let mut prims: Vec<Primitive> = Primitive::non_deprecated()
+                .filter(|prim| prim.name().is_some_and(|name| name.starts_with(prefix)))
+                .collect();
+            prims.sort_by_key(|prim| (Some(*prim) != formatted, prim.name()));
+            for (i, prim) in prims.into_iter().enumerate() {
+                let name = prim.name().unwrap();
+                let glyph = prim.glyph();
+                let insert = glyph.map_or_else(|| name.into(), String::from);
+                items.push(CompletionItem {
+                    label: name.into(),
+                    label_details: glyph.map(|glyph| CompletionItemLabelDetails {
+                        detail: None,
+                        description: Some(glyph.into()),
+                    }),
+                    kind: Some(CompletionItemKind::FUNCTION),
+                    preselect: (Some(prim) == formatted).then_some(true),
+                    sort_text: Some(format!("0{i:03}")),
+                    filter_text: Some(name.into()),
+                    text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(
+                        range(prefix.len()),
+                        insert,
+                    ))),
+                    data: Some(name.into()),
+                    ..Default::default()
+                });
+            }
+        }
+        let lower_prefix = prefix.to_lowercase();
+        let mut binding = |name: &str, comment: Option<&String>, detail: Option<String>| {
+            if !name.to_lowercase().starts_with(&lower_prefix)
+                || items.iter().any(|item| item.label == name)
+            {
+                return;
+            }
+            items.push(CompletionItem {
+                label: name.into(),
+                kind: Some(CompletionItemKind::VARIABLE),
+                detail,
+                documentation: comment.cloned().map(Documentation::String),
+                sort_text: Some(format!("1{name}")),
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(
+                    range(prefix.len()),
+                    name.into(),
+                ))),
+                ..Default::default()
+            });
+        };
+        for (ident, info) in &doc.bindings {
+            // Only bindings defined before the position are in scope
+            if ident.span == info.span
+                && (ident.span.start.line, ident.span.start.col) < (line, col)
+            {
+                binding(&ident.value, info.comment.as_ref(), None);
+            }
+        }
+        let dir = doc.path.as_deref().and_then(Path::parent);
+        for path in imports(&doc.items) {
+            let path = dir.map_or_else(|| PathBuf::from(&path), |dir| dir.join(&path));
+            let Ok(input) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            let (items, _, _) = parse(&input, Some(&path));
+            let file = path.file_name().unwrap_or_default().to_string_lossy();
+            for (ident, info) in &bindings_info(&items) {
+                if ident.span == info.span {
+                    binding(
+                        &ident.value,
+                        info.comment.as_ref(),
+                        Some(format!("from {file}")),
+                    );
+                }
+            }
+        }
+        items
+    }
+
+    /// Get the paths of the files imported with string literals
+    fn imports(items: &[Item]) -> Vec<String> {
+        fn words_imports(words: &[Sp<Word>], paths: &mut Vec<String>) {
+            let mut words = words.iter().filter(|w| !matches!(w.value, Word::Spaces));
+            while let Some(word) = words.next() {
+                if let Word::Primitive(Primitive::Sys(SysOp::Import)) = word.value {
+                    if let Some(Word::String(path)) = words.next().map(|w| &w.value) {
+                        paths.push(path.clone());
+                    }
+                }
+            }
+        }
+        let mut paths = Vec::new();
+        for item in items {
+            match item {
+                Item::Words(words) => words_imports(words, &mut paths),
+                Item::Binding(binding) => words_imports(&binding.words, &mut paths),
+                Item::Scoped { items, .. } => paths.extend(imports(items)),
+                Item::ExtraNewlines(_) => {}
+            }
+        }
+        paths
+    }
+
+    /// Get edits that turn one text into another
+    ///
+    /// Only lines that differ are edited, and a changed line with a single replacement is only
+    /// edited where it changed, so that the cursor stays in place in the rest of the document.
+    fn diff_edits(old: &str, new: &str, enc: Encoding) -> Vec<TextEdit> {
+        let old: Vec<&str> = old.split('\n').collect();
+        let new: Vec<&str> = new.split('\n').collect();
+        // The length of the longest common subsequence of lines after each pair of positions
+        let mut lcs = vec![vec![0u32; new.len() + 1]; old.len() + 1];
+        for i in (0..old.len()).rev() {
+            for j in (0..new.len()).rev() {
+                lcs[i][j] = if old[i] == new[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+        let pos = |line: usize, text: &str| Position::new(line as u32, enc.len(text) as u32);
+        let mut edits = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < old.len() || j < new.len() {
+            if i < old.len() && j < new.len() && old[i] == new[j] {
+                i += 1;
+                j += 1;
+                continue;
+            }
+            // Find the end of the differing lines
+            let (start_i, start_j) = (i, j);
+            while i < old.len() || j < new.len() {
+                if i < old.len() && j < new.len() && old[i] == new[j] {
+                    break;
+                }
+                if j == new.len() || i < old.len() && lcs[i + 1][j] >= lcs[i][j + 1] {
+                    i += 1;
+                } else {
+                    j += 1;
+                }
+            }
+            let (old_lines, new_lines) = (&old[start_i..i], &new[start_j..j]);
+            if old_lines.len() == new_lines.len() {
+                for (k, (a, b)) in old_lines.iter().zip(new_lines).enumerate() {
+                    if a == b {
+                        continue;
+                    }
+                    let prefix = (a.char_indices().zip(b.chars()))
+                        .find(|((_, x), y)| x != y)
+                        .map_or(a.len().min(b.len()), |((i, _), _)| i);
+                    let suffix = (a[prefix..].chars().rev().zip(b[prefix..].chars().rev()))
+                        .take_while(|(x, y)| x == y)
+                        .map(|(x, _)| x.len_utf8())
+                        .sum::<usize>();
+                    let line = start_i + k;
+                    let range =
+                        Range::new(pos(line, &a[..prefix]), pos(line, &a[..a.len() - suffix]));
+                    edits.push(TextEdit::new(range, b[prefix..b.len() - suffix].into()));
+                }
+            } else if i < old.len() {
+                let text: String = new_lines.iter().map(|line| format!("{line}\n")).collect();
+                let range =
+                    Range::new(Position::new(start_i as u32, 0), Position::new(i as u32, 0));
+                edits.push(TextEdit::new(range, text));
+            } else {
+                // The differing lines are at the end, so the newline before them is replaced
+                let start = match start_i.checked_sub(1) {
+                    Some(line) => pos(line, old[line]),
+                    None => Position::new(0, 0),
+                };
+                let end = pos(old.len() - 1, old[old.len() - 1]);
+                let mut text = new_lines.join("\n");
+                if start_i > 0 && !new_lines.is_empty() {
+                    text.insert(0, '\n');
+                }
+                edits.push(TextEdit::new(Range::new(start, end), text));
+            }
+        }
+        edits
     }
 
-    fn uiua_loc_to_lsp(loc: Loc) -> Position {
-        Position::new(loc.line as u32 - 1, loc.col as u32 - 1)
+    /// How the columns of LSP positions are counted
+    ///
+    /// This is negotiated with the client. Uiua locations count columns in chars.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    enum Encoding {
+        Utf8,
+        #[default]
+        Utf16,
+        Utf32,
     }
 
-    fn uiua_locs_to_lsp(start: Loc, end: Loc) -> Range {
-        Range::new(uiua_loc_to_lsp(start), uiua_loc_to_lsp(end))
+    impl Encoding {
+        fn from_kind(kind: &PositionEncodingKind) -> Option<Self> {
+            Some(match kind.as_str() {
+                "utf-8" => Encoding::Utf8,
+                "utf-16" => Encoding::Utf16,
+                "utf-32" => Encoding::Utf32,
+                _ => return None,
+            })
+        }
+        fn kind(self) -> PositionEncodingKind {
+            match self {
+                Encoding::Utf8 => PositionEncodingKind::UTF8,
+                Encoding::Utf16 => PositionEncodingKind::UTF16,
+                Encoding::Utf32 => PositionEncodingKind::UTF32,
+            }
+        }
+        fn char_len(self, c: char) -> usize {
+            match self {
+                Encoding::Utf8 => c.len_utf8(),
+                Encoding::Utf16 => c.len_utf16(),
+                Encoding::Utf32 => 1,
+            }
+        }
+        fn len(self, s: &str) -> usize {
+            s.chars().map(|c| self.char_len(c)).sum()
+        }
     }
 
-    fn uiua_span_to_lsp(span: &CodeSpan) -> Range {
-        uiua_locs_to_lsp(span.start, span.end)
+    fn lsp_pos_to_uiua(pos: Position, input: &str, enc: Encoding) -> (usize, usize) {
+        let line = input.split('\n').nth(pos.line as usize).unwrap_or("");
+        let mut units = 0;
+        let col = (line.chars())
+            .take_while(|&c| {
+                units += enc.char_len(c);
+                units <= pos.character as usize
+            })
+            .count();
+        (pos.line as usize + 1, col + 1)
+    }
+
+    fn uiua_loc_to_lsp(loc: Loc, input: &str, enc: Encoding) -> Position {
+        let before = &input[..loc.byte_pos.min(input.len())];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let character = enc.len(&before[line_start..]);
+        Position::new(loc.line as u32 - 1, character as u32)
+    }
+
+    fn uiua_span_to_lsp(span: &CodeSpan, enc: Encoding) -> Range {
+        Range::new(
+            uiua_loc_to_lsp(span.start, &span.input, enc),
+            uiua_loc_to_lsp(span.end, &span.input, enc),
+        )
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::fs;
+
+        use futures::{channel::mpsc, StreamExt};
+        use serde_json::{json, Value};
+        use tower::{Service, ServiceExt};
+        use tower_lsp::{jsonrpc::Request, lsp_types::Url, LspService};
+
+        use super::Backend;
+
+        struct TestClient {
+            service: LspService<Backend>,
+            diagnostics: mpsc::UnboundedReceiver<Value>,
+        }
+
+        impl TestClient {
+            async fn new() -> Self {
+                Self::with_params(json!({ "capabilities": {} })).await
+            }
+            async fn with_params(params: Value) -> Self {
+                let (service, mut socket) = LspService::new(Backend::new);
+                let (send, diagnostics) = mpsc::unbounded();
+                tokio::spawn(async move {
+                    while let Some(req) = socket.next().await {
+                        if req.method() == "textDocument/publishDiagnostics" {
+                            send.unbounded_send(req.params().unwrap().clone()).unwrap();
+                        }
+                    }
+                });
+                let mut client = TestClient {
+                    service,
+                    diagnostics,
+                };
+                let init = Request::build("initialize").params(params).id(1).finish();
+                client.call(init).await;
+                client.notify("initialized", json!({})).await;
+                client
+            }
+            async fn call(&mut self, req: Request) -> Option<Value> {
+                let res = self.service.ready().await.unwrap().call(req).await.unwrap();
+                res.map(|res| res.into_parts().1.unwrap())
+            }
+            async fn request(&mut self, method: &'static str, params: Value) -> Value {
+                let req = Request::build(method).params(params).id(2).finish();
+                self.call(req).await.unwrap()
+            }
+            async fn notify(&mut self, method: &'static str, params: Value) {
+                let req = Request::build(method).params(params).finish();
+                assert!(self.call(req).await.is_none());
+            }
+            async fn open(&mut self, uri: &str, text: &str) {
+                let params = json!({
+                    "textDocument": { "uri": uri, "languageId": "uiua", "version": 1, "text": text }
+                });
+                self.notify("textDocument/didOpen", params).await;
+            }
+            async fn complete(&mut self, uri: &str, line: u32, character: u32) -> Vec<Value> {
+                let params = json!({
+                    "textDocument": { "uri": uri },
+                    "position": { "line": line, "character": character }
+                });
+                let res = self.request("textDocument/completion", params).await;
+                res.as_array().unwrap().clone()
+            }
+            fn change(&mut self, uri: &str, text: &str) -> impl std::future::Future {
+                let params = json!({
+                    "textDocument": { "uri": uri, "version": 2 },
+                    "contentChanges": [{ "text": text }]
+                });
+                let req = Request::build("textDocument/didChange")
+                    .params(params)
+                    .finish();
+                self.service.call(req)
+            }
+            async fn diagnostics(&mut self) -> Vec<Value> {
+                let params = self.diagnostics.next().await.unwrap();
+                params["diagnostics"].as_array().unwrap().clone()
+            }
+            fn no_more_diagnostics(&mut self) {
+                assert!(self.diagnostics.try_next().is_err());
+            }
+        }
+
+        fn test(f: impl std::future::Future<Output = ()>) {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_time()
+                .build()
+                .unwrap()
+                .block_on(f)
+        }
+
+        fn range(diag: &Value) -> [u64; 4] {
+            let range = &diag["range"];
+            [
+                range["start"]["line"].as_u64().unwrap(),
+                range["start"]["character"].as_u64().unwrap(),
+                range["end"]["line"].as_u64().unwrap(),
+                range["end"]["character"].as_u64().unwrap(),
+            ]
+        }
+
+        #[test]
+        fn diagnostics_update_and_clear() {
+            test(async {
+                let mut client = TestClient::new().await;
+                let uri = "file:///tmp/uiua_lsp_test/main.ua";
+                client.open(uri, "1\nF ← |1 +").await;
+                let diags = client.diagnostics().await;
+                assert_eq!(diags.len(), 1);
+                assert_eq!(diags[0]["severity"], 1);
+                assert_eq!(range(&diags[0]), [1, 4, 1, 6]);
+
+                // Documents are compiled but not run
+                client.change(uri, "+ [1 2] [1 2 3]\n&p \"hi\"").await;
+                assert!(client.diagnostics().await.is_empty());
+
+                // Only the newest of several quick changes is checked
+                let first = client.change(uri, "1 +");
+                let second = client.change(uri, "+ 1 2");
+                futures::join!(first, second);
+                assert!(client.diagnostics().await.is_empty());
+                client.no_more_diagnostics();
+
+                client.change(uri, "\"𝄞\" ) 1").await;
+                let diags = client.diagnostics().await;
+                assert_eq!(diags.len(), 1);
+                assert_eq!(range(&diags[0]), [0, 5, 0, 6]);
+
+                client
+                    .notify(
+                        "textDocument/didClose",
+                        json!({ "textDocument": { "uri": uri } }),
+                    )
+                    .await;
+                assert!(client.diagnostics().await.is_empty());
+            });
+        }
+
+        #[test]
+        fn imported_files() {
+            let dir = std::env::temp_dir().join(format!("uiua_lsp_test_{}", std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("lib.ua"), "F = +1\n\n+[1 2] [1 2 3]").unwrap();
+            let uri = Url::from_file_path(dir.join("main.ua")).unwrap();
+            test(async {
+                let mut client = TestClient::new().await;
+                client.open(uri.as_str(), "1\n&i \"lib.ua\"").await;
+                let diags = client.diagnostics().await;
+                assert_eq!(diags.len(), 1, "{diags:?}");
+                assert_eq!(range(&diags[0]), [1, 0, 1, 2]);
+                let message = diags[0]["message"].as_str().unwrap();
+                assert!(message.contains("lib.ua:3:1"), "{message}");
+                let related = &diags[0]["relatedInformation"][0]["location"];
+                assert!(related["uri"].as_str().unwrap().ends_with("lib.ua"));
+                assert_eq!(related["range"]["start"]["line"], 2);
+
+                // Bindings from imported files are completed too
+                let uri = Url::from_file_path(dir.join("other.ua")).unwrap();
+                client.open(uri.as_str(), "&i \"lib.ua\"\nF").await;
+                client.diagnostics().await;
+                let items = client.complete(uri.as_str(), 1, 1).await;
+                let item = items.iter().find(|item| item["label"] == "F").unwrap();
+                assert_eq!(item["detail"], "from lib.ua");
+            });
+            fs::remove_dir_all(dir).unwrap();
+        }
+
+        #[test]
+        fn completion() {
+            test(async {
+                let mut client = TestClient::new().await;
+                let uri = "file:///tmp/uiua_lsp_test/completion.ua";
+                client
+                    .open(uri, "# Add one\nTakes ← +1\nDouble =\n⇌ revers\ntak\n")
+                    .await;
+                client.diagnostics().await;
+
+                let items = client.complete(uri, 3, 8).await;
+                assert_eq!(items[0]["label"], "reverse");
+                assert_eq!(items[0]["textEdit"]["newText"], "⇌");
+                let range = &items[0]["textEdit"]["range"];
+                assert_eq!(range["start"]["character"], 2);
+                assert_eq!(range["end"]["character"], 8);
+                assert!(items[0].get("detail").is_none());
+                let item = client
+                    .request("completionItem/resolve", items[0].clone())
+                    .await;
+                assert!(item["detail"].as_str().unwrap().contains("Reverse"));
+
+                // The formatter turns `tak` into take, so it is preselected
+                let items = client.complete(uri, 4, 3).await;
+                let labels: Vec<_> = items.iter().map(|item| &item["label"]).collect();
+                assert_eq!(items[0]["label"], "take");
+                assert_eq!(items[0]["preselect"], true);
+                assert!(labels.contains(&&json!("Takes")), "{labels:?}");
+                let takes = items.iter().find(|item| item["label"] == "Takes").unwrap();
+                assert_eq!(takes["documentation"], "Add one");
+                assert!(!labels.contains(&&json!("Double")));
+
+                let items = client.complete(uri, 2, 8).await;
+                assert_eq!(items.len(), 1);
+                assert_eq!(items[0]["textEdit"]["newText"], "← ");
+                assert_eq!(items[0]["textEdit"]["range"]["start"]["character"], 7);
+            });
+        }
+
+        #[test]
+        fn definitions_and_references() {
+            let dir = std::env::temp_dir().join(format!("uiua_lsp_refs_{}", std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("lib.ua"), "# Square a number\nSquare ← ×.\n").unwrap();
+            let other_text = "Lib ← &i \"lib.ua\"\nSquare ← use \"Square\" Lib\n";
+            fs::write(dir.join("other.ua"), other_text).unwrap();
+            let root = Url::from_file_path(&dir).unwrap();
+            let lib = Url::from_file_path(dir.join("lib.ua")).unwrap();
+            let other = Url::from_file_path(dir.join("other.ua")).unwrap();
+            let main = Url::from_file_path(dir.join("main.ua")).unwrap();
+            test(async {
+                let params = json!({ "capabilities": {}, "rootUri": root });
+                let mut client = TestClient::with_params(params).await;
+                let text = "Ex ← &i \"lib.ua\"\nSquare ← use \"Square\" Ex\nSquare 5\n[SQUARE 3]";
+                client.open(main.as_str(), text).await;
+                client.diagnostics().await;
+                let position = |line: u32, character: u32| {
+                    json!({
+                        "textDocument": { "uri": main },
+                        "position": { "line": line, "character": character }
+                    })
+                };
+
+                // Definitions are followed through `use` into the imported file
+                let def = client
+                    .request("textDocument/definition", position(2, 1))
+                    .await;
+                assert_eq!(def["uri"], lib.as_str());
+                assert_eq!(def["range"]["start"], json!({ "line": 1, "character": 0 }));
+                assert_eq!(def["range"]["end"], json!({ "line": 1, "character": 6 }));
+                // Names are matched case-insensitively if there is no exact match
+                let def = client
+                    .request("textDocument/definition", position(3, 3))
+                    .await;
+                assert_eq!(def["uri"], lib.as_str());
+                let def = client
+                    .request("textDocument/definition", position(1, 22))
+                    .await;
+                assert_eq!(def["uri"], main.as_str());
+                assert_eq!(def["range"]["start"], json!({ "line": 0, "character": 0 }));
+                let def = client
+                    .request("textDocument/definition", position(2, 7))
+                    .await;
+                assert!(def.is_null());
+
+                let mut params = position(2, 1);
+                params["context"] = json!({ "includeDeclaration": true });
+                let refs = client
+                    .request("textDocument/references", params.clone())
+                    .await;
+                let refs: Vec<_> = (refs.as_array().unwrap().iter())
+                    .map(|loc| {
+                        let start = &loc["range"]["start"];
+                        (
+                            loc["uri"].as_str().unwrap(),
+                            start["line"].as_u64().unwrap(),
+                        )
+                    })
+                    .collect();
+                let expected = [
+                    (lib.as_str(), 1),
+                    (main.as_str(), 1),
+                    (main.as_str(), 2),
+                    (main.as_str(), 3),
+                    (other.as_str(), 1),
+                ];
+                assert_eq!(refs, expected);
+                params["context"] = json!({ "includeDeclaration": false });
+                let refs = client.request("textDocument/references", params).await;
+                assert_eq!(refs.as_array().unwrap().len(), 4);
+            });
+            fs::remove_dir_all(dir).unwrap();
+        }
+
+        #[test]
+        fn rename() {
+            let dir = std::env::temp_dir().join(format!("uiua_lsp_rename_{}", std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            let lib_text = "# Square a number\nSquare ← ×.\n";
+            fs::write(dir.join("lib.ua"), lib_text).unwrap();
+            let other_text = "Lib ← &i \"lib.ua\"\nSquare ← use \"Square\" Lib\n";
+            fs::write(dir.join("other.ua"), other_text).unwrap();
+            let root = Url::from_file_path(&dir).unwrap();
+            let lib = Url::from_file_path(dir.join("lib.ua")).unwrap();
+            let other = Url::from_file_path(dir.join("other.ua")).unwrap();
+            let main = Url::from_file_path(dir.join("main.ua")).unwrap();
+            test(async {
+                let params = json!({
+                    "capabilities": { "general": { "positionEncodings": ["utf-8"] } },
+                    "rootUri": root
+                });
+                let mut client = TestClient::with_params(params).await;
+                let text = "Ex ← &i \"lib.ua\"\nSquare ← use \"Square\" Ex\nSquare 5\n[SQUARE 3]";
+                client.open(main.as_str(), text).await;
+                client.diagnostics().await;
+                let position = |line: u32, character: u32| {
+                    json!({
+                        "textDocument": { "uri": main },
+                        "position": { "line": line, "character": character }
+                    })
+                };
+
+                // Only bindings can be renamed
+                let prepared = client
+                    .request("textDocument/prepareRename", position(2, 1))
+                    .await;
+                assert_eq!(prepared["placeholder"], "Square");
+                assert_eq!(
+                    prepared["range"]["start"],
+                    json!({ "line": 2, "character": 0 })
+                );
+                assert_eq!(
+                    prepared["range"]["end"],
+                    json!({ "line": 2, "character": 6 })
+                );
+                let prepared = client
+                    .request("textDocument/prepareRename", position(2, 7))
+                    .await;
+                assert!(prepared.is_null());
+
+                // Names that are not formatted as a single identifier are rejected
+                for name in ["rev", "Two Words", "X2", "&Foo", ""] {
+                    let mut params = position(2, 1);
+                    params["newName"] = name.into();
+                    let req = Request::build("textDocument/rename")
+                        .params(params)
+                        .id(2)
+                        .finish();
+                    let res = client.service.ready().await.unwrap().call(req).await;
+                    let res = res.unwrap().unwrap().into_parts().1;
+                    assert!(res.is_err(), "{name:?} should not be a valid name");
+                }
+
+                // The definition, the references, and `use` strings in all files are renamed
+                let mut params = position(2, 1);
+                params["newName"] = "Sq".into();
+                let edit = client.request("textDocument/rename", params).await;
+                let changes = edit["changes"].as_object().unwrap();
+                assert_eq!(changes.len(), 3);
+                let renamed = |uri: &Url, text: &str| {
+                    apply_edits(text, changes[uri.as_str()].as_array().unwrap())
+                };
+                assert_eq!(renamed(&lib, lib_text), "# Square a number\nSq ← ×.\n");
+                assert_eq!(
+                    renamed(&other, other_text),
+                    "Lib ← &i \"lib.ua\"\nSq ← use \"Sq\" Lib\n"
+                );
+                assert_eq!(
+                    renamed(&main, text),
+                    "Ex ← &i \"lib.ua\"\nSq ← use \"Sq\" Ex\nSq 5\n[Sq 3]"
+                );
+            });
+            fs::remove_dir_all(dir).unwrap();
+        }
+
+        #[test]
+        fn symbols() {
+            let dir = std::env::temp_dir().join(format!("uiua_lsp_symbols_{}", std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("lib.ua"), "Double ← ×2\n").unwrap();
+            let root = Url::from_file_path(&dir).unwrap();
+            let lib = Url::from_file_path(dir.join("lib.ua")).unwrap();
+            let main = Url::from_file_path(dir.join("main.ua")).unwrap();
+            test(async {
+                let params = json!({ "capabilities": {}, "rootUri": root });
+                let mut client = TestClient::with_params(params).await;
+                let text = "Inc ← +1\nX ← 5\n---\nF ← ×2\n---\n~~~\nT ← [1 2]";
+                client.open(main.as_str(), text).await;
+                client.diagnostics().await;
+                let range = |start: (u32, u32), end: (u32, u32)| {
+                    json!({
+                        "start": { "line": start.0, "character": start.1 },
+                        "end": { "line": end.0, "character": end.1 }
+                    })
+                };
+
+                // Scopes contain the symbols of their bindings
+                let params = json!({ "textDocument": { "uri": main } });
+                let symbols = client.request("textDocument/documentSymbol", params).await;
+                let summary = |symbols: &Value| -> Vec<(String, u64)> {
+                    (symbols.as_array().unwrap().iter())
+                        .map(|s| {
+                            (
+                                s["name"].as_str().unwrap().into(),
+                                s["kind"].as_u64().unwrap(),
+                            )
+                        })
+                        .collect()
+                };
+                let (function, variable, namespace) = (12, 13, 3);
+                assert_eq!(
+                    summary(&symbols),
+                    [
+                        ("Inc".into(), function),
+                        ("X".into(), variable),
+                        ("scope".into(), namespace),
+                        ("test scope".into(), namespace),
+                    ]
+                );
+                assert_eq!(symbols[0]["range"], range((0, 0), (0, 8)));
+                assert_eq!(symbols[0]["selectionRange"], range((0, 0), (0, 3)));
+                assert_eq!(symbols[2]["range"], range((2, 0), (4, 3)));
+                assert_eq!(summary(&symbols[2]["children"]), [("F".into(), function)]);
+                // An unclosed scope ends at the end of the document
+                assert_eq!(symbols[3]["range"], range((5, 0), (6, 9)));
+                assert_eq!(summary(&symbols[3]["children"]), [("T".into(), variable)]);
+
+                // Workspace symbols include files that are not open
+                let search = |query: &str| json!({ "query": query });
+                let found = client.request("workspace/symbol", search("")).await;
+                let names: Vec<&str> = (found.as_array().unwrap().iter())
+                    .map(|s| s["name"].as_str().unwrap())
+                    .collect();
+                assert_eq!(names, ["Double", "Inc", "X", "F", "T"]);
+                let found = client.request("workspace/symbol", search("doub")).await;
+                assert_eq!(summary(&found), [("Double".into(), function)]);
+                assert_eq!(found[0]["location"]["uri"], lib.as_str());
+                assert_eq!(found[0]["location"]["range"], range((0, 0), (0, 6)));
+            });
+            fs::remove_dir_all(dir).unwrap();
+        }
+
+        #[test]
+        fn code_actions() {
+            async fn actions_at(
+                client: &mut TestClient,
+                uri: &str,
+                line: u32,
+                character: u32,
+            ) -> (Vec<String>, Vec<Value>) {
+                let params = json!({
+                    "textDocument": { "uri": uri },
+                    "range": {
+                        "start": { "line": line, "character": character },
+                        "end": { "line": line, "character": character }
+                    },
+                    "context": { "diagnostics": [] }
+                });
+                let actions = client.request("textDocument/codeAction", params).await;
+                let actions = actions.as_array().unwrap();
+                let titles = (actions.iter())
+                    .map(|action| action["title"].as_str().unwrap().into())
+                    .collect();
+                let edits = (actions.iter())
+                    .map(|action| action["edit"]["changes"][uri][0].clone())
+                    .collect();
+                (titles, edits)
+            }
+            test(async {
+                let mut client = TestClient::new().await;
+                let uri = "file:///tmp/uiua_lsp_test/actions.ua";
+                let text = "X = reduce+ [1 2]\n\"max\" max 1 2\nY =";
+                client.open(uri, text).await;
+                client.diagnostics().await;
+                let (titles, edits) = actions_at(&mut client, uri, 0, 6).await;
+                assert_eq!(titles, ["Replace with glyph /"]);
+                assert_eq!(apply_edits(text, &edits), text.replacen("reduce", "/", 1));
+                let (titles, edits) = actions_at(&mut client, uri, 0, 3).await;
+                assert_eq!(titles, ["Insert ← "]);
+                assert_eq!(apply_edits(text, &edits), text.replacen('=', "←", 1));
+                let (titles, edits) = actions_at(&mut client, uri, 2, 3).await;
+                assert_eq!(titles, ["Insert ← "]);
+                assert!(apply_edits(text, &edits).ends_with("Y ← "));
+                // Names in strings are not primitives
+                let (titles, _) = actions_at(&mut client, uri, 1, 2).await;
+                assert!(titles.is_empty());
+                let (titles, edits) = actions_at(&mut client, uri, 1, 7).await;
+                assert_eq!(titles, ["Replace with glyph ↥"]);
+                assert_eq!(edits[0]["range"]["start"]["character"], 6);
+            });
+        }
+
+        #[test]
+        fn code_lenses() {
+            test(async {
+                let mut client = TestClient::new().await;
+                let uri = "file:///tmp/uiua_lsp_test/lenses.ua";
+                let text = "Inc ← +1\nInc 1\n~~~\n⍤\"bad\" =3 Inc 1\n⍤\"good\" =2 Inc 1\n~~~";
+                client.open(uri, text).await;
+                assert!(client.diagnostics().await.is_empty());
+
+                let params = json!({ "textDocument": { "uri": uri } });
+                let lenses = client.request("textDocument/codeLens", params).await;
+                let lenses: Vec<_> = (lenses.as_array().unwrap().iter())
+                    .map(|lens| {
+                        (
+                            lens["range"]["start"]["line"].as_u64().unwrap(),
+                            lens["command"]["title"].as_str().unwrap(),
+                            lens["command"]["command"].as_str().unwrap(),
+                        )
+                    })
+                    .collect();
+                assert_eq!(
+                    lenses,
+                    [
+                        (0, "Run file", "uiua.runFile"),
+                        (2, "Run tests", "uiua.runTests")
+                    ]
+                );
+
+                // Failed tests are shown as errors on their lines
+                let command = |command: &str| json!({ "command": command, "arguments": [uri] });
+                let summary = client
+                    .request("workspace/executeCommand", command("uiua.runTests"))
+                    .await;
+                assert_eq!(summary, "lenses.ua: 1 passed / 1 failed");
+                let diagnostics = client.diagnostics().await;
+                assert_eq!(diagnostics.len(), 1);
+                assert_eq!(range(&diagnostics[0])[..2], [3, 0]);
+                assert!(diagnostics[0]["message"]
+                    .as_str()
+                    .unwrap()
+                    .starts_with("Test failed: bad"));
+                let summary = client
+                    .request("workspace/executeCommand", command("uiua.runFile"))
+                    .await;
+                assert_eq!(summary, "Ran lenses.ua: 1 value left on the stack");
+                assert!(client.diagnostics().await.is_empty());
+
+                // A run that never ends can be stopped
+                client.change(uri, "⍥(+1)∞ 0").await;
+                client.diagnostics().await;
+                let req = Request::build("workspace/executeCommand")
+                    .params(command("uiua.runFile"))
+                    .id(3)
+                    .finish();
+                let run = client.service.call(req);
+                let stop = async {
+                    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                    client
+                        .request("workspace/executeCommand", command("uiua.stopRun"))
+                        .await
+                };
+                let (res, _) = futures::future::join(run, stop).await;
+                let summary = res.unwrap().unwrap().into_parts().1.unwrap();
+                assert_eq!(summary, "Stopped running lenses.ua");
+            });
+        }
+
+        /// Apply edits with UTF-8 positions to a text
+        fn apply_edits(text: &str, edits: &[Value]) -> String {
+            let offset = |pos: &Value| {
+                let line = pos["line"].as_u64().unwrap() as usize;
+                let line_start: usize = text.split('\n').take(line).map(|l| l.len() + 1).sum();
+                line_start + pos["character"].as_u64().unwrap() as usize
+            };
+            let mut text = text.to_string();
+            for edit in edits.iter().rev() {
+                let range = &edit["range"];
+                let (start, end) = (offset(&range["start"]), offset(&range["end"]));
+                text.replace_range(start..end, edit["newText"].as_str().unwrap());
+            }
+            text
+        }
+
+        #[test]
+        fn formatting() {
+            test(async {
+                let params = json!({
+                    "capabilities": { "general": { "positionEncodings": ["utf-8", "utf-16"] } }
+                });
+                let mut client = TestClient::with_params(params).await;
+                let uri = "file:///tmp/uiua_lsp_test/format.ua";
+                let text = "\"𝄞\" rev [1 2 3]\n⇌ [1 2]\n\n\n+ 1 2";
+                client.open(uri, text).await;
+                client.diagnostics().await;
+                let params = json!({
+                    "textDocument": { "uri": uri },
+                    "options": { "tabSize": 4, "insertSpaces": true }
+                });
+                let edits = client
+                    .request("textDocument/formatting", params.clone())
+                    .await;
+                let edits = edits.as_array().unwrap();
+                // Only the changed parts of lines are edited
+                assert_eq!(edits.len(), 3, "{edits:?}");
+                assert_eq!(
+                    edits[0]["range"]["start"],
+                    json!({ "line": 0, "character": 7 })
+                );
+                assert_eq!(
+                    edits[0]["range"]["end"],
+                    json!({ "line": 0, "character": 10 })
+                );
+                assert_eq!(edits[0]["newText"], "⇌");
+                let formatted = apply_edits(text, edits);
+                assert_eq!(formatted, "\"𝄞\" ⇌ [1 2 3]\n⇌ [1 2]\n\n+ 1 2\n");
+
+                // Formatting a formatted document does nothing
+                client.change(uri, &formatted).await;
+                client.diagnostics().await;
+                let edits = client.request("textDocument/formatting", params).await;
+                assert_eq!(edits, json!([]));
+
+                client.change(uri, "rev 1\nrev  2").await;
+                client.diagnostics().await;
+                let params = json!({
+                    "textDocument": { "uri": uri },
+                    "range": {
+                        "start": { "line": 1, "character": 0 },
+                        "end": { "line": 1, "character": 6 }
+                    },
+                    "options": { "tabSize": 4, "insertSpaces": true }
+                });
+                let edits = client.request("textDocument/rangeFormatting", params).await;
+                let edits = edits.as_array().unwrap();
+                assert_eq!(apply_edits("rev 1\nrev  2", edits), "rev 1\n⇌  2");
+            });
+        }
+
+        #[test]
+        fn semantic_tokens_golden() {
+            use std::fmt::Write;
+
+            use super::{semantic_tokens, Encoding, LspDoc, SEMANTIC_TOKEN_TYPES};
+
+            let input = fs::read_to_string("tests/lsp/semantic_tokens.ua").unwrap();
+            let lines: Vec<Vec<u16>> = input.lines().map(|l| l.encode_utf16().collect()).collect();
+            let doc = LspDoc::new(input.clone(), None);
+            let mut decoded = String::new();
+            let (mut line, mut character) = (0, 0);
+            for token in semantic_tokens(&doc, Encoding::Utf16) {
+                line += token.delta_line as usize;
+                character = if token.delta_line == 0 {
+                    character + token.delta_start as usize
+                } else {
+                    token.delta_start as usize
+                };
+                let text = &lines[line][character..character + token.length as usize];
+                writeln!(
+                    decoded,
+                    "{}:{} {}{} {:?}",
+                    line + 1,
+                    character + 1,
+                    SEMANTIC_TOKEN_TYPES[token.token_type as usize].as_str(),
+                    if token.token_modifiers_bitset & 1 == 1 {
+                        " declaration"
+                    } else {
+                        ""
+                    },
+                    String::from_utf16(text).unwrap()
+                )
+                .unwrap();
+            }
+            let expected = fs::read_to_string("tests/lsp/semantic_tokens.txt").unwrap();
+            assert_eq!(decoded, expected, "\n{decoded}");
+        }
+
+        #[test]
+        fn inlay_hints() {
+            test(async {
+                let params = json!({
+                    "capabilities": {},
+                    "initializationOptions": { "stackHints": false }
+                });
+                let mut client = TestClient::with_params(params).await;
+                let uri = "file:///tmp/uiua_lsp_test/hints.ua";
+                client
+                    .open(uri, "Square ← ×.\nAvg ← ÷⧻:/+.\nX ← 5\nSquare 3 4\n+ 1")
+                    .await;
+                client.diagnostics().await;
+                let params = json!({
+                    "textDocument": { "uri": uri },
+                    "range": {
+                        "start": { "line": 0, "character": 0 },
+                        "end": { "line": 10, "character": 0 }
+                    }
+                });
+                let hints = |hints: Value| -> Vec<(u64, u64, String)> {
+                    (hints.as_array().unwrap().iter())
+                        .map(|hint| {
+                            let pos = &hint["position"];
+                            (
+                                pos["line"].as_u64().unwrap(),
+                                pos["character"].as_u64().unwrap(),
+                                hint["label"].as_str().unwrap().into(),
+                            )
+                        })
+                        .collect()
+                };
+                let res = client
+                    .request("textDocument/inlayHint", params.clone())
+                    .await;
+                assert_eq!(res[0]["textEdits"][0]["newText"], " |1.1");
+                assert_eq!(hints(res), [(0, 8, "|1.1".into()), (1, 5, "|1.1".into())]);
+
+                let settings = json!({ "settings": { "uiua": { "stackHints": true } } });
+                client
+                    .notify("workspace/didChangeConfiguration", settings)
+                    .await;
+                let res = client
+                    .request("textDocument/inlayHint", params.clone())
+                    .await;
+                assert_eq!(
+                    hints(res),
+                    [
+                        (0, 8, "|1.1".into()),
+                        (1, 5, "|1.1".into()),
+                        (3, 10, "|0.2".into()),
+                        (4, 3, "|1.1".into())
+                    ]
+                );
+
+                // Hints are updated when the document changes, but not on lines with parse errors
+                client.change(uri, "1 2 3\n[1 2").await;
+                client.diagnostics().await;
+                let res = client.request("textDocument/inlayHint", params).await;
+                assert_eq!(hints(res), [(0, 5, "|0.3".into())]);
+            });
+        }
     }
 }
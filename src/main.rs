@@ -2,16 +2,23 @@
 compile_error!("To compile the uiua interpreter binary, you must enable the `binary` feature flag");
 
 use std::{
+    any::Any,
     env, fmt, fs,
-    io::{self, stderr, Write},
+    io::{self, stderr, IsTerminal, Write},
+    panic,
     path::{Path, PathBuf},
     process::{exit, Child, Command, Stdio},
-    sync::mpsc::channel,
-    thread::sleep,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::channel,
+        Arc,
+    },
+    thread::{self, sleep},
     time::Duration,
 };
 
-use clap::{error::ErrorKind, Parser};
+use clap::{CommandFactory, Parser, Subcommand};
 use colored::Colorize;
 use instant::Instant;
 use notify::{EventKind, RecursiveMode, Watcher};
@@ -19,8 +26,11 @@ use once_cell::sync::Lazy;
 use parking_lot::Mutex;
 use uiua::{
     format::{format_file, FormatConfig, FormatConfigSource},
-    run::RunMode,
-    Uiua, UiuaError, UiuaResult,
+    lex::{CodeSpan, Loc},
+    primitive::{PrimDocFragment, PrimDocLine, Primitive},
+    run::{RunMode, TestResults},
+    value::Value,
+    Diagnostic, DiagnosticKind, NativeSys, SysBackend, Uiua, UiuaError, UiuaResult,
 };
 
 fn main() {
@@ -33,29 +43,62 @@ fn main() {
             *child = None;
             println!("# Program interrupted");
             print_watching();
+        } else if let Some(interrupted) = RUN_INTERRUPT.lock().as_ref() {
+            if interrupted.swap(true, Ordering::Relaxed) {
+                // A second ctrl+C exits immediately
+                exit(1)
+            }
         } else {
-            if let Ok(App::Watch { .. }) | Err(_) = App::try_parse() {
+            if let Ok(Cli {
+                app: Some(App::Watch { .. }) | None,
+                ..
+            })
+            | Err(_) = Cli::try_parse()
+            {
                 clear_watching_with(" ", "");
             }
             exit(0)
         }
     });
 
-    if let Err(e) = run() {
-        println!("{}", e.show(true));
+    // Run on a thread with enough stack to reach the recursion limit
+    let res = thread::Builder::new()
+        .stack_size(uiua::run::STACK_SIZE)
+        .spawn(run)
+        .unwrap()
+        .join()
+        .unwrap_or_else(|e| panic::resume_unwind(e));
+    if let Err(e) = res {
+        println!("{}", e.show(color()));
         exit(1);
     }
 }
 
 static WATCH_CHILD: Lazy<Mutex<Option<Child>>> = Lazy::new(Default::default);
+static RUN_INTERRUPT: Lazy<Mutex<Option<Arc<AtomicBool>>>> = Lazy::new(Default::default);
+static COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Whether output should be colored, as decided by `--color` and `NO_COLOR`
+fn color() -> bool {
+    COLOR.load(Ordering::Relaxed)
+}
 
 fn run() -> UiuaResult {
     if cfg!(feature = "profile") {
         uiua::profile::run_profile();
         return Ok(());
     }
-    match App::try_parse() {
-        Ok(app) => match app {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            _ = e.print();
+            return Ok(());
+        }
+    };
+    COLOR.store(cli.color.enabled(), Ordering::Relaxed);
+    colored::control::set_override(color());
+    match cli.app {
+        Some(app) => match app {
             App::Init => {
                 show_update_message();
                 if let Ok(path) = working_file_path() {
@@ -67,13 +110,20 @@ fn run() -> UiuaResult {
             App::Fmt {
                 path,
                 formatter_options,
+                check,
+                diagnostics,
             } => {
                 let config = FormatConfig::from_source(
                     formatter_options.format_config_source,
                     path.as_deref(),
                 )?;
 
-                if let Some(path) = path {
+                if check {
+                    let paths = path.map_or_else(uiua_files, |path| vec![path]);
+                    if !check_formatted(&paths, &config, diagnostics) {
+                        exit(1);
+                    }
+                } else if let Some(path) = path {
                     format_single_file(path, &config, formatter_options.stdout)?;
                 } else {
                     format_multi_files(&config, formatter_options.stdout)?;
@@ -86,8 +136,13 @@ fn run() -> UiuaResult {
                 no_update,
                 time_instrs,
                 mode,
+                run_options,
+                diagnostics,
+                stack_format,
                 #[cfg(feature = "audio")]
                 audio_options,
+                #[cfg(feature = "image")]
+                image_dir,
                 args,
             } => {
                 if !no_update {
@@ -114,32 +169,65 @@ fn run() -> UiuaResult {
                 let mode = mode.unwrap_or(RunMode::Normal);
                 #[cfg(feature = "audio")]
                 setup_audio(audio_options);
-                let mut rt = Uiua::with_native_sys()
+                #[cfg(feature = "image")]
+                if let Some(dir) = image_dir {
+                    uiua::set_image_dir(dir);
+                }
+                let json = diagnostics == DiagnosticsFormat::Json;
+                let rt = Uiua::with_native_sys()
                     .with_mode(mode)
                     .with_file_path(&path)
                     .with_args(args)
-                    .print_diagnostics(true)
+                    .print_diagnostics(!json)
+                    .with_color(color())
                     .time_instrs(time_instrs);
-                rt.load_file(path)?;
-                for value in rt.take_stack() {
-                    println!("{}", value.show());
+                let mut rt = run_options.configure(rt);
+                *RUN_INTERRUPT.lock() = Some(rt.interrupt_handle());
+                let res = rt.load_file(path);
+                if json {
+                    print_json_diagnostics(&rt.take_diagnostics(), res.as_ref().err(), true);
+                    if res.is_err() {
+                        exit(1);
+                    }
+                }
+                if let Err(e) = res {
+                    if e.is_interrupted() {
+                        print_stack(&rt.take_stack(), stack_format)?;
+                    }
+                    return Err(e);
+                }
+                let stack = rt.take_stack();
+                print_stack(&stack, stack_format)?;
+                if mode == RunMode::All {
+                    print_test_summary(stack.len(), rt.take_test_results());
                 }
             }
             App::Eval {
                 code,
+                run_options,
                 #[cfg(feature = "audio")]
                 audio_options,
                 args,
             } => {
                 #[cfg(feature = "audio")]
                 setup_audio(audio_options);
-                let mut rt = Uiua::with_native_sys()
+                let rt = Uiua::with_native_sys()
                     .with_mode(RunMode::Normal)
                     .with_args(args)
-                    .print_diagnostics(true);
-                rt.load_str(&code)?;
+                    .print_diagnostics(true)
+                    .with_color(color());
+                let mut rt = run_options.configure(rt);
+                *RUN_INTERRUPT.lock() = Some(rt.interrupt_handle());
+                if let Err(e) = rt.load_str(&code) {
+                    if e.is_interrupted() {
+                        for value in rt.take_stack() {
+                            println!("{}", show_value(&value));
+                        }
+                    }
+                    return Err(e);
+                }
                 for value in rt.take_stack() {
-                    println!("{}", value.show());
+                    println!("{}", show_value(&value));
                 }
             }
             App::Test {
@@ -160,11 +248,25 @@ fn run() -> UiuaResult {
                 let config =
                     FormatConfig::from_source(formatter_options.format_config_source, Some(&path))?;
                 format_file(&path, &config)?;
-                Uiua::with_native_sys()
+                let mut rt = Uiua::with_native_sys()
                     .with_mode(RunMode::Test)
                     .print_diagnostics(true)
-                    .load_file(path)?;
-                println!("No failures!");
+                    .with_color(color());
+                rt.load_file(path)?;
+                let results = rt.take_test_results();
+                for error in &results.failures {
+                    println!("{}", error.show(color()));
+                }
+                let failed = results.failures.len();
+                if failed == 0 {
+                    println!("No failures!");
+                } else {
+                    println!(
+                        "{}",
+                        format!("{} passed / {failed} failed", results.passed).bright_red()
+                    );
+                    exit(1);
+                }
             }
             App::Watch {
                 no_format,
@@ -188,10 +290,31 @@ fn run() -> UiuaResult {
                     eprintln!("Error watching file: {e}");
                 }
             }
+            App::Check {
+                path,
+                deny_warnings,
+                diagnostics,
+            } => {
+                let path = if let Some(path) = path {
+                    path
+                } else {
+                    match working_file_path() {
+                        Ok(path) => path,
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            return Ok(());
+                        }
+                    }
+                };
+                if !check(&path, deny_warnings, diagnostics) {
+                    exit(1);
+                }
+            }
+            App::Doc { name } => doc(&name),
             #[cfg(feature = "lsp")]
             App::Lsp => uiua::lsp::run_server(),
         },
-        Err(e) if e.kind() == ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand => {
+        None => {
             show_update_message();
             let res = match working_file_path() {
                 Ok(path) => watch(
@@ -211,7 +334,7 @@ fn run() -> UiuaResult {
                     None,
                 ),
                 Err(nwf) => {
-                    _ = e.print();
+                    _ = Cli::command().print_help();
                     eprintln!("\n{nwf}");
                     return Ok(());
                 }
@@ -220,7 +343,6 @@ fn run() -> UiuaResult {
                 eprintln!("Error watching file: {e}");
             }
         }
-        Err(e) => _ = e.print(),
     }
     Ok(())
 }
@@ -248,6 +370,256 @@ impl fmt::Display for NoWorkingFile {
     }
 }
 
+// Checking a file should not have any side effects, so output is discarded
+// and other IO is an error, except for the reads needed to import files
+struct CheckSys;
+
+impl SysBackend for CheckSys {
+    fn any(&self) -> &dyn Any {
+        self
+    }
+    fn print_str_stdout(&self, _: &str) -> Result<(), String> {
+        Ok(())
+    }
+    fn print_str_stderr(&self, _: &str) -> Result<(), String> {
+        Ok(())
+    }
+    fn file_exists(&self, path: &str) -> bool {
+        NativeSys.file_exists(path)
+    }
+    fn file_read_all(&self, path: &str) -> Result<Vec<u8>, String> {
+        NativeSys.file_read_all(path)
+    }
+}
+
+/// Run a file without IO and print its errors and diagnostics
+///
+/// Returns whether there were no problems
+fn check(path: &Path, deny_warnings: bool, format: DiagnosticsFormat) -> bool {
+    let mut rt = Uiua::with_backend(CheckSys)
+        .with_mode(RunMode::All)
+        .with_deny_warnings(deny_warnings);
+    let res = rt.load_file(path);
+    let diagnostics = rt.take_diagnostics();
+    if format == DiagnosticsFormat::Json {
+        print_json_diagnostics(&diagnostics, res.as_ref().err(), false);
+        return res.is_ok();
+    }
+    for diagnostic in &diagnostics {
+        eprintln!("{}", diagnostic.show(color()));
+    }
+    match res {
+        Err(e) => {
+            eprintln!("{}", e.show(color()));
+            false
+        }
+        Ok(()) => {
+            if diagnostics.is_empty() {
+                println!("No problems found");
+            }
+            true
+        }
+    }
+}
+
+/// Check that files are formatted without changing them
+///
+/// Returns whether all of them are formatted
+fn check_formatted(paths: &[PathBuf], config: &FormatConfig, format: DiagnosticsFormat) -> bool {
+    let mut diagnostics = Vec::new();
+    let mut errors = Vec::new();
+    for path in paths {
+        let input = match fs::read_to_string(path) {
+            Ok(input) => input,
+            Err(e) => {
+                errors.push(UiuaError::Load(path.clone(), e.into()));
+                continue;
+            }
+        };
+        match uiua::format::format(&input, path, config) {
+            Ok(formatted) if formatted.output == input => {}
+            Ok(_) => {
+                let start = Loc {
+                    char_pos: 0,
+                    byte_pos: 0,
+                    line: 1,
+                    col: 1,
+                };
+                let span = CodeSpan {
+                    start,
+                    end: start,
+                    path: Some(path.as_path().into()),
+                    input: input.into(),
+                };
+                diagnostics.push(Diagnostic::new(
+                    "This file is not formatted",
+                    span,
+                    DiagnosticKind::Warning,
+                ));
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    match format {
+        DiagnosticsFormat::Human => {
+            for diagnostic in &diagnostics {
+                eprintln!("{}", diagnostic.show(color()));
+            }
+            for error in &errors {
+                eprintln!("{}", error.show(color()));
+            }
+        }
+        DiagnosticsFormat::Json => {
+            for diagnostic in &diagnostics {
+                println!("{}", diagnostic.to_json());
+            }
+            for error in &errors {
+                println!("{}", error.to_json());
+            }
+        }
+    }
+    diagnostics.is_empty() && errors.is_empty()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ColorChoice {
+    Always,
+    Auto,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to color output
+    ///
+    /// In `auto` mode, output is colored if stdout is a terminal and `NO_COLOR` is not set.
+    fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                env::var_os("NO_COLOR")
+                    .filter(|var| !var.is_empty())
+                    .is_none()
+                    && io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "always" => Ok(ColorChoice::Always),
+            "auto" => Ok(ColorChoice::Auto),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!("unknown color choice `{s}`")),
+        }
+    }
+}
+
+impl fmt::Display for ColorChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorChoice::Always => write!(f, "always"),
+            ColorChoice::Auto => write!(f, "auto"),
+            ColorChoice::Never => write!(f, "never"),
+        }
+    }
+}
+
+fn show_value(value: &Value) -> String {
+    if color() {
+        value.show_colored()
+    } else {
+        value.show()
+    }
+}
+
+/// Print the values left on the stack
+fn print_stack(stack: &[Value], format: StackFormat) -> UiuaResult {
+    for value in stack {
+        match format {
+            StackFormat::Pretty => println!("{}", show_value(value)),
+            StackFormat::Json => println!("{}", value.dump_json()?),
+            StackFormat::Raw => println!("{}", value.show_raw()?),
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum StackFormat {
+    Pretty,
+    Json,
+    Raw,
+}
+
+impl FromStr for StackFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pretty" => Ok(StackFormat::Pretty),
+            "json" => Ok(StackFormat::Json),
+            "raw" => Ok(StackFormat::Raw),
+            _ => Err(format!("unknown stack format `{s}`")),
+        }
+    }
+}
+
+impl fmt::Display for StackFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StackFormat::Pretty => write!(f, "pretty"),
+            StackFormat::Json => write!(f, "json"),
+            StackFormat::Raw => write!(f, "raw"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DiagnosticsFormat {
+    Human,
+    Json,
+}
+
+impl FromStr for DiagnosticsFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(DiagnosticsFormat::Human),
+            "json" => Ok(DiagnosticsFormat::Json),
+            _ => Err(format!("unknown diagnostics format `{s}`")),
+        }
+    }
+}
+
+impl fmt::Display for DiagnosticsFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DiagnosticsFormat::Human => write!(f, "human"),
+            DiagnosticsFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// Print diagnostics and an error as JSON, one object per line
+fn print_json_diagnostics<'a>(
+    diagnostics: impl IntoIterator<Item = &'a Diagnostic>,
+    error: Option<&UiuaError>,
+    stderr: bool,
+) {
+    let lines =
+        (diagnostics.into_iter().map(Diagnostic::to_json)).chain(error.map(UiuaError::to_json));
+    for line in lines {
+        if stderr {
+            eprintln!("{line}");
+        } else {
+            println!("{line}");
+        }
+    }
+}
+
 fn working_file_path() -> Result<PathBuf, NoWorkingFile> {
     let main_in_src = PathBuf::from("src/main.ua");
     let main = if main_in_src.exists() {
@@ -302,6 +674,12 @@ fn watch(
         socket.set_nonblocking(true)?;
         (socket, port)
     };
+    // Shown images are written to the same paths on every run so that viewers are not reopened
+    #[cfg(feature = "image")]
+    let image_dir = env::temp_dir()
+        .join(format!("uiua_watch_{}", std::process::id()))
+        .to_string_lossy()
+        .into_owned();
     let run = |path: &Path, stdin_file: Option<&PathBuf>| -> io::Result<()> {
         if let Some(mut child) = WATCH_CHILD.lock().take() {
             _ = child.kill();
@@ -340,6 +718,8 @@ fn watch(
                                 "--no-update",
                                 "--mode",
                                 "all",
+                                "--color",
+                                if color() { "always" } else { "never" },
                                 #[cfg(feature = "audio")]
                                 "--audio-time",
                                 #[cfg(feature = "audio")]
@@ -348,6 +728,10 @@ fn watch(
                                 "--audio-port",
                                 #[cfg(feature = "audio")]
                                 &audio_port,
+                                #[cfg(feature = "image")]
+                                "--image-dir",
+                                #[cfg(feature = "image")]
+                                &image_dir,
                             ])
                             .args(&args)
                             .stdin(stdin_file.map_or_else(Stdio::inherit, Into::into))
@@ -359,7 +743,7 @@ fn watch(
                 Err(UiuaError::Format(..)) => sleep(Duration::from_millis((i as u64 + 1) * 10)),
                 Err(e) => {
                     clear_watching();
-                    println!("{}", e.show(true));
+                    println!("{}", e.show(color()));
                     print_watching();
                     return Ok(());
                 }
@@ -412,8 +796,38 @@ fn watch(
     }
 }
 
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (n, unit) = s.split_at(split);
+    let n: f64 = n.parse().map_err(|_| format!("invalid duration: {s:?}"))?;
+    let secs = match unit.trim() {
+        "" | "s" => n,
+        "ms" => n / 1000.0,
+        "m" => n * 60.0,
+        "h" => n * 3600.0,
+        unit => return Err(format!("invalid duration unit: {unit:?}")),
+    };
+    Ok(Duration::from_secs_f64(secs))
+}
+
 #[derive(Parser)]
 #[clap(version)]
+struct Cli {
+    #[clap(subcommand)]
+    app: Option<App>,
+    #[clap(
+        long,
+        global = true,
+        default_value_t = ColorChoice::Auto,
+        help = "When to color output (always, auto, or never)"
+    )]
+    color: ColorChoice,
+}
+
+#[derive(Subcommand)]
 enum App {
     #[clap(about = "Initialize a new main.ua file")]
     Init,
@@ -430,15 +844,29 @@ enum App {
         time_instrs: bool,
         #[clap(long, help = "Run the file in a specific mode")]
         mode: Option<RunMode>,
+        #[clap(flatten)]
+        run_options: RunOptions,
+        #[clap(long, default_value_t = DiagnosticsFormat::Human, help = "How to print diagnostics to stderr (human or json)")]
+        diagnostics: DiagnosticsFormat,
+        #[clap(long, default_value_t = StackFormat::Pretty, help = "How to print the values left on the stack (pretty, json, or raw). Values are printed one per line. Functions cannot be printed as json or raw.")]
+        stack_format: StackFormat,
         #[cfg(feature = "audio")]
         #[clap(flatten)]
         audio_options: AudioOptions,
+        #[cfg(feature = "image")]
+        #[clap(
+            long,
+            help = "Write shown images to this directory, reusing their paths between runs"
+        )]
+        image_dir: Option<PathBuf>,
         #[clap(trailing_var_arg = true)]
         args: Vec<String>,
     },
     #[clap(about = "Evaluate an expression and print its output")]
     Eval {
         code: String,
+        #[clap(flatten)]
+        run_options: RunOptions,
         #[cfg(feature = "audio")]
         #[clap(flatten)]
         audio_options: AudioOptions,
@@ -471,7 +899,24 @@ enum App {
         path: Option<PathBuf>,
         #[clap(flatten)]
         formatter_options: FormatterOptions,
+        #[clap(
+            long,
+            help = "Report files that are not formatted instead of formatting them"
+        )]
+        check: bool,
+        #[clap(long, default_value_t = DiagnosticsFormat::Human, help = "How to print diagnostics (human or json)")]
+        diagnostics: DiagnosticsFormat,
     },
+    #[clap(about = "Check a file for errors and warnings without doing any IO")]
+    Check {
+        path: Option<PathBuf>,
+        #[clap(long, help = "Treat warnings as errors")]
+        deny_warnings: bool,
+        #[clap(long, default_value_t = DiagnosticsFormat::Human, help = "How to print diagnostics (human or json)")]
+        diagnostics: DiagnosticsFormat,
+    },
+    #[clap(about = "Show the documentation for a primitive")]
+    Doc { name: String },
     #[cfg(feature = "lsp")]
     #[clap(about = "Run the Language Server")]
     Lsp,
@@ -494,6 +939,62 @@ struct FormatterOptions {
     stdout: bool,
 }
 
+#[derive(clap::Args)]
+struct RunOptions {
+    #[clap(long, help = "Don't truncate large arrays in the output")]
+    full_output: bool,
+    #[clap(long, value_parser = parse_duration, help = "Stop execution after a duration (e.g. 5s or 500ms)")]
+    timeout: Option<Duration>,
+    #[clap(long, help = "Seed the random number generator")]
+    seed: Option<u64>,
+    #[clap(long, help = "Error on math operations that would produce NaN")]
+    strict_math: bool,
+    #[clap(long, help = "Don't evaluate operations on constants before running")]
+    no_constant_folding: bool,
+    #[clap(long, help = "Don't report warnings")]
+    no_warnings: bool,
+    #[clap(
+        long,
+        conflicts_with = "no_warnings",
+        help = "Treat warnings as errors"
+    )]
+    deny_warnings: bool,
+    #[cfg(feature = "parallel")]
+    #[clap(
+        long,
+        help = "Run rows and each on this many threads (0 for one per core)"
+    )]
+    threads: Option<usize>,
+}
+
+impl RunOptions {
+    fn configure(&self, rt: Uiua) -> Uiua {
+        if self.full_output {
+            uiua::set_show_limit(None);
+        }
+        let mut rt = rt
+            .with_strict_math(self.strict_math)
+            .with_constant_folding(!self.no_constant_folding)
+            .with_warnings(!self.no_warnings)
+            .with_deny_warnings(self.deny_warnings);
+        if let Some(timeout) = self.timeout {
+            rt = rt.with_execution_limit(timeout);
+        }
+        if let Some(seed) = self.seed {
+            rt = rt.with_rng_seed(seed);
+        }
+        #[cfg(feature = "parallel")]
+        if let Some(threads) = self.threads {
+            rt = rt.with_thread_count(if threads == 0 {
+                num_cpus::get()
+            } else {
+                threads
+            });
+        }
+        rt
+    }
+}
+
 #[cfg(feature = "audio")]
 #[derive(clap::Args)]
 struct AudioOptions {
@@ -516,6 +1017,157 @@ fn setup_audio(options: AudioOptions) {
     }
 }
 
+fn print_test_summary(main_values: usize, results: TestResults) {
+    if !results.values.is_empty() {
+        println!("{}", "tests:".dimmed());
+        for value in &results.values {
+            println!("{}", show_value(value));
+        }
+    }
+    let failed = results.failures.len();
+    if results.passed + failed == 0 {
+        return;
+    }
+    for error in &results.failures {
+        println!("{}", error.show(color()));
+    }
+    let tests = format!("{} passed / {failed} failed", results.passed);
+    let tests = if failed > 0 {
+        tests.bright_red()
+    } else {
+        tests.bright_green()
+    };
+    let s = if main_values == 1 { "" } else { "s" };
+    println!(
+        "{}",
+        format!("main: {main_values} value{s}, tests: {tests}").dimmed()
+    );
+}
+
+fn doc(name: &str) {
+    let lower = name.to_lowercase();
+    let mut chars = name.chars();
+    let prim = match (chars.next(), chars.next()) {
+        (Some(c), None) => Primitive::from_glyph(c),
+        _ => None,
+    }
+    .or_else(|| Primitive::from_name(name))
+    .or_else(|| Primitive::all().find(|p| p.ascii().is_some_and(|a| a.to_string() == name)))
+    .or_else(|| Primitive::from_format_name(&lower));
+    if let Some(prim) = prim {
+        print_doc(prim);
+        return;
+    }
+    let candidates: Vec<Primitive> = Primitive::non_deprecated()
+        .filter(|p| p.name().is_some_and(|n| n.starts_with(&lower)))
+        .collect();
+    if candidates.len() > 1 {
+        eprintln!("{name:?} is ambiguous. Did you mean one of these?");
+        for prim in candidates {
+            eprintln!("  {}", prim_title(prim));
+        }
+        return;
+    }
+    let mut suggestions: Vec<(usize, Primitive)> = Primitive::non_deprecated()
+        .filter_map(|p| {
+            let dist = edit_distance(&lower, p.name()?);
+            (dist <= 2).then_some((dist, p))
+        })
+        .collect();
+    suggestions.sort_by_key(|(dist, _)| *dist);
+    eprintln!("No primitive found with the name {name:?}");
+    if !suggestions.is_empty() {
+        eprintln!("Did you mean:");
+        for (_, prim) in suggestions {
+            eprintln!("  {}", prim_title(prim));
+        }
+    }
+}
+
+fn prim_title(prim: Primitive) -> String {
+    let name = prim.name().unwrap_or_default();
+    if let Primitive::Sys(op) = prim {
+        return format!("{name} {}", op.long_name());
+    }
+    match (prim.glyph(), prim.ascii()) {
+        (Some(glyph), Some(ascii)) if ascii.to_string() != glyph.to_string() => {
+            format!("{glyph} {name} ({ascii})")
+        }
+        (Some(glyph), _) => format!("{glyph} {name}"),
+        (None, _) => name.into(),
+    }
+}
+
+fn print_doc(prim: Primitive) {
+    println!("{}", prim_title(prim).bold());
+    let mut signature = Vec::new();
+    if let Some(margs) = prim.modifier_args() {
+        signature.push(format!("{margs} function argument(s)"));
+    }
+    if let Some(args) = prim.args() {
+        signature.push(format!("{args} argument(s)"));
+    }
+    if let Some(outputs) = prim.outputs() {
+        signature.push(format!("{outputs} output(s)"));
+    }
+    if !signature.is_empty() {
+        println!("{}", signature.join(", ").dimmed());
+    }
+    let Some(doc) = prim.doc() else {
+        return;
+    };
+    println!();
+    println!("{}", fragments_text(&doc.short));
+    for line in &doc.lines {
+        match line {
+            PrimDocLine::Text(frags) => println!("{}", fragments_text(frags)),
+            PrimDocLine::Example(ex) => {
+                for (i, line) in ex.input().lines().enumerate() {
+                    let prefix = if i == 0 { "ex:" } else { "  :" };
+                    println!("{} {}", prefix.dimmed(), line.bright_white());
+                }
+            }
+        }
+    }
+}
+
+fn fragments_text(frags: &[PrimDocFragment]) -> String {
+    let mut s = String::new();
+    for frag in frags {
+        match frag {
+            PrimDocFragment::Text(text) => s.push_str(text),
+            PrimDocFragment::Code(code) => s.push_str(&code.bright_white().to_string()),
+            PrimDocFragment::Emphasis(text) => s.push_str(&text.italic().to_string()),
+            PrimDocFragment::Strong(text) => s.push_str(&text.bold().to_string()),
+            PrimDocFragment::Link { text, url } => s.push_str(&format!("{text} ({url})")),
+            PrimDocFragment::Primitive { prim, named } => {
+                let text = match (prim.glyph(), prim.name()) {
+                    (Some(glyph), Some(name)) if *named => format!("{glyph} {name}"),
+                    (Some(glyph), _) => glyph.to_string(),
+                    (None, Some(name)) => name.into(),
+                    (None, None) => format!("{prim:?}"),
+                };
+                s.push_str(&text.bright_white().to_string());
+            }
+        }
+    }
+    s
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ac) in a.chars().enumerate() {
+        let mut curr = vec![i + 1; b.len() + 1];
+        for (j, bc) in b.iter().enumerate() {
+            let cost = if ac == *bc { 0 } else { 1 };
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        prev = curr;
+    }
+    prev[b.len()]
+}
+
 fn uiua_files() -> Vec<PathBuf> {
     fs::read_dir(".")
         .unwrap()
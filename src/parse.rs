@@ -197,18 +197,28 @@ impl Parser {
             Item::Binding(binding)
         } else if let Some(words) = self.try_words() {
             Item::Words(words)
-        } else if parse_scopes && self.try_exact(TripleMinus).is_some() {
+        } else if let Some(open) = parse_scopes.then(|| self.try_exact(TripleMinus)).flatten() {
             let items = self.items(false);
-            if self.try_exact(TripleMinus).is_none() {
+            let close = self.try_exact(TripleMinus);
+            if close.is_none() {
                 self.errors.push(self.expected([TripleMinus]));
             }
-            Item::Scoped { items, test: false }
-        } else if parse_scopes && self.try_exact(TripleTilde).is_some() {
+            Item::Scoped {
+                items,
+                test: false,
+                delimiters: (open, close),
+            }
+        } else if let Some(open) = parse_scopes.then(|| self.try_exact(TripleTilde)).flatten() {
             let items = self.items(false);
-            if self.try_exact(TripleTilde).is_none() {
+            let close = self.try_exact(TripleTilde);
+            if close.is_none() {
                 self.errors.push(self.expected([TripleTilde]));
             }
-            Item::Scoped { items, test: true }
+            Item::Scoped {
+                items,
+                test: true,
+                delimiters: (open, close),
+            }
         } else {
             return None;
         })
@@ -227,10 +237,11 @@ impl Parser {
                     .push(self.prev_span().sp(ParseError::AmpersandBindingName));
             }
             self.try_spaces();
-            if self.try_exact(Equal).is_none() && self.try_exact(LeftArrow).is_none() {
+            let Some(arrow_span) = (self.try_exact(Equal)).or_else(|| self.try_exact(LeftArrow))
+            else {
                 self.index = start;
                 return None;
-            }
+            };
             self.try_spaces();
             let sig = self.try_signature();
             let words = self.try_words().unwrap_or_default();
@@ -259,6 +270,7 @@ impl Parser {
             }
             Binding {
                 name: ident,
+                arrow_span,
                 words,
                 signature: sig,
             }
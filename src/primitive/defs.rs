@@ -41,9 +41,18 @@ constant!(
     /// The primary path separator character
     (Sep, std::path::MAIN_SEPARATOR),
     /// The number of processors available
-    (NumProcs, num_cpus::get() as f64),
+    (NumProcs, num_procs()),
 );
 
+/// The number of processors available
+fn num_procs() -> f64 {
+    #[cfg(feature = "parallel")]
+    let count = num_cpus::get();
+    #[cfg(not(feature = "parallel"))]
+    let count = std::thread::available_parallelism().map_or(1, |count| count.get());
+    count as f64
+}
+
 macro_rules! primitive {
     ($(
         #[doc = $doc_rust:literal]
@@ -291,7 +300,43 @@ primitive!(
     /// ex: ⁅¯1.2
     /// ex: ⁅1.5
     /// ex: ⁅[0.1 π 2 9.9 7.5]
+    ///
+    /// To round to a multiple of something other than `1`, use [roundto].
     (1, Round, MonadicPervasive, ("round", '⁅')),
+    /// Convert characters to uppercase
+    ///
+    /// ex: upper "Hello, World!"
+    /// ex: upper ["abc" "δεζ"]
+    ///
+    /// Characters without an uppercase form are unchanged.
+    /// A character whose uppercase form is multiple characters, like `ß`, is also unchanged, so the [shape] of the array is always preserved.
+    /// ex: upper "straße"
+    ///
+    /// Only characters can be converted.
+    /// ex! upper 5
+    (1, Upper, MonadicPervasive, "upper"),
+    /// Convert characters to lowercase
+    ///
+    /// ex: lower "Hello, World!"
+    /// ex: lower "ΔΕΖ"
+    ///
+    /// Characters without a lowercase form are unchanged.
+    /// Only characters can be converted.
+    /// ex! lower 5
+    (1, Lower, MonadicPervasive, "lower"),
+    /// Classify characters as letters, digits, whitespace, or punctuation
+    ///
+    /// Each character is replaced with a number:
+    /// `1` for letters, `2` for digits, `3` for whitespace, `4` for punctuation, and `0` for anything else.
+    /// ex: charclass "Hi, 5 ½!"
+    ///
+    /// Combine with [keep] or [where] to filter or find characters of a certain class.
+    /// ex: ▽=2 charclass . "a1b2c3"
+    /// ex: ⊚=1 charclass . "42 is Δ"
+    ///
+    /// Only characters can be classified.
+    /// ex! charclass 5
+    (1, CharClass, MonadicPervasive, "charclass"),
     /// Compare for equality
     ///
     /// ex: =1 2
@@ -430,7 +475,22 @@ primitive!(
     /// ex: ₙ2 8
     /// ex: ₙ2 [8 16 32]
     /// ex: ₙ [2 3 4] [16 27 1024]
+    ///
+    /// The logarithm of `0` is `¯∞`, and the logarithm of a negative number is `NaN`.
+    /// ex: ₙ2 [0 ¯1]
+    /// Like [divide]ing by `0`, using a base of `1` gives `∞`.
+    /// ex: ₙ1 5
     (2, Log, DyadicPervasive, ("logarithm", 'ₙ')),
+    /// Round a number to the nearest multiple of another
+    ///
+    /// The first value is the step, and the second value is the number to round.
+    /// ex: roundto 0.05 1.23
+    /// ex: roundto 10 [4 15 ¯26 1234]
+    ///
+    /// The sign of the step is ignored, and a step of `0` leaves the number unchanged.
+    /// ex: roundto ¯0.5 [1.2 ¯1.3]
+    /// ex: roundto 0 π
+    (2, RoundTo, DyadicPervasive, "roundto"),
     /// Take the minimum of two arrays
     ///
     /// ex: ↧ 3 5
@@ -481,7 +541,13 @@ primitive!(
     ///
     /// The rank of the input must be `0` or `1`.
     /// ex: ⇡5
+    ///
+    /// If the input is a list, the result is an array of every index into an array with that [shape].
+    /// Its [shape] is the input followed by the input's [length].
     /// ex: ⇡2_3
+    /// ex: △⇡2_3_4
+    /// The only index into a scalar is the empty list.
+    /// ex: ⇡[]
     ///
     /// When creating ranges with upper bounds that are rank `1`, [pick]ing the generated range array from an array with the [shape] of the input will yield that array.
     /// ex:     [1_2_3 4_5_6]
@@ -502,6 +568,14 @@ primitive!(
     ///
     /// ex: ⇌1_2_3_9
     /// ex: ⇌[1_2 3_4 5_6]
+    ///
+    /// To reverse along a different axis, use [rows] or [level].
+    /// [rows][reverse] reverses the second axis, which mirrors an image horizontally.
+    /// ex: ≡⇌[1_2 3_4 5_6]
+    /// [level] with a negative number reverses the axis at that depth, while a positive number counts from the last axis.
+    /// ex: ⍚¯2⇌ ↯2_2_3 ⇡12
+    /// ex: ⍚1⇌ ↯2_2_3 ⇡12
+    /// These are optimized to swap rows in place rather than call [reverse] on each row.
     (1, Reverse, MonadicArray, ("reverse", '⇌')),
     /// Make an array 1-dimensional
     ///
@@ -513,10 +587,12 @@ primitive!(
     ///
     /// See also: [reshape]
     (1, Deshape, MonadicArray, ("deshape", '♭')),
-    /// Encode an array as bits (big-endian)
+    /// Encode an array as bits (little-endian)
     ///
     /// The result will always be 1 rank higher than the input.
     /// ex: ⋯27
+    /// [bits] of `0` is a single `0` bit.
+    /// ex: ⋯0
     /// ex: ⋯⇡8
     /// ex: ⋯[1_2 3_4 5_6]
     ///
@@ -548,9 +624,16 @@ primitive!(
     /// Using the [rise] as a selector in [select] yields the sorted array.
     /// ex: ⊏⍏.6_2_7_0_¯1_5
     ///
+    /// The sort is stable, so equal rows keep their relative order. `NaN` sorts after all other numbers.
+    /// ex: ⍏[3 NaN 1 3]
+    ///
     /// If we transform the array before [rise]ing, we can sort by a key.
     /// Here, we sort the array ascending by the [absolute value] of its elements.
     /// ex: ⊏⍏⌵.6_2_7_0_¯1_5
+    ///
+    /// [first][rise] gets the index of the minimum row. If there are ties, the lowest index is used.
+    /// This is optimized to not actually sort the array.
+    /// ex: ⊢⍏ 4_1_3_1
     (1, Rise, MonadicArray, ("rise", '⍏')),
     /// Get the indices into an array if it were sorted descending
     ///
@@ -562,7 +645,17 @@ primitive!(
     /// If we transform the array before [fall]ing, we can sort by a key.
     /// Here, we sort the array descending by the [absolute value] of its elements.
     /// ex: ⊏⍖⌵.6_2_7_0_¯1_5
+    ///
+    /// [first][fall] gets the index of the maximum row. If there are ties, the lowest index is used.
+    /// Because `NaN` sorts after all other numbers, it is considered the maximum.
+    /// This is optimized to not actually sort the array.
+    /// ex: ⊢⍖ 1_5_3_5
+    /// ex: ⊢⍖ [1 NaN 5]
     (1, Fall, MonadicArray, ("fall", '⍖')),
+    /// Get the index of the first minimum row of an array
+    (1, FirstMinIndex, MonadicArray),
+    /// Get the index of the first maximum row of an array
+    (1, FirstMaxIndex, MonadicArray),
     /// Get indices where array values are not equal to zero
     ///
     /// The most basic use is to convert a mask into a list of indices.
@@ -575,7 +668,11 @@ primitive!(
     /// ex:     ⊚ [0 1 0 0 2 0 1]
     /// ex: ▽∶⇡⧻. [0 1 0 0 2 0 1]
     ///
-    /// [invert][where] will convert the indices back into a a list of counts
+    /// For arrays of rank 2 or more, [where] returns a list of the coordinates of the non-zero elements.
+    /// ex: ⊚ [1_0_0 0_1_2]
+    /// ex: ⊡⊚. [1_0_0 0_1_2]
+    ///
+    /// [invert][where] will convert the indices back into a list of counts
     /// ex: ⍘⊚ [0 0 0 1 1 2 2 2 2 2 3]
     /// The indices need not be in order
     /// ex: ⍘⊚ [0 1 2 2 0 3 2 1 2 0 2]
@@ -592,7 +689,7 @@ primitive!(
     /// ex: ⊛"Hello, World!"
     ///
     /// When combined with [group], you can do things like counting the number of occurrences of each character in a string.
-    /// ex: $ Count the characters is this string
+    /// ex: $ Count the characters in this string
     ///   : ⊕($"_ _"⊢∶⧻.) ⊛.⊏⍏.
     (1, Classify, MonadicArray, ("classify", '⊛')),
     /// Remove duplicate elements from an array
@@ -600,7 +697,18 @@ primitive!(
     /// ex: ⊝7_7_8_0_1_2_0
     /// ex: ⊝"Hello, World!"
     /// ex: ⊝[3_2 1_4 3_2 5_6 1_4 7_8]
+    ///
+    /// See also: [unique]
     (1, Deduplicate, MonadicArray, ("deduplicate", '⊝')),
+    /// Get a mask of the first occurrence of each unique row of an array
+    ///
+    /// ex: ◰7_7_8_0_1_2_0
+    /// ex: ◰"Hello, World!"
+    /// ex: ◰[3_2 1_4 3_2 5_6 1_4 7_8]
+    ///
+    /// [keep]ing by the [unique] mask is the same as [deduplicate].
+    /// ex: ▽◰.[3_2 1_4 3_2 5_6 1_4 7_8]
+    (1, Unique, MonadicArray, ("unique", '◰')),
     /// Turn an array into a box
     ///
     /// This is Uiua's primary way to create nested or mixed-type arrays.
@@ -667,6 +775,29 @@ primitive!(
     (2, Couple, DyadicArray, ("couple", '⊟')),
     /// Split an array into two arrays
     (1(2), Uncouple, MonadicArray),
+    /// Push each row of an array onto the stack
+    ///
+    /// The first row ends up on top of the stack, so [unpack]ing inside an array's brackets gives back the original array.
+    /// ex: unpack [1 2 3]
+    /// ex: [unpack] [1_2 3_4]
+    /// ex! unpack 5
+    ///
+    /// Because the number of rows is usually not known until the program runs, the signature of a function that uses [unpack] usually cannot be inferred.
+    /// Either declare the signature or, if you know how many rows an array has, use [destructure] instead.
+    /// ex: F ← |1.2 unpack
+    ///   : F [1 2]
+    (1(None), Unpack, MonadicArray, "unpack"),
+    /// Push each row of an array onto the stack, checking that there are a certain number of them
+    ///
+    /// The first row ends up on top of the stack, just like with [unpack].
+    /// ex: destructure 3 [1 2 3]
+    /// ex! destructure 2 [1 2 3]
+    ///
+    /// Unlike [unpack], the signature of a function that uses [destructure] with a constant number is known.
+    /// This makes it useful for taking apart the result of a function.
+    /// ex: MinMax ← ⊟⊃(/↧)(/↥)
+    ///   : -destructure 2 MinMax [4 1 9 3]
+    (2(None), Destructure, MonadicArray, "destructure"),
     /// Append two arrays end-to-end
     ///
     /// For scalars, it is equivalent to [couple].
@@ -690,6 +821,27 @@ primitive!(
     ///
     /// [join]'s glyph is `⊂` because it kind of looks like a magnet pulling its two arguments together.
     (2, Join, DyadicArray, ("join", '⊂')),
+    /// Join the rows of an array with a separator between them
+    ///
+    /// The first argument is the separator, and the second is the array whose rows are joined.
+    /// ex: joinsep 0 [1_2 3_4 5_6]
+    /// ex: joinsep [0 0] [1_2 3_4 5_6]
+    /// ex: joinsep 0 [1 2 3]
+    ///
+    /// This is most useful for joining lists of strings.
+    /// A list of [box]ed strings is joined by the strings' contents.
+    /// ex: joinsep ", " {"apples" "bananas" "cherries"}
+    /// ex: joinsep @\n {"one" "two" "three"}
+    ///
+    /// Joining an array with zero or one rows does not add a separator.
+    /// ex: joinsep ", " {"lonely"}
+    /// ex: joinsep ", " {}
+    ///
+    /// [invert][joinsep] splits an array by the separator into a list of [box]ed pieces.
+    /// ex: ⍘(joinsep ", ") "a, b, c"
+    /// This is equivalent to [split][box].
+    /// ex: split□ ", " "a, b, c"
+    (2, JoinSep, DyadicArray, "joinsep"),
     /// Select multiple rows from an array
     ///
     /// For a scalar selector, [select] is equivalent to [pick].
@@ -734,7 +886,7 @@ primitive!(
     /// ex: ▽ 4 [1 2 3 4 5]
     /// ex: ▽ 2 [1_2_3 4_5_6]
     ///
-    /// At most one of the dimensions of the new shape may be negative. This indicates that this is a *derived* dimension, and it will be calculated to make the total number of elements in the new shape be `less or equal` the total number of elements in the original shape.
+    /// At most one of the dimensions of the new shape may be `¯1`. This indicates that this is a *derived* dimension, and it will be calculated to make the total number of elements in the new shape be `less or equal` the total number of elements in the original shape.
     /// ex: ↯5_¯1 ⇡15
     /// ex: ↯¯1_5 ⇡15
     /// ex: ↯2_2_¯1 ⇡15
@@ -795,6 +947,13 @@ primitive!(
     ///
     /// Multi-dimensional window sizes are supported.
     /// ex: ◫2_2 .[1_2_3 4_5_6 7_8_9]
+    ///
+    /// If the window size is a rank `2` array, the first row is the sizes and the second row is the strides.
+    /// Windows only start at multiples of the stride. Windows that would extend past the end of the array are not included.
+    /// ex: ◫[[3] [2]] ⇡8
+    /// ex: ◫[4_ 4_] ⇡10
+    /// Each axis can have its own stride.
+    /// ex: ◫[2_2 2_2] .↯4_4⇡16
     (2, Windows, DyadicArray, ("windows", '◫')),
     /// Discard or copy some rows of an array
     ///
@@ -824,6 +983,11 @@ primitive!(
     /// ex: ⌕ 5 [1 8 5 2 3 5 4 5 6 7]
     /// ex: ⌕ "ab" "abracadabra"
     /// ex: ⌕ 1_2 . ↯4_4⇡3
+    ///
+    /// Occurrences may overlap.
+    /// ex: ⌕ "aa" "aaaa"
+    /// An empty array is found at every position.
+    /// ex: ⌕ "" "abc"
     (2, Find, DyadicArray, ("find", '⌕')),
     /// Check if each row of one array exists in another
     ///
@@ -857,6 +1021,24 @@ primitive!(
     ///
     /// [indexof] is closely related to [member].
     (2, IndexOf, DyadicArray, ("indexof", '⊗')),
+    /// Multiply two matrices
+    ///
+    /// The first argument is the left matrix, and the second is the right matrix.
+    /// The number of columns of the first must match the number of rows of the second.
+    /// ex: matmul [1_2 3_4 5_6] [7_8_9 10_11_12]
+    ///
+    /// If either argument is a list, it is treated as a vector.
+    /// ex: matmul [1_2 3_4] [5 6]
+    /// ex: matmul [1 2] [3_4 5_6]
+    /// The matrix product of two lists is their dot product.
+    /// ex: matmul [1 2 3] [4 5 6]
+    ///
+    /// [matmul] is equivalent to `cross``(``reduce``add``multiply``)``dip``transpose`, but it is much faster and does not build the intermediate arrays.
+    /// ex: ⊠(/+×)⊙⍉ [1_2 3_4 5_6] [7_8_9 10_11_12]
+    ///
+    /// The arrays must be numbers, and their inner dimensions must match.
+    /// ex! matmul [1_2 3_4] [1_2 3_4 5_6]
+    (2, MatMul, DyadicArray, "matmul"),
     /// Apply a reducing function to an array
     ///
     /// For reducing with an initial value, see [fold].
@@ -885,6 +1067,8 @@ primitive!(
     /// ex: ∧- 10 1_2_3_4
     /// The accumulator is always the first argument to the function.
     /// ex: ∧⊂ [] 1_2_3_4
+    /// If the array is empty, the accumulator is returned unchanged.
+    /// ex: ∧+ 10 []
     /// Multiple accumulators can be used. In this case, each row of the array will always be the last argument to the function.
     /// ex: ∧⊃(+⊙;)(×;) 0 1 [1 2 3 4 5]
     ///
@@ -900,7 +1084,14 @@ primitive!(
     /// [break]ing out of [scan] appends the unscanned values without applying the function to them.
     /// This means the length of the output is always the same as that of the input.
     /// ex: \(⎋≥10.+) [1 2 3 4 5 6 7 8]
+    ///
+    /// [under][reverse][scan] scans from the end of the array to the beginning.
+    /// This is optimized to not actually reverse the array.
+    /// ex: ⍜⇌\+ 1_2_3_4
+    /// ex: ⍜⇌\- 1_2_3_4
     (1[1], Scan, AggregatingModifier, ("scan", '\\')),
+    /// Scan from the end of an array to the beginning
+    (1[1], ReverseScan, AggregatingModifier),
     /// Apply a function to each element of an array or arrays.
     ///
     /// This is the element-wise version of [rows].
@@ -986,18 +1177,20 @@ primitive!(
     /// The function then processes each group in order. The result depends on what the function is.
     /// If the function takes 0 or 1 arguments, then [group] behaves like [rows].
     /// ex: ⊕∘ [0 2 2 1 0 1] [1 2 3 4 5 6]
-    /// If the function takes 2 arguments, then [group] requires and accumulator and behaves like [fold].
+    /// If the function takes 2 arguments, then [group] requires an accumulator and behaves like [fold].
     /// ex: ⊕⊂ [] [0 2 2 1 0 1] [1 2 3 4 5 6]
     /// If the values returned by the function do not have the same [shape], concatenation will fail.
     /// ex! ⊕∘ [0 1 0 2 1 1] [1 2 3 4 5 6]
     /// It is common to use [box] to encapsulate groups of different [shape]s.
     /// ex: ⊕□ [0 1 0 2 1 1] [1 2 3 4 5 6]
+    /// Alternatively, [fill] can be used to pad the groups to the same [shape].
+    /// ex: ⬚0⊕∘ [0 1 0 2 1 1] [1 2 3 4 5 6]
     ///
     /// If you want to get the length of each group, use [length].
     /// ex: ⊕⧻ [0 1 0 2 1 1] [1 2 3 4 5 6]
     ///
     /// When combined with [classify], you can do things like counting the number of occurrences of each character in a string.
-    /// ex: $ Count the characters is this string
+    /// ex: $ Count the characters in this string
     ///   : ⊕{⊢∶⧻.} ⊛.⊏⍏.
     ///
     /// [group] is closely related to [partition].
@@ -1026,7 +1219,31 @@ primitive!(
     /// ex: ⊜□ ≠@ . $ Hey there friendo
     ///
     /// [partition] is closely related to [group].
+    /// To split by a delimiter while keeping empty fields, use [split].
     (2[1], Partition, AggregatingModifier, ("partition", '⊜')),
+    /// Split an array by a delimiter and call a function on each piece
+    ///
+    /// Takes a function, a delimiter, and an array.
+    /// The delimiter must either have the same [shape] as a row of the array or be a list of such rows.
+    /// The function is called on each piece between delimiters, and the results are collected into an array.
+    /// ex: split□ @, "a,b,c"
+    ///
+    /// Unlike [partition], empty pieces are kept. This includes pieces at the start or end of the array.
+    /// ex: split□ @, "a,b,,c"
+    /// ex: split□ @, ",a,"
+    /// ex: split⧻ 0 [1 2 0 0 3 4 5]
+    ///
+    /// The delimiter can be a row of a higher-rank array.
+    /// ex: split□ 0_0 [1_2 0_0 3_4 5_6]
+    ///
+    /// If the function takes 2 arguments, the delimiter is passed as the second argument.
+    /// ex: split(□⊂) @; "a;b;c"
+    ///
+    /// A delimiter with multiple rows only matches that exact sequence of rows.
+    /// ex: split□ ", " "a, b,c, d"
+    ///
+    /// [split][box] is the inverse of [joinsep].
+    (2[1], Split, AggregatingModifier, "split"),
     /// Rearrange the stack
     ///
     /// Deprecated because it was never a good idea.
@@ -1067,6 +1284,13 @@ primitive!(
     /// [invert][sine] gives the arcsine.
     /// ex: ⍘○ 1
     ///
+    /// Functions made of invertible functions can be inverted, including named ones.
+    /// ex: F ← ×2+1
+    ///   : ⍘F F 5
+    ///
+    /// Trying to invert a function with no inverse is an error.
+    /// ex! ⍘⧻ [1 2 3]
+    ///
     /// While more inverses exists, most of them are not useful on their own.
     /// They are usually used within [under].
     ([1], Invert, OtherModifier, ("invert", '⍘')),
@@ -1093,6 +1317,13 @@ primitive!(
     ///
     /// ex: [⊙+ 1 2 3]
     /// ex: [⊙⊙+ 1 2 3 4]
+    /// [dip]s can be chained to reach deeper into the stack.
+    /// ex: [⊙⊙⊙¯ 1 2 3 4 5]
+    /// Values pushed by the function still count toward an array being built.
+    /// ex: [⊙(1 2 3) 0]
+    /// If the function errors inside a [try], the dipped value is discarded along with the rest of the stack the [try]'s function used.
+    /// ex: [⍣(⊙(⍤"x" 0) 1 2)(5;) 3]
+    ///
     /// This is especially useful when used in a [fork].
     /// In a [fork] expression, you can use [dip], [gap], and [identity] to select out values.
     /// For example, if you wanted to add 3 values but keep the all 3 on top of the stack:
@@ -1107,6 +1338,8 @@ primitive!(
     ///
     /// For monadic functions, [both] calls its function on each of the top 2 values on the stack.
     /// ex: ∩⇡ 3 5
+    /// Without [both], the same thing requires [flip]ping the values around.
+    /// ex: ⇡∶⇡∶ 3 5
     ///
     /// One good use of this is when working with [box] data.
     /// You can use [both][unbox] to get 2 [box] values out.
@@ -1127,10 +1360,22 @@ primitive!(
     /// ex: [⊃⊃⊃+-×÷ 5 8]
     /// If the functions take different numbers of arguments, then the number of arguments is the maximum. Functions that take fewer than the maximum will work on the top values.
     /// ex: [⊃+¯ 3 5]
+    ///
+    /// [fork] removes the need for a lot of stack shuffling.
+    /// Calculating the discriminant `b² - 4ac` of a quadratic from `a`, `b`, and `c` with only [flip] and [dip] is hard to follow.
+    /// ex: -××4 ∶⊙(∶×.) 1 2 ¯3
+    /// With [fork], each side of the subtraction gets its own function, and [dip], [gap], and [identity] pick out its arguments.
+    /// ex: -⊃(××4⊙⋅∘)(×.⋅⊙;) 1 2 ¯3
+    /// Together with [dip], this is enough to write the whole quadratic formula.
+    /// ex: Disc ← -⊃(××4⊙⋅∘)(×.⋅⊙;)
+    ///   : Quad ← ⊃÷(÷⊙⋅∘) ×2 ⊙(⊃+(-∶) ¯⊙√) ⊃⊙∘Disc
+    ///   : Quad 1 2 ¯3
     ([2], Fork, Stack, ("fork", '⊃')),
     /// Call two functions on two distinct sets of values
     ///
     /// ex: ⊓⇌⊝ 1_2_3 [1 4 2 4 2]
+    /// Without [bracket], the values have to be [flip]ped before and after the second function.
+    /// ex: ⇌∶⊝∶ 1_2_3 [1 4 2 4 2]
     /// Each function will always be called on its own set of values.
     /// ex: ⊓+× 1 2 3 4
     /// The functions' signatures need not be the same.
@@ -1177,6 +1422,11 @@ primitive!(
     /// ex! ⍜⊏'×10 1_3_3 1_2_3_4_5
     /// [under][keep] works as long as the counts list is boolean.
     /// ex: ⍜▽'×10 =0◿3.⇡10
+    /// [under][transpose] lets you work on the columns of an array.
+    /// ex: ⍜⍉(⊂[0 0]) [1_2 3_4]
+    ///
+    /// Using a function with no inverse is an error.
+    /// ex! ⍜⧻(+1) [1 2 3]
     ///
     /// If `g` takes more than 1 argument, keep in mind that `f` will be called on the stack as it is when the full under expression begins.
     /// This means you may have to flip the arguments to `g`.
@@ -1261,6 +1511,14 @@ primitive!(
     /// [fill][reshape] fills in the shape with the fill element instead of cycling the data.
     /// ex:   ↯3_5 ⇡9
     /// ex: ⬚0↯3_5 ⇡9
+    ///
+    /// An inner [fill] overrides an outer one only for the same type of value.
+    /// Number and character fills can be nested to set both at once.
+    /// ex: ⬚0(⬚5↙4) [1 2]
+    /// ex: ⬚0(⬚@-{⊟ 1 2_3 ↙4 "ab"})
+    ///
+    /// The fill value only applies while the function runs.
+    /// ex! ⊟ ⬚0↙3 [1] [1 2 3 4]
     ([2], Fill, OtherModifier, ("fill", '⬚')),
     /// Compose two functions
     ///
@@ -1298,7 +1556,7 @@ primitive!(
     /// [if] can be chained to check more than one condition.
     /// Make sure to use [pop] or [gap] to git rid of excess conditions if the number of branches is not a [power] of `2`.
     /// ex: f ← ??+×⋅-
-    ///   : f ← ?(?+×)(-;) # Equivalent
+    ///   : g ← ?(?+×)(-;) # Equivalent to f
     ///   : xs ← (3 5)
     ///   : f 1 1 xs
     ///   : f 1 0 xs
@@ -1313,6 +1571,7 @@ primitive!(
     /// Call a function and catch errors
     ///
     /// If the first function errors, the second function is called with the original arguments and the error value below.
+    /// Anything the first function did to the stack before erroring is discarded.
     ///
     /// Normal runtime errors become strings.
     /// ex: ⍣(+1 2)$"Error: _"
@@ -1328,22 +1587,39 @@ primitive!(
     /// [gap] can often look nicer.
     /// ex: ⍣parse⋅⋅0 "dog"
     /// ex: ⍣parse⋅⋅0 "5"
+    ///
+    /// Errors in the second function are not caught, but an outer [try] can catch them.
+    /// ex: ⍣(⍣(⍤"inner" 0 1)(1 ⍤"handler" 0;))∘
     ([2], Try, OtherModifier, ("try", '⍣')),
     /// Throw an error if a condition is not met
     ///
     /// Expects a message and a test value.
     /// If the test value is anything but `1`, then the message will be thrown as an error.
     ///
-    /// ex! ⍤"Oh no!" "any array"
     /// ex: ⍤"Oh no!" 1
     /// ex! ⍤"Oh no!" 0
+    /// ex! ⍤"Oh no!" 5
+    ///
+    /// The test value must be a scalar. Other arrays are an error, even if all of their elements are `1`.
+    /// ex! ⍤"Oh no!" [1 1]
     ///
     /// Use [duplicate] if you do not care about the message.
     /// ex: ⍤. =6 6
     /// ex! ⍤. =8 9
     ///
     /// Errors thrown by [assert] can be caught with [try].
+    /// To throw an error unconditionally, use [throw].
     (2(0), Assert, Control, ("assert", '⍤')),
+    /// Throw an error
+    ///
+    /// The value is used as the error message. Strings are shown as-is, and other values are shown as they would be on the stack.
+    /// ex! throw "Matrix is not square"
+    /// ex! throw [1 2 3]
+    ///
+    /// Errors thrown by [throw] can be caught with [try], which receives the thrown value.
+    /// ex: ⍣(throw "Oh no!")∘
+    /// ex: ⍣(throw 5)(×2)
+    (1(0), Throw, Control, "throw"),
     /// Spawn a thread
     ///
     /// Expects a function.
@@ -1423,10 +1699,27 @@ primitive!(
     /// ex: parse "17"
     /// ex: parse "3.1415926535897932"
     /// ex! parse "dog"
+    ///
+    /// Numbers are parsed the way they would be written in code. Either `¯` or `-` may be used for negative numbers.
+    /// ex: parse "¯5"
+    /// ex: parse "-1.5e¯3"
+    /// ex: parse "∞"
+    ///
+    /// A rank 2 character array is parsed row by row into a list of numbers. Whitespace and [fill] padding are ignored.
+    /// ex: parse ["1  " " 22" "333"]
+    /// ex: parse ⬚@ ["1" "22" "333"]
+    /// ex: ⬚@_parse ⬚@_["1" "22" "333"]
+    ///
+    /// [invert][parse] formats numbers as strings the same way they are shown.
+    /// ex: ⍘parse 3.5
+    /// ex: ⍘parse [1 ¯22 π]
     (1, Parse, Misc, "parse"),
+    /// Inverse of Parse
+    (1, InvParse, Misc),
     /// Generate a random number between 0 and 1
     ///
     /// If you need a seeded random number, use [gen].
+    /// To make [random] itself reproducible, pass `--seed` to `uiua run`.
     ///
     /// ex: ⚂
     /// ex: [⚂⚂⚂]
@@ -1453,6 +1746,8 @@ primitive!(
     /// If you don't care about a seed, just seed with [random].
     /// ex: deal⚂ [1 2 3 4 5]
     /// ex: deal⚂ [1_2 3_4 5_6 7_8]
+    /// [take]ing from a [deal]t [range] gives distinct random indices.
+    /// ex: ↙3 deal⚂ ⇡10
     (2, Deal, Misc, "deal"),
     /// Extract a named function from a module
     ///
@@ -1474,11 +1769,17 @@ primitive!(
     /// `0` indicates a number array.
     /// `1` indicates a character array.
     /// `2` indicates a function array.
+    /// The shape of the array does not matter, so empty arrays have a type too.
     /// ex: type 5
     /// ex: type "hello"
     /// ex: type (+)
     /// ex: ∵type  {10 "dog" (≅⇌.)}
     ///   : ∵(|1 type!) {10 "dog" (≅⇌.)}
+    ///
+    /// Combined with [pick] and [call], [type] can be used to do something different for each type.
+    /// ex: F ← !⊡∶[(+1) (⊂∶"!")] type .
+    ///   : F 5
+    ///   : F "hi"
     (1, Type, Misc, "type"),
     /// Get the stack signature of a value
     ///
@@ -1494,9 +1795,12 @@ primitive!(
     (1, Sig, Misc, "sig"),
     /// Get the current time in seconds
     ///
+    /// The time is the number of seconds since the Unix epoch.
     /// ex: now
     /// [under][now] can be used to time a function.
     /// ex: ⍜now(5&sl1)
+    /// The time can be formatted with [&tf].
+    /// ex: &tf "%H:%M:%S" now
     (0, Now, Misc, "now"),
     /// The number of radians in a quarter circle
     ///
@@ -7,7 +7,6 @@ pub use defs::*;
 
 use std::{
     borrow::Cow,
-    cell::RefCell,
     f64::{
         consts::{PI, TAU},
         INFINITY,
@@ -24,7 +23,7 @@ use once_cell::sync::Lazy;
 use rand::prelude::*;
 
 use crate::{
-    algorithm::{fork, loops},
+    algorithm::{fork, loops, pervade},
     array::Array,
     cowslice::cowslice,
     function::Function,
@@ -118,6 +117,7 @@ impl fmt::Display for Primitive {
                 InverseBits => write!(f, "⍘{Bits}"),
                 InvTrace => write!(f, "⍘{Trace}"),
                 InvWhere => write!(f, "⍘{Where}"),
+                InvParse => write!(f, "⍘{Parse}"),
                 Uncouple => write!(f, "⍘{Couple}"),
                 Untake => write!(f, "⍘{Take}"),
                 Undrop => write!(f, "⍘{Drop}"),
@@ -127,6 +127,7 @@ impl fmt::Display for Primitive {
                 Asin => write!(f, "{Invert}{Sin}"),
                 Acos => write!(f, "{Invert}{Cos}"),
                 Last => write!(f, "{First}{Reverse}"),
+                ReverseScan => write!(f, "{Under}{Reverse}{Scan}"),
                 _ => write!(f, "{self:?}"),
             }
         }
@@ -199,6 +200,8 @@ impl Primitive {
             Unbox => Box,
             Where => InvWhere,
             InvWhere => Where,
+            Parse => InvParse,
+            InvParse => Parse,
             _ => return None,
         })
     }
@@ -283,6 +286,7 @@ impl Primitive {
             Primitive::Neg => env.monadic_env(Value::neg)?,
             Primitive::Abs => env.monadic_env(Value::abs)?,
             Primitive::Sign => env.monadic_env(Value::sign)?,
+            Primitive::Sqrt if env.strict_math => env.monadic_env(pervade::strict_sqrt)?,
             Primitive::Sqrt => env.monadic_env(Value::sqrt)?,
             Primitive::Sin => env.monadic_env(Value::sin)?,
             Primitive::Cos => env.monadic_env(Value::cos)?,
@@ -291,6 +295,9 @@ impl Primitive {
             Primitive::Floor => env.monadic_env(Value::floor)?,
             Primitive::Ceil => env.monadic_env(Value::ceil)?,
             Primitive::Round => env.monadic_env(Value::round)?,
+            Primitive::Upper => env.monadic_env(Value::upper)?,
+            Primitive::Lower => env.monadic_env(Value::lower)?,
+            Primitive::CharClass => env.monadic_env(Value::char_class)?,
             Primitive::Eq => env.dyadic_oo_env(Value::is_eq)?,
             Primitive::Ne => env.dyadic_oo_env(Value::is_ne)?,
             Primitive::Lt => env.dyadic_oo_env(Value::is_lt)?,
@@ -300,15 +307,21 @@ impl Primitive {
             Primitive::Add => env.dyadic_oo_env(Value::add)?,
             Primitive::Sub => env.dyadic_oo_env(Value::sub)?,
             Primitive::Mul => env.dyadic_oo_env(Value::mul)?,
+            Primitive::Div if env.strict_math => env.dyadic_oo_env(pervade::strict_div)?,
             Primitive::Div => env.dyadic_oo_env(Value::div)?,
             Primitive::Mod => env.dyadic_oo_env(Value::modulus)?,
+            Primitive::Pow if env.strict_math => env.dyadic_oo_env(pervade::strict_pow)?,
             Primitive::Pow => env.dyadic_oo_env(Value::pow)?,
+            Primitive::Log if env.strict_math => env.dyadic_oo_env(pervade::strict_log)?,
             Primitive::Log => env.dyadic_oo_env(Value::log)?,
             Primitive::Min => env.dyadic_oo_env(Value::min)?,
             Primitive::Max => env.dyadic_oo_env(Value::max)?,
             Primitive::Atan => env.dyadic_oo_env(Value::atan2)?,
+            Primitive::RoundTo => env.dyadic_oo_env(Value::round_to)?,
             Primitive::Match => env.dyadic_rr(|a, b| a == b)?,
             Primitive::Join => env.dyadic_oo_env(Value::join)?,
+            Primitive::JoinSep => env.dyadic_oo_env(Value::join_sep)?,
+            Primitive::MatMul => env.dyadic_rr_env(Value::matrix_product)?,
             Primitive::Transpose => env.monadic_mut(Value::transpose)?,
             Primitive::InvTranspose => env.monadic_mut(Value::inv_transpose)?,
             Primitive::Keep => env.dyadic_ro_env(Value::keep)?,
@@ -340,8 +353,39 @@ impl Primitive {
                 env.push(b);
                 env.push(a);
             }
+            Primitive::Unpack => {
+                let arr = env.pop(1)?;
+                if arr.rank() == 0 {
+                    return Err(env.error("Cannot unpack a scalar"));
+                }
+                for row in arr.into_rows_rev() {
+                    env.push(row);
+                }
+            }
+            Primitive::Destructure => {
+                let count = env
+                    .pop(1)?
+                    .as_nat(env, "Destructure count must be a natural number")?;
+                let arr = env.pop(2)?;
+                if arr.rank() == 0 {
+                    return Err(env.error("Cannot destructure a scalar"));
+                }
+                if arr.row_count() != count {
+                    return Err(env.error(format!(
+                        "Cannot destructure array with {} row{} into {count} value{}",
+                        arr.row_count(),
+                        if arr.row_count() == 1 { "" } else { "s" },
+                        if count == 1 { "" } else { "s" }
+                    )));
+                }
+                for row in arr.into_rows_rev() {
+                    env.push(row);
+                }
+            }
             Primitive::Rise => env.monadic_ref_env(|v, env| v.rise(env))?,
             Primitive::Fall => env.monadic_ref_env(|v, env| v.fall(env))?,
+            Primitive::FirstMinIndex => env.monadic_ref_env(Value::first_min_index)?,
+            Primitive::FirstMaxIndex => env.monadic_ref_env(Value::first_max_index)?,
             Primitive::Pick => env.dyadic_oo_env(Value::pick)?,
             Primitive::Unpick => {
                 let from = env.pop(1)?;
@@ -361,6 +405,7 @@ impl Primitive {
             Primitive::InvWhere => env.monadic_ref_env(Value::inverse_where)?,
             Primitive::Classify => env.monadic_ref_env(Value::classify)?,
             Primitive::Deduplicate => env.monadic_mut(Value::deduplicate)?,
+            Primitive::Unique => env.monadic_ref_env(Value::unique)?,
             Primitive::Member => env.dyadic_rr_env(Value::member)?,
             Primitive::Find => env.dyadic_rr_env(Value::find)?,
             Primitive::IndexOf => env.dyadic_rr_env(Value::index_of)?,
@@ -381,6 +426,7 @@ impl Primitive {
                 env.call(f)?
             }
             Primitive::Parse => env.monadic_env(|v, env| v.parse_num(env))?,
+            Primitive::InvParse => env.monadic_ref_env(Value::unparse_num)?,
             Primitive::Range => env.monadic_ref_env(Value::range)?,
             Primitive::Reverse => env.monadic_mut(Value::reverse)?,
             Primitive::Deshape => env.monadic_mut(Value::deshape)?,
@@ -410,10 +456,12 @@ impl Primitive {
             Primitive::Table => loops::table(env)?,
             Primitive::Cross => loops::cross(env)?,
             Primitive::Scan => loops::scan(env)?,
+            Primitive::ReverseScan => loops::reverse_scan(env)?,
             Primitive::Repeat => loops::repeat(env)?,
             Primitive::Level => loops::level(env)?,
             Primitive::Group => loops::group(env)?,
             Primitive::Partition => loops::partition(env)?,
+            Primitive::Split => loops::split(env)?,
             Primitive::Reshape => {
                 let shape = env.pop(1)?;
                 let mut array = env.pop(2)?;
@@ -515,10 +563,15 @@ impl Primitive {
                 let handler = env.pop(FunctionArg(2))?;
                 let f_args = f.signature().args;
                 let backup = env.clone_stack_top(f_args);
-                let bottom = env.stack_size().saturating_sub(f_args);
+                let heights = env.stack_heights(f_args);
                 if let Err(e) = env.call(f) {
-                    env.truncate_stack(bottom);
-                    env.backend.save_error_color(&e);
+                    if e.is_interrupted() || e.is_timeout() {
+                        return Err(e);
+                    }
+                    env.truncate_stacks(heights);
+                    if env.color {
+                        env.backend.save_error_color(&e);
+                    }
                     env.push(e.value());
                     for val in backup {
                         env.push(val);
@@ -527,17 +580,26 @@ impl Primitive {
                 }
             }
             Primitive::Assert => {
+                (env.assertions).fetch_add(1, atomic::Ordering::Relaxed);
                 let msg = env.pop(1)?;
                 let cond = env.pop(2)?;
+                if cond.rank() != 0 {
+                    return Err(env.error(format!(
+                        "Assertion condition must be a scalar, but its shape is {}",
+                        cond.format_shape()
+                    )));
+                }
                 if !cond.as_nat(env, "").is_ok_and(|n| n == 1) {
                     return Err(UiuaError::Throw(msg.into(), env.span().clone()));
                 }
             }
+            Primitive::Throw => {
+                let msg = env.pop(1)?;
+                return Err(UiuaError::Throw(msg.into(), env.span().clone()));
+            }
             Primitive::Rand => {
-                thread_local! {
-                    static RNG: RefCell<SmallRng> = RefCell::new(SmallRng::seed_from_u64(instant::now().to_bits()));
-                }
-                env.push(RNG.with(|rng| rng.borrow_mut().gen::<f64>()));
+                let val: f64 = env.rng.gen();
+                env.push(val);
             }
             Primitive::Gen => {
                 let seed = env.pop(1)?;
@@ -592,7 +654,7 @@ impl Primitive {
                 let handle = env.pop(1)?;
                 env.wait(handle)?;
             }
-            Primitive::Now => env.push(instant::now() / 1000.0),
+            Primitive::Now => env.push(env.backend.now()),
             Primitive::Trace => trace(env, false)?,
             Primitive::InvTrace => trace(env, true)?,
             Primitive::Dump => dump(env)?,
@@ -807,9 +869,12 @@ impl PrimExample {
         self.should_error
     }
     pub fn should_run(&self) -> bool {
-        !["&sl", "&tcpc"]
+        !["&sl", "&tcpc", "&httpg", "&httpr"]
             .iter()
             .any(|prim| self.input.contains(prim))
+            && (SysOp::ALL.iter())
+                .filter(|op| !op.is_supported())
+                .all(|op| !self.input.contains(op.name()))
     }
     pub fn output(&self) -> &Result<Vec<String>, String> {
         self.output.get_or_init(|| {
@@ -73,16 +73,50 @@ u ← ↥<0.2∶>0.7.+×2 ×.∶⍘⊟xy
 c ← <∶√/+ⁿ2 xy
 ⍉⊂∶-¬u c1 +0.1 ∺c0.95↧Rgb",
         ),
+        ("MATMUL", "matmul . ÷100 ↯100_100 ⇡10000"),
+        ("MATMUL_NAIVE", "⊠(/+×)⊙⍉ . ÷100 ↯100_100 ⇡10000"),
+        ("REDUCE", "[⊃⊃/+/↥/×] ÷1e5 ⇡1e5"),
+        ("REDUCE_NAIVE", "[⊃⊃/(∘+)/(∘↥)/(∘×)] ÷1e5 ⇡1e5"),
+        ("REDUCE_ROWS", "[⊃/+/↧] ↯1000_1000 ÷1e6 ⇡1e6"),
+        ("DUP", "△[×2.×2.×2.×2.⇡1e6]"),
+        ("PERVADE_RANKS", "△-⇡1000 ↯1000_1000 ÷1e6 ⇡1e6"),
     ];
 
     const RUNS: usize = 20;
 
+    /// Generate a program with many bindings that reference each other and repeat string literals
+    fn bindings_benchmark(count: usize) -> String {
+        let name = |mut i: usize| {
+            let mut name = String::new();
+            loop {
+                name.insert(0, (b'a' + (i % 26) as u8) as char);
+                i /= 26;
+                if i == 0 {
+                    break;
+                }
+            }
+            format!("F{name}")
+        };
+        let mut code = String::new();
+        for i in 0..count {
+            if i % 2 == 0 {
+                code.push_str(&format!("{} ← ⊂\"hello\" ⊂\"world\"\n", name(i)));
+            } else {
+                let prev = name(i - 1);
+                code.push_str(&format!("{} ← ⊂⊃{prev}(⍜⇌{prev})\n", name(i)));
+            }
+        }
+        code
+    }
+
     pub fn run_profile() {
         if cfg!(debug_assertions) {
             eprintln!("Profiling must be done in release mode");
             return;
         }
 
+        let bindings = bindings_benchmark(5000);
+
         const WARMUP_RUNS: usize = 3;
         for i in 0..WARMUP_RUNS {
             print!("\rProfiling... warmup {}/{}", i + 1, WARMUP_RUNS);
@@ -90,6 +124,7 @@ c ← <∶√/+ⁿ2 xy
             for (_, bench) in BENCHMARKS {
                 Uiua::with_native_sys().load_str(bench).unwrap();
             }
+            Uiua::with_native_sys().load_str(&bindings).unwrap();
         }
 
         init_profiler();
@@ -102,6 +137,8 @@ c ← <∶√/+ⁿ2 xy
                 profile_scope!(name);
                 Uiua::with_native_sys().load_str(bench).unwrap();
             }
+            profile_scope!("BINDINGS");
+            Uiua::with_native_sys().load_str(&bindings).unwrap();
         }
 
         println!("\rProfiling complete         ");
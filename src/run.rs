@@ -1,28 +1,49 @@
 use std::{
-    collections::{BTreeSet, HashMap, HashSet},
+    collections::{hash_map::DefaultHasher, BTreeSet, HashMap, HashSet},
     fs,
-    hash::Hash,
+    hash::{Hash, Hasher},
     mem::take,
     panic::{catch_unwind, AssertUnwindSafe},
     path::{Path, PathBuf},
     str::FromStr,
-    sync::Arc,
+    sync::{
+        atomic::{self, AtomicBool, AtomicUsize},
+        Arc,
+    },
 };
 
 use instant::Duration;
 use parking_lot::Mutex;
+use rand::prelude::*;
 
 use crate::{
     array::Array,
     function::*,
-    lex::Span,
+    lex::{lex, CodeSpan, Span, Token},
     parse::parse,
     primitive::{Primitive, CONSTANTS},
+    sys::OpenHandles,
     value::Value,
     Diagnostic, DiagnosticKind, Handle, Ident, NativeSys, SysBackend, TraceFrame, UiuaError,
     UiuaResult,
 };
 
+/// How many instructions are executed between checks of the interrupt flag and the clock
+const CHECK_INTERVAL: usize = 4096;
+
+/// The default maximum number of nested function calls
+const DEFAULT_RECURSION_LIMIT: usize = 1000;
+
+/// The native stack size that a thread needs to reach the default recursion limit
+///
+/// Each call takes much more of the native stack in debug builds.
+/// The threads that run loops in parallel are given this much stack.
+pub const STACK_SIZE: usize = if cfg!(debug_assertions) {
+    256 << 20
+} else {
+    16 << 20
+};
+
 /// The Uiua runtime
 #[derive(Clone)]
 pub struct Uiua {
@@ -32,6 +53,8 @@ pub struct Uiua {
     pub(crate) globals: Arc<Mutex<Vec<Value>>>,
     /// Indexable spans
     pub(crate) spans: Arc<Mutex<Vec<Span>>>,
+    /// String literals that have already been compiled, so that identical ones share a buffer
+    pub(crate) string_literals: Arc<Mutex<HashMap<String, Value>>>,
     /// The thread's stack
     pub(crate) stack: Vec<Value>,
     /// The thread's temp stack for inlining
@@ -44,18 +67,55 @@ pub struct Uiua {
     pub(crate) higher_scopes: Vec<Scope>,
     /// Determines which How test scopes are run
     pub(crate) mode: RunMode,
+    /// The results of test scopes run in [`RunMode::Test`] or [`RunMode::All`]
+    pub(crate) test_results: TestResults,
     /// A limit on the execution duration in milliseconds
     execution_limit: Option<f64>,
     /// The time at which execution started
     execution_start: f64,
+    /// A limit on the number of instructions executed
+    instruction_limit: Option<usize>,
+    /// The number of instructions executed, shared with the threads that run loops in parallel
+    instructions_executed: Arc<AtomicUsize>,
+    /// The maximum number of nested function calls
+    recursion_limit: usize,
+    /// The current number of nested function calls
+    call_depth: usize,
+    /// Set to interrupt execution
+    interrupted: Arc<AtomicBool>,
+    /// The number of assertions checked, so that test lines can tell if they asserted anything
+    pub(crate) assertions: Arc<AtomicUsize>,
+    /// Whether math functions error instead of returning `NaN`
+    pub(crate) strict_math: bool,
+    /// Whether primitives applied to constants are evaluated at compile time
+    pub(crate) constant_folding: bool,
+    /// How many [`Primitive::Try`] operands are being compiled, where failing constants are expected
+    pub(crate) try_depth: usize,
+    /// Whether code is compiled without being run
+    pub(crate) compile_only: bool,
+    /// Whether warnings are reported
+    pub(crate) warnings: bool,
+    /// Whether warnings stop compilation with [`UiuaError::Warnings`]
+    pub(crate) deny_warnings: bool,
+    /// The thread pool used to run [`Primitive::Rows`] and [`Primitive::Each`] in parallel
+    #[cfg(feature = "parallel")]
+    pub(crate) thread_pool: Option<Arc<rayon::ThreadPool>>,
+    /// The number of instructions executed since the interrupt flag and the clock were last checked
+    instrs_since_check: usize,
+    /// The random number generator used by [`Primitive::Rand`]
+    pub(crate) rng: SmallRng,
     /// The paths of files currently being imported (used to detect import cycles)
     current_imports: Arc<Mutex<HashSet<PathBuf>>>,
+    /// The number of nested imports being run, whose unused bindings are not reported
+    pub(crate) import_depth: usize,
     /// The stacks of imported files
     imports: Arc<Mutex<HashMap<PathBuf, Vec<Value>>>>,
     /// Accumulated diagnostics
     pub(crate) diagnostics: BTreeSet<Diagnostic>,
     /// Print diagnostics as they are encountered
     pub(crate) print_diagnostics: bool,
+    /// Whether printed diagnostics and errors are colored
+    pub(crate) color: bool,
     /// Whether to print the time taken to execute each instruction
     time_instrs: bool,
     /// The time at which the last instruction was executed
@@ -66,6 +126,8 @@ pub struct Uiua {
     cli_file_path: PathBuf,
     /// The system backend
     pub(crate) backend: Arc<dyn SysBackend>,
+    /// Handles opened by the program, which are closed when the runtime is dropped
+    pub(crate) open_handles: Arc<OpenHandles>,
 }
 
 #[derive(Clone)]
@@ -78,6 +140,10 @@ pub struct Scope {
     pub names: HashMap<Ident, usize>,
     /// Whether this scope is local
     pub local: bool,
+    /// Where each name was bound in this scope
+    pub(crate) binding_spans: HashMap<Ident, CodeSpan>,
+    /// Names bound in this scope that have not been referenced yet
+    pub(crate) unused: HashSet<Ident>,
     /// The current fill values
     fills: Fills,
 }
@@ -95,9 +161,12 @@ impl Default for Scope {
                 call_span: 0,
                 pc: 0,
                 spans: Vec::new(),
+                row: None,
             }],
             names: HashMap::new(),
             local: false,
+            binding_spans: HashMap::new(),
+            unused: HashSet::new(),
             fills: Fills::default(),
         }
     }
@@ -110,6 +179,15 @@ struct Fills {
     functions: Vec<Arc<Function>>,
 }
 
+/// The heights of the runtime's stacks
+#[derive(Clone, Copy)]
+pub(crate) struct StackHeights {
+    stack: usize,
+    array: usize,
+    inline: usize,
+    under: usize,
+}
+
 #[derive(Clone)]
 struct StackFrame {
     /// The function being executed
@@ -120,6 +198,8 @@ struct StackFrame {
     pc: usize,
     /// Additional spans for error reporting
     spans: Vec<(usize, Option<Primitive>)>,
+    /// The row being processed by a loop primitive in this function, for error reporting
+    row: Option<usize>,
 }
 
 impl Default for Uiua {
@@ -142,6 +222,17 @@ pub enum RunMode {
     All,
 }
 
+/// The results of running test scopes in [`RunMode::Test`] or [`RunMode::All`]
+#[derive(Debug, Clone, Default)]
+pub struct TestResults {
+    /// The values left on the stack by test scopes
+    pub values: Vec<Value>,
+    /// The number of test lines that checked an assertion and ran without error
+    pub passed: usize,
+    /// The errors from test lines that failed
+    pub failures: Vec<UiuaError>,
+}
+
 impl FromStr for RunMode {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -172,18 +263,39 @@ impl Uiua {
             higher_scopes: Vec::new(),
             globals: Arc::new(Mutex::new(globals)),
             new_functions: Vec::new(),
+            string_literals: Arc::new(Mutex::new(HashMap::new())),
             current_imports: Arc::new(Mutex::new(HashSet::new())),
+            import_depth: 0,
             imports: Arc::new(Mutex::new(HashMap::new())),
             mode: RunMode::Normal,
+            test_results: TestResults::default(),
             diagnostics: BTreeSet::new(),
             backend: Arc::new(NativeSys),
+            open_handles: Arc::default(),
             print_diagnostics: false,
+            color: true,
             time_instrs: false,
             last_time: 0.0,
             cli_arguments: Vec::new(),
             cli_file_path: PathBuf::new(),
             execution_limit: None,
             execution_start: 0.0,
+            instruction_limit: None,
+            instructions_executed: Arc::new(AtomicUsize::new(0)),
+            recursion_limit: DEFAULT_RECURSION_LIMIT,
+            call_depth: 0,
+            interrupted: Arc::new(AtomicBool::new(false)),
+            assertions: Arc::new(AtomicUsize::new(0)),
+            strict_math: false,
+            constant_folding: true,
+            try_depth: 0,
+            compile_only: false,
+            warnings: true,
+            deny_warnings: false,
+            #[cfg(feature = "parallel")]
+            thread_pool: None,
+            instrs_since_check: 0,
+            rng: SmallRng::seed_from_u64(instant::now().to_bits()),
         }
     }
     /// Create a new Uiua runtime with a custom IO backend
@@ -203,15 +315,120 @@ impl Uiua {
         self.print_diagnostics = print_diagnostics;
         self
     }
+    /// Set whether printed diagnostics and errors written to stdout or stderr are colored
+    ///
+    /// This is on by default.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
     pub fn time_instrs(mut self, time_instrs: bool) -> Self {
         self.time_instrs = time_instrs;
         self
     }
     /// Limit the execution duration
+    ///
+    /// Exceeding the limit causes execution to stop with [`UiuaError::Timeout`].
+    /// The clock is only sampled every few thousand instructions, so the limit may be overshot slightly.
     pub fn with_execution_limit(mut self, limit: Duration) -> Self {
         self.execution_limit = Some(limit.as_millis() as f64);
         self
     }
+    /// Limit the number of instructions executed per load
+    ///
+    /// Exceeding the limit causes execution to stop with [`UiuaError::Timeout`].
+    /// Unlike [`Uiua::with_execution_limit`], this is deterministic.
+    pub fn with_instruction_limit(mut self, limit: usize) -> Self {
+        self.instruction_limit = Some(limit);
+        self
+    }
+    /// Make math functions error on inputs outside their domain
+    ///
+    /// In strict math mode, [`Primitive::Sqrt`], [`Primitive::Div`], [`Primitive::Pow`], and [`Primitive::Log`]
+    /// raise an error instead of returning `NaN` when none of their inputs are `NaN`.
+    pub fn with_strict_math(mut self, strict_math: bool) -> Self {
+        self.strict_math = strict_math;
+        self
+    }
+    /// Set whether primitives applied to constants are evaluated at compile time
+    ///
+    /// This is on by default. Turning it off can help when debugging the compiler.
+    pub fn with_constant_folding(mut self, constant_folding: bool) -> Self {
+        self.constant_folding = constant_folding;
+        self
+    }
+    /// Set whether code is only compiled and not run
+    ///
+    /// Lines that import files, and the imported files themselves, are still run,
+    /// because what they bind is only known by running them.
+    /// Bindings whose values are only known by running them are bound to an empty array.
+    ///
+    /// This is off by default.
+    pub fn with_compile_only(mut self, compile_only: bool) -> Self {
+        self.compile_only = compile_only;
+        self
+    }
+    /// Set whether warnings are reported
+    ///
+    /// This is on by default. Warnings are diagnostics about code that is probably a mistake,
+    /// like a line that has no effect.
+    pub fn with_warnings(mut self, warnings: bool) -> Self {
+        self.warnings = warnings;
+        self
+    }
+    /// Set whether warnings are treated as errors
+    ///
+    /// When on, the first code that produces a warning stops compilation with
+    /// [`UiuaError::Warnings`], which contains all the warnings produced so far.
+    pub fn with_deny_warnings(mut self, deny_warnings: bool) -> Self {
+        self.deny_warnings = deny_warnings;
+        self
+    }
+    /// Set the number of threads used to run [`Primitive::Rows`] and [`Primitive::Each`]
+    ///
+    /// With more than one thread, large arrays are split between the threads
+    /// if the applied function does no IO. Results are always assembled in order.
+    #[cfg(feature = "parallel")]
+    pub fn with_thread_count(mut self, count: usize) -> Self {
+        self.thread_pool = (count > 1)
+            .then(|| {
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(count)
+                    .stack_size(STACK_SIZE)
+                    .build()
+                    .ok()
+            })
+            .flatten()
+            .map(Arc::new);
+        self
+    }
+    /// Get a handle that can be used to interrupt execution
+    ///
+    /// Setting the flag to `true` causes the running program to stop with [`UiuaError::Interrupted`].
+    /// The flag is reset whenever new code is loaded.
+    pub fn interrupt_handle(&self) -> Arc<AtomicBool> {
+        self.interrupted.clone()
+    }
+    pub(crate) fn is_interrupted(&self) -> bool {
+        self.interrupted.load(atomic::Ordering::Relaxed)
+    }
+    /// Limit the number of nested function calls
+    ///
+    /// Exceeding the limit causes an error instead of overflowing the native stack,
+    /// as long as the thread running the code has [`STACK_SIZE`] of stack.
+    /// Default is 1000.
+    pub fn with_recursion_limit(mut self, limit: usize) -> Self {
+        self.recursion_limit = limit;
+        self
+    }
+    /// Seed the random number generator
+    ///
+    /// This makes [`Primitive::Rand`] produce the same sequence of numbers every time a program is run.
+    /// By default, the generator is seeded from the current time.
+    pub fn with_rng_seed(mut self, seed: u64) -> Self {
+        self.rng = SmallRng::seed_from_u64(seed);
+        self
+    }
     /// Set the [`RunMode`]
     ///
     /// Default is [`RunMode::Normal`]
@@ -223,6 +440,13 @@ impl Uiua {
     pub fn mode(&self) -> RunMode {
         self.mode
     }
+    /// Take the results of test scopes
+    ///
+    /// Test results are only collected in [`RunMode::Test`] and [`RunMode::All`].
+    /// In [`RunMode::Normal`], test scopes are not run.
+    pub fn take_test_results(&mut self) -> TestResults {
+        take(&mut self.test_results)
+    }
     /// Set the command line arguments
     pub fn with_args(mut self, args: Vec<String>) -> Self {
         self.cli_arguments = args;
@@ -270,16 +494,164 @@ impl Uiua {
         self.scope.local = local;
         let start_height = self.stack.len();
         f(self)?;
+        self.warn_unused_bindings()?;
         let end_height = self.stack.len();
         self.scope = self.higher_scopes.pop().unwrap();
         Ok(self.stack.split_off(start_height.min(end_height)))
     }
-    fn load_impl(&mut self, input: &str, path: Option<&Path>) -> UiuaResult {
+    /// Call a function bound in the current scope with some arguments
+    ///
+    /// The name is matched case-insensitively if there is no exact match.
+    /// The arguments are pushed in order, so the last one is on top of the stack when the function is called.
+    ///
+    /// Returns the values the function left on the stack above the height the stack had before the call.
+    pub fn call_binding(
+        &mut self,
+        name: &str,
+        args: impl IntoIterator<Item = Value>,
+    ) -> UiuaResult<Vec<Value>> {
+        let idx = if let Some(idx) = self.scope.names.get(name) {
+            *idx
+        } else {
+            let mut matches = (self.scope.names.iter())
+                .filter(|(ident, _)| ident.to_lowercase() == name.to_lowercase());
+            match (matches.next(), matches.next()) {
+                (Some((_, idx)), None) => *idx,
+                (Some((a, _)), Some((b, _))) => {
+                    return Err(Span::Builtin.error(format!(
+                        "`{name}` is ambiguous. It could refer to `{a}` or `{b}`."
+                    )))
+                }
+                (None, _) => {
+                    return Err(Span::Builtin.error(format!("No binding named `{name}` exists")))
+                }
+            }
+        };
+        let value = self.globals.lock()[idx].clone();
+        let f = match value {
+            Value::Func(fs) => fs.into_scalar().map_err(|fs| {
+                Span::Builtin.error(format!(
+                    "`{name}` is not a function, it is an array of functions with shape {}",
+                    fs.format_shape()
+                ))
+            })?,
+            value => {
+                return Err(Span::Builtin.error(format!(
+                    "`{name}` is not a function, it is a {} array",
+                    value.type_name()
+                )))
+            }
+        };
+        self.start_execution();
+        let height = self.stack.len();
+        self.stack.extend(args);
+        self.call_function(f)?;
+        Ok(self.stack.split_off(height.min(self.stack.len())))
+    }
+    /// Bind a name to a function implemented in Rust
+    ///
+    /// The function should pop its arguments from the stack with [`Uiua::pop`]
+    /// and push its outputs with [`Uiua::push`].
+    /// Errors created with [`Uiua::error`] are reported at the span of the call.
+    /// Calling the function is an error if it does not take and produce the declared
+    /// number of values.
+    ///
+    /// The name must be parsed as a single identifier, so that the formatter does not
+    /// turn it into primitives. Binding a name that is already bound replaces it.
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::HashMap;
+    ///
+    /// use uiua::Uiua;
+    ///
+    /// let urls = HashMap::from([("uiua.org", "Uiua is a stack-based array programming language")]);
+    /// let mut env = Uiua::with_native_sys();
+    /// env.bind_fn("HttpGet", 1, 1, move |env| {
+    ///     let url = env.pop("url")?.try_string()?;
+    ///     let body = urls.get(url.as_str()).ok_or_else(|| env.error(format!("404: {url}")))?;
+    ///     env.push(*body);
+    ///     Ok(())
+    /// })
+    /// .unwrap();
+    /// env.load_str("HttpGet \"uiua.org\"").unwrap();
+    /// let body = env.pop("body").unwrap().try_string().unwrap();
+    /// assert_eq!(body, "Uiua is a stack-based array programming language");
+    /// ```
+    pub fn bind_fn(
+        &mut self,
+        name: impl Into<Ident>,
+        args: usize,
+        outputs: usize,
+        f: impl Fn(&mut Uiua) -> UiuaResult + Send + Sync + 'static,
+    ) -> UiuaResult {
+        let name: Ident = name.into();
+        let (tokens, errors) = lex(&name, None);
+        if !errors.is_empty() || tokens.len() != 1 || tokens[0].value != Token::Ident {
+            return Err(Span::Builtin.error(format!(
+                "`{name}` cannot be used as a function name because it is not parsed \
+                as a single identifier"
+            )));
+        }
+        let signature = Signature::new(args, outputs);
+        let id = {
+            let mut hasher = DefaultHasher::new();
+            name.hash(&mut hasher);
+            self.globals.lock().len().hash(&mut hasher);
+            hasher.finish()
+        };
+        let fn_name = name.clone();
+        let dynamic = DynamicFunction {
+            id,
+            f: Arc::new(move |env| {
+                let height = env.stack.len();
+                if height < args {
+                    return Err(env.error(format!(
+                        "`{fn_name}` takes {args} arguments, but the stack only has {height} values"
+                    )));
+                }
+                f(env)?;
+                let expected = height - args + outputs;
+                if env.stack.len() != expected {
+                    return Err(env.error(format!(
+                        "`{fn_name}`'s signature is {signature}, but it changed \
+                        the stack height from {height} to {}",
+                        env.stack.len()
+                    )));
+                }
+                Ok(())
+            }),
+            signature,
+        };
+        let func = Function::new(
+            FunctionId::Named(name.clone()),
+            vec![Instr::Dynamic(dynamic)],
+            signature,
+        );
+        let mut globals = self.globals.lock();
+        let idx = globals.len();
+        globals.push(func.into());
+        self.scope.names.insert(name, idx);
+        Ok(())
+    }
+    /// Reset the execution limits and the interrupt flag before running code
+    ///
+    /// Imports count toward the limits of the code that imports them,
+    /// so this does nothing while an import is being loaded.
+    fn start_execution(&mut self) {
+        if self.import_depth > 0 {
+            return;
+        }
         self.execution_start = instant::now();
+        (self.instructions_executed).store(0, atomic::Ordering::Relaxed);
+        self.interrupted.store(false, atomic::Ordering::Relaxed);
+    }
+    fn load_impl(&mut self, input: &str, path: Option<&Path>) -> UiuaResult {
+        self.start_execution();
         let (items, errors, diagnostics) = parse(input, path);
         if self.print_diagnostics {
             for diagnostic in diagnostics {
-                println!("{}", diagnostic.show(true));
+                println!("{}", diagnostic.show(self.color));
             }
         } else {
             self.diagnostics.extend(diagnostics);
@@ -290,10 +662,14 @@ impl Uiua {
         if let Some(path) = path {
             self.current_imports.lock().insert(path.into());
         }
+        let call_depth = self.call_depth;
         let res = match catch_unwind(AssertUnwindSafe(|| self.items(items, false))) {
             Ok(res) => res,
-            Err(_) => Err(self.error(format!(
-                "\
+            Err(_) => {
+                // The panic skipped the decrements of the calls it unwound through
+                self.call_depth = call_depth;
+                Err(self.error(format!(
+                    "\
 The interpreter has crashed!
 Hooray! You found a bug!
 Please report this at http://github.com/uiua-lang/uiua/issues/new
@@ -301,14 +677,16 @@ Please report this at http://github.com/uiua-lang/uiua/issues/new
 code:
 {}
 {}",
-                self.span(),
-                input
-            ))),
+                    self.span(),
+                    input
+                )))
+            }
         };
         if let Some(path) = path {
             self.current_imports.lock().remove(path);
         }
-        res
+        res?;
+        self.warn_unused_bindings()
     }
     fn trace_error(&self, mut error: UiuaError, frame: StackFrame) -> UiuaError {
         let mut frames = Vec::new();
@@ -317,12 +695,14 @@ code:
                 frames.push(TraceFrame {
                     id: FunctionId::Primitive(*prim),
                     span: self.spans.lock()[*span].clone(),
+                    row: None,
                 });
             }
         }
         frames.push(TraceFrame {
             id: frame.function.id.clone(),
             span: self.spans.lock()[frame.call_span].clone(),
+            row: None,
         });
         if let UiuaError::Traced { trace, .. } = &mut error {
             trace.extend(frames);
@@ -342,8 +722,10 @@ code:
             )));
         }
         if !self.imports.lock().contains_key(path) {
-            let import = self.in_scope(false, |env| env.load_str_path(input, path).map(drop))?;
-            self.imports.lock().insert(path.into(), import);
+            self.import_depth += 1;
+            let import = self.in_scope(false, |env| env.load_str_path(input, path).map(drop));
+            self.import_depth -= 1;
+            self.imports.lock().insert(path.into(), import?);
         }
         self.stack.extend(self.imports.lock()[path].iter().cloned());
         Ok(())
@@ -354,10 +736,23 @@ code:
             function: Arc::new(func),
             call_span: 0,
             spans: Vec::new(),
+            row: None,
             pc: 0,
         })
     }
     fn exec(&mut self, frame: StackFrame) -> UiuaResult {
+        if self.call_depth >= self.recursion_limit {
+            return Err(self.error(format!(
+                "Maximum recursion depth of {} exceeded",
+                self.recursion_limit
+            )));
+        }
+        self.call_depth += 1;
+        let res = self.exec_frame(frame);
+        self.call_depth -= 1;
+        res
+    }
+    fn exec_frame(&mut self, frame: StackFrame) -> UiuaResult {
         let ret_height = self.scope.call.len();
         self.scope.call.push(frame);
         let mut formatted_instr = String::new();
@@ -388,8 +783,20 @@ code:
                 &Instr::Prim(prim, span) => {
                     self.push_span(span, Some(prim));
                     let res = prim.run(self);
+                    let row = self.scope.call.last_mut().unwrap().row.take();
                     self.pop_span();
-                    res
+                    match res {
+                        // Errors from functions called by a modifier are traced through it
+                        Err(UiuaError::Traced { error, mut trace }) => {
+                            trace.push(TraceFrame {
+                                id: FunctionId::Primitive(prim),
+                                span: self.spans.lock()[span].clone(),
+                                row,
+                            });
+                            Err(UiuaError::Traced { error, trace })
+                        }
+                        res => res,
+                    }
                 }
                 Instr::Push(val) => {
                     self.stack.push(Value::clone(val));
@@ -523,11 +930,25 @@ code:
             } else {
                 // Go to next instruction
                 self.scope.call.last_mut().unwrap().pc += 1;
-                if let Some(limit) = self.execution_limit {
-                    if instant::now() - self.execution_start > limit {
+                if let Some(limit) = self.instruction_limit {
+                    let executed =
+                        (self.instructions_executed).fetch_add(1, atomic::Ordering::Relaxed);
+                    if executed >= limit {
                         return Err(UiuaError::Timeout(self.span()));
                     }
                 }
+                self.instrs_since_check += 1;
+                if self.instrs_since_check >= CHECK_INTERVAL {
+                    self.instrs_since_check = 0;
+                    if self.interrupted.load(atomic::Ordering::Relaxed) {
+                        return Err(UiuaError::Interrupted(self.span()));
+                    }
+                    if let Some(limit) = self.execution_limit {
+                        if instant::now() - self.execution_start > limit {
+                            return Err(UiuaError::Timeout(self.span()));
+                        }
+                    }
+                }
             }
         }
         Ok(())
@@ -538,6 +959,12 @@ code:
     pub(crate) fn pop_span(&mut self) {
         self.scope.call.last_mut().unwrap().spans.pop();
     }
+    /// Record the row that a loop primitive is about to process
+    ///
+    /// If the loop's function fails, the row is shown in the error's trace.
+    pub(crate) fn set_row(&mut self, row: usize) {
+        self.scope.call.last_mut().unwrap().row = Some(row);
+    }
     fn call_with_span(&mut self, f: Value, call_span: usize) -> UiuaResult {
         match f.into_function() {
             Ok(f) => self.call_function_with_span(f, call_span)?,
@@ -554,6 +981,7 @@ code:
             function: f.into(),
             call_span,
             spans: Vec::new(),
+            row: None,
             pc: 0,
         })
     }
@@ -747,6 +1175,24 @@ code:
     pub(crate) fn truncate_stack(&mut self, size: usize) {
         self.stack.truncate(size);
     }
+    /// Get the heights of the stacks, leaving `under` values off the main stack
+    pub(crate) fn stack_heights(&self, under: usize) -> StackHeights {
+        StackHeights {
+            stack: self.stack.len().saturating_sub(under),
+            array: self.scope.array.len(),
+            inline: self.inline_stack.len(),
+            under: self.under_stack.len(),
+        }
+    }
+    /// Restore the stacks to previously-recorded heights
+    ///
+    /// This discards anything left behind by a function that errored partway through.
+    pub(crate) fn truncate_stacks(&mut self, heights: StackHeights) {
+        self.stack.truncate(heights.stack);
+        self.scope.array.truncate(heights.array);
+        self.inline_stack.truncate(heights.inline);
+        self.under_stack.truncate(heights.under);
+    }
     pub(crate) fn num_fill(&self) -> Option<f64> {
         self.scope.fills.nums.last().copied()
     }
@@ -826,35 +1272,63 @@ code:
                 self.stack.len()
             )))?;
         }
-        let env = Uiua {
+        let stack = self
+            .stack
+            .drain(self.stack.len() - capture_count..)
+            .collect();
+        let env = self.thread_env(stack);
+        self.backend
+            .spawn(env, Box::new(f))
+            .map(Value::from)
+            .map_err(|e| self.error(e))
+    }
+    /// Create a runtime for running code on another thread
+    ///
+    /// It shares this runtime's bindings, scope, and settings, and starts with the given stack.
+    pub(crate) fn thread_env(&mut self, stack: Vec<Value>) -> Self {
+        Uiua {
             new_functions: Vec::new(),
             globals: self.globals.clone(),
             spans: self.spans.clone(),
-            stack: self
-                .stack
-                .drain(self.stack.len() - capture_count..)
-                .collect(),
+            string_literals: self.string_literals.clone(),
+            stack,
             inline_stack: Vec::new(),
             under_stack: Vec::new(),
             scope: self.scope.clone(),
             higher_scopes: self.higher_scopes.last().cloned().into_iter().collect(),
             mode: self.mode,
+            test_results: TestResults::default(),
             current_imports: self.current_imports.clone(),
+            import_depth: self.import_depth,
             imports: self.imports.clone(),
             diagnostics: BTreeSet::new(),
             print_diagnostics: self.print_diagnostics,
+            color: self.color,
             time_instrs: self.time_instrs,
             last_time: self.last_time,
             cli_arguments: self.cli_arguments.clone(),
             cli_file_path: self.cli_file_path.clone(),
             backend: self.backend.clone(),
+            open_handles: self.open_handles.clone(),
             execution_limit: self.execution_limit,
             execution_start: self.execution_start,
-        };
-        self.backend
-            .spawn(env, Box::new(f))
-            .map(Value::from)
-            .map_err(|e| self.error(e))
+            instruction_limit: self.instruction_limit,
+            instructions_executed: self.instructions_executed.clone(),
+            recursion_limit: self.recursion_limit,
+            call_depth: 0,
+            interrupted: self.interrupted.clone(),
+            assertions: self.assertions.clone(),
+            strict_math: self.strict_math,
+            constant_folding: self.constant_folding,
+            try_depth: 0,
+            compile_only: self.compile_only,
+            warnings: self.warnings,
+            deny_warnings: self.deny_warnings,
+            #[cfg(feature = "parallel")]
+            thread_pool: self.thread_pool.clone(),
+            instrs_since_check: 0,
+            rng: SmallRng::seed_from_u64(self.rng.gen()),
+        }
     }
     /// Wait for a thread to finish
     pub(crate) fn wait(&mut self, handle: Value) -> UiuaResult {
@@ -963,3 +1437,186 @@ where
         format!("function {}'s {}", self.0, self.1.arg_name())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_results() {
+        let mut env = Uiua::with_native_sys().with_mode(RunMode::All);
+        env.load_str("1\n~~~\n⍤\"fail\" 0\n2\n3\n~~~\n4").unwrap();
+        let results = env.take_test_results();
+        assert_eq!(env.take_stack().len(), 2);
+        assert_eq!(results.values.len(), 2);
+        // Lines that only push values are not tests
+        assert_eq!(results.passed, 0);
+        assert_eq!(results.failures.len(), 1);
+        // Assertions in called functions count
+        let mut env = Uiua::with_native_sys().with_mode(RunMode::Test);
+        let code =
+            "Eq ← ⍤\"unequal\" ≅\n~~~\nEq 1 1\n⍤\"ok\" 1\n∵(⍤\"ok\" =1) [1 1]\n5\nEq 1 2\n~~~";
+        env.load_str(code).unwrap();
+        let results = env.take_test_results();
+        assert_eq!(results.passed, 3);
+        assert_eq!(results.failures.len(), 1);
+    }
+
+    #[test]
+    fn assert_messages() {
+        let mut env = Uiua::with_native_sys().with_mode(RunMode::Test);
+        env.load_str("~~~\n⍤\"a\" 1\n⍤\"b\" 0\n⍤\"c\" [1]\n~~~")
+            .unwrap();
+        let results = env.take_test_results();
+        assert_eq!(results.passed, 1);
+        let messages: Vec<String> = results
+            .failures
+            .iter()
+            .map(|e| e.located_message())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "3:1: b",
+                "4:1: Assertion condition must be a scalar, but its shape is [1]"
+            ]
+        );
+        let err = Uiua::with_native_sys().load_str("⍤\"d\" 0").unwrap_err();
+        assert_eq!(err.located_message(), "1:1: d");
+    }
+
+    #[test]
+    fn interrupt() {
+        let mut env = Uiua::with_native_sys();
+        let interrupted = env.interrupt_handle();
+        std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            interrupted.store(true, std::sync::atomic::Ordering::Relaxed);
+        });
+        let err = env.load_str("⍣(⍥(+1)∞)(0;) 0").unwrap_err();
+        assert!(err.is_interrupted());
+    }
+
+    #[test]
+    fn execution_limits() {
+        let err = Uiua::with_native_sys()
+            .with_instruction_limit(10000)
+            .load_str("⍣(⍥(+1)∞)(0;) 0")
+            .unwrap_err();
+        assert!(err.is_timeout());
+        let err = Uiua::with_native_sys()
+            .with_execution_limit(std::time::Duration::from_millis(50))
+            .load_str("∵(⍥(+1)∞) ⇡3")
+            .unwrap_err();
+        assert!(err.is_timeout());
+        // Importing a file does not reset the limits
+        let path = std::env::temp_dir().join("uiua_execution_limits_lib.ua");
+        std::fs::write(&path, "X ← 1\nX").unwrap();
+        let code = format!(
+            "⍥(+1)2000 0\nLib ← &i {:?}\n⍥(+1)2000 0",
+            path.to_string_lossy()
+        );
+        let err = Uiua::with_native_sys()
+            .with_instruction_limit(5000)
+            .with_constant_folding(false)
+            .load_str(&code)
+            .unwrap_err();
+        assert!(err.is_timeout());
+        _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn recursion_limit() {
+        let message = |mut env: Uiua| {
+            std::thread::Builder::new()
+                .stack_size(STACK_SIZE)
+                .spawn(move || {
+                    let err = env.load_str("F ← |1 ↬1 +1\nF 0").unwrap_err();
+                    err.located_message()
+                })
+                .unwrap()
+                .join()
+                .unwrap()
+        };
+        assert_eq!(
+            message(Uiua::with_native_sys().with_recursion_limit(20)),
+            "1:8: Maximum recursion depth of 20 exceeded"
+        );
+        assert_eq!(
+            message(Uiua::with_native_sys()),
+            "1:8: Maximum recursion depth of 1000 exceeded"
+        );
+    }
+
+    #[test]
+    fn rng_seed() {
+        let randoms = |seed| {
+            let mut env = Uiua::with_native_sys().with_rng_seed(seed);
+            env.load_str("[⍥⚂5]").unwrap();
+            env.take_stack()
+        };
+        assert_eq!(randoms(0), randoms(0));
+        assert_ne!(randoms(0), randoms(1));
+    }
+
+    #[test]
+    fn call_binding() {
+        let mut env = Uiua::with_native_sys();
+        env.load_str("Add ← +\nSplit ← ⊃⊢(⊢⇌)\nTen ← 10\nFs ← [(+)(-)]\n5")
+            .unwrap();
+        let results = env.call_binding("Add", [1.into(), 2.into()]).unwrap();
+        assert_eq!(results, [Value::from(3)]);
+        let results = env.call_binding("split", ["abc".into()]).unwrap();
+        assert_eq!(results, [Value::from('c'), 'a'.into()]);
+        // The stack below the arguments is left alone
+        assert_eq!(env.take_stack(), [Value::from(5)]);
+
+        let message = |env: &mut Uiua, name| env.call_binding(name, []).unwrap_err().message();
+        assert_eq!(message(&mut env, "Sub"), "No binding named `Sub` exists");
+        assert_eq!(
+            message(&mut env, "Ten"),
+            "`Ten` is not a function, it is a number array"
+        );
+        assert_eq!(
+            message(&mut env, "Fs"),
+            "`Fs` is not a function, it is an array of functions with shape [2]"
+        );
+        assert!(message(&mut env, "Add").contains("Stack was empty"));
+    }
+
+    #[test]
+    fn bind_fn() {
+        let mut env = Uiua::with_native_sys();
+        env.bind_fn("Plus", 2, 1, |env| {
+            let a = env.pop(1)?.try_num()?;
+            let b = env.pop(2)?.try_num()?;
+            env.push(a + b);
+            Ok(())
+        })
+        .unwrap();
+        env.bind_fn("Fail", 0, 0, |env| Err(env.error("Nope")))
+            .unwrap();
+        env.bind_fn("Extra", 1, 1, |env| {
+            env.push(1);
+            Ok(())
+        })
+        .unwrap();
+        env.load_str("/Plus [1 2 3]\nF ← Plus 1\nF 5").unwrap();
+        let stack: Vec<f64> = (env.take_stack().iter())
+            .map(|val| val.try_num().unwrap())
+            .collect();
+        assert_eq!(stack, [6.0, 6.0]);
+
+        let error = env.load_str("1\n  Fail").unwrap_err();
+        assert_eq!(error.located_message(), "2:3: Nope");
+        let error = env.load_str("Extra 1").unwrap_err();
+        assert_eq!(
+            error.message(),
+            "`Extra`'s signature is |1.1, but it changed the stack height from 2 to 3"
+        );
+
+        for name in ["tabl", "Two words", "", "X1"] {
+            assert!(env.bind_fn(name, 0, 0, |_| Ok(())).is_err(), "{name}");
+        }
+    }
+}
@@ -0,0 +1,305 @@
+//! [`serde`] implementations for arrays and values
+//!
+//! Every array is serialized as a struct with a `shape` and a flat, row-major `data` list.
+//! A [`Value`] is serialized as an externally tagged enum whose variant is one of
+//! `num`, `byte`, `char`, or `box`.
+//!
+//! In human-readable formats like JSON, the data of a character array is a single string,
+//! and the non-finite numbers `NaN`, `∞`, and `-∞` are written as the strings
+//! `"NaN"`, `"∞"`, and `"-∞"`, since JSON numbers cannot represent them.
+//! Binary formats store all numbers as-is.
+//!
+//! Arrays of boxed values are serialized as their contents.
+//! Other functions cannot be serialized.
+
+use std::{fmt, sync::Arc};
+
+use serde::{
+    de::{self, Visitor},
+    ser, Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+    array::{Array, ArrayValue},
+    function::Function,
+    value::{conversion_error, Value},
+    UiuaResult,
+};
+
+#[derive(Serialize)]
+struct ArrayRef<'a, D> {
+    shape: &'a [usize],
+    data: D,
+}
+
+#[derive(Deserialize)]
+struct ArrayRep<D> {
+    shape: Vec<usize>,
+    data: D,
+}
+
+fn into_array<T: ArrayValue, E: de::Error>(shape: Vec<usize>, data: Vec<T>) -> Result<Array<T>, E> {
+    let elements: usize = shape.iter().product();
+    if elements != data.len() {
+        return Err(E::custom(format!(
+            "An array of shape {shape:?} has {elements} elements, but the data has {}",
+            data.len()
+        )));
+    }
+    Ok(Array::new(shape.as_slice(), data.as_slice()))
+}
+
+/// A number that serializes non-finite values as strings in human-readable formats
+struct Num(f64);
+
+impl Serialize for Num {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.0.is_finite() || !serializer.is_human_readable() {
+            serializer.serialize_f64(self.0)
+        } else if self.0.is_nan() {
+            serializer.serialize_str("NaN")
+        } else if self.0 > 0.0 {
+            serializer.serialize_str("∞")
+        } else {
+            serializer.serialize_str("-∞")
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Num {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct NumVisitor;
+        impl<'de> Visitor<'de> for NumVisitor {
+            type Value = Num;
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a number, \"NaN\", \"∞\", or \"-∞\"")
+            }
+            fn visit_f64<E: de::Error>(self, n: f64) -> Result<Num, E> {
+                Ok(Num(n))
+            }
+            fn visit_i64<E: de::Error>(self, n: i64) -> Result<Num, E> {
+                Ok(Num(n as f64))
+            }
+            fn visit_u64<E: de::Error>(self, n: u64) -> Result<Num, E> {
+                Ok(Num(n as f64))
+            }
+            fn visit_str<E: de::Error>(self, s: &str) -> Result<Num, E> {
+                match s {
+                    "NaN" => Ok(Num(f64::NAN)),
+                    "∞" => Ok(Num(f64::INFINITY)),
+                    "-∞" | "¯∞" => Ok(Num(f64::NEG_INFINITY)),
+                    _ => Err(E::invalid_value(de::Unexpected::Str(s), &self)),
+                }
+            }
+        }
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(NumVisitor)
+        } else {
+            f64::deserialize(deserializer).map(Num)
+        }
+    }
+}
+
+struct Nums<'a>(&'a [f64]);
+
+impl<'a> Serialize for Nums<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(|&n| Num(n)))
+    }
+}
+
+impl Serialize for Array<f64> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ArrayRef {
+            shape: &self.shape,
+            data: Nums(&self.data),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Array<f64> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let ArrayRep { shape, data } = ArrayRep::<Vec<Num>>::deserialize(deserializer)?;
+        into_array(shape, data.into_iter().map(|n| n.0).collect())
+    }
+}
+
+impl Serialize for Array<u8> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ArrayRef {
+            shape: &self.shape,
+            data: &*self.data,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Array<u8> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let ArrayRep { shape, data } = ArrayRep::<Vec<u8>>::deserialize(deserializer)?;
+        into_array(shape, data)
+    }
+}
+
+impl Serialize for Array<char> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            ArrayRef {
+                shape: &self.shape,
+                data: self.data.iter().collect::<String>(),
+            }
+            .serialize(serializer)
+        } else {
+            ArrayRef {
+                shape: &self.shape,
+                data: &*self.data,
+            }
+            .serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Array<char> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let ArrayRep { shape, data } = ArrayRep::<String>::deserialize(deserializer)?;
+            into_array(shape, data.chars().collect())
+        } else {
+            let ArrayRep { shape, data } = ArrayRep::<Vec<char>>::deserialize(deserializer)?;
+            into_array(shape, data)
+        }
+    }
+}
+
+impl Serialize for Array<Arc<Function>> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let values = (self.data.iter())
+            .map(|f| match f.as_boxed().filter(|_| f.is_constant()) {
+                Some(value) => Ok(value),
+                None => Err(ser::Error::custom(format!(
+                    "Functions cannot be serialized, but the array contains {f}"
+                ))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        ArrayRef {
+            shape: &self.shape,
+            data: values,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Array<Arc<Function>> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let ArrayRep { shape, data } = ArrayRep::<Vec<Value>>::deserialize(deserializer)?;
+        let data = data.into_iter().map(Function::constant).map(Arc::new);
+        into_array(shape, data.collect())
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename = "Value", rename_all = "snake_case")]
+enum ValueRef<'a> {
+    Num(&'a Array<f64>),
+    Byte(&'a Array<u8>),
+    Char(&'a Array<char>),
+    Box(&'a Array<Arc<Function>>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "Value", rename_all = "snake_case")]
+enum ValueRep {
+    Num(Array<f64>),
+    Byte(Array<u8>),
+    Char(Array<char>),
+    Box(Array<Arc<Function>>),
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Num(array) => ValueRef::Num(array),
+            Value::Byte(array) => ValueRef::Byte(array),
+            Value::Char(array) => ValueRef::Char(array),
+            Value::Func(array) => ValueRef::Box(array),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match ValueRep::deserialize(deserializer)? {
+            ValueRep::Num(array) => array.into(),
+            ValueRep::Byte(array) => array.into(),
+            ValueRep::Char(array) => array.into(),
+            ValueRep::Box(array) => array.into(),
+        })
+    }
+}
+
+impl Value {
+    /// Serialize the value to JSON
+    ///
+    /// See the [module documentation](crate::serialize) for the format.
+    /// Numbers become JSON numbers, except for `NaN` and infinities, which become strings.
+    /// Character data becomes a JSON string.
+    pub fn to_json(&self) -> UiuaResult<String> {
+        serde_json::to_string(self)
+            .map_err(|e| conversion_error(format!("Unable to serialize value: {e}")))
+    }
+    /// Deserialize a value from JSON produced by [`Value::to_json`]
+    pub fn from_json(json: &str) -> UiuaResult<Self> {
+        serde_json::from_str(json)
+            .map_err(|e| conversion_error(format!("Unable to deserialize value: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn value_serde() {
+        use Value;
+        let mut env = Uiua::with_native_sys();
+        env.load_str("[1 ∞ ¯∞ NaN] 0.5 ⇡0 ↯0_3 \"\" [\"ab\" \"cd\"] ↯2_2 [1 0 1 1] {\"x\" [1 2]}")
+            .unwrap();
+        for value in env.take_stack() {
+            let json = value.to_json().unwrap();
+            let back = Value::from_json(&json).unwrap();
+            assert_eq!(back.shape(), value.shape(), "{json}");
+            assert_eq!(back.type_name(), value.type_name(), "{json}");
+            assert_eq!(back.to_json().unwrap(), json);
+        }
+        assert_eq!(
+            Value::from(vec![1.0, f64::INFINITY, f64::NAN])
+                .to_json()
+                .unwrap(),
+            r#"{"num":{"shape":[3],"data":[1.0,"∞","NaN"]}}"#
+        );
+        assert_eq!(
+            Value::from(vec!["hi".to_string()]).to_json().unwrap(),
+            r#"{"box":{"shape":[1],"data":[{"char":{"shape":[2],"data":"hi"}}]}}"#
+        );
+        assert_eq!(
+            Value::from_json(r#"{"num":{"shape":[],"data":["-∞"]}}"#).unwrap(),
+            Value::from(f64::NEG_INFINITY)
+        );
+
+        let message = |res: UiuaResult<Value>| res.unwrap_err().message();
+        assert_eq!(
+            message(Value::from_json(r#"{"char":{"shape":[2,2],"data":"abc"}}"#)),
+            "Unable to deserialize value: An array of shape [2, 2] has 4 elements, \
+            but the data has 3"
+        );
+        env.load_str("[(+)(-)]").unwrap();
+        let functions = env.pop("functions").unwrap();
+        assert_eq!(
+            functions.to_json().unwrap_err().message(),
+            "Unable to serialize value: Functions cannot be serialized, but the array contains +"
+        );
+    }
+}
@@ -1,17 +1,17 @@
 use std::{
     any::Any,
-    collections::{HashMap, HashSet},
+    collections::HashMap,
     env,
     fs::{self, File},
-    io::{stderr, stdin, stdout, BufRead, Cursor, Read, Write},
+    io::{stderr, stdin, stdout, BufRead, Cursor, ErrorKind, Read, Write},
     net::*,
     process::Command,
     sync::{
         atomic::{self, AtomicU64},
         Arc, OnceLock,
     },
-    thread::{sleep, spawn, JoinHandle},
-    time::Duration,
+    thread::{self, sleep, JoinHandle},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use bufreaderwriter::seq::BufReaderWriterSeq;
@@ -19,17 +19,22 @@ use dashmap::DashMap;
 use ecow::EcoVec;
 use enum_iterator::Sequence;
 use hound::{SampleFormat, WavReader, WavSpec, WavWriter};
+#[cfg(feature = "image")]
 use image::{DynamicImage, ImageOutputFormat};
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
 use tinyvec::tiny_vec;
 
 use crate::{
+    algorithm::padded_strings,
     array::Array,
     cowslice::{cowslice, CowSlice},
+    csv::{csv_to_chars, csv_to_nums, parse_csv, write_csv},
     function::Function,
     grid_fmt::GridFmt,
+    json::{dump_json, parse_json},
     primitive::PrimDoc,
+    run::STACK_SIZE,
     value::Value,
     Uiua, UiuaError, UiuaResult,
 };
@@ -112,10 +117,20 @@ sys_op! {
     (1(0), Print, "&p", "print with newline"),
     /// Read a line from stdin
     ///
-    /// The normal output is a string.
+    /// The normal output is a string without the trailing newline.
     /// If EOF is reached, the number `0` is returned instead.
     /// Programs that wish to properly handle EOF should check for this.
+    ///
+    /// On the web, stdin is the text in the pad's input box.
+    ///
+    /// See also: [&sca]
     (0, ScanLine, "&sc", "scan line"),
+    /// Read all the remaining input from stdin
+    ///
+    /// The output is a string, which is empty if EOF has already been reached.
+    ///
+    /// See also: [&sc]
+    (0, ScanAll, "&sca", "scan all"),
     /// Get the size of the terminal
     ///
     /// The result is a 2-element array of the height and width of the terminal.
@@ -126,26 +141,162 @@ sys_op! {
     /// The first element will always be the name of your script
     (0, Args, "&args", "arguments"),
     /// Get the value of an environment variable
+    ///
+    /// If the variable is not set, the result is an empty string.
+    /// ex: &var "HOME"
     (1, Var, "&var", "environment variable"),
     /// Run a command and wait for it to finish
     ///
     /// Standard IO will be inherited.
+    /// The exit code of the command is pushed to the stack.
     ///
     /// Expects either a string, a rank `2` character array, or a rank `1` array of [box] strings.
-    (1(0), RunInherit, "&runi", "run command inherit"),
+    /// The first string is the program, and the rest are its arguments.
+    /// The program is run directly rather than through a shell, so arguments are passed exactly as they are.
+    ///
+    /// See also: [&runc]
+    (1, RunInherit, "&runi", "run command inherit"),
     /// Run a command and wait for it to finish
     ///
-    /// Standard IO will be captured. Stdout and stderr will each be pushed to the stack as strings.
+    /// Standard IO will be captured.
+    /// Stdout and stderr will each be pushed to the stack as strings, followed by the exit code of the command.
+    /// Output that is not valid UTF-8 is converted lossily, with invalid sequences replaced by `�`.
     ///
     /// Expects either a string, a rank `2` character array, or a rank `1` array of [box] strings.
-    (1(2), RunCapture, "&runc", "run command capture"),
+    /// The first string is the program, and the rest are its arguments.
+    /// The program is run directly rather than through a shell, so arguments are passed exactly as they are.
+    ///
+    /// If the command was terminated by a signal, the exit code is `¯1`.
+    ///
+    /// See also: [&runi]
+    (1(3), RunCapture, "&runc", "run command capture"),
+    /// Get the current directory
+    ///
+    /// On the web, this is always `/home/uiua` unless it is changed with [&cd].
+    ///
+    /// See also: [&cd]
+    (0, CurrentDirectory, "&cwd", "current directory"),
     /// Change the current directory
+    ///
+    /// See also: [&cwd]
     (1(0), ChangeDirectory, "&cd", "change directory"),
     /// Sleep for n seconds
     ///
     /// On the web, this example will hang for 1 second.
     /// ex: ⚂ &sl 1
+    ///
+    /// Sleeping can be interrupted like any other long-running code.
     (1(0), Sleep, "&sl", "sleep"),
+    /// Format a time as a string
+    ///
+    /// The first argument is the format string, and the second is a time in seconds since the Unix epoch, like the one returned by [now].
+    /// The time is always formatted in UTC.
+    /// ex: &tf "%Y-%m-%d %H:%M:%S" 1e9
+    /// ex: &tf "%A, %B %d" now
+    ///
+    /// The supported specifiers are:
+    /// - `%Y` - the year
+    /// - `%y` - the last two digits of the year
+    /// - `%m` - the month, `01` to `12`
+    /// - `%d` - the day of the month, `01` to `31`
+    /// - `%j` - the day of the year, `001` to `366`
+    /// - `%H` - the hour, `00` to `23`
+    /// - `%M` - the minute, `00` to `59`
+    /// - `%S` - the second, `00` to `59`
+    /// - `%L` - the millisecond, `000` to `999`
+    /// - `%a` and `%A` - the abbreviated and full weekday name
+    /// - `%b` and `%B` - the abbreviated and full month name
+    /// - `%s` - the whole number of seconds since the epoch
+    /// - `%%` - a literal `%`
+    (2, TimeFormat, "&tf", "time - format"),
+    /// Find all matches of a regex in a string
+    ///
+    /// The first argument is the regex pattern, and the second is the string to search.
+    /// Two values are pushed: the start index of each match, then the matches themselves.
+    /// The matches are the rows of a [rank]`2` character array, padded with the [fill] character or spaces.
+    /// ex: &rxf "[0-9]+" "a12b345c6"
+    /// ex: ⬚@_&rxf "[0-9]+" "a12b345c6"
+    ///
+    /// Indices count characters, not bytes.
+    /// ex: &rxf "β+" "αββγβ"
+    ///
+    /// See also: [&rxc]
+    (2(2), RegexFind, "&rxf", "regex - find"),
+    /// Get the capture groups of the first match of a regex in a string
+    ///
+    /// The first argument is the regex pattern, and the second is the string to search.
+    /// The result is a [rank]`2` character array, padded with the [fill] character or spaces.
+    /// The first row is the whole match, and each subsequent row is a capture group.
+    /// Groups that did not participate in the match are empty.
+    /// If there is no match, the result is empty.
+    /// ex: &rxc "(\\w+)@(\\w+)" "mail me@here now"
+    ///
+    /// See also: [&rxf]
+    (2, RegexCaptures, "&rxc", "regex - captures"),
+    /// Parse a JSON string into a value
+    ///
+    /// Numbers become numbers, `true` and `false` become `1` and `0`, `null` becomes `NaN`, and strings become character arrays.
+    /// The items of a JSON array become the rows of an array if they have the same type and shape, or if a [fill] value makes them fit.
+    /// Otherwise, each item is [box]ed.
+    /// ex: &jsp "[1, 2, true]"
+    /// ex: &jsp "[\"cat\", \"dog\"]"
+    /// ex: &jsp "[[1, 2], [3], \"four\"]"
+    /// ex: ⬚0&jsp "[[1, 2], [3]]"
+    ///
+    /// An object becomes a list of two boxes.
+    /// The first is a list of the [box]ed keys, and the second is the values, combined like the items of an array.
+    /// ex: &jsp "{\"name\": \"Uiua\", \"age\": 0}"
+    ///
+    /// See also: [&jsd]
+    (1, JsonParse, "&jsp", "json - parse"),
+    /// Convert a value into a JSON string
+    ///
+    /// This is the reverse of [&jsp].
+    /// Arrays become nested JSON arrays, and character arrays of [rank] `1` become strings.
+    /// `NaN` becomes `null`.
+    /// A list of two boxes where the first is a list of [box]ed strings with as many rows as the second becomes an object.
+    /// ex: &jsd [1_2 3_4]
+    /// ex: &jsd {"hello" 5 [1 2 3]}
+    /// ex: &jsd &jsp "{\"a\": [1, 2], \"b\": null}"
+    ///
+    /// See also: [&jsp]
+    (1, JsonDump, "&jsd", "json - dump"),
+    /// Parse CSV text into a table of fields
+    ///
+    /// The result is a [rank] `3` character array.
+    /// Each row is a record, and each row of a record is a field.
+    /// Fields are padded with the [fill] character or spaces, as are missing fields in short records.
+    /// ex: &csvr "name,age\nAda,36\nAlan,41"
+    ///
+    /// Quoted fields may contain commas, newlines, and doubled quotes.
+    /// Records may end with either `\r\n` or `\n`.
+    /// ex: ⬚@·&csvr "\"Hello, World\",\"a \"\"quote\"\"\"\r\n1,2\r\n"
+    ///
+    /// To read a file, use [&fras] first.
+    ///
+    /// See also: [&csvn] [&csvw]
+    (1, CsvRead, "&csvr", "csv - read"),
+    /// Parse CSV text into a table of numbers
+    ///
+    /// The result is a [rank] `2` numeric array.
+    /// It is an error if any field is not a number.
+    /// ex: &csvn "1,2,3\n4,5,6"
+    ///
+    /// Short records are padded with the [fill] value.
+    /// ex: ⬚0&csvn "1,2,3\n4"
+    ///
+    /// See also: [&csvr] [&csvw]
+    (1, CsvReadNums, "&csvn", "csv - read numbers"),
+    /// Write a table as CSV text
+    ///
+    /// The table can be a [rank] `2` array of numbers or [box]ed strings and numbers.
+    /// It can also be a [rank] `3` character array like the one returned by [&csvr], in which case trailing spaces are trimmed from the fields.
+    /// Records end with `\r\n`, and fields that contain commas, quotes, or newlines are quoted.
+    /// ex: &csvw [1_2_3 4_5_6]
+    /// ex: &csvw [{"name" "quote"} {"Ada" "Hello, \"World\""}]
+    ///
+    /// See also: [&csvr] [&csvn]
+    (1, CsvWrite, "&csvw", "csv - write"),
     /// Read at most n bytes from a stream
     (2, ReadStr, "&rs", "read to string"),
     /// Read at most n bytes from a stream
@@ -180,9 +331,30 @@ sys_op! {
     /// Check if a file exists at a path
     (1, FExists, "&fe", "file - exists"),
     /// List the contents of a directory
-    (1, FListDir, "&fld", "file - list directory"),
+    ///
+    /// Two values are pushed: a mask of which entries are directories, then the names of the entries.
+    /// The names are sorted and are the rows of a [rank]`2` character array, padded with the [fill] character or spaces.
+    /// Names that are not valid UTF-8 are converted lossily, with invalid sequences replaced by `�`.
+    ///
+    /// See also: [&fe] [&fif]
+    (1(2), FListDir, "&fld", "file - list directory"),
     /// Check if a path is a file
     (1, FIsFile, "&fif", "file - is file"),
+    /// Delete a file
+    ///
+    /// Directories cannot be deleted with this function. Use [&frd] instead.
+    (1(0), FDelete, "&fde", "file - delete"),
+    /// Remove a directory
+    ///
+    /// The directory must be empty.
+    ///
+    /// See also: [&fde]
+    (1(0), FRemoveDir, "&frd", "file - remove directory"),
+    /// Get the size and modification time of a file
+    ///
+    /// The result is a list of the file's size in bytes and the time it was last modified.
+    /// The time is in seconds since the Unix epoch, like the one returned by [now].
+    (1, FMeta, "&fmeta", "file - metadata"),
     /// Read all the contents of a file into a string
     ///
     /// Expects a path and returns a [rank]`1` character array.
@@ -199,6 +371,13 @@ sys_op! {
     ///
     /// Supported formats are `jpg`, `png`, `bmp`, `gif`, and `ico`.
     ///
+    /// The image is returned as a [rank]`3` array of numbers between `0` and `1`.
+    /// Axes 0 and 1 contain the rows and columns of the image.
+    /// The last axis contains the channels of each pixel.
+    /// It is length 1 for a grayscale image, 2 for a grayscale image with an alpha channel, 3 for an RGB image, and 4 for an RGB image with an alpha channel.
+    ///
+    /// To decode an image file, read it with [&frab] first.
+    ///
     /// See also: [&ime]
     (1, ImDecode, "&imd", "image - decode"),
     /// Encode an image into a byte array with the specified format
@@ -215,6 +394,8 @@ sys_op! {
     /// A length 3 last axis is an RGB image.
     /// A length 4 last axis is an RGB image with an alpha channel.
     ///
+    /// Numbers are clamped between `0` and `1`.
+    ///
     /// Supported formats are `jpg`, `png`, `bmp`, `gif`, and `ico`.
     ///
     /// See also: [&ims] [&imd]
@@ -223,7 +404,8 @@ sys_op! {
     ///
     /// How the image is shown depends on the system backend.
     ///
-    /// In the default backend, the image is shown in the terminal.
+    /// In the default backend, the image is shown in the terminal if it supports a graphics protocol.
+    /// Otherwise, it is written to a temporary file and opened in the default image viewer.
     /// On the web, the image is shown in the output area.
     ///
     /// The image must be a rank 2 or 3 numeric array.
@@ -252,6 +434,8 @@ sys_op! {
     /// The second argument is the gif data and must be a rank 3 or 4 numeric array.
     /// The rows of the array are the frames of the gif, and their format must conform to that of [&ime].
     ///
+    /// In the default backend, the gif is written to a temporary file and opened in the default image viewer.
+    ///
     /// See also: [&gife]
     (1(0), GifShow, "&gifs", "gif - show"),
     /// Decode audio from a byte array
@@ -269,13 +453,23 @@ sys_op! {
     /// A rank 1 array is a list of mono audio samples.
     /// For a rank 2 array, each row is a channel.
     ///
-    /// The samples must be between -1 and 1.
-    /// The sample rate is [&asr].
+    /// The samples should be between -1 and 1. Samples outside that range are clamped.
+    /// The sample rate is [&asr]. Use [&aer] to choose a different one.
     ///
-    /// Only the `wav` format is supported.
+    /// Only the `wav` format is supported. The samples are written as 16-bit PCM.
     ///
     /// See also: [&ap] [&ad]
     (2, AudioEncode, "&ae", "audio - encode"),
+    /// Encode audio into a byte array with a given sample rate
+    ///
+    /// The first argument is the format, the second is the sample rate, and the third is the audio samples.
+    /// The samples work the same as in [&ae].
+    ///
+    /// ex: ⧻&aer "wav" 8000 ÷2○×τ×220 ÷∶⇡. 8000
+    /// To render audio to a file, write the bytes with [&fwa].
+    ///
+    /// See also: [&ae] [&asr]
+    (3, AudioEncodeRate, "&aer", "audio - encode with sample rate"),
     /// Play some audio
     ///
     /// The audio must be a rank 1 or 2 numeric array.
@@ -290,6 +484,8 @@ sys_op! {
     (1(0), AudioPlay, "&ap", "audio - play"),
     /// Get the sample rate of the audio output backend
     ///
+    /// This is the sample rate used by [&ae], [&ap], and [&ast].
+    ///
     /// ex: &asr
     /// Here is how you can generate a list of sample times for `4` seconds of audio:
     /// ex: ÷∶⇡×, 4 &asr
@@ -300,6 +496,14 @@ sys_op! {
     ///
     /// Expects a function that takes a list of sample times and returns a list of samples.
     /// The function will be called repeatedly to generate the audio.
+    ///
+    /// The function may instead return a rank 2 array with 1 or 2 rows, which are the left and right channels.
+    /// There should be one sample for each time. If there are too few, the rest are silent and a message is printed once.
+    /// Extra samples are ignored.
+    ///
+    /// When run with `uiua watch`, the stream resumes from the same time after the file is changed.
+    ///
+    /// See also: [&ap] [&asr]
     (1(0), AudioStream, "&ast", "audio - stream"),
     /// Create a TCP listener and bind it to an address
     (1, TcpListen, "&tcpl", "tcp - listen"),
@@ -310,6 +514,10 @@ sys_op! {
     /// Set a TCP socket to non-blocking mode
     (1, TcpSetNonBlocking, "&tcpsnb", "tcp - set non-blocking"),
     /// Set the read timeout of a TCP socket in seconds
+    ///
+    /// If a read times out before any data is received, it is an error.
+    /// Otherwise, the data received so far is returned.
+    /// A timeout of [infinity] means reads will wait forever.
     (2(0), TcpSetReadTimeout, "&tcpsrt", "tcp - set read timeout"),
     /// Set the write timeout of a TCP socket in seconds
     (2(0), TcpSetWriteTimeout, "&tcpswt", "tcp - set write timeout"),
@@ -336,6 +544,29 @@ sys_op! {
     /// - The HTTP version
     /// - The `Host` header (if not defined)
     (2, HttpsWrite, "&httpsw", "http - Make an HTTP request"),
+    /// Make an HTTP GET request to a URL
+    ///
+    /// Pushes the response's body, its headers, and its status code, with the status code on top.
+    /// Statuses that indicate failure, like 404, are returned rather than being errors.
+    ///
+    /// The headers are a rank 2 array of [box]ed strings, where each row is a name and a value.
+    /// The body is a string if the `Content-Type` header indicates text, JSON, XML, or JavaScript.
+    /// Otherwise, it is a list of bytes.
+    ///
+    /// The request times out after 30 seconds.
+    /// See [&httpr] for making other kinds of requests.
+    ///
+    /// ex: &httpg "https://example.com"
+    (1(3), HttpGet, "&httpg", "http - get"),
+    /// Make an HTTP request
+    ///
+    /// Expects a method, a URL, headers, and a body.
+    /// The headers are a rank 2 array of [box]ed strings, where each row is a name and a value, or an empty list.
+    /// The body is a string or a list of bytes.
+    /// The response is pushed the same way as with [&httpg].
+    ///
+    /// ex: &httpr "POST" "https://httpbin.org/post" [{"Content-Type" "application/json"}] $ {"name": "Uiua"}
+    (4(3), HttpRequest, "&httpr", "http - request"),
 }
 
 /// A handle to an IO stream
@@ -365,7 +596,56 @@ impl From<Handle> for Value {
     }
 }
 
-type AudioStreamFn = Box<dyn FnMut(Vec<f64>) -> UiuaResult<Vec<[f64; 2]>> + Send>;
+/// The handles opened by a runtime and the backends that opened them
+///
+/// Any handles that are still open when the last copy of the runtime is dropped are closed.
+#[derive(Default)]
+pub(crate) struct OpenHandles(Mutex<HashMap<Handle, Arc<dyn SysBackend>>>);
+
+impl OpenHandles {
+    pub(crate) fn insert(&self, handle: Handle, backend: Arc<dyn SysBackend>) {
+        self.0.lock().insert(handle, backend);
+    }
+    pub(crate) fn remove(&self, handle: Handle) {
+        self.0.lock().remove(&handle);
+    }
+}
+
+impl Drop for OpenHandles {
+    fn drop(&mut self) {
+        for (handle, backend) in self.0.get_mut().drain() {
+            _ = backend.close(handle);
+        }
+    }
+}
+
+/// An HTTP request made with [`SysOp::HttpGet`] or [`SysOp::HttpRequest`]
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// How long to wait for the server to respond
+    pub timeout: Duration,
+}
+
+impl HttpRequest {
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+}
+
+/// The response to an [`HttpRequest`]
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// A function that takes a list of sample times and returns a stereo frame for each time
+///
+/// It is passed to [`SysBackend::stream_audio`] by [`SysOp::AudioStream`].
+pub type AudioStreamFn = Box<dyn FnMut(Vec<f64>) -> UiuaResult<Vec<[f64; 2]>> + Send>;
 
 #[allow(unused_variables)]
 pub trait SysBackend: Any + Send + Sync + 'static {
@@ -388,6 +668,9 @@ pub trait SysBackend: Any + Send + Sync + 'static {
     fn scan_line_stdin(&self) -> Result<Option<String>, String> {
         Err("Reading from stdin is not supported in this environment".into())
     }
+    fn scan_all_stdin(&self) -> Result<String, String> {
+        Err("Reading from stdin is not supported in this environment".into())
+    }
     fn var(&self, name: &str) -> Option<String> {
         None
     }
@@ -397,12 +680,23 @@ pub trait SysBackend: Any + Send + Sync + 'static {
     fn file_exists(&self, path: &str) -> bool {
         false
     }
-    fn list_dir(&self, path: &str) -> Result<Vec<String>, String> {
+    /// List the names of the entries in a directory, along with whether each is a directory
+    fn list_dir(&self, path: &str) -> Result<Vec<(String, bool)>, String> {
         Err("This IO operation is not supported in this environment".into())
     }
     fn is_file(&self, path: &str) -> Result<bool, String> {
         Err("This IO operation is not supported in this environment".into())
     }
+    fn delete_file(&self, path: &str) -> Result<(), String> {
+        Err("This IO operation is not supported in this environment".into())
+    }
+    fn remove_dir(&self, path: &str) -> Result<(), String> {
+        Err("This IO operation is not supported in this environment".into())
+    }
+    /// Get the size of a file in bytes and its modification time in seconds since the Unix epoch
+    fn file_metadata(&self, path: &str) -> Result<(u64, f64), String> {
+        Err("This IO operation is not supported in this environment".into())
+    }
     fn read(&self, handle: Handle, count: usize) -> Result<Vec<u8>, String> {
         Err("This IO operation is not supported in this environment".into())
     }
@@ -441,9 +735,14 @@ pub trait SysBackend: Any + Send + Sync + 'static {
         self.close(handle)?;
         Ok(())
     }
+    /// Get the current time in seconds since the Unix epoch
+    fn now(&self) -> f64 {
+        instant::now() / 1000.0
+    }
     fn sleep(&self, seconds: f64) -> Result<(), String> {
         Err("Sleeping is not supported in this environment".into())
     }
+    #[cfg(feature = "image")]
     fn show_image(&self, image: DynamicImage) -> Result<(), String> {
         Err("Showing images not supported in this environment".into())
     }
@@ -503,22 +802,41 @@ pub trait SysBackend: Any + Send + Sync + 'static {
             "Joining threads is not supported in this environment".into()
         ))
     }
-    fn run_command_inherit(&self, command: &str, args: &[&str]) -> Result<(), String> {
+    /// Run a command with inherited standard IO and return its exit code
+    fn run_command_inherit(&self, command: &str, args: &[&str]) -> Result<i32, String> {
         Err("Running commands is not supported in this environment".into())
     }
+    /// Run a command and return its exit code, stdout, and stderr
     fn run_command_capture(
         &self,
         command: &str,
         args: &[&str],
-    ) -> Result<(String, String), String> {
+    ) -> Result<(i32, String, String), String> {
         Err("Running commands is not supported in this environment".into())
     }
+    fn current_directory(&self) -> Result<String, String> {
+        Err("Getting the current directory is not supported in this environment".into())
+    }
     fn change_directory(&self, path: &str) -> Result<(), String> {
         Err("Changing directories is not supported in this environment".into())
     }
     fn https_get(&self, request: &str, handle: Handle) -> Result<String, String> {
         Err("Making HTTPS requests is not supported in this environment".into())
     }
+    /// Make an HTTP request and wait for the response
+    fn http_request(&self, request: HttpRequest) -> Result<HttpResponse, String> {
+        Err("Making HTTP requests is not supported in this environment".into())
+    }
+    /// Find all matches of a regex, along with their character indices
+    fn regex_find(&self, pattern: &str, subject: &str) -> Result<Vec<(usize, String)>, String> {
+        Err("Regular expressions are not supported in this environment".into())
+    }
+    /// Get the capture groups of the first match of a regex
+    ///
+    /// The first group is the whole match
+    fn regex_captures(&self, pattern: &str, subject: &str) -> Result<Vec<String>, String> {
+        Err("Regular expressions are not supported in this environment".into())
+    }
 }
 
 #[derive(Default)]
@@ -530,7 +848,8 @@ struct GlobalNativeSys {
     next_handle: AtomicU64,
     files: DashMap<Handle, Buffered<File>>,
     tcp_listeners: DashMap<Handle, TcpListener>,
-    tcp_sockets: DashMap<Handle, Buffered<TcpStream>>,
+    /// Sockets are behind their own locks so that blocking on one does not block others
+    tcp_sockets: DashMap<Handle, Arc<Mutex<Buffered<TcpStream>>>>,
     hostnames: DashMap<Handle, String>,
     threads: DashMap<Handle, JoinHandle<UiuaResult<Vec<Value>>>>,
     #[cfg(feature = "audio")]
@@ -538,12 +857,17 @@ struct GlobalNativeSys {
     #[cfg(feature = "audio")]
     audio_time_socket: Mutex<Option<Arc<std::net::UdpSocket>>>,
     colored_errors: DashMap<String, String>,
+    /// The directory that shown images are written to, if their paths should be reused between runs
+    #[cfg(feature = "image")]
+    image_dir: Mutex<Option<std::path::PathBuf>>,
+    #[cfg(feature = "image")]
+    images_shown: AtomicU64,
 }
 
 enum SysStream<'a> {
     File(dashmap::mapref::one::RefMut<'a, Handle, Buffered<File>>),
     TcpListener(dashmap::mapref::one::RefMut<'a, Handle, TcpListener>),
-    TcpSocket(dashmap::mapref::one::RefMut<'a, Handle, Buffered<TcpStream>>),
+    TcpSocket(Arc<Mutex<Buffered<TcpStream>>>),
 }
 
 impl Default for GlobalNativeSys {
@@ -560,6 +884,10 @@ impl Default for GlobalNativeSys {
             #[cfg(feature = "audio")]
             audio_time_socket: Mutex::new(None),
             colored_errors: DashMap::new(),
+            #[cfg(feature = "image")]
+            image_dir: Mutex::new(None),
+            #[cfg(feature = "image")]
+            images_shown: AtomicU64::new(0),
         }
     }
 }
@@ -582,8 +910,8 @@ impl GlobalNativeSys {
             SysStream::File(file)
         } else if let Some(listener) = self.tcp_listeners.get_mut(&handle) {
             SysStream::TcpListener(listener)
-        } else if let Some(socket) = self.tcp_sockets.get_mut(&handle) {
-            SysStream::TcpSocket(socket)
+        } else if let Some(socket) = self.tcp_sockets.get(&handle) {
+            SysStream::TcpSocket(socket.clone())
         } else {
             return Err("Invalid file handle".to_string());
         })
@@ -605,6 +933,80 @@ pub fn set_audio_stream_time_port(port: u16) -> std::io::Result<()> {
     Ok(())
 }
 
+/// Write images shown with [`SysOp::ImShow`] and [`SysOp::GifShow`] to a directory
+///
+/// The `n`th image shown is always written to the same path, so an image viewer that is
+/// already showing it from a previous run can reload it instead of a new viewer being opened.
+#[cfg(feature = "image")]
+pub fn set_image_dir(dir: impl Into<std::path::PathBuf>) {
+    *NATIVE_SYS.image_dir.lock() = Some(dir.into());
+}
+
+/// Write an encoded image to a file and open it in the default viewer
+///
+/// If no viewer can be opened, the path is printed to the backend's stdout instead.
+#[cfg(feature = "image")]
+fn open_image_file(sys: &NativeSys, bytes: &[u8], extension: &str) -> Result<(), String> {
+    let n = NATIVE_SYS
+        .images_shown
+        .fetch_add(1, atomic::Ordering::Relaxed);
+    let (path, reused) = match &*NATIVE_SYS.image_dir.lock() {
+        Some(dir) => {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+            let path = dir.join(format!("image_{n}.{extension}"));
+            let reused = path.exists();
+            (path, reused)
+        }
+        None => {
+            let name = format!("uiua_image_{}_{n}.{extension}", std::process::id());
+            (env::temp_dir().join(name), false)
+        }
+    };
+    fs::write(&path, bytes)
+        .map_err(|e| format!("Failed to write image to {}: {e}", path.display()))?;
+    if reused {
+        return Ok(());
+    }
+    #[cfg(feature = "open")]
+    let opened = open::that(&path).is_ok();
+    #[cfg(not(feature = "open"))]
+    let opened = false;
+    if !opened {
+        sys.print_str_stdout(&format!("Image written to {}\n", path.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "terminal_image")]
+fn show_image_in_terminal(image: &DynamicImage) -> Result<(), String> {
+    let (width, height) = if let Some((w, h)) = term_size::dimensions() {
+        let (tw, th) = (w as u32, h.saturating_sub(1) as u32);
+        let (iw, ih) = (image.width(), image.height() / 2);
+        let scaled_to_height = (iw * th / ih.max(1), th);
+        let scaled_to_width = (tw, ih * tw / iw.max(1));
+        let (w, h) = if scaled_to_height.0 <= tw {
+            scaled_to_height
+        } else {
+            scaled_to_width
+        };
+        (Some(w), Some(h))
+    } else {
+        (None, None)
+    };
+    viuer::print(
+        image,
+        &viuer::Config {
+            width,
+            height,
+            absolute_offset: false,
+            transparent: true,
+            ..Default::default()
+        },
+    )
+    .map(drop)
+    .map_err(|e| format!("Failed to show image: {e}"))
+}
+
 impl SysBackend for NativeSys {
     fn any(&self) -> &dyn Any {
         self
@@ -627,10 +1029,15 @@ impl SysBackend for NativeSys {
             .transpose()
             .map_err(|e| e.to_string())
     }
+    fn scan_all_stdin(&self) -> Result<String, String> {
+        let mut input = String::new();
+        (stdin().lock().read_to_string(&mut input)).map_err(|e| e.to_string())?;
+        Ok(input)
+    }
     fn save_error_color(&self, error: &UiuaError) {
         NATIVE_SYS
             .colored_errors
-            .insert(error.message(), error.show(true));
+            .insert(error.located_message(), error.show(true));
     }
     fn term_size(&self) -> Result<(usize, usize), String> {
         let (w, h) = term_size::dimensions().ok_or("Failed to get terminal size")?;
@@ -647,13 +1054,30 @@ impl SysBackend for NativeSys {
             .map(|m| m.is_file())
             .map_err(|e| e.to_string())
     }
-    fn list_dir(&self, path: &str) -> Result<Vec<String>, String> {
-        let mut paths = Vec::new();
+    fn list_dir(&self, path: &str) -> Result<Vec<(String, bool)>, String> {
+        let mut entries = Vec::new();
         for entry in fs::read_dir(path).map_err(|e| e.to_string())? {
             let entry = entry.map_err(|e| e.to_string())?;
-            paths.push(entry.path().to_string_lossy().into());
+            let name = entry.file_name().to_string_lossy().into_owned();
+            entries.push((name, entry.path().is_dir()));
         }
-        Ok(paths)
+        entries.sort();
+        Ok(entries)
+    }
+    fn delete_file(&self, path: &str) -> Result<(), String> {
+        fs::remove_file(path).map_err(|e| e.to_string())
+    }
+    fn remove_dir(&self, path: &str) -> Result<(), String> {
+        fs::remove_dir(path).map_err(|e| e.to_string())
+    }
+    fn file_metadata(&self, path: &str) -> Result<(u64, f64), String> {
+        let meta = fs::metadata(path).map_err(|e| e.to_string())?;
+        let modified = (meta.modified())
+            .map_err(|e| e.to_string())?
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or_else(|e| -e.duration().as_secs_f64());
+        Ok((meta.len(), modified))
     }
     fn open_file(&self, path: &str) -> Result<Handle, String> {
         let handle = NATIVE_SYS.new_handle();
@@ -678,12 +1102,22 @@ impl SysBackend for NativeSys {
                 buf
             }
             SysStream::TcpListener(_) => return Err("Cannot read from a tcp listener".to_string()),
-            SysStream::TcpSocket(mut socket) => {
+            SysStream::TcpSocket(socket) => {
                 let mut buf = Vec::new();
-                Write::by_ref(&mut *socket)
+                match Write::by_ref(&mut *socket.lock())
                     .take(len as u64)
                     .read_to_end(&mut buf)
-                    .map_err(|e| e.to_string())?;
+                {
+                    Ok(_) => {}
+                    // Return what was received before the timeout
+                    Err(e)
+                        if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+                            && !buf.is_empty() => {}
+                    Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                        return Err("Timed out reading from tcp socket".into())
+                    }
+                    Err(e) => return Err(e.to_string()),
+                }
                 buf
             }
         })
@@ -703,41 +1137,37 @@ impl SysBackend for NativeSys {
         match NATIVE_SYS.get_stream(handle)? {
             SysStream::File(mut file) => file.write_all(conts).map_err(|e| e.to_string()),
             SysStream::TcpListener(_) => Err("Cannot write to a tcp listener".to_string()),
-            SysStream::TcpSocket(mut socket) => socket.write_all(conts).map_err(|e| e.to_string()),
+            SysStream::TcpSocket(socket) => {
+                let mut socket = socket.lock();
+                (socket.write_all(conts))
+                    .and_then(|_| socket.flush())
+                    .map_err(|e| e.to_string())
+            }
         }
     }
+    fn now(&self) -> f64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or_else(|e| -e.duration().as_secs_f64())
+    }
     fn sleep(&self, seconds: f64) -> Result<(), String> {
         sleep(Duration::from_secs_f64(seconds));
         Ok(())
     }
-    #[cfg(feature = "terminal_image")]
+    #[cfg(feature = "image")]
     fn show_image(&self, image: DynamicImage) -> Result<(), String> {
-        let (width, height) = if let Some((w, h)) = term_size::dimensions() {
-            let (tw, th) = (w as u32, h.saturating_sub(1) as u32);
-            let (iw, ih) = (image.width(), image.height() / 2);
-            let scaled_to_height = (iw * th / ih.max(1), th);
-            let scaled_to_width = (tw, ih * tw / iw.max(1));
-            let (w, h) = if scaled_to_height.0 <= tw {
-                scaled_to_height
-            } else {
-                scaled_to_width
-            };
-            (Some(w), Some(h))
-        } else {
-            (None, None)
-        };
-        viuer::print(
-            &image,
-            &viuer::Config {
-                width,
-                height,
-                absolute_offset: false,
-                transparent: true,
-                ..Default::default()
-            },
-        )
-        .map(drop)
-        .map_err(|e| format!("Failed to show image: {e}"))
+        // Only terminals with a graphics protocol show images well enough
+        #[cfg(feature = "terminal_image")]
+        if viuer::get_kitty_support() != viuer::KittySupport::None || viuer::is_iterm_supported() {
+            return show_image_in_terminal(&image);
+        }
+        let bytes = image_to_bytes(&image, ImageOutputFormat::Png)?;
+        open_image_file(self, &bytes, "png")
+    }
+    #[cfg(feature = "image")]
+    fn show_gif(&self, gif_bytes: Vec<u8>) -> Result<(), String> {
+        open_image_file(self, &gif_bytes, "gif")
     }
     #[cfg(feature = "audio")]
     fn play_audio(&self, wav_bytes: Vec<u8>) -> Result<(), String> {
@@ -823,16 +1253,18 @@ impl SysBackend for NativeSys {
         Ok(handle)
     }
     fn tcp_accept(&self, handle: Handle) -> Result<Handle, String> {
+        // The listener is cloned so that the map is not locked while waiting for a connection
         let listener = NATIVE_SYS
             .tcp_listeners
-            .get_mut(&handle)
-            .ok_or_else(|| "Invalid tcp listener handle".to_string())?;
+            .get(&handle)
+            .ok_or_else(|| "Invalid tcp listener handle".to_string())?
+            .try_clone()
+            .map_err(|e| e.to_string())?;
         let (stream, _) = listener.accept().map_err(|e| e.to_string())?;
-        drop(listener);
         let handle = NATIVE_SYS.new_handle();
         NATIVE_SYS
             .tcp_sockets
-            .insert(handle, Buffered::new_reader(stream));
+            .insert(handle, Arc::new(Mutex::new(Buffered::new_reader(stream))));
         Ok(handle)
     }
     fn tcp_connect(&self, addr: &str) -> Result<Handle, String> {
@@ -840,7 +1272,7 @@ impl SysBackend for NativeSys {
         let stream = TcpStream::connect(addr).map_err(|e| e.to_string())?;
         NATIVE_SYS
             .tcp_sockets
-            .insert(handle, Buffered::new_writer(stream));
+            .insert(handle, Arc::new(Mutex::new(Buffered::new_writer(stream))));
         NATIVE_SYS.hostnames.insert(
             handle,
             addr.split_once(':')
@@ -855,11 +1287,8 @@ impl SysBackend for NativeSys {
             .tcp_sockets
             .get(&handle)
             .ok_or_else(|| "Invalid tcp socket handle".to_string())?;
-        Ok(socket
-            .get_ref()
-            .peer_addr()
-            .map_err(|e| e.to_string())?
-            .to_string())
+        let addr = socket.lock().get_ref().peer_addr();
+        Ok(addr.map_err(|e| e.to_string())?.to_string())
     }
     fn tcp_set_non_blocking(&self, handle: Handle, non_blocking: bool) -> Result<(), String> {
         let socket = NATIVE_SYS
@@ -867,6 +1296,7 @@ impl SysBackend for NativeSys {
             .get(&handle)
             .ok_or_else(|| "Invalid tcp socket handle".to_string())?;
         socket
+            .lock()
             .get_ref()
             .set_nonblocking(non_blocking)
             .map_err(|e| e.to_string())?;
@@ -882,6 +1312,7 @@ impl SysBackend for NativeSys {
             .get(&handle)
             .ok_or_else(|| "Invalid tcp socket handle".to_string())?;
         socket
+            .lock()
             .get_ref()
             .set_read_timeout(timeout)
             .map_err(|e| e.to_string())?;
@@ -897,20 +1328,25 @@ impl SysBackend for NativeSys {
             .get(&handle)
             .ok_or_else(|| "Invalid tcp socket handle".to_string())?;
         socket
+            .lock()
             .get_ref()
             .set_write_timeout(timeout)
             .map_err(|e| e.to_string())?;
         Ok(())
     }
     fn close(&self, handle: Handle) -> Result<(), String> {
-        if NATIVE_SYS.files.remove(&handle).is_some()
+        if NATIVE_SYS.tcp_sockets.remove(&handle).is_some() {
+            NATIVE_SYS.hostnames.remove(&handle);
+            Ok(())
+        } else if NATIVE_SYS.files.remove(&handle).is_some()
             || NATIVE_SYS.tcp_listeners.remove(&handle).is_some()
-            || (NATIVE_SYS.tcp_sockets.remove(&handle).is_some()
-                && NATIVE_SYS.hostnames.remove(&handle).is_some())
         {
             Ok(())
         } else {
-            Err("Invalid stream handle".to_string())
+            Err(format!(
+                "Handle {} is not open. It may have already been closed.",
+                handle.0
+            ))
         }
     }
     fn spawn(
@@ -918,10 +1354,13 @@ impl SysBackend for NativeSys {
         mut env: Uiua,
         f: Box<dyn FnOnce(&mut Uiua) -> UiuaResult + Send>,
     ) -> Result<Handle, String> {
-        let thread = spawn(move || {
-            f(&mut env)?;
-            Ok(env.take_stack())
-        });
+        let thread = thread::Builder::new()
+            .stack_size(STACK_SIZE)
+            .spawn(move || {
+                f(&mut env)?;
+                Ok(env.take_stack())
+            })
+            .map_err(|e| e.to_string())?;
         let handle = NATIVE_SYS.new_handle();
         NATIVE_SYS.threads.insert(handle, thread);
         Ok(handle)
@@ -937,32 +1376,41 @@ impl SysBackend for NativeSys {
             Err(e) => Err(Err(format!("Thread panicked: {:?}", e))),
         }
     }
-    fn run_command_inherit(&self, command: &str, args: &[&str]) -> Result<(), String> {
-        Command::new(command)
+    fn run_command_inherit(&self, command: &str, args: &[&str]) -> Result<i32, String> {
+        let status = Command::new(command)
             .args(args)
             .spawn()
-            .map_err(|e| e.to_string())?
+            .map_err(|e| format!("Failed to run `{command}`: {e}"))?
             .wait()
             .map_err(|e| e.to_string())?;
-        Ok(())
+        Ok(status.code().unwrap_or(-1))
     }
     fn run_command_capture(
         &self,
         command: &str,
         args: &[&str],
-    ) -> Result<(String, String), String> {
+    ) -> Result<(i32, String, String), String> {
         let output = Command::new(command)
             .args(args)
             .output()
-            .map_err(|e| e.to_string())?;
+            .map_err(|e| format!("Failed to run `{command}`: {e}"))?;
         Ok((
+            output.status.code().unwrap_or(-1),
             String::from_utf8_lossy(&output.stdout).into(),
             String::from_utf8_lossy(&output.stderr).into(),
         ))
     }
+    fn current_directory(&self) -> Result<String, String> {
+        env::current_dir()
+            .map(|path| path.to_string_lossy().into_owned())
+            .map_err(|e| e.to_string())
+    }
     fn change_directory(&self, path: &str) -> Result<(), String> {
         env::set_current_dir(path).map_err(|e| e.to_string())
     }
+    fn http_request(&self, request: HttpRequest) -> Result<HttpResponse, String> {
+        crate::http::request(&request)
+    }
     #[cfg(feature = "https")]
     fn https_get(&self, request: &str, handle: Handle) -> Result<String, String> {
         let host = NATIVE_SYS
@@ -971,32 +1419,17 @@ impl SysBackend for NativeSys {
             .ok_or_else(|| "Invalid tcp socket handle".to_string())?;
         let request = check_http(request.to_string(), &host)?;
 
-        // https://github.com/rustls/rustls/blob/c9cfe3499681361372351a57a00ccd793837ae9c/examples/src/bin/simpleclient.rs
-        static CLIENT_CONFIG: Lazy<Arc<rustls::ClientConfig>> = Lazy::new(|| {
-            let mut store = rustls::RootCertStore::empty();
-            store.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
-                rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(
-                    ta.subject,
-                    ta.spki,
-                    ta.name_constraints,
-                )
-            }));
-            rustls::ClientConfig::builder()
-                .with_safe_defaults()
-                .with_root_certificates(store)
-                .with_no_client_auth()
-                .into()
-        });
-
-        let mut socket = NATIVE_SYS
+        let socket = NATIVE_SYS
             .tcp_sockets
-            .get_mut(&handle)
-            .ok_or_else(|| "Invalid tcp socket handle".to_string())?;
+            .get(&handle)
+            .ok_or_else(|| "Invalid tcp socket handle".to_string())?
+            .clone();
+        let mut socket = socket.lock();
 
         let server_name = rustls::ServerName::try_from(host.as_str()).map_err(|e| e.to_string())?;
         let tcp_stream = socket.get_mut();
 
-        let mut conn = rustls::ClientConnection::new(CLIENT_CONFIG.clone(), server_name)
+        let mut conn = rustls::ClientConnection::new(crate::http::tls_config(), server_name)
             .map_err(|e| e.to_string())?;
         let mut tls = rustls::Stream::new(&mut conn, tcp_stream);
 
@@ -1010,6 +1443,70 @@ impl SysBackend for NativeSys {
 
         Ok(s)
     }
+    #[cfg(feature = "regex")]
+    fn regex_find(&self, pattern: &str, subject: &str) -> Result<Vec<(usize, String)>, String> {
+        regex_find(pattern, subject)
+    }
+    #[cfg(feature = "regex")]
+    fn regex_captures(&self, pattern: &str, subject: &str) -> Result<Vec<String>, String> {
+        regex_captures(pattern, subject)
+    }
+}
+
+/// The number of compiled regexes that are kept for reuse
+#[cfg(feature = "regex")]
+const REGEX_CACHE_SIZE: usize = 64;
+
+/// Compile a regex, reusing it if it was used recently
+#[cfg(feature = "regex")]
+fn compile_regex(pattern: &str) -> Result<regex::Regex, String> {
+    // The most recently used regexes are at the end
+    static REGEXES: Lazy<Mutex<Vec<(String, regex::Regex)>>> = Lazy::new(Default::default);
+    let mut regexes = REGEXES.lock();
+    let regex = if let Some(i) = regexes.iter().position(|(p, _)| p == pattern) {
+        regexes.remove(i).1
+    } else {
+        regex::Regex::new(pattern).map_err(|e| format!("Invalid regex: {e}"))?
+    };
+    if regexes.len() == REGEX_CACHE_SIZE {
+        regexes.remove(0);
+    }
+    regexes.push((pattern.into(), regex.clone()));
+    Ok(regex)
+}
+
+/// Find all matches of a regex, along with their character indices
+///
+/// This can be used to implement [`SysBackend::regex_find`] in any backend.
+#[cfg(feature = "regex")]
+pub fn regex_find(pattern: &str, subject: &str) -> Result<Vec<(usize, String)>, String> {
+    let regex = compile_regex(pattern)?;
+    let mut char_index = 0;
+    let mut byte_index = 0;
+    Ok(regex
+        .find_iter(subject)
+        .map(|m| {
+            char_index += subject[byte_index..m.start()].chars().count();
+            byte_index = m.start();
+            (char_index, m.as_str().into())
+        })
+        .collect())
+}
+
+/// Get the capture groups of the first match of a regex
+///
+/// This can be used to implement [`SysBackend::regex_captures`] in any backend.
+#[cfg(feature = "regex")]
+pub fn regex_captures(pattern: &str, subject: &str) -> Result<Vec<String>, String> {
+    let regex = compile_regex(pattern)?;
+    Ok(regex
+        .captures(subject)
+        .map(|caps| {
+            caps.iter()
+                .map(|m| m.map_or_else(String::new, |m| m.as_str().into()))
+                .collect()
+        })
+        .unwrap_or_default())
 }
 
 /// Takes an HTTP request, validates it, and fixes it (if possible) by adding
@@ -1101,6 +1598,24 @@ fn check_http(mut request: String, hostname: &str) -> Result<String, String> {
 }
 
 impl SysOp {
+    /// Check whether this build of Uiua supports the system function
+    ///
+    /// Unsupported functions exist but always error when called
+    pub fn is_supported(&self) -> bool {
+        match self {
+            SysOp::RegexFind | SysOp::RegexCaptures => cfg!(feature = "regex"),
+            SysOp::ImDecode
+            | SysOp::ImEncode
+            | SysOp::ImShow
+            | SysOp::GifEncode
+            | SysOp::GifShow => {
+                cfg!(feature = "image")
+            }
+            SysOp::AudioPlay | SysOp::AudioStream => cfg!(feature = "audio"),
+            SysOp::HttpsWrite => cfg!(feature = "https"),
+            _ => true,
+        }
+    }
     pub(crate) fn run(&self, env: &mut Uiua) -> UiuaResult {
         match self {
             SysOp::Show => {
@@ -1132,6 +1647,10 @@ impl SysOp {
                     env.push(0u8);
                 }
             }
+            SysOp::ScanAll => {
+                let input = env.backend.scan_all_stdin().map_err(|e| env.error(e))?;
+                env.push(input);
+            }
             SysOp::TermSize => {
                 let (width, height) = env.backend.term_size().map_err(|e| env.error(e))?;
                 env.push(cowslice![height as f64, width as f64])
@@ -1152,11 +1671,13 @@ impl SysOp {
             SysOp::FOpen => {
                 let path = env.pop(1)?.as_string(env, "Path must be a string")?;
                 let handle = env.backend.open_file(&path).map_err(|e| env.error(e))?;
+                env.open_handles.insert(handle, env.backend.clone());
                 env.push(handle);
             }
             SysOp::FCreate => {
                 let path = env.pop(1)?.as_string(env, "Path must be a string")?;
                 let handle = env.backend.create_file(&path).map_err(|e| env.error(e))?;
+                env.open_handles.insert(handle, env.backend.clone());
                 env.push(handle.0 as f64);
             }
             SysOp::ReadStr => {
@@ -1356,8 +1877,25 @@ impl SysOp {
             }
             SysOp::FListDir => {
                 let path = env.pop(1)?.as_string(env, "Path must be a string")?;
-                let paths = env.backend.list_dir(&path).map_err(|e| env.error(e))?;
-                env.push(Array::<Arc<Function>>::from_iter(paths));
+                let entries = env.backend.list_dir(&path).map_err(|e| env.error(e))?;
+                let (names, is_dir): (Vec<String>, Vec<bool>) = entries.into_iter().unzip();
+                env.push(Array::<u8>::from_iter(is_dir.into_iter().map(u8::from)));
+                env.push(padded_strings(tiny_vec![names.len()], names, env));
+            }
+            SysOp::FDelete => {
+                let path = env.pop(1)?.as_string(env, "Path must be a string")?;
+                env.backend.delete_file(&path).map_err(|e| env.error(e))?;
+            }
+            SysOp::FRemoveDir => {
+                let path = env.pop(1)?.as_string(env, "Path must be a string")?;
+                env.backend.remove_dir(&path).map_err(|e| env.error(e))?;
+            }
+            SysOp::FMeta => {
+                let path = env.pop(1)?.as_string(env, "Path must be a string")?;
+                let (size, modified) = (env.backend)
+                    .file_metadata(&path)
+                    .map_err(|e| env.error(e))?;
+                env.push(Array::<f64>::from_iter([size as f64, modified]));
             }
             SysOp::FIsFile => {
                 let path = env.pop(1)?.as_string(env, "Path must be a string")?;
@@ -1381,6 +1919,7 @@ impl SysOp {
                 .map_err(|e| env.error(format!("Failed to read file: {e}")))?;
                 env.import(&input, path.as_ref())?;
             }
+            #[cfg(feature = "image")]
             SysOp::ImDecode => {
                 let bytes = match env.pop(1)? {
                     Value::Byte(arr) => {
@@ -1404,19 +1943,24 @@ impl SysOp {
                     _ => return Err(env.error("Image bytes must be a numeric array")),
                 };
                 let image = image::load_from_memory(&bytes)
-                    .map_err(|e| env.error(format!("Failed to read image: {}", e)))?
-                    .into_rgba8();
-                let shape = tiny_vec![image.height() as usize, image.width() as usize, 4];
+                    .map_err(|e| env.error(format!("Failed to read image: {}", e)))?;
+                let (height, width) = (image.height() as usize, image.width() as usize);
+                let color = image.color();
+                let (channels, raw) = match (color.has_color(), color.has_alpha()) {
+                    (false, false) => (1, image.into_luma8().into_raw()),
+                    (false, true) => (2, image.into_luma_alpha8().into_raw()),
+                    (true, false) => (3, image.into_rgb8().into_raw()),
+                    (true, true) => (4, image.into_rgba8().into_raw()),
+                };
                 let array = Array::<f64>::new(
-                    shape,
-                    image
-                        .into_raw()
-                        .into_iter()
+                    tiny_vec![height, width, channels],
+                    (raw.into_iter())
                         .map(|b| b as f64 / 255.0)
                         .collect::<CowSlice<_>>(),
                 );
                 env.push(array);
             }
+            #[cfg(feature = "image")]
             SysOp::ImEncode => {
                 let format = env
                     .pop(1)?
@@ -1428,29 +1972,45 @@ impl SysOp {
                     "bmp" => ImageOutputFormat::Bmp,
                     "gif" => ImageOutputFormat::Gif,
                     "ico" => ImageOutputFormat::Ico,
-                    format => return Err(env.error(format!("Invalid image format: {}", format))),
+                    format => {
+                        return Err(env.error(format!(
+                            "Invalid image format {format:?}. \
+                            Supported formats are jpg, png, bmp, gif, and ico."
+                        )))
+                    }
                 };
                 let bytes =
                     value_to_image_bytes(&value, output_format).map_err(|e| env.error(e))?;
                 env.push(Array::<u8>::from(bytes.as_slice()));
             }
+            #[cfg(feature = "image")]
             SysOp::ImShow => {
                 let value = env.pop(1)?;
                 let image = value_to_image(&value).map_err(|e| env.error(e))?;
                 env.backend.show_image(image).map_err(|e| env.error(e))?;
             }
+            #[cfg(feature = "image")]
             SysOp::GifEncode => {
                 let delay = env.pop(1)?.as_num(env, "Delay must be a number")?;
                 let value = env.pop(2)?;
                 let bytes = value_to_gif_bytes(&value, delay).map_err(|e| env.error(e))?;
                 env.push(Array::<u8>::from(bytes.as_slice()));
             }
+            #[cfg(feature = "image")]
             SysOp::GifShow => {
                 let delay = env.pop(1)?.as_num(env, "Delay must be a number")?;
                 let value = env.pop(2)?;
                 let bytes = value_to_gif_bytes(&value, delay).map_err(|e| env.error(e))?;
                 env.backend.show_gif(bytes).map_err(|e| env.error(e))?;
             }
+            #[cfg(not(feature = "image"))]
+            SysOp::ImDecode
+            | SysOp::ImEncode
+            | SysOp::ImShow
+            | SysOp::GifEncode
+            | SysOp::GifShow => {
+                return Err(env.error("Images are not supported in this build of Uiua"))
+            }
             SysOp::AudioDecode => {
                 let bytes = match env.pop(1)? {
                     Value::Byte(arr) => {
@@ -1482,12 +2042,37 @@ impl SysOp {
                     .as_string(env, "Audio format must be a string")?;
                 let value = env.pop(2)?;
                 let bytes = match format.as_str() {
-                    "wav" => value_to_wav_bytes(&value, env.backend.audio_sample_rate())
+                    "wav" => value_to_pcm_wav_bytes(&value, env.backend.audio_sample_rate())
                         .map_err(|e| env.error(e))?,
                     format => return Err(env.error(format!("Invalid audio format: {}", format))),
                 };
                 env.push(Array::<u8>::from(bytes.as_slice()));
             }
+            SysOp::AudioEncodeRate => {
+                let format = env
+                    .pop(1)?
+                    .as_string(env, "Audio format must be a string")?;
+                let sample_rate = env
+                    .pop(2)?
+                    .as_nat(env, "Sample rate must be a natural number")?;
+                let sample_rate = u32::try_from(sample_rate)
+                    .ok()
+                    .filter(|&rate| rate > 0)
+                    .ok_or_else(|| {
+                        env.error(format!(
+                            "Sample rate must be between 1 and {}, but it is {sample_rate}",
+                            u32::MAX
+                        ))
+                    })?;
+                let value = env.pop(3)?;
+                let bytes = match format.as_str() {
+                    "wav" => {
+                        value_to_pcm_wav_bytes(&value, sample_rate).map_err(|e| env.error(e))?
+                    }
+                    format => return Err(env.error(format!("Invalid audio format: {}", format))),
+                };
+                env.push(Array::<u8>::from(bytes.as_slice()));
+            }
             SysOp::AudioPlay => {
                 let value = env.pop(1)?;
                 let bytes = value_to_wav_bytes(&value, env.backend.audio_sample_rate())
@@ -1504,26 +2089,25 @@ impl SysOp {
                     .into_function()
                     .map_err(|_| env.error("Audio stream must be a function"))?;
                 let mut stream_env = env.clone();
+                let mut underrun_reported = false;
                 if let Err(e) = env.backend.stream_audio(Box::new(move |time_array| {
+                    let time_count = time_array.len();
                     let time_array = Array::<f64>::from(time_array.as_slice());
                     stream_env.push(time_array);
                     stream_env.call_function(f.clone())?;
-                    let samples = &stream_env.pop(1)?;
-                    let samples = samples.as_num_array().ok_or_else(|| {
-                        stream_env.error("Audio stream function must return a numeric array")
-                    })?;
-                    match samples.shape() {
-                        [_] => Ok(samples.data.iter().map(|&x| [x, x]).collect()),
-                        [_, 2] => Ok(samples
-                            .data
-                            .chunks(2)
-                            .map(|s| [s[0], s.get(1).copied().unwrap_or(0.0)])
-                            .collect()),
-                        _ => Err(stream_env.error(format!(
-                            "Audio stream function must return a rank 1 or 2 array, but returned a rank {} array",
-                            samples.rank()
-                        ))),
+                    let samples = stream_env.pop(1)?;
+                    let mut frames = stream_frames(&samples).map_err(|e| stream_env.error(e))?;
+                    if frames.len() < time_count && !underrun_reported {
+                        underrun_reported = true;
+                        eprintln!(
+                            "Audio stream underrun: the stream function returned {} samples \
+                            for {time_count} times, so the rest are silent. \
+                            This is only reported once.",
+                            frames.len()
+                        );
                     }
+                    frames.resize(time_count, [0.0; 2]);
+                    Ok(frames)
                 })) {
                     return Err(env.error(e));
                 }
@@ -1533,11 +2117,80 @@ impl SysOp {
                     .pop(1)?
                     .as_num(env, "Sleep time must be a number")?
                     .max(0.0);
-                env.backend.sleep(seconds).map_err(|e| env.error(e))?;
+                // Sleep in short chunks so that the program can still be interrupted
+                let mut remaining = seconds;
+                while remaining > 0.0 {
+                    if env.is_interrupted() {
+                        return Err(UiuaError::Interrupted(env.span()));
+                    }
+                    let chunk = remaining.min(SLEEP_CHUNK);
+                    env.backend.sleep(chunk).map_err(|e| env.error(e))?;
+                    remaining -= chunk;
+                }
+            }
+            SysOp::TimeFormat => {
+                let format = env.pop(1)?.as_string(env, "Time format must be a string")?;
+                let time = env.pop(2)?.as_num(env, "Time must be a number")?;
+                let formatted = format_time(&format, time).map_err(|e| env.error(e))?;
+                env.push(formatted);
+            }
+            SysOp::RegexFind => {
+                let pattern = env
+                    .pop(1)?
+                    .as_string(env, "Regex pattern must be a string")?;
+                let subject = env
+                    .pop(2)?
+                    .as_string(env, "Regex subject must be a string")?;
+                let matches = (env.backend)
+                    .regex_find(&pattern, &subject)
+                    .map_err(|e| env.error(e))?;
+                let (indices, matches): (Vec<usize>, Vec<String>) = matches.into_iter().unzip();
+                let indices: Array<f64> = indices.into_iter().map(|i| i as f64).collect();
+                env.push(indices);
+                env.push(padded_strings(tiny_vec![matches.len()], matches, env));
+            }
+            SysOp::RegexCaptures => {
+                let pattern = env
+                    .pop(1)?
+                    .as_string(env, "Regex pattern must be a string")?;
+                let subject = env
+                    .pop(2)?
+                    .as_string(env, "Regex subject must be a string")?;
+                let groups = (env.backend)
+                    .regex_captures(&pattern, &subject)
+                    .map_err(|e| env.error(e))?;
+                env.push(padded_strings(tiny_vec![groups.len()], groups, env));
+            }
+            SysOp::JsonParse => {
+                let json = env.pop(1)?.as_string(env, "JSON must be a string")?;
+                let value = parse_json(&json, env).map_err(|e| env.error(e))?;
+                env.push(value);
+            }
+            SysOp::JsonDump => {
+                let value = env.pop(1)?;
+                let json = dump_json(&value).map_err(|e| env.error(e))?;
+                env.push(json);
+            }
+            SysOp::CsvRead => {
+                let csv = env.pop(1)?.as_string(env, "CSV must be a string")?;
+                let records = parse_csv(&csv).map_err(|e| env.error(e))?;
+                env.push(csv_to_chars(records, env));
+            }
+            SysOp::CsvReadNums => {
+                let csv = env.pop(1)?.as_string(env, "CSV must be a string")?;
+                let records = parse_csv(&csv).map_err(|e| env.error(e))?;
+                let nums = csv_to_nums(records, env).map_err(|e| env.error(e))?;
+                env.push(nums);
+            }
+            SysOp::CsvWrite => {
+                let table = env.pop(1)?;
+                let csv = write_csv(&table).map_err(|e| env.error(e))?;
+                env.push(csv);
             }
             SysOp::TcpListen => {
                 let addr = env.pop(1)?.as_string(env, "Address must be a string")?;
                 let handle = env.backend.tcp_listen(&addr).map_err(|e| env.error(e))?;
+                env.open_handles.insert(handle, env.backend.clone());
                 env.push(handle);
             }
             SysOp::TcpAccept => {
@@ -1546,11 +2199,13 @@ impl SysOp {
                     .as_nat(env, "Handle must be an natural number")?
                     .into();
                 let new_handle = env.backend.tcp_accept(handle).map_err(|e| env.error(e))?;
+                env.open_handles.insert(new_handle, env.backend.clone());
                 env.push(new_handle);
             }
             SysOp::TcpConnect => {
                 let addr = env.pop(1)?.as_string(env, "Address must be a string")?;
                 let handle = env.backend.tcp_connect(&addr).map_err(|e| env.error(e))?;
+                env.open_handles.insert(handle, env.backend.clone());
                 env.push(handle);
             }
             SysOp::TcpAddr => {
@@ -1614,29 +2269,85 @@ impl SysOp {
                     .map_err(|e| env.error(e))?;
                 env.push(res);
             }
+            SysOp::HttpGet => {
+                let url = env.pop(1)?.as_string(env, "URL must be a string")?;
+                let response = env
+                    .backend
+                    .http_request(HttpRequest {
+                        method: "GET".into(),
+                        url,
+                        headers: Vec::new(),
+                        body: Vec::new(),
+                        timeout: HttpRequest::DEFAULT_TIMEOUT,
+                    })
+                    .map_err(|e| env.error(e))?;
+                push_http_response(response, env);
+            }
+            SysOp::HttpRequest => {
+                let method = env.pop(1)?.as_string(env, "Method must be a string")?;
+                let url = env.pop(2)?.as_string(env, "URL must be a string")?;
+                let headers = http_headers(&env.pop(3)?, env)?;
+                let body = match env.pop(4)? {
+                    Value::Char(chars) if chars.rank() <= 1 => {
+                        chars.data.iter().collect::<String>().into_bytes()
+                    }
+                    Value::Byte(bytes) if bytes.rank() <= 1 => bytes.data.to_vec(),
+                    Value::Num(nums) if nums.rank() <= 1 => (nums.data.iter())
+                        .map(|&n| {
+                            if n.fract() == 0.0 && (0.0..=255.0).contains(&n) {
+                                Ok(n as u8)
+                            } else {
+                                Err(env.error(format!(
+                                    "Body bytes must be integers from 0 to 255, but {n} is not"
+                                )))
+                            }
+                        })
+                        .collect::<UiuaResult<_>>()?,
+                    _ => return Err(env.error("Body must be a string or a list of bytes")),
+                };
+                let response = env
+                    .backend
+                    .http_request(HttpRequest {
+                        method: method.to_uppercase(),
+                        url,
+                        headers,
+                        body,
+                        timeout: HttpRequest::DEFAULT_TIMEOUT,
+                    })
+                    .map_err(|e| env.error(e))?;
+                push_http_response(response, env);
+            }
             SysOp::Close => {
                 let handle = env
                     .pop(1)?
                     .as_nat(env, "Handle must be an natural number")?
                     .into();
                 env.backend.close(handle).map_err(|e| env.error(e))?;
+                env.open_handles.remove(handle);
             }
             SysOp::RunInherit => {
                 let (command, args) = value_to_command(&env.pop(1)?, env)?;
                 let args: Vec<_> = args.iter().map(|s| s.as_str()).collect();
-                env.backend
+                let code = env
+                    .backend
                     .run_command_inherit(&command, &args)
                     .map_err(|e| env.error(e))?;
+                env.push(code);
             }
             SysOp::RunCapture => {
                 let (command, args) = value_to_command(&env.pop(1)?, env)?;
                 let args: Vec<_> = args.iter().map(|s| s.as_str()).collect();
-                let (stdout, stderr) = env
+                let (code, stdout, stderr) = env
                     .backend
                     .run_command_capture(&command, &args)
                     .map_err(|e| env.error(e))?;
                 env.push(stdout);
                 env.push(stderr);
+                env.push(code);
+            }
+            SysOp::CurrentDirectory => {
+                let dir = env.backend.current_directory().map_err(|e| env.error(e))?;
+                env.push(dir);
             }
             SysOp::ChangeDirectory => {
                 let path = env.pop(1)?.as_string(env, "Path must be a string")?;
@@ -1649,6 +2360,50 @@ impl SysOp {
     }
 }
 
+fn http_headers(value: &Value, env: &Uiua) -> UiuaResult<Vec<(String, String)>> {
+    match value {
+        _ if value.row_count() == 0 => Ok(Vec::new()),
+        Value::Func(fs) if fs.rank() == 2 && fs.shape()[1] == 2 => {
+            let string = |f: &Arc<Function>| match f.as_boxed().filter(|_| f.is_constant()) {
+                Some(Value::Char(s)) if s.rank() <= 1 => Ok(s.data.iter().collect::<String>()),
+                _ => Err(env.error("Header names and values must be boxed strings")),
+            };
+            (fs.data.chunks_exact(2))
+                .map(|pair| Ok((string(&pair[0])?, string(&pair[1])?)))
+                .collect()
+        }
+        _ => Err(env.error(format!(
+            "Headers must be a rank 2 array of boxed strings with 2 columns, \
+            but they are a {} array with shape {}",
+            value.type_name(),
+            value.format_shape()
+        ))),
+    }
+}
+
+fn push_http_response(response: HttpResponse, env: &mut Uiua) {
+    let content_type = (response.headers.iter())
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+        .map(|(_, value)| value.to_ascii_lowercase())
+        .unwrap_or_default();
+    let is_text = content_type.starts_with("text/")
+        || ["json", "xml", "javascript"]
+            .iter()
+            .any(|kind| content_type.contains(kind));
+    if is_text {
+        env.push(String::from_utf8_lossy(&response.body).into_owned());
+    } else {
+        env.push(Array::from(response.body.as_slice()));
+    }
+    let header_count = response.headers.len();
+    let headers: CowSlice<_> = (response.headers.into_iter())
+        .flat_map(|(name, value)| [name, value])
+        .map(|s| Arc::new(Function::constant(s)))
+        .collect();
+    env.push(Array::new(tiny_vec![header_count, 2], headers));
+    env.push(response.status as f64);
+}
+
 fn value_to_command(value: &Value, env: &Uiua) -> UiuaResult<(String, Vec<String>)> {
     let mut strings = Vec::new();
     match value {
@@ -1710,10 +2465,12 @@ fn value_to_command(value: &Value, env: &Uiua) -> UiuaResult<(String, Vec<String
     Ok((command, strings))
 }
 
+#[cfg(feature = "image")]
 pub fn value_to_image_bytes(value: &Value, format: ImageOutputFormat) -> Result<Vec<u8>, String> {
     image_to_bytes(&value_to_image(value)?, format)
 }
 
+#[cfg(feature = "image")]
 pub fn image_to_bytes(image: &DynamicImage, format: ImageOutputFormat) -> Result<Vec<u8>, String> {
     let mut bytes = Cursor::new(Vec::new());
     image
@@ -1722,15 +2479,19 @@ pub fn image_to_bytes(image: &DynamicImage, format: ImageOutputFormat) -> Result
     Ok(bytes.into_inner())
 }
 
+#[cfg(feature = "image")]
 pub fn value_to_image(value: &Value) -> Result<DynamicImage, String> {
     if ![2, 3].contains(&value.rank()) {
-        return Err("Image must be a rank 2 or 3 numeric array".into());
+        return Err(format!(
+            "Image must be a rank 2 or 3 numeric array, but its shape is {}",
+            value.format_shape()
+        ));
     }
     let bytes = match value {
         Value::Num(nums) => nums
             .data
             .iter()
-            .map(|f| (*f * 255.0).floor() as u8)
+            .map(|f| (f.clamp(0.0, 1.0) * 255.0).round() as u8)
             .collect(),
         Value::Byte(bytes) => bytes.data.iter().map(|&b| (b > 0) as u8 * 255).collect(),
         _ => return Err("Image must be a numeric array".into()),
@@ -1754,9 +2515,33 @@ pub fn value_to_image(value: &Value) -> Result<DynamicImage, String> {
         4 => image::RgbaImage::from_raw(width as u32, height as u32, bytes)
             .ok_or("Failed to create image")?
             .into(),
-        n => {
+        _ => {
             return Err(format!(
-                "For a color image, the last dimension of the image array must be between 1 and 4 but it is {n}"
+                "The last axis of a rank 3 image array must be 1, 2, 3, or 4 \
+                for grayscale, grayscale with alpha, RGB, or RGBA, but its shape is {}",
+                value.format_shape()
+            ))
+        }
+    })
+}
+
+/// Get the stereo frames returned by an [`SysOp::AudioStream`] function
+fn stream_frames(samples: &Value) -> Result<Vec<[f64; 2]>, String> {
+    let channels = value_to_audio_channels(samples).map_err(|_| {
+        format!(
+            "Audio stream function must return a list of samples or a rank 2 array \
+            with 1 or 2 rows for the channels, but it returned a {} array with shape {}",
+            samples.type_name(),
+            samples.format_shape()
+        )
+    })?;
+    Ok(match channels.as_slice() {
+        [mono] => mono.iter().map(|&s| [s, s]).collect(),
+        [left, right] => left.iter().zip(right).map(|(&l, &r)| [l, r]).collect(),
+        _ => {
+            return Err(format!(
+                "Audio stream function must return at most 2 channels, but it returned {}",
+                channels.len()
             ))
         }
     })
@@ -1838,13 +2623,7 @@ pub fn value_to_audio_channels(audio: &Value) -> Result<Vec<Vec<f64>>, String> {
 pub fn value_to_wav_bytes(audio: &Value, sample_rate: u32) -> Result<Vec<u8>, String> {
     #[cfg(not(feature = "audio"))]
     {
-        value_to_wav_bytes_impl(
-            audio,
-            |f| (f * i16::MAX as f64) as i16,
-            16,
-            SampleFormat::Int,
-            sample_rate,
-        )
+        value_to_pcm_wav_bytes(audio, sample_rate)
     }
     #[cfg(feature = "audio")]
     {
@@ -1852,6 +2631,17 @@ pub fn value_to_wav_bytes(audio: &Value, sample_rate: u32) -> Result<Vec<u8>, St
     }
 }
 
+/// Encode audio as a 16-bit PCM WAV file
+pub fn value_to_pcm_wav_bytes(audio: &Value, sample_rate: u32) -> Result<Vec<u8>, String> {
+    value_to_wav_bytes_impl(
+        audio,
+        |f| (f * i16::MAX as f64).round() as i16,
+        16,
+        SampleFormat::Int,
+        sample_rate,
+    )
+}
+
 fn value_to_wav_bytes_impl<T: hound::Sample + Copy>(
     audio: &Value,
     convert_samples: impl Fn(f64) -> T + Copy,
@@ -1863,7 +2653,11 @@ fn value_to_wav_bytes_impl<T: hound::Sample + Copy>(
     let channels = value_to_audio_channels(audio)?;
     let channels: Vec<Vec<T>> = channels
         .into_iter()
-        .map(|c| c.into_iter().map(convert_samples).collect())
+        .map(|c| {
+            (c.into_iter())
+                .map(|s| convert_samples(s.clamp(-1.0, 1.0)))
+                .collect()
+        })
         .collect();
     let spec = WavSpec {
         channels: channels.len() as u16,
@@ -1928,7 +2722,9 @@ fn array_from_wav_bytes_impl<T: hound::Sample>(
     }
 }
 
+#[cfg(feature = "image")]
 pub fn value_to_gif_bytes(value: &Value, frame_rate: f64) -> Result<Vec<u8>, String> {
+    use std::collections::HashSet;
     if value.row_count() == 0 {
         return Err("Cannot convert empty array into GIF".into());
     }
@@ -1992,3 +2788,370 @@ pub fn value_to_gif_bytes(value: &Value, frame_rate: f64) -> Result<Vec<u8>, Str
     drop(encoder);
     Ok(bytes.into_inner())
 }
+
+/// The longest time that [`SysOp::Sleep`] sleeps before checking for interrupts
+const SLEEP_CHUNK: f64 = 0.1;
+
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Convert a number of days since the Unix epoch to a (year, month, day) date
+///
+/// Months and days start at 1.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+/// Format a time in seconds since the Unix epoch as a UTC date and time
+fn format_time(format: &str, time: f64) -> Result<String, String> {
+    if !time.is_finite() {
+        return Err(format!("Cannot format {time} as a time"));
+    }
+    let millis = (time * 1000.0).floor() as i64;
+    let secs = millis.div_euclid(1000);
+    let days = secs.div_euclid(86400);
+    let secs_of_day = secs.rem_euclid(86400);
+    let (year, month, day) = civil_from_days(days);
+    let day_of_year = {
+        const CUMULATIVE: [i64; 12] = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+        let leap = (month > 2 && is_leap_year(year)) as i64;
+        CUMULATIVE[month as usize - 1] + day + leap
+    };
+    let weekday = WEEKDAYS[(days + 4).rem_euclid(7) as usize];
+    let month_name = MONTHS[month as usize - 1];
+    let mut formatted = String::new();
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            formatted.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => formatted.push_str(&year.to_string()),
+            Some('y') => formatted.push_str(&format!("{:02}", year.rem_euclid(100))),
+            Some('m') => formatted.push_str(&format!("{month:02}")),
+            Some('d') => formatted.push_str(&format!("{day:02}")),
+            Some('j') => formatted.push_str(&format!("{day_of_year:03}")),
+            Some('H') => formatted.push_str(&format!("{:02}", secs_of_day / 3600)),
+            Some('M') => formatted.push_str(&format!("{:02}", secs_of_day / 60 % 60)),
+            Some('S') => formatted.push_str(&format!("{:02}", secs_of_day % 60)),
+            Some('L') => formatted.push_str(&format!("{:03}", millis.rem_euclid(1000))),
+            Some('a') => formatted.push_str(&weekday[..3]),
+            Some('A') => formatted.push_str(weekday),
+            Some('b') => formatted.push_str(&month_name[..3]),
+            Some('B') => formatted.push_str(month_name),
+            Some('s') => formatted.push_str(&secs.to_string()),
+            Some('%') => formatted.push('%'),
+            Some(c) => return Err(format!("Unknown time format specifier %{c}")),
+            None => return Err("Time format cannot end with a lone %".into()),
+        }
+    }
+    Ok(formatted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::tests::error_message;
+
+    #[test]
+    fn environment() {
+        let mut env = Uiua::with_native_sys();
+        env.load_str("&cwd").unwrap();
+        let cwd = env.pop("cwd").unwrap().try_string().unwrap();
+        assert_eq!(std::path::Path::new(&cwd), std::env::current_dir().unwrap());
+        let error = env.load_str("&cd \"tests/not a directory\"").unwrap_err();
+        assert!(error.message().contains("No such file or directory") || cfg!(windows));
+        let path = std::env::var("PATH").unwrap_or_default();
+        env.load_str("&var \"PATH\"").unwrap();
+        assert_eq!(env.pop("path").unwrap().try_string().unwrap(), path);
+    }
+
+    #[test]
+    fn file_system() {
+        use std::fs;
+        let dir = std::env::temp_dir().join(format!("uiua-fs-test-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        let dir_str = dir.to_string_lossy().into_owned();
+        let mut env = Uiua::with_native_sys();
+        env.push(dir_str.clone());
+        env.load_str(
+            "Dir ←\n\
+            &fwa ⊂Dir \"/b.csv\" \"1,2\"\n\
+            &fwa ⊂Dir \"/a.txt\" \"\"\n\
+            &fld Dir\n\
+            &fmeta ⊂Dir \"/b.csv\"\n\
+            &fe ⊂Dir \"/a.txt\"\n\
+            &fde ⊂Dir \"/a.txt\"\n\
+            &fe ⊂Dir \"/a.txt\"\n\
+            &frd ⊂Dir \"/sub\"\n\
+            &fld Dir",
+        )
+        .unwrap();
+        let stack = env.take_stack();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(stack[1].try_strings().unwrap(), ["a.txt", "b.csv", "sub  "]);
+        assert_eq!(stack[0].try_nums().unwrap(), [0.0, 0.0, 1.0]);
+        let [size, modified] = stack[2].try_nums().unwrap()[..] else {
+            panic!("Expected size and modification time")
+        };
+        assert_eq!(size, 3.0);
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs_f64();
+        assert!((now - modified).abs() < 60.0, "{modified}");
+        assert!(stack[3].try_bool().unwrap());
+        assert!(!stack[4].try_bool().unwrap());
+        assert_eq!(stack[6].try_strings().unwrap(), ["b.csv"]);
+        assert_eq!(stack[5].try_nums().unwrap(), [0.0]);
+
+        let error = env.load_str("&fde \"tests\"").unwrap_err();
+        assert!(!error.message().is_empty());
+        let error = env.load_str("&frd \"tests\"").unwrap_err();
+        assert!(!error.message().is_empty());
+        assert!(std::path::Path::new("tests/units.ua").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_commands() {
+        let mut env = Uiua::with_native_sys();
+        // Arguments are passed as-is, without shell interpretation
+        env.load_str("&runc {\"printf\" \"%s|%s\" \"a b\" \"$HOME 'c'\"}")
+            .unwrap();
+        assert_eq!(env.pop("code").unwrap().try_int().unwrap(), 0);
+        assert_eq!(env.pop("stderr").unwrap().try_string().unwrap(), "");
+        assert_eq!(
+            env.pop("stdout").unwrap().try_string().unwrap(),
+            "a b|$HOME 'c'"
+        );
+        env.load_str("&runc {\"sh\" \"-c\" \"printf 'x\\\\377y'; echo oops >&2; exit 3\"}")
+            .unwrap();
+        assert_eq!(env.pop("code").unwrap().try_int().unwrap(), 3);
+        assert_eq!(env.pop("stderr").unwrap().try_string().unwrap(), "oops\n");
+        assert_eq!(
+            env.pop("stdout").unwrap().try_string().unwrap(),
+            "x\u{FFFD}y"
+        );
+        env.load_str("&runi {\"sh\" \"-c\" \"exit 2\"}").unwrap();
+        assert_eq!(env.pop("code").unwrap().try_int().unwrap(), 2);
+        let error = env
+            .load_str("&runc \"uiua-surely-not-a-program\"")
+            .unwrap_err();
+        assert!(error
+            .message()
+            .starts_with("Failed to run `uiua-surely-not-a-program`: "));
+    }
+
+    #[test]
+    fn tcp_sockets() {
+        // Let the OS pick a free port
+        let addr = (std::net::TcpListener::bind("127.0.0.1:0").unwrap())
+            .local_addr()
+            .unwrap();
+        let mut env = Uiua::with_native_sys();
+        env.load_str(&format!(
+            "L ← &tcpl \"{addr}\"\nC ← &tcpc \"{addr}\"\nS ← &tcpa L\n\
+            &w \"hello\" C\n&tcpsrt 0.1 S\n+@\\0 &rb 100 S"
+        ))
+        .unwrap();
+        // Data received before a timeout is returned
+        assert_eq!(env.pop("bytes").unwrap().try_string().unwrap(), "hello");
+        let error = env.load_str("&rb 100 S").unwrap_err();
+        assert_eq!(error.message(), "Timed out reading from tcp socket");
+        env.load_str("&cl C").unwrap();
+        let error = env.load_str("&cl C").unwrap_err();
+        assert!(error
+            .message()
+            .ends_with("is not open. It may have already been closed."));
+        // Handles left open are closed when the runtime is dropped
+        drop(env);
+        std::net::TcpListener::bind(addr).unwrap();
+    }
+
+    #[test]
+    fn fake_clock() {
+        use std::sync::Mutex;
+        struct FakeClock(Mutex<f64>);
+        impl SysBackend for FakeClock {
+            fn any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn now(&self) -> f64 {
+                *self.0.lock().unwrap()
+            }
+            fn sleep(&self, seconds: f64) -> Result<(), String> {
+                *self.0.lock().unwrap() += seconds;
+                Ok(())
+            }
+        }
+        let mut env = Uiua::with_backend(FakeClock(Mutex::new(1e9)));
+        env.load_str(
+            r#"⍤"wrong time" ≅ "2001-09-09 01:46:40" &tf "%Y-%m-%d %H:%M:%S" now
+⍤"wrong sleep" = 3600 ⁅⍜now(&sl 3600)
+⍤"wrong time after sleep" ≅ "Sun 02:46" &tf "%a %H:%M" now"#,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn regex_errors() {
+        let message = Uiua::with_native_sys()
+            .load_str("&rxf \"[\" \"abc\"")
+            .unwrap_err()
+            .located_message();
+        assert!(message.starts_with("1:1: Invalid regex: "), "{message}");
+        assert!(message.contains("unclosed character class"), "{message}");
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn regex_cache() {
+        // Regexes are still found after being pushed out of the cache
+        for i in 0..=REGEX_CACHE_SIZE * 2 {
+            let pattern = format!("{i}+");
+            let subject = format!("a{i}{i}");
+            assert_eq!(regex_find(&pattern, &subject).unwrap()[0].0, 1);
+        }
+        assert_eq!(regex_captures("(b)", "abc").unwrap(), ["b", "b"]);
+        assert_eq!(regex_find("0+", "a00").unwrap(), [(1, "00".into())]);
+    }
+
+    #[test]
+    #[cfg(feature = "image")]
+    fn image_errors() {
+        assert_eq!(
+            error_message("&ime \"png\" [1 2 3]"),
+            "1:1: Image must be a rank 2 or 3 numeric array, but its shape is [3]"
+        );
+        assert_eq!(
+            error_message("&ime \"png\" ↯2_2_5 0"),
+            "1:1: The last axis of a rank 3 image array must be 1, 2, 3, or 4 \
+            for grayscale, grayscale with alpha, RGB, or RGBA, but its shape is [2 × 2 × 5]"
+        );
+        assert_eq!(
+            error_message("&ime \"tiff\" [[0]]"),
+            "1:1: Invalid image format \"tiff\". Supported formats are jpg, png, bmp, gif, and ico."
+        );
+        assert!(error_message("&imd [1 2 3]").starts_with("1:1: Failed to read image: "));
+    }
+
+    #[test]
+    fn wav_encoding() {
+        let mut env = Uiua::with_native_sys();
+        env.load_str("&aer \"wav\" 8000 [[0 0.5 ¯1 2] [1 ¯0.25 ¯3 0]]")
+            .unwrap();
+        let Some(Value::Byte(bytes)) = env.take_stack().pop() else {
+            panic!("&aer did not return bytes");
+        };
+        let mut reader = hound::WavReader::new(bytes.data.as_slice()).unwrap();
+        let spec = reader.spec();
+        assert_eq!(spec.channels, 2);
+        assert_eq!(spec.sample_rate, 8000);
+        assert_eq!(spec.bits_per_sample, 16);
+        assert_eq!(spec.sample_format, hound::SampleFormat::Int);
+        let samples: Vec<i16> = reader.samples().map(Result::unwrap).collect();
+        // Samples are interleaved and out-of-range samples are clamped
+        assert_eq!(samples, [0, 32767, 16384, -8192, -32767, -32767, 32767, 0]);
+
+        assert_eq!(
+            error_message("&aer \"wav\" 0 [0]"),
+            "1:1: Sample rate must be between 1 and 4294967295, but it is 0"
+        );
+        assert_eq!(
+            error_message("&aer \"mp3\" 8000 [0]"),
+            "1:1: Invalid audio format: mp3"
+        );
+    }
+
+    #[test]
+    fn audio_stream_channels() {
+        use std::sync::Mutex;
+        // Calls the stream function once with 4 sample times
+        struct StreamOnce(Mutex<Option<Result<Vec<[f64; 2]>, String>>>);
+        impl SysBackend for StreamOnce {
+            fn any(&self) -> &dyn std::any::Any {
+                self
+            }
+            fn stream_audio(&self, mut f: AudioStreamFn) -> Result<(), String> {
+                let frames = f(vec![0.0, 0.25, 0.5, 0.75]).map_err(|e| e.message());
+                *self.0.lock().unwrap() = Some(frames);
+                Ok(())
+            }
+        }
+        let stream = |code: &str| {
+            let mut env = Uiua::with_backend(StreamOnce(Mutex::new(None)));
+            env.load_str(code).unwrap();
+            let backend = env.downcast_backend::<StreamOnce>().unwrap();
+            let frames = backend.0.lock().unwrap().take();
+            frames.unwrap()
+        };
+        // Mono samples are played in both channels
+        assert_eq!(
+            stream("&ast(×2)"),
+            Ok(vec![[0.0, 0.0], [0.5, 0.5], [1.0, 1.0], [1.5, 1.5]])
+        );
+        // Rows are the left and right channels
+        assert_eq!(
+            stream("&ast(⊟∶¯.)"),
+            Ok(vec![[0.0, -0.0], [0.25, -0.25], [0.5, -0.5], [0.75, -0.75]])
+        );
+        // Missing samples are silent, and extra ones are ignored
+        assert_eq!(
+            stream("&ast(↙2)"),
+            Ok(vec![[0.0, 0.0], [0.25, 0.25], [0.0, 0.0], [0.0, 0.0]])
+        );
+        assert_eq!(
+            stream("&ast(⊂∶5)"),
+            Ok(vec![[0.0, 0.0], [0.25, 0.25], [0.5, 0.5], [0.75, 0.75]])
+        );
+        assert_eq!(
+            stream("&ast(↯3_4)"),
+            Err("Audio stream function must return at most 2 channels, but it returned 3".into())
+        );
+        assert_eq!(
+            stream("&ast(\"hi\";)"),
+            Err(
+                "Audio stream function must return a list of samples or a rank 2 array \
+                with 1 or 2 rows for the channels, but it returned a character array with shape [2]"
+                    .into()
+            )
+        );
+    }
+}
@@ -14,9 +14,10 @@ use crate::{
     array::*,
     cowslice::CowSlice,
     function::{Function, Signature},
-    grid_fmt::GridFmt,
+    grid_fmt::{colorize_shown, GridFmt},
+    lex::Span,
     primitive::Primitive,
-    Uiua, UiuaResult,
+    Uiua, UiuaError, UiuaResult,
 };
 
 #[derive(Clone)]
@@ -344,6 +345,66 @@ impl Value {
             Self::Func(array) => array.grid_string(),
         }
     }
+    /// Get the pretty-printed string representation of the value with ANSI colors
+    ///
+    /// Negative numbers and the shape of truncated arrays are colored,
+    /// and array outlines and elisions are dimmed.
+    pub fn show_colored(&self) -> String {
+        colorize_shown(&self.show(), !matches!(self, Value::Char(_)))
+    }
+    /// Write the value as plain JSON data, as [`&jsd`](crate::SysOp::JsonDump) does
+    ///
+    /// Lists become JSON arrays and character lists become strings, so the shape and type of
+    /// the value are not kept. Functions and infinite numbers cannot be written.
+    /// `Value::to_json` from the `serde` feature is the format that keeps both.
+    pub fn dump_json(&self) -> UiuaResult<String> {
+        crate::json::dump_json(self).map_err(conversion_error)
+    }
+    /// Get a plain text representation of the value for other programs to read
+    ///
+    /// Numbers are written with `-` for negatives and separated by spaces.
+    /// Character arrays are written as their characters.
+    /// Rows of a rank 2 array are on separate lines,
+    /// and the rows of higher rank arrays are separated by blank lines.
+    /// Boxes are written as their contents. Functions cannot be written.
+    pub fn show_raw(&self) -> UiuaResult<String> {
+        let mut raw = String::new();
+        self.write_raw(&mut raw)?;
+        Ok(raw)
+    }
+    fn write_raw(&self, raw: &mut String) -> UiuaResult {
+        if self.rank() == 0 {
+            match self {
+                Value::Num(nums) => raw.push_str(&nums.data[0].to_string()),
+                Value::Byte(bytes) => raw.push_str(&bytes.data[0].to_string()),
+                Value::Char(chars) => raw.push(chars.data[0]),
+                Value::Func(fs) => match fs.data[0].as_boxed().filter(|_| fs.data[0].is_constant())
+                {
+                    Some(value) => value.write_raw(raw)?,
+                    None => {
+                        return Err(conversion_error(format!(
+                            "Cannot write function {} as raw text",
+                            fs.data[0]
+                        )))
+                    }
+                },
+            }
+            return Ok(());
+        }
+        let separator = match (self, self.rank()) {
+            (Value::Char(_), 1) => "",
+            (Value::Func(_), 1) => "\n",
+            (_, 1) => " ",
+            (_, rank) => &"\n\n"[..rank.min(3) - 1],
+        };
+        for (i, row) in self.rows().enumerate() {
+            if i > 0 {
+                raw.push_str(separator);
+            }
+            row.write_raw(raw)?;
+        }
+        Ok(())
+    }
     pub fn as_primitive(&self) -> Option<(Primitive, usize)> {
         if let Value::Func(fs) = self {
             if fs.rank() == 0 {
@@ -352,6 +413,14 @@ impl Value {
         }
         None
     }
+    /// Check that calling this value, or any function in it, can only affect the stack
+    #[cfg(feature = "parallel")]
+    pub(crate) fn is_pure(&self) -> bool {
+        match self {
+            Value::Func(fs) => fs.data.iter().all(|f| f.is_pure()),
+            _ => true,
+        }
+    }
     pub(crate) fn as_flipped_primitive(&self) -> Option<(Primitive, bool)> {
         if let Value::Func(fs) = self {
             if fs.rank() == 0 {
@@ -743,6 +812,148 @@ impl From<i32> for Value {
     }
 }
 
+impl From<i64> for Value {
+    fn from(i: i64) -> Self {
+        Value::from(i as f64)
+    }
+}
+
+impl From<Vec<f64>> for Value {
+    fn from(nums: Vec<f64>) -> Self {
+        nums.into_iter().collect()
+    }
+}
+
+/// Each string is boxed, so strings of different lengths are preserved
+impl From<Vec<String>> for Value {
+    fn from(strings: Vec<String>) -> Self {
+        (strings.into_iter())
+            .map(|s| Arc::new(Function::constant(s)))
+            .collect()
+    }
+}
+
+/// Fails if the rows do not all have the same length, like building an array from them would
+impl TryFrom<Vec<Vec<f64>>> for Value {
+    type Error = UiuaError;
+    fn try_from(rows: Vec<Vec<f64>>) -> UiuaResult<Self> {
+        let row_len = rows.first().map_or(0, Vec::len);
+        let mut data = EcoVec::with_capacity(rows.len() * row_len);
+        for row in &rows {
+            if row.len() != row_len {
+                return Err(conversion_error(format!(
+                    "Cannot make an array from rows of lengths {row_len} and {}. \
+                    All rows of an array must have the same shape.",
+                    row.len()
+                )));
+            }
+            data.extend_from_slice(row);
+        }
+        Ok((Shape::from([rows.len(), row_len].as_slice()), data).into())
+    }
+}
+
+pub(crate) fn conversion_error(message: String) -> UiuaError {
+    UiuaError::Run(Span::Builtin.sp(message))
+}
+
+/// Conversions to Rust values, for getting results out of the interpreter
+impl Value {
+    fn expect_shape(&self, expected: &str, rank: usize) -> UiuaResult {
+        if self.rank() == rank {
+            Ok(())
+        } else {
+            Err(conversion_error(format!(
+                "Expected {expected}, but the value has shape {}",
+                self.format_shape()
+            )))
+        }
+    }
+    fn expect_type(&self, expected: &str) -> UiuaError {
+        conversion_error(format!(
+            "Expected {expected}, but the value is a {} array",
+            self.type_name()
+        ))
+    }
+    /// Get the value as a number if it is a scalar number
+    pub fn try_num(&self) -> UiuaResult<f64> {
+        self.expect_shape("a scalar number", 0)?;
+        match self {
+            Value::Num(nums) => Ok(nums.data[0]),
+            Value::Byte(bytes) => Ok(bytes.data[0] as f64),
+            value => Err(value.expect_type("a scalar number")),
+        }
+    }
+    /// Get the value as an integer if it is a scalar integer
+    pub fn try_int(&self) -> UiuaResult<i64> {
+        let num = self.try_num()?;
+        if num.fract() != 0.0 || num.abs() > i64::MAX as f64 {
+            return Err(conversion_error(format!(
+                "Expected an integer, but it is {num}"
+            )));
+        }
+        Ok(num as i64)
+    }
+    /// Get the value as a boolean if it is a scalar `0` or `1`
+    pub fn try_bool(&self) -> UiuaResult<bool> {
+        match self.try_num()? {
+            0.0 => Ok(false),
+            1.0 => Ok(true),
+            num => Err(conversion_error(format!(
+                "Expected a boolean, but it is {num}"
+            ))),
+        }
+    }
+    /// Get the value as a list of numbers if it is a rank 1 number array
+    pub fn try_nums(&self) -> UiuaResult<Vec<f64>> {
+        self.expect_shape("a list of numbers", 1)?;
+        match self {
+            Value::Num(nums) => Ok(nums.data.to_vec()),
+            Value::Byte(bytes) => Ok(bytes.data.iter().map(|&b| b as f64).collect()),
+            value => Err(value.expect_type("a list of numbers")),
+        }
+    }
+    /// Get the value as a string if it is a rank 1 character array
+    pub fn try_string(&self) -> UiuaResult<String> {
+        self.expect_shape("a string", 1)?;
+        match self {
+            Value::Char(chars) => Ok(chars.data.iter().collect()),
+            value => Err(value.expect_type("a string")),
+        }
+    }
+    /// Get the value as a list of strings
+    ///
+    /// The value must be a rank 2 character array or a list of boxed strings.
+    pub fn try_strings(&self) -> UiuaResult<Vec<String>> {
+        match self {
+            Value::Char(_) => {
+                self.expect_shape("a list of strings", 2)?;
+                self.rows().map(|row| row.try_string()).collect()
+            }
+            Value::Func(fs) => {
+                self.expect_shape("a list of strings", 1)?;
+                (fs.data.iter())
+                    .map(|f| match f.as_boxed().filter(|_| f.is_constant()) {
+                        Some(value) => value.try_string(),
+                        None => Err(conversion_error(
+                            "Expected a list of strings, but it contains a function".into(),
+                        )),
+                    })
+                    .collect()
+            }
+            value => Err(value.expect_type("a list of strings")),
+        }
+    }
+    /// Get the rows of the value as lists of numbers if it is a rank 2 number array
+    pub fn try_rows(&self) -> UiuaResult<Vec<Vec<f64>>> {
+        self.expect_shape("a rank 2 array of numbers", 2)?;
+        match self {
+            Value::Num(_) | Value::Byte(_) => self.rows().map(|row| row.try_nums()).collect(),
+            value => Err(value.expect_type("a rank 2 array of numbers")),
+        }
+    }
+}
+
 macro_rules! value_un_impl {
     ($name:ident, $(
         $([$in_place:ident, $f:ident])?
@@ -795,6 +1006,9 @@ value_un_impl!(acos, [Num, num], (Byte, byte));
 value_un_impl!(floor, [Num, num], [Byte, byte]);
 value_un_impl!(ceil, [Num, num], [Byte, byte]);
 value_un_impl!(round, [Num, num], [Byte, byte]);
+value_un_impl!(upper, [Char, char]);
+value_un_impl!(lower, [Char, char]);
+value_un_impl!(char_class, (Char, char));
 
 macro_rules! val_retry {
     (Byte, $env:expr) => {
@@ -806,13 +1020,13 @@ macro_rules! val_retry {
 }
 
 macro_rules! value_bin_impl {
-    ($name:ident, $(
+    ($name:ident as $fn_name:ident, $(
         $(($na:ident, $nb:ident, $f:ident $(, $retry:ident)?))*
         $([$ip:ident, $f2:ident $(, $retry2:ident)?])*
     ),* ) => {
         impl Value {
             #[allow(unreachable_patterns)]
-            pub fn $name(self, other: Self, env: &Uiua) -> UiuaResult<Self> {
+            pub fn $fn_name(self, other: Self, env: &Uiua) -> UiuaResult<Self> {
                 Ok(match (self, other) {
                     $($((Value::$ip(mut a), Value::$ip(b)) => {
                         if val_retry!($ip, env) {
@@ -882,6 +1096,9 @@ macro_rules! value_bin_impl {
             }
         }
     };
+    ($name:ident, $($rest:tt)*) => {
+        value_bin_impl!($name as $name, $($rest)*);
+    };
 }
 
 value_bin_impl!(
@@ -907,8 +1124,25 @@ value_bin_impl!(
     (Num, Byte, num_byte, num_num),
 );
 
+impl Value {
+    /// Multiply two values
+    ///
+    /// The product of two boolean byte arrays is kept as bytes.
+    pub fn mul(self, other: Self, env: &Uiua) -> UiuaResult<Self> {
+        match (self, other) {
+            (Value::Byte(mut a), Value::Byte(b))
+                if env.num_fill().is_none() && a.is_boolean() && b.is_boolean() =>
+            {
+                bin_pervade_mut(&mut a, b, env, mul::bool_bool)?;
+                Ok(a.into())
+            }
+            (a, b) => a.mul_numbers(b, env),
+        }
+    }
+}
+
 value_bin_impl!(
-    mul,
+    mul as mul_numbers,
     [Num, num_num],
     (Byte, Byte, byte_byte, num_num),
     (Byte, Num, byte_num, num_num),
@@ -942,7 +1176,20 @@ value_bin_impl!(
     (Byte, Num, byte_num, num_num),
     (Num, Byte, num_byte, num_num),
 );
-value_bin_impl!(atan2, (Num, Num, num_num));
+value_bin_impl!(
+    round_to,
+    [Num, num_num],
+    (Byte, Byte, byte_byte, num_num),
+    (Byte, Num, byte_num, num_num),
+    (Num, Byte, num_byte, num_num),
+);
+value_bin_impl!(
+    atan2,
+    (Num, Num, num_num),
+    (Byte, Byte, byte_byte, num_num),
+    (Byte, Num, byte_num, num_num),
+    (Num, Byte, num_byte, num_num),
+);
 
 value_bin_impl!(
     min,
@@ -1035,7 +1282,7 @@ impl Hash for Value {
                 arr.hash(state);
             }
             Value::Byte(arr) => {
-                1u8.hash(state);
+                0u8.hash(state);
                 arr.hash(state);
             }
             Value::Char(arr) => {
@@ -1104,3 +1351,143 @@ impl ValueBuilder {
         self.value.unwrap_or_default()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn byte_arrays() {
+        // Masks are stored as bytes, but must behave exactly like numbers
+        let is_bytes = |code: &str| {
+            let mut env = Uiua::with_native_sys();
+            env.load_str(code).unwrap();
+            matches!(env.take_stack().pop(), Some(Value::Byte(_)))
+        };
+        for code in [
+            "1",
+            "[1 0 1]",
+            "=1 [1 2 3]",
+            "∊ 2 [1 2 3]",
+            "◰ [1 2 1]",
+            "×=1 [1 2] [1 0]",
+            "↥ [1 0] [0 1]",
+            "/↥ [1 0 1]",
+        ] {
+            assert!(is_bytes(code), "{code} should be bytes");
+        }
+        for code in [
+            "2.5",
+            "×[2 3] [1 0]",
+            "+[1 0] [1 1]",
+            "⬚0.5×[1 0] [1]",
+            "/↥ =1 []",
+        ] {
+            assert!(!is_bytes(code), "{code} should not be bytes");
+        }
+    }
+
+    #[test]
+    fn value_conversions() {
+        use Value;
+        let num = Value::from(2.5).try_num().unwrap();
+        assert_eq!(num, 2.5);
+        assert!(Value::from(f64::NAN).try_num().unwrap().is_nan());
+        assert_eq!(Value::from(-7i64).try_int().unwrap(), -7);
+        assert!(Value::from(true).try_bool().unwrap());
+        assert!(!Value::from(false).try_bool().unwrap());
+        assert_eq!(
+            Value::from("héllo ∧ 世界").try_string().unwrap(),
+            "héllo ∧ 世界"
+        );
+        assert_eq!(Value::from(String::from("ok")).try_string().unwrap(), "ok");
+        let nums = vec![1.0, f64::NAN, -3.5];
+        let back = Value::from(nums).try_nums().unwrap();
+        assert_eq!(back[0], 1.0);
+        assert!(back[1].is_nan());
+        assert_eq!(back[2], -3.5);
+        let strings = vec!["a".to_string(), "".into(), "ünïcode".into()];
+        assert_eq!(Value::from(strings.clone()).try_strings().unwrap(), strings);
+        let rows = vec![vec![1.0, 2.0], vec![3.0, 4.0]];
+        assert_eq!(
+            Value::try_from(rows.clone()).unwrap().try_rows().unwrap(),
+            rows
+        );
+
+        // Mismatches
+        fn message<T>(res: UiuaResult<T>) -> String {
+            res.map(drop).unwrap_err().message()
+        }
+        assert_eq!(
+            message(Value::from("abc").try_num()),
+            "Expected a scalar number, but the value has shape [3]"
+        );
+        assert_eq!(
+            message(Value::from('a').try_num()),
+            "Expected a scalar number, but the value is a character array"
+        );
+        assert_eq!(
+            message(Value::from(1.5).try_int()),
+            "Expected an integer, but it is 1.5"
+        );
+        assert_eq!(
+            message(Value::from(vec![1.0]).try_rows()),
+            "Expected a rank 2 array of numbers, but the value has shape [1]"
+        );
+        assert_eq!(
+            message(Value::try_from(vec![vec![1.0, 2.0], vec![3.0]])),
+            "Cannot make an array from rows of lengths 2 and 1. \
+            All rows of an array must have the same shape."
+        );
+
+        // Values can be passed into and out of code
+        let mut env = Uiua::with_native_sys();
+        env.push(vec![1.0, 2.0, 3.0]);
+        env.push("abc");
+        env.load_str("⇌").unwrap();
+        assert_eq!(env.pop("string").unwrap().try_string().unwrap(), "cba");
+        env.load_str("/+").unwrap();
+        assert_eq!(env.pop("sum").unwrap().try_num().unwrap(), 6.0);
+    }
+
+    #[test]
+    fn stack_formats() {
+        let stack = |code: &str| {
+            let mut env = Uiua::with_native_sys();
+            env.load_str(code).unwrap();
+            env.take_stack()
+        };
+        let formatted = |code: &str| {
+            (stack(code).iter())
+                .map(|value| (value.dump_json().unwrap(), value.show_raw().unwrap()))
+                .collect::<Vec<_>>()
+        };
+        assert!(formatted("").is_empty());
+        assert_eq!(
+            formatted("↯2_2_2⇡8"),
+            [(
+                "[[[0,1],[2,3]],[[4,5],[6,7]]]".into(),
+                "0 1\n2 3\n\n4 5\n6 7".into()
+            )]
+        );
+        assert_eq!(
+            formatted("[] \"\" ↯0_2 0"),
+            [
+                ("[]".into(), "".into()),
+                ("\"\"".into(), "".into()),
+                ("[]".into(), "".into())
+            ]
+        );
+        assert_eq!(
+            formatted("{1_2 \"ef\"} [\"ab\" \"cd\"] [¯1.5 NaN]"),
+            [
+                ("[-1.5,null]".into(), "-1.5 NaN".into()),
+                ("[\"ab\",\"cd\"]".into(), "ab\ncd".into()),
+                ("[[1,2],\"ef\"]".into(), "1 2\nef".into())
+            ]
+        );
+        let function = &stack("(+1)")[0];
+        assert!(function.dump_json().is_err());
+        assert!(function.show_raw().is_err());
+    }
+}